@@ -4,8 +4,11 @@ use axfs::fops::{Directory, File};
 
 pub use axfs::fops::DirEntry as AxDirEntry;
 pub use axfs::fops::FileAttr as AxFileAttr;
+pub use axfs::fops::FileLock as AxFileLock;
+pub use axfs::fops::FileLocker as AxFileLocker;
 pub use axfs::fops::FilePerm as AxFilePerm;
 pub use axfs::fops::FileType as AxFileType;
+pub use axfs::fops::LockKind as AxLockKind;
 pub use axfs::fops::OpenOptions as AxOpenOptions;
 pub use axio::SeekFrom as AxSeekFrom;
 
@@ -58,6 +61,45 @@ pub fn ax_file_attr(file: &AxFileHandle) -> AxResult<AxFileAttr> {
     file.0.get_attr()
 }
 
+pub fn ax_file_locker(file: &AxFileHandle) -> &AxFileLocker {
+    file.0.locker()
+}
+
+pub fn ax_process_file_locker(file: &AxFileHandle) -> AxFileLocker {
+    file.0.locker().process_locker()
+}
+
+pub fn ax_lock_file(locker: &AxFileLocker, kind: AxLockKind, wait: bool) -> AxResult {
+    locker.lock(kind, wait)
+}
+
+pub fn ax_unlock_file(locker: &AxFileLocker) -> AxResult {
+    locker.unlock()
+}
+
+pub fn ax_lock_file_range(
+    locker: &AxFileLocker,
+    kind: AxLockKind,
+    start: u64,
+    end: u64,
+    wait: bool,
+) -> AxResult {
+    locker.lock_range(kind, start, end, wait)
+}
+
+pub fn ax_unlock_file_range(locker: &AxFileLocker, start: u64, end: u64) -> AxResult {
+    locker.unlock_range(start, end)
+}
+
+pub fn ax_test_lock_file_range(
+    locker: &AxFileLocker,
+    kind: AxLockKind,
+    start: u64,
+    end: u64,
+) -> AxResult<Option<AxFileLock>> {
+    locker.test_lock_range(kind, start, end)
+}
+
 pub fn ax_read_dir(dir: &mut AxDirHandle, dirents: &mut [AxDirEntry]) -> AxResult<usize> {
     dir.0.read_dir(dirents)
 }
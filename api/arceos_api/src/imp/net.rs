@@ -1,7 +1,9 @@
 use crate::io::AxPollState;
-use axerrno::AxResult;
-use axnet::{UdpSocket, TcpSocket};
+use axerrno::{AxError, AxResult};
+use axnet::{IcmpSocket, UdpSocket, TcpSocket};
 use core::net::{IpAddr, SocketAddr};
+use core::task::{ready, Context, Poll};
+use core::time::Duration;
 
 /// A handle to a TCP socket.
 pub struct AxTcpSocketHandle(TcpSocket);
@@ -9,6 +11,12 @@ pub struct AxTcpSocketHandle(TcpSocket);
 /// A handle to a UDP socket.
 pub struct AxUdpSocketHandle(UdpSocket);
 
+/// A handle to an ICMP socket.
+pub struct AxIcmpSocketHandle(IcmpSocket);
+
+/// Information of a network interface.
+pub type AxNetInterfaceInfo = axnet::InterfaceInfo;
+
 ////////////////////////////////////////////////////////////////////////////////
 // TCP socket
 ////////////////////////////////////////////////////////////////////////////////
@@ -38,6 +46,10 @@ pub fn ax_tcp_bind(socket: &AxTcpSocketHandle, addr: SocketAddr) -> AxResult {
     socket.0.bind(addr)
 }
 
+pub fn ax_tcp_bind_to_device(socket: &AxTcpSocketHandle, name: &str) -> AxResult {
+    socket.0.bind_to_device(name)
+}
+
 pub fn ax_tcp_listen(socket: &AxTcpSocketHandle, _backlog: usize) -> AxResult {
     socket.0.listen()
 }
@@ -60,10 +72,82 @@ pub fn ax_tcp_poll(socket: &AxTcpSocketHandle) -> AxResult<AxPollState> {
     socket.0.poll()
 }
 
+pub fn ax_tcp_poll_connect(socket: &AxTcpSocketHandle, cx: &mut Context<'_>, addr: SocketAddr) -> Poll<AxResult> {
+    socket.0.poll_connect(cx, addr)
+}
+
+pub fn ax_tcp_poll_accept(
+    socket: &AxTcpSocketHandle,
+    cx: &mut Context<'_>,
+) -> Poll<AxResult<(AxTcpSocketHandle, SocketAddr)>> {
+    let new_sock = ready!(socket.0.poll_accept(cx))?;
+    let addr = new_sock.peer_addr()?;
+    Poll::Ready(Ok((AxTcpSocketHandle(new_sock), addr)))
+}
+
+pub fn ax_tcp_poll_send(socket: &AxTcpSocketHandle, cx: &mut Context<'_>, buf: &[u8]) -> Poll<AxResult<usize>> {
+    socket.0.poll_send(cx, buf)
+}
+
+pub fn ax_tcp_poll_recv(socket: &AxTcpSocketHandle, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<AxResult<usize>> {
+    socket.0.poll_recv(cx, buf)
+}
+
 pub fn ax_tcp_shutdown(socket: &AxTcpSocketHandle) -> AxResult {
     socket.0.shutdown()
 }
 
+pub fn ax_tcp_set_reuse_addr(socket: &AxTcpSocketHandle, reuse_addr: bool) -> AxResult {
+    socket.0.set_reuse_addr(reuse_addr);
+    Ok(())
+}
+
+pub fn ax_tcp_set_recv_buffer_size(socket: &AxTcpSocketHandle, size: usize) -> AxResult {
+    socket.0.set_recv_buffer_size(size)
+}
+
+pub fn ax_tcp_set_send_buffer_size(socket: &AxTcpSocketHandle, size: usize) -> AxResult {
+    socket.0.set_send_buffer_size(size)
+}
+
+pub fn ax_tcp_set_nodelay(socket: &AxTcpSocketHandle, nodelay: bool) -> AxResult {
+    socket.0.set_nodelay(nodelay);
+    Ok(())
+}
+
+pub fn ax_tcp_nodelay(socket: &AxTcpSocketHandle) -> AxResult<bool> {
+    Ok(socket.0.nodelay())
+}
+
+pub fn ax_tcp_set_keep_alive(socket: &AxTcpSocketHandle, keep_alive: bool) -> AxResult {
+    socket.0.set_keep_alive(keep_alive);
+    Ok(())
+}
+
+pub fn ax_tcp_set_keep_idle(socket: &AxTcpSocketHandle, idle: Duration) -> AxResult {
+    socket.0.set_keep_idle(idle)
+}
+
+pub fn ax_tcp_set_recv_timeout(socket: &AxTcpSocketHandle, timeout: Option<Duration>) -> AxResult {
+    socket.0.set_recv_timeout(timeout)
+}
+
+pub fn ax_tcp_recv_timeout(socket: &AxTcpSocketHandle) -> AxResult<Option<Duration>> {
+    Ok(socket.0.recv_timeout())
+}
+
+pub fn ax_tcp_set_send_timeout(socket: &AxTcpSocketHandle, timeout: Option<Duration>) -> AxResult {
+    socket.0.set_send_timeout(timeout)
+}
+
+pub fn ax_tcp_send_timeout(socket: &AxTcpSocketHandle) -> AxResult<Option<Duration>> {
+    Ok(socket.0.send_timeout())
+}
+
+pub fn ax_tcp_take_error(socket: &AxTcpSocketHandle) -> AxResult<Option<AxError>> {
+    Ok(socket.0.take_error())
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 // UDP socket
 ////////////////////////////////////////////////////////////////////////////////
@@ -89,6 +173,10 @@ pub fn ax_udp_bind(socket: &AxUdpSocketHandle, addr: SocketAddr) -> AxResult {
     socket.0.bind(addr)
 }
 
+pub fn ax_udp_bind_to_device(socket: &AxUdpSocketHandle, name: &str) -> AxResult {
+    socket.0.bind_to_device(name)
+}
+
 pub fn ax_udp_recv_from(socket: &AxUdpSocketHandle, buf: &mut [u8]) -> AxResult<(usize, SocketAddr)> {
     socket.0.recv_from(buf)
 }
@@ -117,6 +205,97 @@ pub fn ax_udp_poll(socket: &AxUdpSocketHandle) -> AxResult<AxPollState> {
     socket.0.poll()
 }
 
+pub fn ax_udp_poll_send_to(
+    socket: &AxUdpSocketHandle,
+    cx: &mut Context<'_>,
+    buf: &[u8],
+    addr: SocketAddr,
+) -> Poll<AxResult<usize>> {
+    socket.0.poll_send_to(cx, buf, addr)
+}
+
+pub fn ax_udp_poll_recv_from(
+    socket: &AxUdpSocketHandle,
+    cx: &mut Context<'_>,
+    buf: &mut [u8],
+) -> Poll<AxResult<(usize, SocketAddr)>> {
+    socket.0.poll_recv_from(cx, buf)
+}
+
+pub fn ax_udp_poll_send(socket: &AxUdpSocketHandle, cx: &mut Context<'_>, buf: &[u8]) -> Poll<AxResult<usize>> {
+    socket.0.poll_send(cx, buf)
+}
+
+pub fn ax_udp_poll_recv(socket: &AxUdpSocketHandle, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<AxResult<usize>> {
+    socket.0.poll_recv(cx, buf)
+}
+
+pub fn ax_udp_set_reuse_addr(socket: &AxUdpSocketHandle, reuse_addr: bool) -> AxResult {
+    socket.0.set_reuse_addr(reuse_addr);
+    Ok(())
+}
+
+pub fn ax_udp_set_recv_timeout(socket: &AxUdpSocketHandle, timeout: Option<Duration>) -> AxResult {
+    socket.0.set_recv_timeout(timeout)
+}
+
+pub fn ax_udp_recv_timeout(socket: &AxUdpSocketHandle) -> AxResult<Option<Duration>> {
+    Ok(socket.0.recv_timeout())
+}
+
+pub fn ax_udp_set_send_timeout(socket: &AxUdpSocketHandle, timeout: Option<Duration>) -> AxResult {
+    socket.0.set_send_timeout(timeout)
+}
+
+pub fn ax_udp_send_timeout(socket: &AxUdpSocketHandle) -> AxResult<Option<Duration>> {
+    Ok(socket.0.send_timeout())
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// ICMP socket
+////////////////////////////////////////////////////////////////////////////////
+
+pub fn ax_icmp_socket() -> AxIcmpSocketHandle {
+    AxIcmpSocketHandle(IcmpSocket::new())
+}
+
+pub fn ax_icmp_ident(socket: &AxIcmpSocketHandle) -> AxResult<u16> {
+    socket.0.ident()
+}
+
+pub fn ax_icmp_set_nonblocking(socket: &AxIcmpSocketHandle, nonblocking: bool) -> AxResult {
+    socket.0.set_nonblocking(nonblocking);
+    Ok(())
+}
+
+pub fn ax_icmp_bind(socket: &AxIcmpSocketHandle, ident: u16) -> AxResult {
+    socket.0.bind(ident)
+}
+
+pub fn ax_icmp_bind_to_device(socket: &AxIcmpSocketHandle, name: &str) -> AxResult {
+    socket.0.bind_to_device(name)
+}
+
+pub fn ax_icmp_send_to(socket: &AxIcmpSocketHandle, buf: &[u8], addr: IpAddr) -> AxResult<usize> {
+    socket.0.send_to(buf, addr)
+}
+
+pub fn ax_icmp_recv_from(socket: &AxIcmpSocketHandle, buf: &mut [u8]) -> AxResult<(usize, IpAddr)> {
+    socket.0.recv_from(buf)
+}
+
+pub fn ax_icmp_poll(socket: &AxIcmpSocketHandle) -> AxResult<AxPollState> {
+    socket.0.poll()
+}
+
+pub fn ax_icmp_set_recv_timeout(socket: &AxIcmpSocketHandle, timeout: Option<Duration>) -> AxResult {
+    socket.0.set_recv_timeout(timeout)
+}
+
+pub fn ax_icmp_recv_timeout(socket: &AxIcmpSocketHandle) -> AxResult<Option<Duration>> {
+    Ok(socket.0.recv_timeout())
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 // Miscellaneous
 ////////////////////////////////////////////////////////////////////////////////
@@ -125,7 +304,28 @@ pub fn ax_dns_query(domain_name: &str) -> AxResult<alloc::vec::Vec<IpAddr>> {
     axnet::dns_query(domain_name)
 }
 
+pub fn ax_set_dns_servers(servers: &[IpAddr]) -> AxResult {
+    axnet::set_dns_servers(servers);
+    Ok(())
+}
+
+pub fn ax_dns_servers() -> alloc::vec::Vec<IpAddr> {
+    axnet::dns_servers()
+}
+
 pub fn ax_poll_interfaces() -> AxResult {
     axnet::poll_interfaces();
     Ok(())
 }
+
+pub fn ax_net_interfaces() -> alloc::vec::Vec<AxNetInterfaceInfo> {
+    axnet::interfaces()
+}
+
+pub fn ax_add_route(dst: IpAddr, prefix_len: u8, gateway: IpAddr, dev: &str) -> AxResult {
+    axnet::add_route(dst, prefix_len, gateway, dev)
+}
+
+pub fn ax_remove_route(dst: IpAddr, prefix_len: u8, dev: &str) -> AxResult {
+    axnet::remove_route(dst, prefix_len, dev)
+}
@@ -24,6 +24,7 @@ pub fn ax_exit(_exit_code: i32) -> ! {
 }
 
 cfg_task! {
+    use core::sync::atomic::AtomicU32;
     use core::time::Duration;
 
     /// A handle to a task.
@@ -67,6 +68,26 @@ cfg_task! {
         }
     }
 
+    pub fn ax_spawn_with_affinity<F>(
+        f: F,
+        name: alloc::string::String,
+        stack_size: usize,
+        cpumask: usize,
+    ) -> crate::AxResult<AxTaskHandle>
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let cpumask = axtask::CpuMask::from_raw_bits(cpumask);
+        if cpumask.is_empty() {
+            return axerrno::ax_err!(InvalidInput, "ax_spawn_with_affinity: no valid CPU");
+        }
+        let inner = axtask::spawn_raw_with_affinity(f, name, stack_size, cpumask);
+        Ok(AxTaskHandle {
+            id: inner.id().as_u64(),
+            inner,
+        })
+    }
+
     pub fn ax_wait_for_exit(task: AxTaskHandle) -> Option<i32> {
         task.inner.join()
     }
@@ -82,6 +103,38 @@ cfg_task! {
         }
     }
 
+    pub fn ax_current_task() -> AxTaskHandle {
+        let inner = axtask::current().as_task_ref().clone();
+        AxTaskHandle {
+            id: inner.id().as_u64(),
+            inner,
+        }
+    }
+
+    pub fn ax_inherit_priority(task_id: u64, cond: impl FnOnce() -> bool) -> bool {
+        axtask::inherit_priority(task_id, cond)
+    }
+
+    pub fn ax_restore_priority() -> bool {
+        axtask::restore_priority()
+    }
+
+    pub fn ax_set_current_affinity(cpumask: usize) -> crate::AxResult {
+        let curr = axtask::current();
+        if axtask::set_affinity(curr.as_task_ref(), axtask::CpuMask::from_raw_bits(cpumask)) {
+            Ok(())
+        } else {
+            axerrno::ax_err!(
+                InvalidInput,
+                "ax_set_current_affinity: no valid CPU"
+            )
+        }
+    }
+
+    pub fn ax_get_current_affinity() -> usize {
+        axtask::current().cpumask().bits()
+    }
+
     pub fn ax_wait_queue_wait(
         wq: &AxWaitQueueHandle,
         until_condition: impl Fn() -> bool,
@@ -99,6 +152,27 @@ cfg_task! {
         false
     }
 
+    pub fn ax_futex_wait(
+        addr: &AtomicU32,
+        expected: u32,
+        timeout: Option<Duration>,
+    ) -> crate::AxResult<bool> {
+        #[cfg(not(feature = "irq"))]
+        if timeout.is_some() {
+            axlog::warn!("ax_futex_wait: the `timeout` argument is ignored without the `irq` feature");
+        }
+        match axtask::futex_wait(addr, expected, timeout) {
+            axtask::FutexWaitResult::Woken => Ok(false),
+            axtask::FutexWaitResult::TimedOut => Ok(true),
+            axtask::FutexWaitResult::Mismatch => Err(axerrno::AxError::WouldBlock),
+        }
+    }
+
+    pub fn ax_futex_wake(addr: &AtomicU32, count: u32) -> u32 {
+        let count = if count == u32::MAX { usize::MAX } else { count as usize };
+        axtask::futex_wake(addr, count) as u32
+    }
+
     pub fn ax_wait_queue_wake(wq: &AxWaitQueueHandle, count: u32) {
         if count == u32::MAX {
             wq.0.notify_all(true);
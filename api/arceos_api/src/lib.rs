@@ -108,17 +108,45 @@ pub mod task {
 
         /// Returns the current task's ID.
         pub fn ax_current_task_id() -> u64;
+        /// Returns a handle to the current task.
+        pub fn ax_current_task() -> AxTaskHandle;
         /// Spawns a new task with the given entry point and other arguments.
         pub fn ax_spawn(
             f: impl FnOnce() + Send + 'static,
             name: alloc::string::String,
             stack_size: usize
         ) -> AxTaskHandle;
+        /// Spawns a new task which is only allowed to run on the CPUs in
+        /// `cpumask` (one bit for each CPU).
+        pub fn ax_spawn_with_affinity(
+            f: impl FnOnce() + Send + 'static,
+            name: alloc::string::String,
+            stack_size: usize,
+            cpumask: usize,
+        ) -> crate::AxResult<AxTaskHandle>;
         /// Waits for the given task to exit, and returns its exit code (the
         /// argument of [`ax_exit`]).
         pub fn ax_wait_for_exit(task: AxTaskHandle) -> Option<i32>;
         /// Sets the priority of the current task.
         pub fn ax_set_current_priority(prio: isize) -> crate::AxResult;
+        /// Raises the priority of the task with ID `task_id` to the priority
+        /// of the current task if the latter is higher and `cond` returns
+        /// `true`, until that task calls [`ax_restore_priority`]. It's used
+        /// for priority inheritance of locks, where `cond` checks whether the
+        /// task still holds the lock.
+        ///
+        /// Returns `true` if the priority is raised.
+        pub fn ax_inherit_priority(task_id: u64, cond: impl FnOnce() -> bool) -> bool;
+        /// Drops the priority that the current task inherited by
+        /// [`ax_inherit_priority`].
+        ///
+        /// Returns `true` if the current task had an inherited priority.
+        pub fn ax_restore_priority() -> bool;
+        /// Sets the CPUs that the current task is allowed to run on, one bit
+        /// for each CPU.
+        pub fn ax_set_current_affinity(cpumask: usize) -> crate::AxResult;
+        /// Returns the CPUs that the current task is allowed to run on.
+        pub fn ax_get_current_affinity() -> usize;
 
         /// Blocks the current task and put it into the wait queue, until the
         /// given condition becomes true, or the the given duration has elapsed
@@ -133,9 +161,33 @@ pub mod task {
         /// The maximum number of tasks to wake up is specified by `count`. If
         /// `count` is `u32::MAX`, it will wake up all tasks in the wait queue.
         pub fn ax_wait_queue_wake(wq: &AxWaitQueueHandle, count: u32);
+
+        /// Blocks the current task on the futex at `addr` if its value is
+        /// `expected`, until it's woken up by [`ax_futex_wake`] on the same
+        /// address, or the given duration has elapsed (if specified).
+        ///
+        /// Returns [`WouldBlock`](crate::AxError::WouldBlock) if the value is
+        /// not `expected`, otherwise returns whether it's timed out.
+        pub fn ax_futex_wait(
+            addr: &core::sync::atomic::AtomicU32,
+            expected: u32,
+            timeout: Option<core::time::Duration>,
+        ) -> crate::AxResult<bool>;
+        /// Wakes up at most `count` tasks waiting on the futex at `addr`, and
+        /// returns the number of tasks woken up.
+        ///
+        /// If `count` is `u32::MAX`, it will wake up all waiting tasks.
+        pub fn ax_futex_wake(addr: &core::sync::atomic::AtomicU32, count: u32) -> u32;
     }
 }
 
+/// Synchronization primitives that block the waiting tasks.
+#[cfg(feature = "multitask")]
+pub mod sync {
+    pub use axsync::{Barrier, BarrierWaitResult, Condvar, WaitTimeoutResult};
+    pub use axsync::{Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard, Semaphore};
+}
+
 /// Filesystem manipulation operations.
 pub mod fs {
     use crate::AxResult;
@@ -150,6 +202,9 @@ pub mod fs {
         pub type AxFilePerm;
         pub type AxDirEntry;
         pub type AxSeekFrom;
+        pub type AxLockKind;
+        pub type AxFileLock;
+        pub type AxFileLocker;
         #[cfg(feature = "myfs")]
         pub type AxDisk;
         #[cfg(feature = "myfs")]
@@ -194,6 +249,27 @@ pub mod fs {
         pub fn ax_seek_file(file: &mut AxFileHandle, pos: AxSeekFrom) -> AxResult<u64>;
         /// Returns attributes of the file.
         pub fn ax_file_attr(file: &AxFileHandle) -> AxResult<AxFileAttr>;
+        /// Returns the handle to place advisory locks on behalf of the file,
+        /// which can be cloned to use without borrowing the file.
+        pub fn ax_file_locker(file: &AxFileHandle) -> &AxFileLocker;
+        /// Returns the handle to place advisory locks on behalf of the process
+        /// instead of the file, like the POSIX record locks of `fcntl(2)`.
+        pub fn ax_process_file_locker(file: &AxFileHandle) -> AxFileLocker;
+        /// Places an advisory lock on the whole file, like `flock(2)`.
+        ///
+        /// If `wait` is `false`, returns `WouldBlock` instead of blocking when
+        /// the lock is held by other opened files.
+        pub fn ax_lock_file(locker: &AxFileLocker, kind: AxLockKind, wait: bool) -> AxResult;
+        /// Removes the advisory lock on the whole file.
+        pub fn ax_unlock_file(locker: &AxFileLocker) -> AxResult;
+        /// Places an advisory lock on the byte range `start..end` of the file,
+        /// like `fcntl(2)` with `F_SETLK` or `F_SETLKW`.
+        pub fn ax_lock_file_range(locker: &AxFileLocker, kind: AxLockKind, start: u64, end: u64, wait: bool) -> AxResult;
+        /// Removes the advisory locks in the byte range `start..end` of the file.
+        pub fn ax_unlock_file_range(locker: &AxFileLocker, start: u64, end: u64) -> AxResult;
+        /// Returns the lock held by other opened files that prevents placing
+        /// the given lock, like `fcntl(2)` with `F_GETLK`.
+        pub fn ax_test_lock_file_range(locker: &AxFileLocker, kind: AxLockKind, start: u64, end: u64) -> AxResult<Option<AxFileLock>>;
 
         /// Reads directory entries starts from the current position into the
         /// given buffer, returns the number of entries read.
@@ -223,13 +299,17 @@ pub mod fs {
 
 /// Networking primitives for TCP/UDP communication.
 pub mod net {
-    use crate::{io::AxPollState, AxResult};
+    use crate::{io::AxPollState, AxError, AxResult};
     use core::net::{IpAddr, SocketAddr};
+    use core::task::{Context, Poll};
+    use core::time::Duration;
 
     define_api_type! {
         @cfg "net";
         pub type AxTcpSocketHandle;
         pub type AxUdpSocketHandle;
+        pub type AxIcmpSocketHandle;
+        pub type AxNetInterfaceInfo;
     }
 
     define_api! {
@@ -250,6 +330,9 @@ pub mod net {
         pub fn ax_tcp_connect(handle: &AxTcpSocketHandle, addr: SocketAddr) -> AxResult;
         /// Binds the TCP socket to the given address and port.
         pub fn ax_tcp_bind(socket: &AxTcpSocketHandle, addr: SocketAddr) -> AxResult;
+        /// Binds the TCP socket to the network interface named `name`, or
+        /// removes the binding if `name` is empty.
+        pub fn ax_tcp_bind_to_device(socket: &AxTcpSocketHandle, name: &str) -> AxResult;
         /// Starts listening on the bound address and port.
         pub fn ax_tcp_listen(socket: &AxTcpSocketHandle, _backlog: usize) -> AxResult;
         /// Accepts a new connection on the TCP socket.
@@ -265,9 +348,56 @@ pub mod net {
         pub fn ax_tcp_recv(socket: &AxTcpSocketHandle, buf: &mut [u8]) -> AxResult<usize>;
         /// Returns whether the TCP socket is readable or writable.
         pub fn ax_tcp_poll(socket: &AxTcpSocketHandle) -> AxResult<AxPollState>;
+
+        /// Connects the TCP socket to the given address asynchronously.
+        ///
+        /// The connection is started by the first call. If it can't be
+        /// established yet, the waker of `cx` is registered to be woken when
+        /// it may be, and `Poll::Pending` is returned. So are the following
+        /// `ax_tcp_poll_*` functions.
+        pub fn ax_tcp_poll_connect(socket: &AxTcpSocketHandle, cx: &mut Context<'_>, addr: SocketAddr) -> Poll<AxResult>;
+        /// Accepts a new connection on the TCP socket asynchronously.
+        pub fn ax_tcp_poll_accept(socket: &AxTcpSocketHandle, cx: &mut Context<'_>) -> Poll<AxResult<(AxTcpSocketHandle, SocketAddr)>>;
+        /// Transmits data on the TCP socket asynchronously.
+        pub fn ax_tcp_poll_send(socket: &AxTcpSocketHandle, cx: &mut Context<'_>, buf: &[u8]) -> Poll<AxResult<usize>>;
+        /// Receives data on the TCP socket asynchronously.
+        pub fn ax_tcp_poll_recv(socket: &AxTcpSocketHandle, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<AxResult<usize>>;
         /// Closes the connection on the TCP socket.
         pub fn ax_tcp_shutdown(socket: &AxTcpSocketHandle) -> AxResult;
 
+        /// Allows the TCP socket to bind a port used by other connections
+        /// (`SO_REUSEADDR`).
+        pub fn ax_tcp_set_reuse_addr(socket: &AxTcpSocketHandle, reuse_addr: bool) -> AxResult;
+        /// Sets the receive buffer size of the TCP socket (`SO_RCVBUF`), it
+        /// takes effect on the next connect or listen.
+        pub fn ax_tcp_set_recv_buffer_size(socket: &AxTcpSocketHandle, size: usize) -> AxResult;
+        /// Sets the send buffer size of the TCP socket (`SO_SNDBUF`), it takes
+        /// effect on the next connect or listen.
+        pub fn ax_tcp_set_send_buffer_size(socket: &AxTcpSocketHandle, size: usize) -> AxResult;
+        /// Disables or enables the Nagle's algorithm on the TCP socket
+        /// (`TCP_NODELAY`).
+        pub fn ax_tcp_set_nodelay(socket: &AxTcpSocketHandle, nodelay: bool) -> AxResult;
+        /// Returns whether the Nagle's algorithm is disabled on the TCP socket.
+        pub fn ax_tcp_nodelay(socket: &AxTcpSocketHandle) -> AxResult<bool>;
+        /// Enables or disables sending keep-alive packets on the TCP socket
+        /// (`SO_KEEPALIVE`).
+        pub fn ax_tcp_set_keep_alive(socket: &AxTcpSocketHandle, keep_alive: bool) -> AxResult;
+        /// Sets how long the connection is idle before sending keep-alive
+        /// packets (`TCP_KEEPIDLE`).
+        pub fn ax_tcp_set_keep_idle(socket: &AxTcpSocketHandle, idle: Duration) -> AxResult;
+        /// Sets the timeout of receiving and accepting on the TCP socket
+        /// (`SO_RCVTIMEO`), `None` means blocking indefinitely.
+        pub fn ax_tcp_set_recv_timeout(socket: &AxTcpSocketHandle, timeout: Option<Duration>) -> AxResult;
+        /// Returns the timeout of receiving on the TCP socket.
+        pub fn ax_tcp_recv_timeout(socket: &AxTcpSocketHandle) -> AxResult<Option<Duration>>;
+        /// Sets the timeout of sending and connecting on the TCP socket
+        /// (`SO_SNDTIMEO`), `None` means blocking indefinitely.
+        pub fn ax_tcp_set_send_timeout(socket: &AxTcpSocketHandle, timeout: Option<Duration>) -> AxResult;
+        /// Returns the timeout of sending on the TCP socket.
+        pub fn ax_tcp_send_timeout(socket: &AxTcpSocketHandle) -> AxResult<Option<Duration>>;
+        /// Returns and clears the pending error of the TCP socket (`SO_ERROR`).
+        pub fn ax_tcp_take_error(socket: &AxTcpSocketHandle) -> AxResult<Option<AxError>>;
+
         // UDP socket
 
         /// Creates a new UDP socket.
@@ -281,6 +411,9 @@ pub mod net {
 
         /// Binds the UDP socket to the given address and port.
         pub fn ax_udp_bind(socket: &AxUdpSocketHandle, addr: SocketAddr) -> AxResult;
+        /// Binds the UDP socket to the network interface named `name`, or
+        /// removes the binding if `name` is empty.
+        pub fn ax_udp_bind_to_device(socket: &AxUdpSocketHandle, name: &str) -> AxResult;
         /// Receives a single datagram message on the UDP socket.
         pub fn ax_udp_recv_from(socket: &AxUdpSocketHandle, buf: &mut [u8]) -> AxResult<(usize, SocketAddr)>;
         /// Receives a single datagram message on the UDP socket, without
@@ -304,15 +437,88 @@ pub mod net {
         /// Returns whether the UDP socket is readable or writable.
         pub fn ax_udp_poll(socket: &AxUdpSocketHandle) -> AxResult<AxPollState>;
 
+        /// Sends data on the UDP socket to the given address asynchronously.
+        ///
+        /// If the data can't be sent yet, the waker of `cx` is registered to be
+        /// woken when it may be, and `Poll::Pending` is returned. So are the
+        /// following `ax_udp_poll_*` functions.
+        pub fn ax_udp_poll_send_to(socket: &AxUdpSocketHandle, cx: &mut Context<'_>, buf: &[u8], addr: SocketAddr) -> Poll<AxResult<usize>>;
+        /// Receives a single datagram message on the UDP socket asynchronously.
+        pub fn ax_udp_poll_recv_from(socket: &AxUdpSocketHandle, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<AxResult<(usize, SocketAddr)>>;
+        /// Sends data on the UDP socket to the remote address to which it is
+        /// connected asynchronously.
+        pub fn ax_udp_poll_send(socket: &AxUdpSocketHandle, cx: &mut Context<'_>, buf: &[u8]) -> Poll<AxResult<usize>>;
+        /// Receives a single datagram message on the UDP socket from the remote
+        /// address to which it is connected asynchronously.
+        pub fn ax_udp_poll_recv(socket: &AxUdpSocketHandle, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<AxResult<usize>>;
+
+        /// Allows the UDP socket to bind a port used by other sockets
+        /// (`SO_REUSEADDR`).
+        pub fn ax_udp_set_reuse_addr(socket: &AxUdpSocketHandle, reuse_addr: bool) -> AxResult;
+        /// Sets the timeout of receiving on the UDP socket (`SO_RCVTIMEO`),
+        /// `None` means blocking indefinitely.
+        pub fn ax_udp_set_recv_timeout(socket: &AxUdpSocketHandle, timeout: Option<Duration>) -> AxResult;
+        /// Returns the timeout of receiving on the UDP socket.
+        pub fn ax_udp_recv_timeout(socket: &AxUdpSocketHandle) -> AxResult<Option<Duration>>;
+        /// Sets the timeout of sending on the UDP socket (`SO_SNDTIMEO`),
+        /// `None` means blocking indefinitely.
+        pub fn ax_udp_set_send_timeout(socket: &AxUdpSocketHandle, timeout: Option<Duration>) -> AxResult;
+        /// Returns the timeout of sending on the UDP socket.
+        pub fn ax_udp_send_timeout(socket: &AxUdpSocketHandle) -> AxResult<Option<Duration>>;
+
+        // ICMP socket
+
+        /// Creates a new ICMP socket for echo requests and replies.
+        pub fn ax_icmp_socket() -> AxIcmpSocketHandle;
+        /// Returns the identifier the ICMP socket is bound to.
+        pub fn ax_icmp_ident(socket: &AxIcmpSocketHandle) -> AxResult<u16>;
+        /// Moves this ICMP socket into or out of nonblocking mode.
+        pub fn ax_icmp_set_nonblocking(socket: &AxIcmpSocketHandle, nonblocking: bool) -> AxResult;
+
+        /// Binds the ICMP socket to the given identifier, a free one is chosen
+        /// if it's 0.
+        pub fn ax_icmp_bind(socket: &AxIcmpSocketHandle, ident: u16) -> AxResult;
+        /// Binds the ICMP socket to the network interface named `name`, or
+        /// removes the binding if `name` is empty.
+        pub fn ax_icmp_bind_to_device(socket: &AxIcmpSocketHandle, name: &str) -> AxResult;
+        /// Sends an ICMP message, including the ICMP header, on the ICMP socket
+        /// to the given address. On success, returns the number of bytes written.
+        pub fn ax_icmp_send_to(socket: &AxIcmpSocketHandle, buf: &[u8], addr: IpAddr) -> AxResult<usize>;
+        /// Receives a single ICMP message, including the ICMP header, on the
+        /// ICMP socket. On success, returns the number of bytes read and the
+        /// origin.
+        pub fn ax_icmp_recv_from(socket: &AxIcmpSocketHandle, buf: &mut [u8]) -> AxResult<(usize, IpAddr)>;
+        /// Returns whether the ICMP socket is readable or writable.
+        pub fn ax_icmp_poll(socket: &AxIcmpSocketHandle) -> AxResult<AxPollState>;
+
+        /// Sets the timeout of receiving on the ICMP socket (`SO_RCVTIMEO`),
+        /// `None` means blocking indefinitely.
+        pub fn ax_icmp_set_recv_timeout(socket: &AxIcmpSocketHandle, timeout: Option<Duration>) -> AxResult;
+        /// Returns the timeout of receiving on the ICMP socket.
+        pub fn ax_icmp_recv_timeout(socket: &AxIcmpSocketHandle) -> AxResult<Option<Duration>>;
+
         // Miscellaneous
 
         /// Resolves the host name to a list of IP addresses.
         pub fn ax_dns_query(domain_name: &str) -> AxResult<alloc::vec::Vec<IpAddr>>;
+        /// Replaces the DNS servers, which take precedence over the ones from
+        /// `/etc/resolv.conf` and DHCP. An empty list removes them.
+        pub fn ax_set_dns_servers(servers: &[IpAddr]) -> AxResult;
+        /// Returns the DNS servers in use.
+        pub fn ax_dns_servers() -> alloc::vec::Vec<IpAddr>;
         /// Poll the network stack.
         ///
         /// It may receive packets from the NIC and process them, and transmit queued
         /// packets to the NIC.
         pub fn ax_poll_interfaces() -> AxResult;
+        /// Returns all network interfaces and their addresses.
+        pub fn ax_net_interfaces() -> alloc::vec::Vec<AxNetInterfaceInfo>;
+        /// Adds a route to the network `dst/prefix_len` via `gateway` on the
+        /// network interface named `dev`.
+        pub fn ax_add_route(dst: IpAddr, prefix_len: u8, gateway: IpAddr, dev: &str) -> AxResult;
+        /// Removes the route to the network `dst/prefix_len` on the network
+        /// interface named `dev`.
+        pub fn ax_remove_route(dst: IpAddr, prefix_len: u8, dev: &str) -> AxResult;
     }
 }
 
@@ -24,6 +24,7 @@ const CMD_TABLE: &[(&str, CmdHandler)] = &[
     ("help", do_help),
     ("ls", do_ls),
     ("mkdir", do_mkdir),
+    ("ping", do_ping),
     ("pwd", do_pwd),
     ("rm", do_rm),
     ("uname", do_uname),
@@ -260,6 +261,121 @@ fn do_uname(_args: &str) {
     );
 }
 
+#[cfg(feature = "net")]
+fn do_ping(args: &str) {
+    use std::net::{IcmpSocket, IpAddr, ToSocketAddrs};
+    use std::time::{Duration, Instant};
+
+    const DEFAULT_COUNT: u16 = 4;
+    const DATA_LEN: usize = 56;
+    const TIMEOUT: Duration = Duration::from_secs(1);
+
+    fn echo_request(ipv6: bool, ident: u16, seq: u16) -> [u8; 8 + DATA_LEN] {
+        let mut msg = [0; 8 + DATA_LEN];
+        msg[0] = if ipv6 { 128 } else { 8 }; // the checksum is filled in by the stack
+        msg[4..6].copy_from_slice(&ident.to_be_bytes());
+        msg[6..8].copy_from_slice(&seq.to_be_bytes());
+        for (i, b) in msg[8..].iter_mut().enumerate() {
+            *b = i as u8;
+        }
+        msg
+    }
+
+    // waits for the echo reply of `seq`, returns its length and round-trip time
+    fn wait_reply(
+        socket: &IcmpSocket,
+        ipv6: bool,
+        seq: u16,
+        start: Instant,
+    ) -> io::Result<Option<(usize, Duration)>> {
+        let reply_type = if ipv6 { 129 } else { 0 };
+        let mut buf = [0; 1500];
+        while start.elapsed() < TIMEOUT {
+            let (len, _) = match socket.recv_from(&mut buf) {
+                Ok(res) => res,
+                Err(io::Error::WouldBlock) => break, // timed out
+                Err(e) => return Err(e),
+            };
+            if len >= 8 && buf[0] == reply_type && buf[6..8] == seq.to_be_bytes() {
+                return Ok(Some((len, start.elapsed())));
+            }
+        }
+        Ok(None)
+    }
+
+    fn ping(host: &str, count: u16) -> io::Result<()> {
+        let addr: IpAddr = match (host, 0).to_socket_addrs()?.next() {
+            Some(addr) => addr.ip(),
+            None => return Err(io::Error::NotFound),
+        };
+        let socket = IcmpSocket::bind(0)?;
+        socket.set_read_timeout(Some(TIMEOUT))?;
+        let ident = socket.ident()?;
+
+        println!("PING {} ({}): {} data bytes", host, addr, DATA_LEN);
+        let mut received = 0;
+        for seq in 1..=count {
+            let start = Instant::now();
+            socket.send_to(&echo_request(addr.is_ipv6(), ident, seq), addr)?;
+            match wait_reply(&socket, addr.is_ipv6(), seq, start)? {
+                Some((len, rtt)) => {
+                    received += 1;
+                    let ms = rtt.as_secs_f64() * 1000.0;
+                    println!(
+                        "{} bytes from {}: icmp_seq={} time={:.3} ms",
+                        len, addr, seq, ms
+                    );
+                }
+                None => println!("Request timeout for icmp_seq {}", seq),
+            }
+            if seq < count {
+                std::thread::sleep(TIMEOUT.saturating_sub(start.elapsed()));
+            }
+        }
+
+        let loss = (count - received) as u32 * 100 / count as u32;
+        println!("--- {} ping statistics ---", host);
+        println!(
+            "{} packets transmitted, {} packets received, {}% packet loss",
+            count, received, loss
+        );
+        Ok(())
+    }
+
+    let mut count = DEFAULT_COUNT;
+    let mut host = None;
+    let mut args = args.split_whitespace();
+    while let Some(arg) = args.next() {
+        if arg == "-c" {
+            match args.next().and_then(|n| n.parse().ok()) {
+                Some(n) if n > 0 => count = n,
+                _ => {
+                    print_err!("ping", "invalid count");
+                    return;
+                }
+            }
+        } else if host.is_none() {
+            host = Some(arg);
+        } else {
+            print_err!("ping", "too many arguments");
+            return;
+        }
+    }
+    match host {
+        Some(host) => {
+            if let Err(e) = ping(host, count) {
+                print_err!("ping", host, e);
+            }
+        }
+        None => print_err!("ping", "missing host operand"),
+    }
+}
+
+#[cfg(not(feature = "net"))]
+fn do_ping(_args: &str) {
+    print_err!("ping", "networking is not enabled");
+}
+
 fn do_help(_args: &str) {
     println!("Available commands:");
     for (name, _) in CMD_TABLE {
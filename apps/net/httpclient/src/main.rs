@@ -8,7 +8,9 @@ extern crate axstd as std;
 use std::io::{self, prelude::*};
 use std::net::{TcpStream, ToSocketAddrs};
 
-#[cfg(feature = "dns")]
+#[cfg(feature = "tls")]
+const DEST: &str = "ident.me:443";
+#[cfg(all(feature = "dns", not(feature = "tls")))]
 const DEST: &str = "ident.me:80";
 #[cfg(not(feature = "dns"))]
 const DEST: &str = "49.12.234.183:80";
@@ -24,7 +26,18 @@ fn client() -> io::Result<()> {
         println!("dest: {} ({})", DEST, addr);
     }
 
-    let mut stream = TcpStream::connect(DEST)?;
+    let stream = TcpStream::connect(DEST)?;
+    #[cfg(feature = "tls")]
+    let mut stream = {
+        // the certificate verification needs the current time, e.g. built
+        // with `AX_UNIX_TIME=$(date +%s)`
+        if let Some(secs) = option_env!("AX_UNIX_TIME").and_then(|s| s.parse().ok()) {
+            std::net::tls::set_unix_time(std::time::Duration::from_secs(secs));
+        }
+        std::net::tls::TlsConnector::new()?.connect("ident.me", stream)?
+    };
+    #[cfg(not(feature = "tls"))]
+    let mut stream = stream;
     stream.write_all(REQUEST.as_bytes())?;
     let mut buf = [0; 2048];
     let n = stream.read(&mut buf)?;
@@ -5,6 +5,15 @@
 //! ```
 //! ab -n 5000 -c 20 http://X.X.X.X:5555/
 //! ```
+//!
+//! With the `tls` feature, it serves HTTPS with the certificate chain and the
+//! private key in `/etc/tls/cert.pem` and `/etc/tls/key.pem` of the disk
+//! image, e.g. generated by:
+//!
+//! ```
+//! openssl req -x509 -newkey ec -pkeyopt ec_paramgen_curve:P-256 -nodes -days 365 \
+//!     -subj /CN=arceos -keyout key.pem -out cert.pem
+//! ```
 
 #![cfg_attr(feature = "axstd", no_std)]
 #![cfg_attr(feature = "axstd", no_main)]
@@ -14,12 +23,17 @@
 extern crate axstd as std;
 
 use std::io::{self, prelude::*};
-use std::net::{TcpListener, TcpStream};
+use std::net::TcpListener;
 use std::thread;
 
 const LOCAL_IP: &str = "0.0.0.0";
 const LOCAL_PORT: u16 = 5555;
 
+#[cfg(feature = "tls")]
+const CERT_PATH: &str = "/etc/tls/cert.pem";
+#[cfg(feature = "tls")]
+const KEY_PATH: &str = "/etc/tls/key.pem";
+
 macro_rules! header {
     () => {
         "\
@@ -59,7 +73,7 @@ macro_rules! info {
     };
 }
 
-fn http_server(mut stream: TcpStream) -> io::Result<()> {
+fn http_server(mut stream: impl Read + Write) -> io::Result<()> {
     let mut buf = [0u8; 4096];
     let _len = stream.read(&mut buf)?;
 
@@ -71,6 +85,11 @@ fn http_server(mut stream: TcpStream) -> io::Result<()> {
 
 fn accept_loop() -> io::Result<()> {
     let listener = TcpListener::bind((LOCAL_IP, LOCAL_PORT))?;
+    #[cfg(feature = "tls")]
+    let acceptor = std::net::tls::TlsAcceptor::from_pem_files(CERT_PATH, KEY_PATH)?;
+    #[cfg(feature = "tls")]
+    println!("listen on: https://{}/", listener.local_addr().unwrap());
+    #[cfg(not(feature = "tls"))]
     println!("listen on: http://{}/", listener.local_addr().unwrap());
 
     let mut i = 0;
@@ -78,9 +97,17 @@ fn accept_loop() -> io::Result<()> {
         match listener.accept() {
             Ok((stream, addr)) => {
                 info!("new client {}: {}", i, addr);
-                thread::spawn(move || match http_server(stream) {
-                    Err(e) => info!("client connection error: {:?}", e),
-                    Ok(()) => info!("client {} closed successfully", i),
+                #[cfg(feature = "tls")]
+                let acceptor = acceptor.clone();
+                thread::spawn(move || {
+                    #[cfg(feature = "tls")]
+                    let res = acceptor.accept(stream).and_then(http_server);
+                    #[cfg(not(feature = "tls"))]
+                    let res = http_server(stream);
+                    match res {
+                        Err(e) => info!("client connection error: {:?}", e),
+                        Ok(()) => info!("client {} closed successfully", i),
+                    }
                 });
             }
             Err(e) => return Err(e),
@@ -7,6 +7,7 @@ extern crate axstd as std;
 
 use rand::{rngs::SmallRng, RngCore, SeedableRng};
 use std::thread;
+use std::time::Instant;
 use std::{sync::Arc, vec::Vec};
 
 #[cfg(feature = "axstd")]
@@ -17,17 +18,9 @@ const NUM_TASKS: usize = 16;
 
 #[cfg(feature = "axstd")]
 fn barrier() {
-    use std::sync::atomic::{AtomicUsize, Ordering};
-    static BARRIER_WQ: AxWaitQueueHandle = AxWaitQueueHandle::new();
-    static BARRIER_COUNT: AtomicUsize = AtomicUsize::new(0);
-
-    BARRIER_COUNT.fetch_add(1, Ordering::Relaxed);
-    api::ax_wait_queue_wait(
-        &BARRIER_WQ,
-        || BARRIER_COUNT.load(Ordering::Relaxed) == NUM_TASKS,
-        None,
-    );
-    api::ax_wait_queue_wake(&BARRIER_WQ, u32::MAX); // wakeup all
+    use std::sync::Barrier;
+    static BARRIER: Barrier = Barrier::new(NUM_TASKS);
+    BARRIER.wait();
 }
 
 #[cfg(not(feature = "axstd"))]
@@ -68,6 +61,7 @@ fn main() {
         assert!(timeout);
     }
 
+    let start = Instant::now();
     let mut tasks = Vec::with_capacity(NUM_TASKS);
     for i in 0..NUM_TASKS {
         let vec = vec.clone();
@@ -92,6 +86,7 @@ fn main() {
 
     let actual = tasks.into_iter().map(|t| t.join().unwrap()).sum();
     println!("sum = {}", actual);
+    println!("elapsed: {:?}", start.elapsed());
     assert_eq!(expect, actual);
 
     println!("Parallel summation tests run OK!");
@@ -5,7 +5,8 @@
 #[cfg(feature = "axstd")]
 extern crate axstd as std;
 
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
 use std::{thread, time};
 use std::{vec, vec::Vec};
 
@@ -121,5 +122,82 @@ fn main() {
 
     assert_eq!(expect, actual);
 
+    test_priority_inversion();
+
     println!("Priority tests run OK!");
 }
+
+#[cfg(feature = "axstd")]
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<T> {
+    mutex.lock()
+}
+
+#[cfg(not(feature = "axstd"))]
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<T> {
+    mutex.lock().unwrap()
+}
+
+/// A low priority thread holds a lock that a high priority thread is waiting
+/// for, while medium priority threads keep the CPU busy. The low priority
+/// thread inherits the priority of the waiter, so the high priority thread is
+/// not delayed until the medium ones finish.
+fn test_priority_inversion() {
+    const NUM_MEDIUM: usize = 3;
+    const VALUE: u64 = 1000000;
+
+    static LOCK: Mutex<u64> = Mutex::new(0);
+    static LOCKED: AtomicBool = AtomicBool::new(false);
+
+    let start_time = time::Instant::now();
+    let low = thread::spawn(|| {
+        #[cfg(feature = "axstd")]
+        ax_set_current_priority(19).ok();
+
+        let mut sum = lock(&LOCK);
+        LOCKED.store(true, Ordering::Release);
+        *sum = (0..4).map(|_| load(&VALUE)).sum();
+        println!("priority inversion: low finished");
+    });
+    while !LOCKED.load(Ordering::Acquire) {
+        thread::yield_now();
+    }
+
+    let mediums = (0..NUM_MEDIUM)
+        .map(|_| {
+            thread::spawn(move || {
+                #[cfg(feature = "axstd")]
+                ax_set_current_priority(0).ok();
+
+                std::hint::black_box((0..40).map(|_| load(&VALUE)).sum::<u64>());
+                start_time.elapsed().as_millis() as u64
+            })
+        })
+        .collect::<Vec<_>>();
+    let high = thread::spawn(move || {
+        #[cfg(feature = "axstd")]
+        ax_set_current_priority(-20).ok();
+
+        let sum = *lock(&LOCK);
+        println!("priority inversion: high finished");
+        (sum, start_time.elapsed().as_millis() as u64)
+    });
+
+    low.join().unwrap();
+    let (sum, high_time) = high.join().unwrap();
+    let medium_times = mediums
+        .into_iter()
+        .map(|t| t.join().unwrap())
+        .collect::<Vec<_>>();
+    println!(
+        "priority inversion: high = {}ms, medium = {:?}ms",
+        high_time, medium_times
+    );
+
+    #[cfg(feature = "axstd")]
+    if cfg!(feature = "sched_cfs") && option_env!("AX_SMP") == Some("1") {
+        assert!(medium_times.iter().all(|&t| t > high_time));
+    }
+
+    assert_eq!(sum, 4 * load(&VALUE));
+    println!("Priority inversion test OK!");
+}
@@ -4,7 +4,7 @@
 
 use core::ptr::NonNull;
 
-use crate::{TriggerMode, GIC_MAX_IRQ, SPI_RANGE};
+use crate::{TriggerMode, GIC_MAX_IRQ, SGI_RANGE, SPI_RANGE};
 use tock_registers::interfaces::{Readable, Writeable};
 use tock_registers::register_structs;
 use tock_registers::registers::{ReadOnly, ReadWrite, WriteOnly};
@@ -177,6 +177,17 @@ impl GicDistributor {
         }
     }
 
+    /// Sends the SGI `sgi_num` to the CPU interface `dest_cpu_id`.
+    pub fn send_sgi(&mut self, dest_cpu_id: usize, sgi_num: usize) {
+        if sgi_num >= SGI_RANGE.end || dest_cpu_id >= 8 {
+            return;
+        }
+        // TargetListFilter = 0b00: forward to the CPUs in CPUTargetList
+        self.regs()
+            .SGIR
+            .set(((1 << dest_cpu_id) << 16) | sgi_num as u32);
+    }
+
     /// Initializes the GIC distributor.
     ///
     /// It disables all interrupts, sets the target of all SPIs to CPU 0,
@@ -85,6 +85,12 @@ pub enum AxError {
     /// An error returned when an operation could not be completed because a
     /// call to `write()` returned [`Ok(0)`](Ok).
     WriteZero,
+    /// The requested address is not available, e.g., no local address can be
+    /// used to reach the destination.
+    AddrNotAvailable,
+    /// The operation was interrupted by signals or cancellation before it
+    /// completed.
+    Interrupted,
 }
 
 /// A specialized [`Result`] type with [`AxError`] as the error type.
@@ -210,6 +216,8 @@ impl AxError {
             Unsupported => "Operation not supported",
             WouldBlock => "Operation would block",
             WriteZero => "Write zero",
+            AddrNotAvailable => "Address not available",
+            Interrupted => "Operation interrupted",
         }
     }
 
@@ -261,6 +269,8 @@ impl From<AxError> for LinuxError {
             Unsupported => LinuxError::ENOSYS,
             UnexpectedEof | WriteZero => LinuxError::EIO,
             WouldBlock => LinuxError::EAGAIN,
+            AddrNotAvailable => LinuxError::EADDRNOTAVAIL,
+            Interrupted => LinuxError::EINTR,
         }
     }
 }
@@ -283,13 +293,15 @@ mod tests {
     #[test]
     fn test_try_from() {
         let max_code = core::mem::variant_count::<AxError>() as i32;
-        assert_eq!(max_code, 22);
-        assert_eq!(max_code, AxError::WriteZero.code());
+        assert_eq!(max_code, 24);
+        assert_eq!(max_code, AxError::Interrupted.code());
 
         assert_eq!(AxError::AddrInUse.code(), 1);
         assert_eq!(Ok(AxError::AddrInUse), AxError::try_from(1));
         assert_eq!(Ok(AxError::AlreadyExists), AxError::try_from(2));
-        assert_eq!(Ok(AxError::WriteZero), AxError::try_from(max_code));
+        assert_eq!(Ok(AxError::WriteZero), AxError::try_from(22));
+        assert_eq!(Ok(AxError::AddrNotAvailable), AxError::try_from(23));
+        assert_eq!(Ok(AxError::Interrupted), AxError::try_from(max_code));
         assert_eq!(Err(max_code + 1), AxError::try_from(max_code + 1));
         assert_eq!(Err(0), AxError::try_from(0));
         assert_eq!(Err(-1), AxError::try_from(-1));
@@ -0,0 +1,77 @@
+use axfs_vfs::{VfsNodeAttr, VfsNodeOps, VfsNodePerm, VfsNodeType, VfsResult};
+use core::sync::atomic::{AtomicU64, Ordering};
+
+/// A random device behaves like `/dev/urandom` and `/dev/random`.
+///
+/// It returns pseudo-random bytes generated by a xorshift generator when read,
+/// and all writes are discarded. It is **not** cryptographically secure.
+pub struct RandomDev {
+    state: AtomicU64,
+}
+
+impl RandomDev {
+    /// Create a new random device with the given seed.
+    pub const fn new(seed: u64) -> Self {
+        // xorshift never leaves the all-zero state, so avoid it
+        let seed = if seed == 0 {
+            0x2545_f491_4f6c_dd1d
+        } else {
+            seed
+        };
+        Self {
+            state: AtomicU64::new(seed),
+        }
+    }
+
+    fn next_u64(&self) -> u64 {
+        let mut x = self.state.load(Ordering::Relaxed);
+        loop {
+            let mut next = x;
+            next ^= next << 13;
+            next ^= next >> 7;
+            next ^= next << 17;
+            match self
+                .state
+                .compare_exchange_weak(x, next, Ordering::Relaxed, Ordering::Relaxed)
+            {
+                Ok(_) => return next,
+                Err(cur) => x = cur,
+            }
+        }
+    }
+}
+
+impl Default for RandomDev {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl VfsNodeOps for RandomDev {
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        Ok(VfsNodeAttr::new(
+            VfsNodePerm::default_file(),
+            VfsNodeType::CharDevice,
+            0,
+            0,
+        ))
+    }
+
+    fn read_at(&self, _offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        for chunk in buf.chunks_mut(8) {
+            let bytes = self.next_u64().to_ne_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+        Ok(buf.len())
+    }
+
+    fn write_at(&self, _offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        Ok(buf.len())
+    }
+
+    fn truncate(&self, _size: u64) -> VfsResult {
+        Ok(())
+    }
+
+    axfs_vfs::impl_vfs_non_dir_default! {}
+}
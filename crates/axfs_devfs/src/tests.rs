@@ -37,6 +37,16 @@ fn test_devfs_ops(devfs: &DeviceFileSystem) -> VfsResult {
     assert_eq!(buf, [0; N]);
     assert_eq!(node.write_at(0, &buf)?, N);
 
+    let node = devfs.root_dir().lookup("random")?;
+    assert_eq!(node.get_attr()?.file_type(), VfsNodeType::CharDevice);
+    assert_eq!(node.get_attr()?.size(), 0);
+    let mut buf2 = [0; N];
+    assert_eq!(node.read_at(0, &mut buf)?, N);
+    assert_eq!(node.read_at(0, &mut buf2[..N - 3])?, N - 3);
+    assert_ne!(buf, [0; N]);
+    assert_ne!(buf[..N - 3], buf2[..N - 3]);
+    assert_eq!(node.write_at(0, &buf)?, N);
+
     let foo = devfs.root_dir().lookup(".///.//././/.////foo")?;
     assert!(foo.get_attr()?.is_dir());
     assert_eq!(
@@ -99,11 +109,13 @@ fn test_devfs() {
     // │   │   └── f1 (null)
     // │   └── f2 (zero)
     // ├── null
+    // ├── random
     // └── zero
 
     let devfs = DeviceFileSystem::new();
     devfs.add("null", Arc::new(NullDev));
     devfs.add("zero", Arc::new(ZeroDev));
+    devfs.add("random", Arc::new(RandomDev::new(42)));
 
     let dir_foo = devfs.mkdir("foo");
     dir_foo.add("f2", Arc::new(ZeroDev));
@@ -7,6 +7,7 @@ use axfs_vfs::{VfsError, VfsResult};
 use spin::RwLock;
 
 use crate::file::FileNode;
+use crate::storage::Storage;
 
 /// The directory node in the RAM filesystem.
 ///
@@ -15,14 +16,16 @@ pub struct DirNode {
     this: Weak<DirNode>,
     parent: RwLock<Weak<dyn VfsNodeOps>>,
     children: RwLock<BTreeMap<String, VfsNodeRef>>,
+    storage: Arc<Storage>,
 }
 
 impl DirNode {
-    pub(super) fn new(parent: Option<Weak<dyn VfsNodeOps>>) -> Arc<Self> {
+    pub(super) fn new(parent: Option<Weak<dyn VfsNodeOps>>, storage: Arc<Storage>) -> Arc<Self> {
         Arc::new_cyclic(|this| Self {
             this: this.clone(),
             parent: RwLock::new(parent.unwrap_or_else(|| Weak::<Self>::new())),
             children: RwLock::new(BTreeMap::new()),
+            storage,
         })
     }
 
@@ -46,10 +49,13 @@ impl DirNode {
             log::error!("AlreadyExists {}", name);
             return Err(VfsError::AlreadyExists);
         }
+        if !matches!(ty, VfsNodeType::File | VfsNodeType::Dir) {
+            return Err(VfsError::Unsupported);
+        }
+        self.storage.charge_node()?;
         let node: VfsNodeRef = match ty {
-            VfsNodeType::File => Arc::new(FileNode::new()),
-            VfsNodeType::Dir => Self::new(Some(self.this.clone())),
-            _ => return Err(VfsError::Unsupported),
+            VfsNodeType::File => Arc::new(FileNode::new(self.storage.clone())),
+            _ => Self::new(Some(self.this.clone()), self.storage.clone()),
         };
         self.children.write().insert(name.into(), node);
         Ok(())
@@ -168,6 +174,12 @@ impl VfsNodeOps for DirNode {
     axfs_vfs::impl_vfs_dir_default! {}
 }
 
+impl Drop for DirNode {
+    fn drop(&mut self) {
+        self.storage.uncharge_node();
+    }
+}
+
 fn split_path(path: &str) -> (&str, Option<&str>) {
     let trimmed_path = path.trim_start_matches('/');
     trimmed_path.find('/').map_or((trimmed_path, None), |n| {
@@ -1,56 +1,229 @@
-use alloc::vec::Vec;
+use alloc::collections::btree_map::{BTreeMap, Entry};
+use alloc::{sync::Arc, vec::Vec};
 use axfs_vfs::{impl_vfs_non_dir_default, VfsNodeAttr, VfsNodeOps, VfsResult};
 use spin::RwLock;
 
+use crate::storage::{Page, Storage, PAGE_SIZE};
+
+/// Files not larger than this are stored in a contiguous buffer, larger files
+/// are stored in separate pages.
+const INLINE_MAX_SIZE: usize = PAGE_SIZE;
+
+enum Content {
+    /// Contents of small files.
+    Inline(Vec<u8>),
+    /// Contents of large files, indexed by the page number. Unallocated pages
+    /// (holes) are read as zeros.
+    Paged {
+        pages: BTreeMap<usize, Page>,
+        size: usize,
+    },
+}
+
 /// The file node in the RAM filesystem.
 ///
 /// It implements [`axfs_vfs::VfsNodeOps`].
 pub struct FileNode {
-    content: RwLock<Vec<u8>>,
+    storage: Arc<Storage>,
+    content: RwLock<Content>,
 }
 
 impl FileNode {
-    pub(super) const fn new() -> Self {
+    pub(super) fn new(storage: Arc<Storage>) -> Self {
         Self {
-            content: RwLock::new(Vec::new()),
+            storage,
+            content: RwLock::new(Content::Inline(Vec::new())),
+        }
+    }
+
+    /// Moves the contents of a small file into pages.
+    fn convert_to_paged(&self, content: &mut Content) -> VfsResult {
+        let Content::Inline(data) = content else {
+            return Ok(());
+        };
+        let num_pages = data.len().div_ceil(PAGE_SIZE);
+        self.storage
+            .charge_bytes(num_pages * PAGE_SIZE - data.len())?;
+
+        let mut pages = BTreeMap::new();
+        for (i, chunk) in data.chunks(PAGE_SIZE).enumerate() {
+            match self.storage.alloc_page() {
+                Ok(mut page) => {
+                    page.as_slice_mut()[..chunk.len()].copy_from_slice(chunk);
+                    pages.insert(i, page);
+                }
+                Err(e) => {
+                    for page in pages.into_values() {
+                        self.storage.dealloc_page(page);
+                    }
+                    self.storage
+                        .uncharge_bytes(num_pages * PAGE_SIZE - data.len());
+                    return Err(e);
+                }
+            }
+        }
+        *content = Content::Paged {
+            pages,
+            size: data.len(),
+        };
+        Ok(())
+    }
+}
+
+impl Content {
+    fn size(&self) -> usize {
+        match self {
+            Self::Inline(data) => data.len(),
+            Self::Paged { size, .. } => *size,
+        }
+    }
+
+    fn allocated_bytes(&self) -> usize {
+        match self {
+            Self::Inline(data) => data.len(),
+            Self::Paged { pages, .. } => pages.len() * PAGE_SIZE,
         }
     }
 }
 
 impl VfsNodeOps for FileNode {
     fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
-        Ok(VfsNodeAttr::new_file(self.content.read().len() as _, 0))
+        let content = self.content.read();
+        let blocks = (content.allocated_bytes() as u64).div_ceil(512);
+        Ok(VfsNodeAttr::new_file(content.size() as _, blocks))
     }
 
     fn truncate(&self, size: u64) -> VfsResult {
+        let size = size as usize;
         let mut content = self.content.write();
-        if size < content.len() as u64 {
-            content.truncate(size as _);
-        } else {
-            content.resize(size as _, 0);
+        if let Content::Inline(data) = &mut *content {
+            if size <= INLINE_MAX_SIZE {
+                if size > data.len() {
+                    self.storage.charge_bytes(size - data.len())?;
+                } else {
+                    self.storage.uncharge_bytes(data.len() - size);
+                }
+                data.resize(size, 0);
+                return Ok(());
+            }
+            self.convert_to_paged(&mut content)?;
+        }
+
+        let Content::Paged {
+            pages,
+            size: old_size,
+        } = &mut *content
+        else {
+            unreachable!()
+        };
+        if size < *old_size {
+            for page in pages.split_off(&size.div_ceil(PAGE_SIZE)).into_values() {
+                self.storage.dealloc_page(page);
+                self.storage.uncharge_bytes(PAGE_SIZE);
+            }
+            // clear the tail, so that it will be read as zeros when extended
+            if !size.is_multiple_of(PAGE_SIZE) {
+                if let Some(page) = pages.get_mut(&(size / PAGE_SIZE)) {
+                    page.as_slice_mut()[size % PAGE_SIZE..].fill(0);
+                }
+            }
+        }
+        *old_size = size;
+        if size == 0 {
+            *content = Content::Inline(Vec::new());
         }
         Ok(())
     }
 
     fn read_at(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
         let content = self.content.read();
-        let start = content.len().min(offset as usize);
-        let end = content.len().min(offset as usize + buf.len());
-        let src = &content[start..end];
-        buf[..src.len()].copy_from_slice(src);
-        Ok(src.len())
+        let start = content.size().min(offset as usize);
+        let end = content.size().min(offset as usize + buf.len());
+        match &*content {
+            Content::Inline(data) => {
+                let src = &data[start..end];
+                buf[..src.len()].copy_from_slice(src);
+            }
+            Content::Paged { pages, .. } => {
+                let mut pos = start;
+                while pos < end {
+                    let page_off = pos % PAGE_SIZE;
+                    let len = (PAGE_SIZE - page_off).min(end - pos);
+                    let dst = &mut buf[pos - start..pos - start + len];
+                    match pages.get(&(pos / PAGE_SIZE)) {
+                        Some(page) => {
+                            dst.copy_from_slice(&page.as_slice()[page_off..page_off + len])
+                        }
+                        None => dst.fill(0),
+                    }
+                    pos += len;
+                }
+            }
+        }
+        Ok(end - start)
     }
 
     fn write_at(&self, offset: u64, buf: &[u8]) -> VfsResult<usize> {
         let offset = offset as usize;
+        let end = offset + buf.len();
         let mut content = self.content.write();
-        if offset + buf.len() > content.len() {
-            content.resize(offset + buf.len(), 0);
+        if let Content::Inline(data) = &mut *content {
+            if end <= INLINE_MAX_SIZE {
+                if end > data.len() {
+                    self.storage.charge_bytes(end - data.len())?;
+                    data.resize(end, 0);
+                }
+                data[offset..end].copy_from_slice(buf);
+                return Ok(buf.len());
+            }
+            self.convert_to_paged(&mut content)?;
         }
-        let dst = &mut content[offset..offset + buf.len()];
-        dst.copy_from_slice(&buf[..dst.len()]);
-        Ok(buf.len())
+
+        let Content::Paged { pages, size } = &mut *content else {
+            unreachable!()
+        };
+        let mut pos = offset;
+        while pos < end {
+            let page_off = pos % PAGE_SIZE;
+            let len = (PAGE_SIZE - page_off).min(end - pos);
+            let page = match pages.entry(pos / PAGE_SIZE) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    let res = self.storage.charge_bytes(PAGE_SIZE).and_then(|_| {
+                        self.storage
+                            .alloc_page()
+                            .inspect_err(|_| self.storage.uncharge_bytes(PAGE_SIZE))
+                    });
+                    match res {
+                        Ok(page) => entry.insert(page),
+                        Err(e) if pos == offset => return Err(e),
+                        Err(_) => break, // a short write
+                    }
+                }
+            };
+            page.as_slice_mut()[page_off..page_off + len]
+                .copy_from_slice(&buf[pos - offset..pos - offset + len]);
+            pos += len;
+            *size = (*size).max(pos);
+        }
+        Ok(pos - offset)
     }
 
     impl_vfs_non_dir_default! {}
 }
+
+impl Drop for FileNode {
+    fn drop(&mut self) {
+        let content = core::mem::replace(self.content.get_mut(), Content::Inline(Vec::new()));
+        match content {
+            Content::Inline(data) => self.storage.uncharge_bytes(data.len()),
+            Content::Paged { pages, .. } => {
+                for page in pages.into_values() {
+                    self.storage.dealloc_page(page);
+                    self.storage.uncharge_bytes(PAGE_SIZE);
+                }
+            }
+        }
+        self.storage.uncharge_node();
+    }
+}
@@ -1,6 +1,13 @@
 //! RAM filesystem used by [ArceOS](https://github.com/rcore-os/arceos).
 //!
 //! The implementation is based on [`axfs_vfs`].
+//!
+//! Small files are stored in contiguous buffers, while large files are stored
+//! in separate pages allocated by a [`PageAlloc`]. A filesystem created by
+//! [`RamFileSystem::new_tmpfs`] limits the total size of file contents and the
+//! number of nodes, and fails with [`StorageFull`] when they are exceeded.
+//!
+//! [`StorageFull`]: axfs_vfs::VfsError::StorageFull
 
 #![cfg_attr(not(test), no_std)]
 
@@ -8,29 +15,51 @@ extern crate alloc;
 
 mod dir;
 mod file;
+mod storage;
 
 #[cfg(test)]
 mod tests;
 
 pub use self::dir::DirNode;
 pub use self::file::FileNode;
+pub use self::storage::{HeapPageAlloc, PageAlloc, PAGE_SIZE};
 
 use alloc::sync::Arc;
-use axfs_vfs::{VfsNodeRef, VfsOps, VfsResult};
+use axfs_vfs::{FileSystemInfo, VfsNodeRef, VfsOps, VfsResult};
 use spin::once::Once;
 
+use self::storage::Storage;
+
 /// A RAM filesystem that implements [`axfs_vfs::VfsOps`].
 pub struct RamFileSystem {
     parent: Once<VfsNodeRef>,
     root: Arc<DirNode>,
+    storage: Arc<Storage>,
 }
 
 impl RamFileSystem {
-    /// Create a new instance.
+    /// Create a new instance without size limits.
+    ///
+    /// Pages of large files are allocated by [`HeapPageAlloc`].
     pub fn new() -> Self {
+        Self::with_storage(Storage::unlimited())
+    }
+
+    /// Create a new tmpfs instance, whose file contents can occupy at most
+    /// `max_bytes` bytes, and which can contain at most `max_nodes` files and
+    /// directories (including the root directory).
+    ///
+    /// Pages of large files are allocated by `page_alloc`.
+    pub fn new_tmpfs(max_bytes: usize, max_nodes: usize, page_alloc: Arc<dyn PageAlloc>) -> Self {
+        Self::with_storage(Storage::new(max_bytes, max_nodes, page_alloc))
+    }
+
+    fn with_storage(storage: Storage) -> Self {
+        let storage = Arc::new(storage);
         Self {
             parent: Once::new(),
-            root: DirNode::new(None),
+            root: DirNode::new(None, storage.clone()),
+            storage,
         }
     }
 
@@ -50,6 +79,10 @@ impl VfsOps for RamFileSystem {
         Ok(())
     }
 
+    fn statfs(&self) -> VfsResult<FileSystemInfo> {
+        Ok(self.storage.statfs())
+    }
+
     fn root_dir(&self) -> VfsNodeRef {
         self.root.clone()
     }
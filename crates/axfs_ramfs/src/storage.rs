@@ -0,0 +1,157 @@
+use alloc::alloc::{alloc, dealloc};
+use alloc::sync::Arc;
+use core::alloc::Layout;
+use core::ptr::NonNull;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use axfs_vfs::{FileSystemInfo, VfsError, VfsResult};
+
+/// Size of the pages used to store large file contents.
+pub const PAGE_SIZE: usize = 0x1000;
+
+/// Allocator of the pages used to store large file contents.
+pub trait PageAlloc: Send + Sync {
+    /// Allocates a page of [`PAGE_SIZE`] bytes, aligned to [`PAGE_SIZE`].
+    ///
+    /// Returns `None` if there is no memory.
+    fn alloc_page(&self) -> Option<NonNull<u8>>;
+
+    /// Gives back a page allocated by [`alloc_page`](PageAlloc::alloc_page).
+    ///
+    /// # Safety
+    ///
+    /// The page must be allocated by this allocator and not used any more.
+    unsafe fn dealloc_page(&self, page: NonNull<u8>);
+}
+
+/// A [`PageAlloc`] that allocates pages from the global heap allocator.
+pub struct HeapPageAlloc;
+
+impl HeapPageAlloc {
+    const LAYOUT: Layout = unsafe { Layout::from_size_align_unchecked(PAGE_SIZE, PAGE_SIZE) };
+}
+
+impl PageAlloc for HeapPageAlloc {
+    fn alloc_page(&self) -> Option<NonNull<u8>> {
+        NonNull::new(unsafe { alloc(Self::LAYOUT) })
+    }
+
+    unsafe fn dealloc_page(&self, page: NonNull<u8>) {
+        dealloc(page.as_ptr(), Self::LAYOUT)
+    }
+}
+
+/// The free blocks and nodes reported by a filesystem without limits, large
+/// enough that it never looks full.
+const UNLIMITED_FREE: u64 = 1 << 32;
+
+/// A page used to store file contents.
+pub(crate) struct Page(NonNull<u8>);
+
+unsafe impl Send for Page {}
+unsafe impl Sync for Page {}
+
+impl Page {
+    pub fn as_slice(&self) -> &[u8] {
+        unsafe { core::slice::from_raw_parts(self.0.as_ptr(), PAGE_SIZE) }
+    }
+
+    pub fn as_slice_mut(&mut self) -> &mut [u8] {
+        unsafe { core::slice::from_raw_parts_mut(self.0.as_ptr(), PAGE_SIZE) }
+    }
+}
+
+/// Memory accounting and page allocation shared by all nodes of a filesystem.
+///
+/// The root directory is always counted as a used node.
+pub(crate) struct Storage {
+    max_bytes: usize,
+    max_nodes: usize,
+    used_bytes: AtomicUsize,
+    used_nodes: AtomicUsize,
+    page_alloc: Arc<dyn PageAlloc>,
+}
+
+impl Storage {
+    pub fn new(max_bytes: usize, max_nodes: usize, page_alloc: Arc<dyn PageAlloc>) -> Self {
+        Self {
+            max_bytes,
+            max_nodes,
+            used_bytes: AtomicUsize::new(0),
+            used_nodes: AtomicUsize::new(1),
+            page_alloc,
+        }
+    }
+
+    pub fn unlimited() -> Self {
+        Self::new(usize::MAX, usize::MAX, Arc::new(HeapPageAlloc))
+    }
+
+    fn charge(counter: &AtomicUsize, limit: usize, n: usize) -> VfsResult {
+        let mut used = counter.load(Ordering::Acquire);
+        loop {
+            let new = match used.checked_add(n) {
+                Some(new) if new <= limit => new,
+                _ => return Err(VfsError::StorageFull),
+            };
+            match counter.compare_exchange_weak(used, new, Ordering::AcqRel, Ordering::Acquire) {
+                Ok(_) => return Ok(()),
+                Err(cur) => used = cur,
+            }
+        }
+    }
+
+    /// Reserves `n` bytes, returns [`StorageFull`](VfsError::StorageFull) if
+    /// exceeding the limit.
+    pub fn charge_bytes(&self, n: usize) -> VfsResult {
+        Self::charge(&self.used_bytes, self.max_bytes, n)
+    }
+
+    pub fn uncharge_bytes(&self, n: usize) {
+        self.used_bytes.fetch_sub(n, Ordering::AcqRel);
+    }
+
+    /// Reserves a node, returns [`StorageFull`](VfsError::StorageFull) if
+    /// exceeding the limit.
+    pub fn charge_node(&self) -> VfsResult {
+        Self::charge(&self.used_nodes, self.max_nodes, 1)
+    }
+
+    pub fn uncharge_node(&self) {
+        self.used_nodes.fetch_sub(1, Ordering::AcqRel);
+    }
+
+    /// Allocates a zeroed page, the caller must have charged [`PAGE_SIZE`]
+    /// bytes for it.
+    pub fn alloc_page(&self) -> VfsResult<Page> {
+        let ptr = self.page_alloc.alloc_page().ok_or(VfsError::NoMemory)?;
+        let mut page = Page(ptr);
+        page.as_slice_mut().fill(0);
+        Ok(page)
+    }
+
+    /// Frees a page, the caller should uncharge [`PAGE_SIZE`] bytes for it.
+    pub fn dealloc_page(&self, page: Page) {
+        unsafe { self.page_alloc.dealloc_page(page.0) }
+    }
+
+    pub fn statfs(&self) -> FileSystemInfo {
+        let page_size = PAGE_SIZE as u64;
+        let used_bytes = self.used_bytes.load(Ordering::Acquire) as u64;
+        let used_blocks = used_bytes.div_ceil(page_size);
+        let used_nodes = self.used_nodes.load(Ordering::Acquire) as u64;
+        let (blocks, blocks_free) = if self.max_bytes == usize::MAX {
+            (used_blocks + UNLIMITED_FREE, UNLIMITED_FREE)
+        } else {
+            let blocks = self.max_bytes as u64 / page_size;
+            (blocks, blocks.saturating_sub(used_blocks))
+        };
+        let (files, files_free) = if self.max_nodes == usize::MAX {
+            (used_nodes + UNLIMITED_FREE, UNLIMITED_FREE)
+        } else {
+            let files = self.max_nodes as u64;
+            (files, files - used_nodes)
+        };
+        FileSystemInfo::new(page_size, blocks, blocks_free, files, files_free)
+    }
+}
@@ -118,6 +118,10 @@ fn test_ramfs() {
     test_ramfs_ops(&ramfs).unwrap();
     test_get_parent(&ramfs).unwrap();
 
+    // never full without limits
+    let info = ramfs.statfs().unwrap();
+    assert!(info.blocks_free() > 0 && info.files_free() > 0);
+
     let root = ramfs.root_dir();
     assert_eq!(root.remove("f1"), Ok(()));
     assert_eq!(root.remove("//f2"), Ok(()));
@@ -134,3 +138,98 @@ fn test_ramfs() {
     assert_eq!(root.remove("./foo"), Ok(()));
     assert!(ramfs.root_dir_node().get_entries().is_empty());
 }
+
+#[test]
+fn test_tmpfs() {
+    const MAX_BYTES: usize = PAGE_SIZE * 4;
+    const MAX_NODES: usize = 4;
+
+    let tmpfs = RamFileSystem::new_tmpfs(MAX_BYTES, MAX_NODES, Arc::new(HeapPageAlloc));
+    let info = tmpfs.statfs().unwrap();
+    assert_eq!(info.block_size(), PAGE_SIZE as u64);
+    assert_eq!(info.blocks(), 4);
+    assert_eq!(info.blocks_free(), 4);
+    assert_eq!((info.files(), info.files_free()), (4, 3)); // root directory
+
+    let root = tmpfs.root_dir();
+    root.create("dir", VfsNodeType::Dir).unwrap();
+    root.create("dir/small", VfsNodeType::File).unwrap();
+    root.create("large", VfsNodeType::File).unwrap();
+    assert_eq!(
+        root.create("f", VfsNodeType::File).err(),
+        Some(VfsError::StorageFull)
+    );
+    assert_eq!(tmpfs.statfs().unwrap().files_free(), 0);
+
+    // small files are charged by bytes
+    let small = root.clone().lookup("dir/small").unwrap();
+    assert_eq!(small.write_at(0, &[1; 100]).unwrap(), 100);
+    assert_eq!(tmpfs.statfs().unwrap().blocks_free(), 3);
+
+    // large files are charged by pages, holes are not allocated
+    let large = root.clone().lookup("large").unwrap();
+    let buf = [2; PAGE_SIZE];
+    assert_eq!(
+        large.write_at(PAGE_SIZE as u64 + 5, &buf).unwrap(),
+        PAGE_SIZE
+    );
+    assert_eq!(tmpfs.statfs().unwrap().blocks_free(), 1);
+    assert_eq!(large.get_attr().unwrap().size(), 2 * PAGE_SIZE as u64 + 5);
+    large.truncate(3 * PAGE_SIZE as u64).unwrap();
+    assert_eq!(tmpfs.statfs().unwrap().blocks_free(), 1);
+
+    let mut rbuf = [0xff; PAGE_SIZE * 3];
+    assert_eq!(large.read_at(0, &mut rbuf).unwrap(), 3 * PAGE_SIZE);
+    assert_eq!(rbuf[..PAGE_SIZE + 5], [0; PAGE_SIZE + 5]);
+    assert_eq!(rbuf[PAGE_SIZE + 5..2 * PAGE_SIZE + 5], buf[..]);
+    assert_eq!(rbuf[2 * PAGE_SIZE + 5..], [0; PAGE_SIZE - 5]);
+
+    // exceeds the byte limit, writes as much as possible
+    assert_eq!(
+        large
+            .write_at(3 * PAGE_SIZE as u64, &[3; PAGE_SIZE * 2])
+            .unwrap(),
+        PAGE_SIZE
+    );
+    assert_eq!(large.get_attr().unwrap().size(), 4 * PAGE_SIZE as u64);
+    assert_eq!(
+        large.write_at(4 * PAGE_SIZE as u64, &[3; 1]).err(),
+        Some(VfsError::StorageFull)
+    );
+    assert_eq!(large.get_attr().unwrap().size(), 4 * PAGE_SIZE as u64);
+    assert_eq!(
+        small.write_at(PAGE_SIZE as u64, &[1; PAGE_SIZE]).err(),
+        Some(VfsError::StorageFull)
+    );
+
+    // shrinking frees pages, and the truncated tail is read as zeros
+    large.truncate(PAGE_SIZE as u64 + 6).unwrap();
+    assert_eq!(tmpfs.statfs().unwrap().blocks_free(), 2);
+    large.truncate(PAGE_SIZE as u64 + 7).unwrap();
+    assert_eq!(large.read_at(PAGE_SIZE as u64 + 5, &mut rbuf).unwrap(), 2);
+    assert_eq!(rbuf[..2], [2, 0]);
+
+    // growing by truncate allocates no pages, and it can be shrunk again
+    large.truncate(0).unwrap();
+    let buf = [4; PAGE_SIZE * 2];
+    assert_eq!(large.write_at(0, &buf).unwrap(), 2 * PAGE_SIZE);
+    large.truncate(100000).unwrap();
+    assert_eq!(tmpfs.statfs().unwrap().blocks_free(), 1);
+    large.truncate(50000).unwrap();
+    assert_eq!(large.get_attr().unwrap().size(), 50000);
+    assert_eq!(tmpfs.statfs().unwrap().blocks_free(), 1);
+
+    // a write far away allocates only the pages written
+    assert_eq!(large.write_at(1 << 40, &[5]).unwrap(), 1);
+    assert_eq!(large.get_attr().unwrap().size(), (1 << 40) + 1);
+    assert_eq!(tmpfs.statfs().unwrap().blocks_free(), 0);
+    assert_eq!(large.read_at((1 << 40) - 1, &mut rbuf).unwrap(), 2);
+    assert_eq!(rbuf[..2], [0, 5]);
+
+    // removing files gives back the space and nodes
+    drop((small, large));
+    root.remove("large").unwrap();
+    root.remove("dir/small").unwrap();
+    let info = tmpfs.statfs().unwrap();
+    assert_eq!((info.blocks_free(), info.files_free()), (4, 2));
+}
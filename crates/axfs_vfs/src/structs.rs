@@ -1,8 +1,17 @@
-/// Filesystem attributes.
-///
-/// Currently not used.
-#[non_exhaustive]
-pub struct FileSystemInfo;
+/// Filesystem attributes, similar to `struct statfs` in Linux.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FileSystemInfo {
+    /// Size of each block in bytes.
+    block_size: u64,
+    /// Total number of blocks.
+    blocks: u64,
+    /// Number of free blocks.
+    blocks_free: u64,
+    /// Total number of nodes (files and directories).
+    files: u64,
+    /// Number of free nodes.
+    files_free: u64,
+}
 
 /// Node (file/directory) attributes.
 #[allow(dead_code)]
@@ -196,6 +205,51 @@ impl VfsNodeType {
     }
 }
 
+impl FileSystemInfo {
+    /// Creates a new `FileSystemInfo` with the given block size, total and
+    /// free number of blocks, and total and free number of nodes.
+    pub const fn new(
+        block_size: u64,
+        blocks: u64,
+        blocks_free: u64,
+        files: u64,
+        files_free: u64,
+    ) -> Self {
+        Self {
+            block_size,
+            blocks,
+            blocks_free,
+            files,
+            files_free,
+        }
+    }
+
+    /// Returns the size of each block in bytes.
+    pub const fn block_size(&self) -> u64 {
+        self.block_size
+    }
+
+    /// Returns the total number of blocks.
+    pub const fn blocks(&self) -> u64 {
+        self.blocks
+    }
+
+    /// Returns the number of free blocks.
+    pub const fn blocks_free(&self) -> u64 {
+        self.blocks_free
+    }
+
+    /// Returns the total number of nodes.
+    pub const fn files(&self) -> u64 {
+        self.files
+    }
+
+    /// Returns the number of free nodes.
+    pub const fn files_free(&self) -> u64 {
+        self.files_free
+    }
+}
+
 impl VfsNodeAttr {
     /// Creates a new `VfsNodeAttr` with the given permission mode, type, size
     /// and number of blocks.
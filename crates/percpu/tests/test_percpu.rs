@@ -66,6 +66,7 @@ fn test_percpu() {
         assert_eq!(base + U64.offset(), U64.current_ptr() as usize);
         assert_eq!(base + USIZE.offset(), USIZE.current_ptr() as usize);
         assert_eq!(base + STRUCT.offset(), STRUCT.current_ptr() as usize);
+        assert_eq!(STRUCT.remote_ptr(0), STRUCT.current_ptr());
     }
 
     BOOL.write_current(true);
@@ -99,4 +100,13 @@ fn test_percpu() {
         assert_eq!(s.foo, 0x2333);
         assert_eq!(s.bar, 100);
     });
+
+    #[cfg(not(feature = "sp-naive"))]
+    unsafe {
+        let remote_base = percpu_area_base(1);
+        assert_eq!(remote_base + USIZE.offset(), USIZE.remote_ptr(1) as usize);
+        *(U8.remote_ptr(1) as *mut u8) = 42;
+        assert_eq!(*U8.remote_ref_raw(1), 42);
+        assert_eq!(U8.read_current(), 123);
+    }
 }
@@ -10,9 +10,14 @@ pub struct CFSTask<T> {
     init_vruntime: AtomicIsize,
     delta: AtomicIsize,
     nice: AtomicIsize,
+    /// The nice value inherited from other tasks, or [`NO_INHERITED_NICE`]
+    /// if there is none.
+    inherited_nice: AtomicIsize,
     id: AtomicIsize,
 }
 
+const NO_INHERITED_NICE: isize = isize::MAX;
+
 // https://elixir.bootlin.com/linux/latest/source/include/linux/sched/prio.h
 
 const NICE_RANGE_POS: usize = 19; // MAX_NICE in Linux
@@ -28,6 +33,15 @@ const NICE2WEIGHT_NEG: [isize; NICE_RANGE_NEG + 1] = [
     29154, 36291, 46273, 56483, 71755, 88761,
 ];
 
+/// Returns the load weight of the nice value, which must be in `-20..=19`.
+pub(crate) fn nice_to_weight(nice: isize) -> isize {
+    if nice >= 0 {
+        NICE2WEIGHT_POS[nice as usize]
+    } else {
+        NICE2WEIGHT_NEG[(-nice) as usize]
+    }
+}
+
 impl<T> CFSTask<T> {
     /// new with default values
     pub const fn new(inner: T) -> Self {
@@ -36,17 +50,20 @@ impl<T> CFSTask<T> {
             init_vruntime: AtomicIsize::new(0_isize),
             delta: AtomicIsize::new(0_isize),
             nice: AtomicIsize::new(0_isize),
+            inherited_nice: AtomicIsize::new(NO_INHERITED_NICE),
             id: AtomicIsize::new(0_isize),
         }
     }
 
+    /// Returns the effective nice value, which is the lower one (i.e., the
+    /// higher priority) of its own and the inherited nice value.
+    fn get_nice(&self) -> isize {
+        let inherited = self.inherited_nice.load(Ordering::Acquire);
+        self.nice.load(Ordering::Acquire).min(inherited)
+    }
+
     fn get_weight(&self) -> isize {
-        let nice = self.nice.load(Ordering::Acquire);
-        if nice >= 0 {
-            NICE2WEIGHT_POS[nice as usize]
-        } else {
-            NICE2WEIGHT_NEG[(-nice) as usize]
-        }
+        nice_to_weight(self.get_nice())
     }
 
     fn get_id(&self) -> isize {
@@ -54,7 +71,7 @@ impl<T> CFSTask<T> {
     }
 
     fn get_vruntime(&self) -> isize {
-        if self.nice.load(Ordering::Acquire) == 0 {
+        if self.get_nice() == 0 {
             self.init_vruntime.load(Ordering::Acquire) + self.delta.load(Ordering::Acquire)
         } else {
             self.init_vruntime.load(Ordering::Acquire)
@@ -69,11 +86,22 @@ impl<T> CFSTask<T> {
     // Simple Implementation: no change in vruntime.
     // Only modifying priority of current process is supported currently.
     fn set_priority(&self, nice: isize) {
+        self.rebase_vruntime();
+        self.nice.store(nice, Ordering::Release);
+    }
+
+    fn set_inherited_nice(&self, nice: isize) {
+        self.rebase_vruntime();
+        self.inherited_nice.store(nice, Ordering::Release);
+    }
+
+    /// Saves the current vruntime before the weight is changed, so the
+    /// vruntime (and the key in the ready queue) is kept unchanged.
+    fn rebase_vruntime(&self) {
         let current_init_vruntime = self.get_vruntime();
         self.init_vruntime
             .store(current_init_vruntime, Ordering::Release);
         self.delta.store(0, Ordering::Release);
-        self.nice.store(nice, Ordering::Release);
     }
 
     fn set_id(&self, id: isize) {
@@ -187,4 +215,23 @@ impl<T> BaseScheduler for CFScheduler<T> {
             false
         }
     }
+
+    fn inherit_priority(
+        &mut self,
+        task: &Self::SchedItem,
+        donor: Option<&Self::SchedItem>,
+    ) -> bool {
+        match donor {
+            Some(donor) if donor.get_nice() < task.get_nice() => {
+                task.set_inherited_nice(donor.get_nice());
+                true
+            }
+            Some(_) => false,
+            None if task.inherited_nice.load(Ordering::Acquire) != NO_INHERITED_NICE => {
+                task.set_inherited_nice(NO_INHERITED_NICE);
+                true
+            }
+            None => false,
+        }
+    }
 }
@@ -0,0 +1,389 @@
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::sync::Arc;
+use core::ops::Deref;
+use core::sync::atomic::{AtomicIsize, AtomicU64, AtomicU8, AtomicUsize, Ordering};
+
+use crate::cfs::nice_to_weight;
+use crate::rt::PrioQueues;
+use crate::{BaseScheduler, RT_PRIO_LEVELS};
+
+/// The virtual runtime that a task with nice value 0 gains in one tick.
+const NICE_0_VRUNTIME: u64 = 1 << 10;
+
+/// Scheduling policies of the tasks in the [`ClassScheduler`], with their
+/// parameters.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum SchedPolicy {
+    /// Real-time class, FIFO among the tasks with the same priority, like
+    /// `SCHED_FIFO` in POSIX. The priority must be less than [`RT_PRIO_LEVELS`].
+    Fifo(usize),
+    /// Real-time class, round-robin among the tasks with the same priority,
+    /// like `SCHED_RR` in POSIX. The priority must be less than
+    /// [`RT_PRIO_LEVELS`].
+    RoundRobin(usize),
+    /// Fair class with the given nice value in `-20..=19`, like `SCHED_OTHER`
+    /// in POSIX.
+    Normal(isize),
+    /// Idle class, runs only when there are no other ready tasks, like
+    /// `SCHED_IDLE` in Linux.
+    Idle,
+}
+
+const POLICY_FIFO: u8 = 0;
+const POLICY_RR: u8 = 1;
+const POLICY_NORMAL: u8 = 2;
+const POLICY_IDLE: u8 = 3;
+
+/// The class that a task is scheduled in, in the order of precedence.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum SchedClass {
+    RealTime(usize),
+    Fair,
+    Idle,
+}
+
+const CLASS_RT: u8 = 0;
+const CLASS_FAIR: u8 = 1;
+const CLASS_IDLE: u8 = 2;
+
+/// A task wrapper for the [`ClassScheduler`].
+///
+/// It adds the scheduling policy and the states of all classes, so that the
+/// task can be moved between classes at any time.
+pub struct ClassTask<T, const MAX_TIME_SLICE: usize> {
+    inner: T,
+    policy: AtomicU8,
+    rt_prio: AtomicUsize,
+    nice: AtomicIsize,
+    /// The real-time priority inherited from other tasks, or -1 if there is
+    /// none.
+    inherited_prio: AtomicIsize,
+    /// The class and the real-time level of the queue where the task is.
+    queued_class: AtomicU8,
+    queued_prio: AtomicUsize,
+    time_slice: AtomicIsize,
+    vruntime: AtomicU64,
+    id: AtomicU64,
+}
+
+impl<T, const S: usize> ClassTask<T, S> {
+    /// Creates a new [`ClassTask`] from the inner task struct, with the
+    /// [`SchedPolicy::Normal`] policy and nice value 0.
+    pub const fn new(inner: T) -> Self {
+        Self {
+            inner,
+            policy: AtomicU8::new(POLICY_NORMAL),
+            rt_prio: AtomicUsize::new(0),
+            nice: AtomicIsize::new(0),
+            inherited_prio: AtomicIsize::new(-1),
+            queued_class: AtomicU8::new(CLASS_FAIR),
+            queued_prio: AtomicUsize::new(0),
+            time_slice: AtomicIsize::new(S as isize),
+            vruntime: AtomicU64::new(0),
+            id: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns the scheduling policy.
+    pub fn policy(&self) -> SchedPolicy {
+        match self.policy.load(Ordering::Acquire) {
+            POLICY_FIFO => SchedPolicy::Fifo(self.rt_prio.load(Ordering::Acquire)),
+            POLICY_RR => SchedPolicy::RoundRobin(self.rt_prio.load(Ordering::Acquire)),
+            POLICY_NORMAL => SchedPolicy::Normal(self.nice.load(Ordering::Acquire)),
+            _ => SchedPolicy::Idle,
+        }
+    }
+
+    /// Returns the virtual runtime, which is only meaningful in the fair
+    /// class.
+    pub fn vruntime(&self) -> u64 {
+        self.vruntime.load(Ordering::Acquire)
+    }
+
+    /// Returns the class the task should be scheduled in. A task with an
+    /// inherited priority is always scheduled in the real-time class.
+    fn class(&self) -> SchedClass {
+        let inherited = self.inherited_prio.load(Ordering::Acquire);
+        match self.policy() {
+            SchedPolicy::Fifo(prio) | SchedPolicy::RoundRobin(prio) => {
+                SchedClass::RealTime(prio.max(inherited.max(0) as usize))
+            }
+            _ if inherited >= 0 => SchedClass::RealTime(inherited as usize),
+            SchedPolicy::Normal(_) => SchedClass::Fair,
+            SchedPolicy::Idle => SchedClass::Idle,
+        }
+    }
+
+    fn set_policy(&self, policy: SchedPolicy) {
+        let tag = match policy {
+            SchedPolicy::Fifo(prio) => {
+                self.rt_prio.store(prio, Ordering::Release);
+                POLICY_FIFO
+            }
+            SchedPolicy::RoundRobin(prio) => {
+                self.rt_prio.store(prio, Ordering::Release);
+                POLICY_RR
+            }
+            SchedPolicy::Normal(nice) => {
+                self.nice.store(nice, Ordering::Release);
+                POLICY_NORMAL
+            }
+            SchedPolicy::Idle => POLICY_IDLE,
+        };
+        self.policy.store(tag, Ordering::Release);
+        self.reset_time_slice();
+    }
+
+    /// Whether the task is preempted when its time slice is used up.
+    fn is_time_sliced(&self) -> bool {
+        matches!(
+            (self.policy(), self.class()),
+            (SchedPolicy::RoundRobin(_), _) | (SchedPolicy::Idle, SchedClass::Idle)
+        )
+    }
+
+    fn time_slice(&self) -> isize {
+        self.time_slice.load(Ordering::Acquire)
+    }
+
+    fn reset_time_slice(&self) {
+        self.time_slice.store(S as isize, Ordering::Release);
+    }
+
+    fn key(&self) -> (u64, u64) {
+        (self.vruntime(), self.id.load(Ordering::Acquire))
+    }
+
+    /// Returns a reference to the inner task struct.
+    pub const fn inner(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T, const S: usize> Deref for ClassTask<T, S> {
+    type Target = T;
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+/// A hierarchical scheduler that dispatches between scheduling classes.
+///
+/// Each task carries its own [`SchedPolicy`], which selects one of the
+/// following classes. A ready task in a higher class always runs before the
+/// tasks in the lower classes, and preempts them at the next timer tick.
+///
+/// 1. Real-time: fixed-priority FIFO or round-robin, the same as the
+///    [`RTScheduler`](crate::RTScheduler).
+/// 2. Fair: tasks share the CPU in proportion to their weights, by picking
+///    the task with the smallest virtual runtime like the
+///    [`CFScheduler`](crate::CFScheduler).
+/// 3. Idle: round-robin, only runs when the CPU would be idle otherwise.
+///
+/// The policy can be changed by [`ClassScheduler::set_policy`] at any time,
+/// and [`BaseScheduler::set_priority`] changes the priority or the nice value
+/// within the current policy.
+pub struct ClassScheduler<T, const MAX_TIME_SLICE: usize> {
+    rt_queues: PrioQueues<Arc<ClassTask<T, MAX_TIME_SLICE>>>,
+    fair_queue: BTreeMap<(u64, u64), Arc<ClassTask<T, MAX_TIME_SLICE>>>, // (vruntime, taskid)
+    idle_queue: VecDeque<Arc<ClassTask<T, MAX_TIME_SLICE>>>,
+    min_vruntime: u64,
+    id_pool: u64,
+}
+
+impl<T, const S: usize> ClassScheduler<T, S> {
+    /// Creates a new empty [`ClassScheduler`].
+    pub const fn new() -> Self {
+        Self {
+            rt_queues: PrioQueues::new(),
+            fair_queue: BTreeMap::new(),
+            idle_queue: VecDeque::new(),
+            min_vruntime: 0,
+            id_pool: 0,
+        }
+    }
+
+    /// get the name of scheduler
+    pub fn scheduler_name() -> &'static str {
+        "Hierarchical"
+    }
+
+    /// Changes the scheduling policy of the task, and moves it to the new
+    /// class if it's ready.
+    ///
+    /// Returns `false` if the parameters of the policy are invalid.
+    pub fn set_policy(&mut self, task: &Arc<ClassTask<T, S>>, policy: SchedPolicy) -> bool {
+        let valid = match policy {
+            SchedPolicy::Fifo(prio) | SchedPolicy::RoundRobin(prio) => prio < RT_PRIO_LEVELS,
+            SchedPolicy::Normal(nice) => (-20..=19).contains(&nice),
+            SchedPolicy::Idle => true,
+        };
+        if valid {
+            self.requeue_with(task, |task| task.set_policy(policy));
+        }
+        valid
+    }
+
+    /// Sets the real-time priority inherited from other tasks, e.g. the tasks
+    /// waiting for a lock held by `task`. Passes `None` to drop the inherited
+    /// priority.
+    ///
+    /// A task with an inherited priority is scheduled in the real-time class
+    /// regardless of its policy.
+    ///
+    /// Returns `false` if the priority is invalid.
+    pub fn set_inherited_priority(
+        &mut self,
+        task: &Arc<ClassTask<T, S>>,
+        prio: Option<usize>,
+    ) -> bool {
+        match prio {
+            Some(prio) if prio >= RT_PRIO_LEVELS => false,
+            _ => {
+                let inherited = prio.map_or(-1, |prio| prio as isize);
+                self.requeue_with(task, |task| {
+                    task.inherited_prio.store(inherited, Ordering::Release)
+                });
+                true
+            }
+        }
+    }
+
+    /// Updates the task, and moves it to the right queue if it's ready.
+    fn requeue_with<F>(&mut self, task: &Arc<ClassTask<T, S>>, update: F)
+    where
+        F: FnOnce(&ClassTask<T, S>),
+    {
+        let removed = self.remove_task(task);
+        update(task);
+        if let Some(task) = removed {
+            self.enqueue(task, false);
+        }
+    }
+
+    fn enqueue(&mut self, task: Arc<ClassTask<T, S>>, front: bool) {
+        match task.class() {
+            SchedClass::RealTime(prio) => {
+                task.queued_class.store(CLASS_RT, Ordering::Release);
+                task.queued_prio.store(prio, Ordering::Release);
+                self.rt_queues.push(prio, task, front);
+            }
+            SchedClass::Fair => {
+                // don't let the tasks that have been sleeping or in other
+                // classes catch up for too long
+                let vruntime = task.vruntime().max(self.min_vruntime);
+                task.vruntime.store(vruntime, Ordering::Release);
+                task.id.store(self.id_pool, Ordering::Release);
+                self.id_pool += 1;
+                task.queued_class.store(CLASS_FAIR, Ordering::Release);
+                self.fair_queue.insert(task.key(), task);
+            }
+            SchedClass::Idle => {
+                task.queued_class.store(CLASS_IDLE, Ordering::Release);
+                if front {
+                    self.idle_queue.push_front(task);
+                } else {
+                    self.idle_queue.push_back(task);
+                }
+            }
+        }
+    }
+}
+
+impl<T, const S: usize> BaseScheduler for ClassScheduler<T, S> {
+    type SchedItem = Arc<ClassTask<T, S>>;
+
+    fn init(&mut self) {}
+
+    fn add_task(&mut self, task: Self::SchedItem) {
+        self.enqueue(task, false);
+    }
+
+    fn remove_task(&mut self, task: &Self::SchedItem) -> Option<Self::SchedItem> {
+        match task.queued_class.load(Ordering::Acquire) {
+            CLASS_RT => {
+                let prio = task.queued_prio.load(Ordering::Acquire);
+                self.rt_queues.remove(prio, |t| Arc::ptr_eq(t, task))
+            }
+            CLASS_FAIR => self.fair_queue.remove(&task.key()),
+            _ => self
+                .idle_queue
+                .iter()
+                .position(|t| Arc::ptr_eq(t, task))
+                .and_then(|idx| self.idle_queue.remove(idx)),
+        }
+    }
+
+    fn pick_next_task(&mut self) -> Option<Self::SchedItem> {
+        if let Some(task) = self.rt_queues.pop() {
+            Some(task)
+        } else if let Some((_, task)) = self.fair_queue.pop_first() {
+            self.min_vruntime = self.min_vruntime.max(task.vruntime());
+            Some(task)
+        } else {
+            self.idle_queue.pop_front()
+        }
+    }
+
+    fn put_prev_task(&mut self, prev: Self::SchedItem, preempt: bool) {
+        if prev.is_time_sliced() && prev.time_slice() <= 0 {
+            prev.reset_time_slice();
+            self.enqueue(prev, false);
+        } else {
+            self.enqueue(prev, preempt);
+        }
+    }
+
+    fn task_tick(&mut self, current: &Self::SchedItem) -> bool {
+        let expired =
+            current.is_time_sliced() && current.time_slice.fetch_sub(1, Ordering::Release) <= 1;
+        let highest_rt = self.rt_queues.highest();
+        let preempted = match current.class() {
+            SchedClass::RealTime(prio) => highest_rt.is_some_and(|highest| highest > prio),
+            SchedClass::Fair => {
+                let weight = nice_to_weight(current.nice.load(Ordering::Acquire)) as u64;
+                let delta = NICE_0_VRUNTIME * NICE_0_VRUNTIME / weight;
+                let vruntime = current.vruntime.fetch_add(delta, Ordering::Release) + delta;
+                highest_rt.is_some()
+                    || self
+                        .fair_queue
+                        .first_key_value()
+                        .is_some_and(|(key, _)| key.0 < vruntime)
+            }
+            SchedClass::Idle => highest_rt.is_some() || !self.fair_queue.is_empty(),
+        };
+        expired || preempted
+    }
+
+    fn set_priority(&mut self, task: &Self::SchedItem, prio: isize) -> bool {
+        match task.policy() {
+            SchedPolicy::Fifo(_) if (0..RT_PRIO_LEVELS as isize).contains(&prio) => {
+                self.set_policy(task, SchedPolicy::Fifo(prio as usize))
+            }
+            SchedPolicy::RoundRobin(_) if (0..RT_PRIO_LEVELS as isize).contains(&prio) => {
+                self.set_policy(task, SchedPolicy::RoundRobin(prio as usize))
+            }
+            SchedPolicy::Normal(_) => self.set_policy(task, SchedPolicy::Normal(prio)),
+            _ => false,
+        }
+    }
+
+    /// Only the real-time priority can be inherited, i.e., `donor` must be
+    /// in the real-time class.
+    fn inherit_priority(
+        &mut self,
+        task: &Self::SchedItem,
+        donor: Option<&Self::SchedItem>,
+    ) -> bool {
+        match (donor.map(|donor| donor.class()), task.class()) {
+            (Some(SchedClass::RealTime(prio)), SchedClass::RealTime(curr)) if prio <= curr => false,
+            (Some(SchedClass::RealTime(prio)), _) => self.set_inherited_priority(task, Some(prio)),
+            (Some(_), _) => false,
+            (None, _) if task.inherited_prio.load(Ordering::Acquire) >= 0 => {
+                self.set_inherited_priority(task, None)
+            }
+            (None, _) => false,
+        }
+    }
+}
@@ -0,0 +1,265 @@
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::sync::Arc;
+use core::ops::Deref;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use crate::BaseScheduler;
+
+/// The total utilization of all reservations, i.e., the sum of
+/// `budget / period`, is scaled by this value.
+const UTIL_SCALE: u64 = 1 << 20;
+
+/// A task wrapper for the [`EDFScheduler`].
+///
+/// It adds the CPU reservation (the budget and period in ticks), the absolute
+/// deadline and the remaining budget in the current period.
+pub struct EDFTask<T> {
+    inner: T,
+    budget: AtomicU64,
+    period: AtomicU64,
+    deadline: AtomicU64,
+    remaining: AtomicU64,
+    id: AtomicU64,
+}
+
+impl<T> EDFTask<T> {
+    /// Creates a new [`EDFTask`] from the inner task struct, without a
+    /// reservation.
+    pub const fn new(inner: T) -> Self {
+        Self {
+            inner,
+            budget: AtomicU64::new(0),
+            period: AtomicU64::new(0),
+            deadline: AtomicU64::new(0),
+            remaining: AtomicU64::new(0),
+            id: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns the reserved `(budget, period)` in ticks, or `None` if the task
+    /// has no reservation.
+    pub fn reservation(&self) -> Option<(u64, u64)> {
+        match self.period.load(Ordering::Acquire) {
+            0 => None,
+            period => Some((self.budget.load(Ordering::Acquire), period)),
+        }
+    }
+
+    /// Returns the absolute deadline of the current period in ticks.
+    pub fn deadline(&self) -> u64 {
+        self.deadline.load(Ordering::Acquire)
+    }
+
+    /// Returns the remaining budget of the current period in ticks.
+    pub fn remaining_budget(&self) -> u64 {
+        self.remaining.load(Ordering::Acquire)
+    }
+
+    fn utilization(&self) -> u64 {
+        self.reservation()
+            .map_or(0, |(budget, period)| utilization(budget, period))
+    }
+
+    fn key(&self) -> (u64, u64) {
+        (self.deadline(), self.id.load(Ordering::Acquire))
+    }
+
+    /// Starts a new period at `now`.
+    fn replenish(&self, now: u64) {
+        self.deadline
+            .store(now + self.period.load(Ordering::Acquire), Ordering::Release);
+        self.remaining
+            .store(self.budget.load(Ordering::Acquire), Ordering::Release);
+    }
+
+    /// Returns a reference to the inner task struct.
+    pub const fn inner(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T> Deref for EDFTask<T> {
+    type Target = T;
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+fn utilization(budget: u64, period: u64) -> u64 {
+    // round up to be conservative in admission control
+    (budget as u128 * UTIL_SCALE as u128).div_ceil(period as u128) as u64
+}
+
+/// An [Earliest Deadline First][1] (EDF) scheduler with CPU reservations.
+///
+/// A task with a reservation runs for at most `budget` ticks in every
+/// `period` ticks, and its deadline is the end of the current period. The
+/// ready task with the earliest deadline is always picked, and a task that
+/// has used up its budget is throttled until its next period.
+///
+/// A reservation is admitted only if the total utilization (the sum of
+/// `budget / period`) doesn't exceed 100%, so that all deadlines are met.
+/// Tasks without reservations run in FIFO order when there is no ready task
+/// with a reservation.
+///
+/// Time is measured in timer ticks, which are counted by
+/// [`BaseScheduler::task_tick`].
+///
+/// [1]: https://en.wikipedia.org/wiki/Earliest_deadline_first_scheduling
+pub struct EDFScheduler<T> {
+    ready_queue: BTreeMap<(u64, u64), Arc<EDFTask<T>>>, // (deadline, taskid)
+    throttled: BTreeMap<(u64, u64), Arc<EDFTask<T>>>,   // (deadline, taskid)
+    background: VecDeque<Arc<EDFTask<T>>>,
+    now: u64,
+    utilization: u64,
+    id_pool: u64,
+}
+
+impl<T> EDFScheduler<T> {
+    /// Creates a new empty [`EDFScheduler`].
+    pub const fn new() -> Self {
+        Self {
+            ready_queue: BTreeMap::new(),
+            throttled: BTreeMap::new(),
+            background: VecDeque::new(),
+            now: 0,
+            utilization: 0,
+            id_pool: 0,
+        }
+    }
+
+    /// get the name of scheduler
+    pub fn scheduler_name() -> &'static str {
+        "Earliest Deadline First"
+    }
+
+    /// Returns the current time in ticks.
+    pub fn now(&self) -> u64 {
+        self.now
+    }
+
+    /// Reserves `budget` ticks in every `period` ticks for the task, which
+    /// replaces the previous reservation and starts a new period.
+    ///
+    /// Returns `false` if the parameters are invalid, or the total utilization
+    /// would exceed 100% (admission control).
+    pub fn set_reservation(&mut self, task: &Arc<EDFTask<T>>, budget: u64, period: u64) -> bool {
+        if budget == 0 || budget > period {
+            return false;
+        }
+        let utilization = self.utilization - task.utilization() + utilization(budget, period);
+        if utilization > UTIL_SCALE {
+            return false;
+        }
+        self.utilization = utilization;
+        let removed = self.remove_task(task);
+        task.budget.store(budget, Ordering::Release);
+        task.period.store(period, Ordering::Release);
+        task.replenish(self.now);
+        if let Some(task) = removed {
+            self.enqueue(task);
+        }
+        true
+    }
+
+    /// Removes the reservation of the task. It must be called before the task
+    /// exits, to release the reserved utilization.
+    pub fn clear_reservation(&mut self, task: &Arc<EDFTask<T>>) {
+        self.utilization -= task.utilization();
+        let removed = self.remove_task(task);
+        task.budget.store(0, Ordering::Release);
+        task.period.store(0, Ordering::Release);
+        if let Some(task) = removed {
+            self.enqueue(task);
+        }
+    }
+
+    fn enqueue(&mut self, task: Arc<EDFTask<T>>) {
+        if task.reservation().is_none() {
+            self.background.push_back(task);
+            return;
+        }
+        if self.now >= task.deadline() {
+            task.replenish(self.now);
+        }
+        task.id.store(self.id_pool, Ordering::Release);
+        self.id_pool += 1;
+        if task.remaining_budget() > 0 {
+            self.ready_queue.insert(task.key(), task);
+        } else {
+            self.throttled.insert(task.key(), task);
+        }
+    }
+
+    /// Moves the throttled tasks to the ready queue when their next periods
+    /// begin.
+    fn release_throttled(&mut self) {
+        while let Some(entry) = self.throttled.first_entry() {
+            if entry.key().0 > self.now {
+                break;
+            }
+            let task = entry.remove();
+            task.replenish(self.now);
+            self.ready_queue.insert(task.key(), task);
+        }
+    }
+}
+
+impl<T> BaseScheduler for EDFScheduler<T> {
+    type SchedItem = Arc<EDFTask<T>>;
+
+    fn init(&mut self) {}
+
+    fn add_task(&mut self, task: Self::SchedItem) {
+        self.enqueue(task);
+    }
+
+    fn remove_task(&mut self, task: &Self::SchedItem) -> Option<Self::SchedItem> {
+        if task.reservation().is_some() {
+            let key = task.key();
+            self.ready_queue
+                .remove(&key)
+                .or_else(|| self.throttled.remove(&key))
+        } else {
+            self.background
+                .iter()
+                .position(|t| Arc::ptr_eq(t, task))
+                .and_then(|idx| self.background.remove(idx))
+        }
+    }
+
+    fn pick_next_task(&mut self) -> Option<Self::SchedItem> {
+        self.release_throttled();
+        if let Some((_, task)) = self.ready_queue.pop_first() {
+            Some(task)
+        } else {
+            self.background.pop_front()
+        }
+    }
+
+    fn put_prev_task(&mut self, prev: Self::SchedItem, preempt: bool) {
+        if prev.reservation().is_none() && preempt {
+            self.background.push_front(prev);
+        } else {
+            self.enqueue(prev);
+        }
+    }
+
+    fn task_tick(&mut self, current: &Self::SchedItem) -> bool {
+        self.now += 1;
+        self.release_throttled();
+        let earliest = self.ready_queue.first_key_value().map(|(key, _)| key.0);
+        if current.reservation().is_some() {
+            let remaining = current.remaining_budget().saturating_sub(1);
+            current.remaining.store(remaining, Ordering::Release);
+            remaining == 0 || earliest.is_some_and(|deadline| deadline < current.deadline())
+        } else {
+            earliest.is_some()
+        }
+    }
+
+    fn set_priority(&mut self, _task: &Self::SchedItem, _prio: isize) -> bool {
+        false
+    }
+}
@@ -5,13 +5,22 @@
 //! - [`FifoScheduler`]: FIFO (First-In-First-Out) scheduler (cooperative).
 //! - [`RRScheduler`]: Round-robin scheduler (preemptive).
 //! - [`CFScheduler`]: Completely Fair Scheduler (preemptive).
+//! - [`RTScheduler`]: Fixed-priority real-time scheduler, with the `SCHED_FIFO`
+//!   and `SCHED_RR` policies (preemptive).
+//! - [`EDFScheduler`]: Earliest Deadline First scheduler with CPU reservations
+//!   (preemptive).
+//! - [`ClassScheduler`]: Hierarchical scheduler that dispatches between the
+//!   real-time, fair and idle classes, with per-task policies (preemptive).
 
 #![cfg_attr(not(test), no_std)]
 #![feature(const_mut_refs)]
 
 mod cfs;
+mod class;
+mod edf;
 mod fifo;
 mod round_robin;
+mod rt;
 
 #[cfg(test)]
 mod tests;
@@ -19,8 +28,11 @@ mod tests;
 extern crate alloc;
 
 pub use cfs::{CFSTask, CFScheduler};
+pub use class::{ClassScheduler, ClassTask, SchedPolicy};
+pub use edf::{EDFScheduler, EDFTask};
 pub use fifo::{FifoScheduler, FifoTask};
 pub use round_robin::{RRScheduler, RRTask};
+pub use rt::{RTPolicy, RTScheduler, RTTask, RT_PRIO_LEVELS};
 
 /// The base scheduler trait that all schedulers should implement.
 ///
@@ -66,4 +78,23 @@ pub trait BaseScheduler {
 
     /// set priority for a task
     fn set_priority(&mut self, task: &Self::SchedItem, prio: isize) -> bool;
+
+    /// Lets `task` inherit the priority of `donor` if it's higher than the
+    /// priority of `task`, or drops the inherited priority of `task` if
+    /// `donor` is [`None`].
+    ///
+    /// It's used to implement priority inheritance of locks, where `task`
+    /// holds a lock that `donor` is waiting for. `task` may be running or
+    /// blocked, i.e., not in the scheduler.
+    ///
+    /// Returns `true` if the priority of `task` is raised, or the inherited
+    /// priority is dropped. Schedulers without priorities don't support it
+    /// and always return `false`.
+    fn inherit_priority(
+        &mut self,
+        _task: &Self::SchedItem,
+        _donor: Option<&Self::SchedItem>,
+    ) -> bool {
+        false
+    }
 }
@@ -0,0 +1,305 @@
+use alloc::{collections::VecDeque, sync::Arc};
+use core::ops::Deref;
+use core::sync::atomic::{AtomicIsize, AtomicU8, AtomicUsize, Ordering};
+
+use crate::BaseScheduler;
+
+/// The number of priority levels of the [`RTScheduler`].
+///
+/// Priorities range from 0 to `RT_PRIO_LEVELS - 1`, a larger value means a
+/// higher priority.
+pub const RT_PRIO_LEVELS: usize = 100;
+
+/// Scheduling policies of the tasks in the [`RTScheduler`].
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum RTPolicy {
+    /// The task runs until it blocks, yields, or is preempted by a higher
+    /// priority task, like `SCHED_FIFO` in POSIX.
+    Fifo = 0,
+    /// Same as [`RTPolicy::Fifo`], except that the tasks with the same
+    /// priority run in turn by time slices, like `SCHED_RR` in POSIX.
+    RoundRobin = 1,
+}
+
+/// A task wrapper for the [`RTScheduler`].
+///
+/// It adds the scheduling policy, the priority and a time slice counter.
+pub struct RTTask<T, const MAX_TIME_SLICE: usize> {
+    inner: T,
+    policy: AtomicU8,
+    prio: AtomicUsize,
+    /// The priority inherited from other tasks, or -1 if there is none.
+    inherited_prio: AtomicIsize,
+    /// The level of the ready queue where the task is.
+    queued_prio: AtomicUsize,
+    time_slice: AtomicIsize,
+}
+
+impl<T, const S: usize> RTTask<T, S> {
+    /// Creates a new [`RTTask`] from the inner task struct, with the
+    /// [`RTPolicy::RoundRobin`] policy and the lowest priority.
+    pub const fn new(inner: T) -> Self {
+        Self {
+            inner,
+            policy: AtomicU8::new(RTPolicy::RoundRobin as u8),
+            prio: AtomicUsize::new(0),
+            inherited_prio: AtomicIsize::new(-1),
+            queued_prio: AtomicUsize::new(0),
+            time_slice: AtomicIsize::new(S as isize),
+        }
+    }
+
+    /// Returns the scheduling policy.
+    pub fn policy(&self) -> RTPolicy {
+        match self.policy.load(Ordering::Acquire) {
+            0 => RTPolicy::Fifo,
+            _ => RTPolicy::RoundRobin,
+        }
+    }
+
+    /// Sets the scheduling policy, it takes effect the next time the task is
+    /// scheduled.
+    pub fn set_policy(&self, policy: RTPolicy) {
+        self.policy.store(policy as u8, Ordering::Release);
+    }
+
+    /// Returns the priority set by [`BaseScheduler::set_priority`].
+    pub fn base_priority(&self) -> usize {
+        self.prio.load(Ordering::Acquire)
+    }
+
+    /// Returns the effective priority, which is the higher one of the base
+    /// priority and the inherited priority.
+    pub fn priority(&self) -> usize {
+        let inherited = self.inherited_prio.load(Ordering::Acquire);
+        self.base_priority().max(inherited.max(0) as usize)
+    }
+
+    fn time_slice(&self) -> isize {
+        self.time_slice.load(Ordering::Acquire)
+    }
+
+    fn reset_time_slice(&self) {
+        self.time_slice.store(S as isize, Ordering::Release);
+    }
+
+    /// Returns a reference to the inner task struct.
+    pub const fn inner(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T, const S: usize> Deref for RTTask<T, S> {
+    type Target = T;
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+/// A fixed-priority preemptive real-time scheduler.
+///
+/// There is a FIFO ready queue for each priority level, the first task in the
+/// highest non-empty level is always picked. A running task is preempted at
+/// the next timer tick when a higher priority task becomes ready. Tasks with
+/// the [`RTPolicy::RoundRobin`] policy are also preempted when their time
+/// slices are used up, and placed at the end of their levels.
+///
+/// The priority of a task can be raised temporarily by
+/// [`RTScheduler::set_inherited_priority`], to implement priority inheritance
+/// of locks.
+pub struct RTScheduler<T, const MAX_TIME_SLICE: usize> {
+    ready_queues: PrioQueues<Arc<RTTask<T, MAX_TIME_SLICE>>>,
+}
+
+/// FIFO queues of [`RT_PRIO_LEVELS`] priority levels.
+pub(crate) struct PrioQueues<I> {
+    queues: [VecDeque<I>; RT_PRIO_LEVELS],
+    /// One bit for each level, set if the queue is not empty.
+    bitmap: u128,
+}
+
+impl<I> PrioQueues<I> {
+    const EMPTY_QUEUE: VecDeque<I> = VecDeque::new();
+
+    pub const fn new() -> Self {
+        Self {
+            queues: [Self::EMPTY_QUEUE; RT_PRIO_LEVELS],
+            bitmap: 0,
+        }
+    }
+
+    /// Returns the highest level of the non-empty queues.
+    pub fn highest(&self) -> Option<usize> {
+        if self.bitmap == 0 {
+            None
+        } else {
+            Some((u128::BITS - 1 - self.bitmap.leading_zeros()) as usize)
+        }
+    }
+
+    pub fn push(&mut self, prio: usize, item: I, front: bool) {
+        if front {
+            self.queues[prio].push_front(item);
+        } else {
+            self.queues[prio].push_back(item);
+        }
+        self.bitmap |= 1 << prio;
+    }
+
+    /// Pops the first item of the highest level.
+    pub fn pop(&mut self) -> Option<I> {
+        let prio = self.highest()?;
+        let item = self.queues[prio].pop_front();
+        self.update_bitmap(prio);
+        item
+    }
+
+    pub fn remove<F>(&mut self, prio: usize, pred: F) -> Option<I>
+    where
+        F: FnMut(&I) -> bool,
+    {
+        let queue = &mut self.queues[prio];
+        let removed = queue
+            .iter()
+            .position(pred)
+            .and_then(|idx| queue.remove(idx));
+        self.update_bitmap(prio);
+        removed
+    }
+
+    fn update_bitmap(&mut self, prio: usize) {
+        if self.queues[prio].is_empty() {
+            self.bitmap &= !(1 << prio);
+        }
+    }
+}
+
+impl<T, const S: usize> RTScheduler<T, S> {
+    /// Creates a new empty [`RTScheduler`].
+    pub const fn new() -> Self {
+        Self {
+            ready_queues: PrioQueues::new(),
+        }
+    }
+
+    /// get the name of scheduler
+    pub fn scheduler_name() -> &'static str {
+        "Real-time"
+    }
+
+    /// Returns the highest priority of the ready tasks.
+    pub fn highest_ready_priority(&self) -> Option<usize> {
+        self.ready_queues.highest()
+    }
+
+    /// Sets the priority inherited from other tasks, e.g. the tasks waiting
+    /// for a lock held by `task`. Passes `None` to drop the inherited priority.
+    ///
+    /// Returns `false` if the priority is invalid.
+    pub fn set_inherited_priority(
+        &mut self,
+        task: &Arc<RTTask<T, S>>,
+        prio: Option<usize>,
+    ) -> bool {
+        match prio {
+            Some(prio) if prio >= RT_PRIO_LEVELS => false,
+            _ => {
+                let inherited = prio.map_or(-1, |prio| prio as isize);
+                self.requeue_with(task, |task| {
+                    task.inherited_prio.store(inherited, Ordering::Release)
+                });
+                true
+            }
+        }
+    }
+
+    /// Updates the task, and moves it to the right level if it's ready.
+    fn requeue_with<F>(&mut self, task: &Arc<RTTask<T, S>>, update: F)
+    where
+        F: FnOnce(&RTTask<T, S>),
+    {
+        let removed = self.remove_task(task);
+        update(task);
+        if let Some(task) = removed {
+            self.enqueue(task, false);
+        }
+    }
+
+    fn enqueue(&mut self, task: Arc<RTTask<T, S>>, front: bool) {
+        let prio = task.priority();
+        task.queued_prio.store(prio, Ordering::Release);
+        self.ready_queues.push(prio, task, front);
+    }
+}
+
+impl<T, const S: usize> BaseScheduler for RTScheduler<T, S> {
+    type SchedItem = Arc<RTTask<T, S>>;
+
+    fn init(&mut self) {}
+
+    fn add_task(&mut self, task: Self::SchedItem) {
+        self.enqueue(task, false);
+    }
+
+    fn remove_task(&mut self, task: &Self::SchedItem) -> Option<Self::SchedItem> {
+        let prio = task.queued_prio.load(Ordering::Acquire);
+        self.ready_queues.remove(prio, |t| Arc::ptr_eq(t, task))
+    }
+
+    fn pick_next_task(&mut self) -> Option<Self::SchedItem> {
+        self.ready_queues.pop()
+    }
+
+    fn put_prev_task(&mut self, prev: Self::SchedItem, preempt: bool) {
+        match prev.policy() {
+            RTPolicy::Fifo => self.enqueue(prev, preempt),
+            RTPolicy::RoundRobin => {
+                if prev.time_slice() > 0 && preempt {
+                    self.enqueue(prev, true)
+                } else {
+                    prev.reset_time_slice();
+                    self.enqueue(prev, false)
+                }
+            }
+        }
+    }
+
+    fn task_tick(&mut self, current: &Self::SchedItem) -> bool {
+        let expired = current.policy() == RTPolicy::RoundRobin
+            && current.time_slice.fetch_sub(1, Ordering::Release) <= 1;
+        let preempted = self
+            .highest_ready_priority()
+            .is_some_and(|prio| prio > current.priority());
+        expired || preempted
+    }
+
+    fn set_priority(&mut self, task: &Self::SchedItem, prio: isize) -> bool {
+        if (0..RT_PRIO_LEVELS as isize).contains(&prio) {
+            self.requeue_with(task, |task| {
+                task.prio.store(prio as usize, Ordering::Release)
+            });
+            true
+        } else {
+            false
+        }
+    }
+
+    fn inherit_priority(
+        &mut self,
+        task: &Self::SchedItem,
+        donor: Option<&Self::SchedItem>,
+    ) -> bool {
+        match donor {
+            Some(donor) if donor.priority() > task.priority() => {
+                self.set_inherited_priority(task, Some(donor.priority()))
+            }
+            Some(_) => false,
+            None if task.inherited_prio.load(Ordering::Acquire) >= 0 => {
+                self.set_inherited_priority(task, None)
+            }
+            None => false,
+        }
+    }
+}
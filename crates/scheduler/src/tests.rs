@@ -82,3 +82,324 @@ macro_rules! def_test_sched {
 def_test_sched!(fifo, FifoScheduler::<usize>, FifoTask::<usize>);
 def_test_sched!(rr, RRScheduler::<usize, 5>, RRTask::<usize, 5>);
 def_test_sched!(cfs, CFScheduler::<usize>, CFSTask::<usize>);
+def_test_sched!(rt, RTScheduler::<usize, 5>, RTTask::<usize, 5>);
+def_test_sched!(edf, EDFScheduler::<usize>, EDFTask::<usize>);
+def_test_sched!(class, ClassScheduler::<usize, 5>, ClassTask::<usize, 5>);
+
+mod rt_prio {
+    use crate::*;
+    use alloc::sync::Arc;
+
+    type Task = Arc<RTTask<usize, 5>>;
+
+    fn new_task(
+        id: usize,
+        prio: isize,
+        policy: RTPolicy,
+        sched: &mut RTScheduler<usize, 5>,
+    ) -> Task {
+        let task = Arc::new(RTTask::new(id));
+        task.set_policy(policy);
+        assert!(sched.set_priority(&task, prio));
+        sched.add_task(task.clone());
+        task
+    }
+
+    #[test]
+    fn test_priority_order() {
+        let mut scheduler = RTScheduler::<usize, 5>::new();
+        new_task(0, 10, RTPolicy::Fifo, &mut scheduler);
+        new_task(1, 50, RTPolicy::Fifo, &mut scheduler);
+        new_task(2, 10, RTPolicy::Fifo, &mut scheduler);
+        let low = new_task(3, 1, RTPolicy::Fifo, &mut scheduler);
+        assert!(!scheduler.set_priority(&low, RT_PRIO_LEVELS as isize));
+        assert!(!scheduler.set_priority(&low, -1));
+
+        // a ready task is moved to the new level
+        assert!(scheduler.set_priority(&low, 99));
+        assert_eq!(scheduler.highest_ready_priority(), Some(99));
+
+        let order: Vec<_> = core::iter::from_fn(|| scheduler.pick_next_task())
+            .map(|t| *t.inner())
+            .collect();
+        assert_eq!(order, [3, 1, 0, 2]);
+        assert_eq!(scheduler.highest_ready_priority(), None);
+    }
+
+    #[test]
+    fn test_preempt() {
+        let mut scheduler = RTScheduler::<usize, 5>::new();
+        let low = new_task(0, 10, RTPolicy::Fifo, &mut scheduler);
+        let curr = scheduler.pick_next_task().unwrap();
+        assert!(Arc::ptr_eq(&curr, &low));
+
+        // FIFO tasks are never preempted by the tasks with the same priority
+        new_task(1, 10, RTPolicy::Fifo, &mut scheduler);
+        for _ in 0..100 {
+            assert!(!scheduler.task_tick(&curr));
+        }
+
+        // but preempted by the higher priority tasks
+        let high = new_task(2, 20, RTPolicy::Fifo, &mut scheduler);
+        assert!(scheduler.task_tick(&curr));
+        scheduler.put_prev_task(curr, true);
+        assert!(Arc::ptr_eq(&scheduler.pick_next_task().unwrap(), &high));
+        // the preempted task is still the first of its level
+        assert!(Arc::ptr_eq(&scheduler.pick_next_task().unwrap(), &low));
+    }
+
+    #[test]
+    fn test_round_robin() {
+        let mut scheduler = RTScheduler::<usize, 5>::new();
+        for i in 0..3 {
+            new_task(i, 10, RTPolicy::RoundRobin, &mut scheduler);
+        }
+        for i in 0..30 {
+            let curr = scheduler.pick_next_task().unwrap();
+            assert_eq!(*curr.inner(), i % 3);
+            for _ in 0..4 {
+                assert!(!scheduler.task_tick(&curr));
+            }
+            assert!(scheduler.task_tick(&curr));
+            scheduler.put_prev_task(curr, true);
+        }
+    }
+
+    #[test]
+    fn test_inherit_priority() {
+        let mut scheduler = RTScheduler::<usize, 5>::new();
+        let low = new_task(0, 1, RTPolicy::Fifo, &mut scheduler);
+        new_task(1, 30, RTPolicy::Fifo, &mut scheduler);
+
+        assert!(scheduler.set_inherited_priority(&low, Some(50)));
+        assert_eq!(low.priority(), 50);
+        assert_eq!(low.base_priority(), 1);
+        assert!(!scheduler.set_inherited_priority(&low, Some(RT_PRIO_LEVELS)));
+        assert!(Arc::ptr_eq(&scheduler.pick_next_task().unwrap(), &low));
+
+        // restore the base priority
+        assert!(scheduler.set_inherited_priority(&low, None));
+        assert_eq!(low.priority(), 1);
+        assert!(scheduler.task_tick(&low));
+
+        // inherit from a blocked donor, only if it has a higher priority
+        let donor = Arc::new(RTTask::new(2));
+        assert!(scheduler.set_priority(&donor, 40));
+        assert!(scheduler.inherit_priority(&low, Some(&donor)));
+        assert_eq!(low.priority(), 40);
+        assert!(!scheduler.inherit_priority(&donor, Some(&low)));
+        assert!(scheduler.inherit_priority(&low, None));
+        assert!(!scheduler.inherit_priority(&low, None));
+        assert_eq!(low.priority(), 1);
+    }
+}
+
+mod cfs_nice {
+    use crate::*;
+    use alloc::sync::Arc;
+
+    #[test]
+    fn test_inherit_priority() {
+        let mut scheduler = CFScheduler::<usize>::new();
+        let holder = Arc::new(CFSTask::new(0));
+        let other = Arc::new(CFSTask::new(1));
+        let donor = Arc::new(CFSTask::new(2));
+        assert!(scheduler.set_priority(&holder, 19));
+        assert!(scheduler.set_priority(&donor, -20));
+        scheduler.add_task(holder.clone());
+        scheduler.add_task(other.clone());
+
+        assert!(scheduler.inherit_priority(&holder, Some(&donor)));
+        assert!(!scheduler.inherit_priority(&donor, Some(&holder)));
+
+        // the holder runs as if its nice value is -20
+        let mut ticks = [0, 0];
+        for _ in 0..1000 {
+            let curr = scheduler.pick_next_task().unwrap();
+            ticks[*curr.inner()] += 1;
+            scheduler.task_tick(&curr);
+            scheduler.put_prev_task(curr, false);
+        }
+        assert!(ticks[0] > ticks[1] * 50);
+
+        // a ready task is still in the ready queue after restoring
+        assert!(scheduler.inherit_priority(&holder, None));
+        assert!(!scheduler.inherit_priority(&holder, None));
+        assert!(scheduler.remove_task(&holder).is_some());
+    }
+}
+
+mod edf_deadline {
+    use crate::*;
+    use alloc::sync::Arc;
+
+    #[test]
+    fn test_admission() {
+        let mut scheduler = EDFScheduler::<usize>::new();
+        let t0 = Arc::new(EDFTask::new(0));
+        let t1 = Arc::new(EDFTask::new(1));
+        assert!(!scheduler.set_reservation(&t0, 0, 10));
+        assert!(!scheduler.set_reservation(&t0, 11, 10));
+        assert!(scheduler.set_reservation(&t0, 5, 10));
+        assert!(scheduler.set_reservation(&t1, 2, 4));
+        assert!(!scheduler.set_reservation(&t1, 3, 4)); // 125%
+        assert_eq!(t1.reservation(), Some((2, 4)));
+
+        scheduler.clear_reservation(&t0);
+        assert_eq!(t0.reservation(), None);
+        assert!(scheduler.set_reservation(&t1, 3, 4));
+    }
+
+    #[test]
+    fn test_deadline_order() {
+        let mut scheduler = EDFScheduler::<usize>::new();
+        let background = Arc::new(EDFTask::new(0));
+        scheduler.add_task(background.clone());
+        for (i, period) in [(1, 30), (2, 10), (3, 20)] {
+            let task = Arc::new(EDFTask::new(i));
+            assert!(scheduler.set_reservation(&task, 2, period));
+            scheduler.add_task(task);
+        }
+
+        let order: Vec<_> = core::iter::from_fn(|| scheduler.pick_next_task())
+            .map(|t| *t.inner())
+            .collect();
+        assert_eq!(order, [2, 3, 1, 0]);
+        assert!(scheduler.remove_task(&background).is_none());
+    }
+
+    #[test]
+    fn test_budget() {
+        let mut scheduler = EDFScheduler::<usize>::new();
+        let task = Arc::new(EDFTask::new(1));
+        assert!(scheduler.set_reservation(&task, 3, 10));
+        scheduler.add_task(task.clone());
+        scheduler.add_task(Arc::new(EDFTask::new(0)));
+
+        let curr = scheduler.pick_next_task().unwrap();
+        assert_eq!(*curr.inner(), 1);
+        assert!(!scheduler.task_tick(&curr));
+        assert!(!scheduler.task_tick(&curr));
+        assert!(scheduler.task_tick(&curr)); // used up the budget
+        scheduler.put_prev_task(curr, true);
+
+        // throttled until the next period
+        let background = scheduler.pick_next_task().unwrap();
+        assert_eq!(*background.inner(), 0);
+        for _ in 3..9 {
+            assert!(!scheduler.task_tick(&background));
+        }
+        assert!(scheduler.task_tick(&background));
+        assert_eq!(scheduler.now(), 10);
+        scheduler.put_prev_task(background, true);
+
+        let curr = scheduler.pick_next_task().unwrap();
+        assert_eq!(*curr.inner(), 1);
+        assert_eq!(curr.deadline(), 20);
+        assert_eq!(curr.remaining_budget(), 3);
+    }
+}
+
+mod class_policy {
+    use crate::*;
+    use alloc::sync::Arc;
+
+    type Task = Arc<ClassTask<usize, 5>>;
+
+    fn new_task(id: usize, policy: SchedPolicy, sched: &mut ClassScheduler<usize, 5>) -> Task {
+        let task = Arc::new(ClassTask::new(id));
+        assert!(sched.set_policy(&task, policy));
+        sched.add_task(task.clone());
+        task
+    }
+
+    #[test]
+    fn test_class_order() {
+        let mut scheduler = ClassScheduler::<usize, 5>::new();
+        new_task(0, SchedPolicy::Idle, &mut scheduler);
+        new_task(1, SchedPolicy::Normal(0), &mut scheduler);
+        new_task(2, SchedPolicy::Fifo(10), &mut scheduler);
+        new_task(3, SchedPolicy::RoundRobin(50), &mut scheduler);
+        let task = new_task(4, SchedPolicy::Normal(0), &mut scheduler);
+        assert!(!scheduler.set_policy(&task, SchedPolicy::Fifo(RT_PRIO_LEVELS)));
+        assert!(!scheduler.set_policy(&task, SchedPolicy::Normal(20)));
+
+        // a ready task is moved to the new class
+        assert!(scheduler.set_policy(&task, SchedPolicy::Idle));
+        assert!(!scheduler.set_priority(&task, 0));
+
+        let order: Vec<_> = core::iter::from_fn(|| scheduler.pick_next_task())
+            .map(|t| *t.inner())
+            .collect();
+        assert_eq!(order, [3, 2, 1, 0, 4]);
+    }
+
+    #[test]
+    fn test_class_preempt() {
+        let mut scheduler = ClassScheduler::<usize, 5>::new();
+        new_task(0, SchedPolicy::Idle, &mut scheduler);
+        let curr = scheduler.pick_next_task().unwrap();
+        assert!(!scheduler.task_tick(&curr));
+
+        // idle tasks are preempted by fair tasks
+        let fair = new_task(1, SchedPolicy::Normal(0), &mut scheduler);
+        assert!(scheduler.task_tick(&curr));
+        scheduler.put_prev_task(curr, true);
+        let curr = scheduler.pick_next_task().unwrap();
+        assert!(Arc::ptr_eq(&curr, &fair));
+        assert!(!scheduler.task_tick(&curr));
+
+        // fair tasks are preempted by real-time tasks
+        let rt = new_task(2, SchedPolicy::Fifo(0), &mut scheduler);
+        assert!(scheduler.task_tick(&curr));
+        scheduler.put_prev_task(curr, true);
+        let curr = scheduler.pick_next_task().unwrap();
+        assert!(Arc::ptr_eq(&curr, &rt));
+        for _ in 0..100 {
+            assert!(!scheduler.task_tick(&curr));
+        }
+
+        // demoted to the idle class, so the fair task runs first
+        assert!(scheduler.set_policy(&curr, SchedPolicy::Idle));
+        scheduler.put_prev_task(curr, false);
+        let order: Vec<_> = core::iter::from_fn(|| scheduler.pick_next_task())
+            .map(|t| *t.inner())
+            .collect();
+        assert_eq!(order, [1, 0, 2]);
+    }
+
+    #[test]
+    fn test_fair_weight() {
+        let mut scheduler = ClassScheduler::<usize, 5>::new();
+        new_task(0, SchedPolicy::Normal(0), &mut scheduler);
+        new_task(1, SchedPolicy::Normal(-5), &mut scheduler);
+
+        let mut ticks = [0; 2];
+        for _ in 0..1000 {
+            let curr = scheduler.pick_next_task().unwrap();
+            ticks[*curr.inner()] += 1;
+            scheduler.task_tick(&curr);
+            scheduler.put_prev_task(curr, false);
+        }
+        // the weight of nice -5 is about 3 times of nice 0
+        assert!(ticks[1] > ticks[0] * 5 / 2 && ticks[1] < ticks[0] * 7 / 2);
+    }
+
+    #[test]
+    fn test_inherit() {
+        let mut scheduler = ClassScheduler::<usize, 5>::new();
+        new_task(0, SchedPolicy::Fifo(20), &mut scheduler);
+        let holder = new_task(1, SchedPolicy::Normal(0), &mut scheduler);
+
+        // the fair task runs in the real-time class with the inherited priority
+        assert!(scheduler.set_inherited_priority(&holder, Some(30)));
+        let curr = scheduler.pick_next_task().unwrap();
+        assert!(Arc::ptr_eq(&curr, &holder));
+        scheduler.put_prev_task(curr, false);
+
+        assert!(scheduler.set_inherited_priority(&holder, None));
+        assert_eq!(*scheduler.pick_next_task().unwrap().inner(), 0);
+        assert_eq!(*scheduler.pick_next_task().unwrap().inner(), 1);
+        assert_eq!(holder.policy(), SchedPolicy::Normal(0));
+    }
+}
@@ -0,0 +1,21 @@
+use std::path::PathBuf;
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=AX_INITRAMFS");
+    if std::env::var("CARGO_FEATURE_INITRAMFS").is_err() {
+        return;
+    }
+    // an empty archive if it's not given, e.g. for `cargo doc` or `clippy`
+    let path = match std::env::var("AX_INITRAMFS") {
+        Ok(path) if !path.is_empty() => {
+            println!("cargo:rerun-if-changed={}", path);
+            PathBuf::from(path)
+        }
+        _ => {
+            let path = PathBuf::from(std::env::var("OUT_DIR").unwrap()).join("empty.cpio");
+            std::fs::write(&path, []).unwrap();
+            path
+        }
+    };
+    println!("cargo:rustc-env=AX_INITRAMFS_PATH={}", path.display());
+}
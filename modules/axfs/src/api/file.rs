@@ -162,6 +162,36 @@ impl File {
     pub fn metadata(&self) -> Result<Metadata> {
         self.inner.get_attr().map(Metadata)
     }
+    /// Acquires an exclusive advisory lock on the file, blocking until it can
+    /// be acquired.
+    pub fn lock(&self) -> Result<()> {
+        self.inner.lock(fops::LockKind::Exclusive, true)
+    }
+
+    /// Acquires a shared advisory lock on the file, blocking until it can be
+    /// acquired.
+    pub fn lock_shared(&self) -> Result<()> {
+        self.inner.lock(fops::LockKind::Shared, true)
+    }
+
+    /// Tries to acquire an exclusive advisory lock on the file. Returns
+    /// [`WouldBlock`](axio::Error::WouldBlock) if it is held by other
+    /// opened files.
+    pub fn try_lock(&self) -> Result<()> {
+        self.inner.lock(fops::LockKind::Exclusive, false)
+    }
+
+    /// Tries to acquire a shared advisory lock on the file. Returns
+    /// [`WouldBlock`](axio::Error::WouldBlock) if an exclusive lock is
+    /// held by other opened files.
+    pub fn try_lock_shared(&self) -> Result<()> {
+        self.inner.lock(fops::LockKind::Shared, false)
+    }
+
+    /// Releases the advisory lock on the file.
+    pub fn unlock(&self) -> Result<()> {
+        self.inner.unlock()
+    }
 }
 
 impl Read for File {
@@ -0,0 +1,239 @@
+//! Device nodes backed by real devices, and the registry of `/dev` entries.
+
+use alloc::{boxed::Box, format, sync::Arc, vec::Vec};
+use axerrno::{ax_err, AxError, AxResult};
+use axfs_vfs::{VfsNodeAttr, VfsNodeOps, VfsNodePerm, VfsNodeRef, VfsNodeType, VfsOps, VfsResult};
+use axsync::Mutex;
+use lazy_init::LazyInit;
+
+use crate::dev::Disk;
+use crate::fs::devfs::DeviceFileSystem;
+
+const BLOCK_SIZE: u64 = 512;
+
+static DEVFS: LazyInit<Arc<DeviceFileSystem>> = LazyInit::new();
+static PENDING_NODES: Mutex<Vec<(&'static str, VfsNodeRef)>> = Mutex::new(Vec::new());
+
+/// Adds a device node at `/dev/<name>`.
+///
+/// It can be called before the filesystems are initialized (e.g., by drivers
+/// at their init time), in which case the node is added when devfs is created.
+pub fn register_device(name: &'static str, node: VfsNodeRef) -> AxResult {
+    if name.is_empty() || name.contains('/') || name == "." || name == ".." {
+        return ax_err!(InvalidInput, "invalid device name");
+    }
+    let mut pending = PENDING_NODES.lock();
+    if DEVFS.is_init() {
+        if DEVFS.root_dir().lookup(name).is_ok() {
+            return ax_err!(AlreadyExists, "device node already exists");
+        }
+        DEVFS.add(name, node);
+    } else {
+        if pending.iter().any(|(n, _)| *n == name) {
+            return ax_err!(AlreadyExists, "device node already exists");
+        }
+        pending.push((name, node));
+    }
+    Ok(())
+}
+
+/// Adds a block device node named `vda`, `vdb`, ... in the probing order.
+pub(crate) fn register_block_device(disk: Disk) -> AxResult {
+    static NEXT_ID: Mutex<u8> = Mutex::new(0);
+    let mut id = NEXT_ID.lock();
+    if *id >= 26 {
+        return ax_err!(Unsupported, "too many block devices");
+    }
+    // names are only created at boot time, so leaking them is fine
+    let name = Box::leak(format!("vd{}", (b'a' + *id) as char).into_boxed_str());
+    register_device(name, Arc::new(BlockDev::new(disk)))?;
+    *id += 1;
+    Ok(())
+}
+
+/// Sets the devfs instance and adds all pending nodes to it.
+pub(crate) fn attach_devfs(devfs: Arc<DeviceFileSystem>) {
+    let mut pending = PENDING_NODES.lock();
+    for (name, node) in pending.drain(..) {
+        devfs.add(name, node);
+    }
+    DEVFS.init_by(devfs);
+}
+
+/// A console device behaves like `/dev/console` or `/dev/ttyS0`.
+///
+/// Reads are non-blocking and return the bytes currently available.
+pub struct ConsoleDev;
+
+impl VfsNodeOps for ConsoleDev {
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        Ok(VfsNodeAttr::new(
+            VfsNodePerm::default_file(),
+            VfsNodeType::CharDevice,
+            0,
+            0,
+        ))
+    }
+
+    fn read_at(&self, _offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        let mut read_len = 0;
+        while read_len < buf.len() {
+            if let Some(c) = axhal::console::getchar() {
+                buf[read_len] = if c == b'\r' { b'\n' } else { c };
+                read_len += 1;
+            } else {
+                break;
+            }
+        }
+        Ok(read_len)
+    }
+
+    fn write_at(&self, _offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        axhal::console::write_bytes(buf);
+        Ok(buf.len())
+    }
+
+    fn fsync(&self) -> VfsResult {
+        Ok(())
+    }
+
+    fn truncate(&self, _size: u64) -> VfsResult {
+        Ok(())
+    }
+
+    axfs_vfs::impl_vfs_non_dir_default! {}
+}
+
+/// A block device node with byte-addressed read/write, like `/dev/vda`.
+pub struct BlockDev {
+    disk: Mutex<Disk>,
+    size: u64,
+}
+
+impl BlockDev {
+    /// Create a new block device node on the given disk.
+    pub fn new(disk: Disk) -> Self {
+        Self {
+            size: disk.size(),
+            disk: Mutex::new(disk),
+        }
+    }
+}
+
+impl VfsNodeOps for BlockDev {
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        Ok(VfsNodeAttr::new(
+            VfsNodePerm::from_bits_truncate(0o660),
+            VfsNodeType::BlockDevice,
+            self.size,
+            self.size / BLOCK_SIZE,
+        ))
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        if offset >= self.size {
+            return Ok(0);
+        }
+        let len = buf.len().min((self.size - offset) as usize);
+        let mut disk = self.disk.lock();
+        disk.set_position(offset);
+        let mut read_len = 0;
+        while read_len < len {
+            match disk.read_one(&mut buf[read_len..len]) {
+                Ok(0) => break,
+                Ok(n) => read_len += n,
+                Err(_) => return Err(AxError::Io),
+            }
+        }
+        Ok(read_len)
+    }
+
+    fn write_at(&self, offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        if offset >= self.size {
+            return ax_err!(StorageFull);
+        }
+        let len = buf.len().min((self.size - offset) as usize);
+        let mut disk = self.disk.lock();
+        disk.set_position(offset);
+        let mut write_len = 0;
+        while write_len < len {
+            match disk.write_one(&buf[write_len..len]) {
+                Ok(0) => break,
+                Ok(n) => write_len += n,
+                Err(_) => return Err(AxError::Io),
+            }
+        }
+        Ok(write_len)
+    }
+
+    fn fsync(&self) -> VfsResult {
+        self.disk.lock().flush().map_err(|_| AxError::Io)
+    }
+
+    fn truncate(&self, _size: u64) -> VfsResult {
+        Ok(())
+    }
+
+    axfs_vfs::impl_vfs_non_dir_default! {}
+}
+
+/// A framebuffer device behaves like `/dev/fb0`.
+///
+/// Written data is flushed to the screen immediately.
+#[cfg(feature = "display")]
+pub struct FrameBufferDev;
+
+#[cfg(feature = "display")]
+impl FrameBufferDev {
+    fn framebuffer() -> &'static mut [u8] {
+        let info = axdisplay::framebuffer_info();
+        unsafe { core::slice::from_raw_parts_mut(info.fb_base_vaddr as *mut u8, info.fb_size) }
+    }
+}
+
+#[cfg(feature = "display")]
+impl VfsNodeOps for FrameBufferDev {
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        let size = axdisplay::framebuffer_info().fb_size as u64;
+        Ok(VfsNodeAttr::new(
+            VfsNodePerm::from_bits_truncate(0o660),
+            VfsNodeType::CharDevice,
+            size,
+            0,
+        ))
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        let fb = Self::framebuffer();
+        if offset >= fb.len() as u64 {
+            return Ok(0);
+        }
+        let src = &fb[offset as usize..];
+        let len = buf.len().min(src.len());
+        buf[..len].copy_from_slice(&src[..len]);
+        Ok(len)
+    }
+
+    fn write_at(&self, offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        let fb = Self::framebuffer();
+        if offset >= fb.len() as u64 {
+            return ax_err!(StorageFull);
+        }
+        let dst = &mut fb[offset as usize..];
+        let len = buf.len().min(dst.len());
+        dst[..len].copy_from_slice(&buf[..len]);
+        axdisplay::framebuffer_flush();
+        Ok(len)
+    }
+
+    fn fsync(&self) -> VfsResult {
+        axdisplay::framebuffer_flush();
+        Ok(())
+    }
+
+    fn truncate(&self, _size: u64) -> VfsResult {
+        Ok(())
+    }
+
+    axfs_vfs::impl_vfs_non_dir_default! {}
+}
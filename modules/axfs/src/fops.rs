@@ -5,6 +5,9 @@ use axfs_vfs::{VfsError, VfsNodeRef};
 use axio::SeekFrom;
 use capability::{Cap, WithCap};
 use core::fmt;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use crate::lock::{PROCESS_OWNER, RANGE_LOCKS, WHOLE_FILE_LOCKS};
 
 #[cfg(feature = "myfs")]
 pub use crate::dev::Disk;
@@ -19,14 +22,31 @@ pub type DirEntry = axfs_vfs::VfsDirEntry;
 pub type FileAttr = axfs_vfs::VfsNodeAttr;
 /// Alias of [`axfs_vfs::VfsNodePerm`].
 pub type FilePerm = axfs_vfs::VfsNodePerm;
+/// Alias of [`axfs_vfs::FileSystemInfo`].
+pub type FileSystemInfo = axfs_vfs::FileSystemInfo;
+
+pub use crate::lock::{FileLock, LockKind, PROCESS_OWNER};
 
 /// An opened file object, with open permissions and a cursor.
 pub struct File {
     node: WithCap<VfsNodeRef>,
+    locker: FileLocker,
     is_append: bool,
     offset: u64,
 }
 
+/// A handle to place advisory locks on behalf of an opened [`File`].
+///
+/// It can be used without borrowing the file, e.g. to block on a lock while
+/// the file is used by others. Locks placed after the file is closed are not
+/// released.
+#[derive(Clone)]
+pub struct FileLocker {
+    node: VfsNodeRef,
+    cap: Cap,
+    owner: u64,
+}
+
 /// An opened directory object, with open permissions and a cursor for
 /// [`read_dir`](Directory::read_dir).
 pub struct Directory {
@@ -152,7 +172,13 @@ impl File {
         if opts.truncate {
             node.truncate(0)?;
         }
+        static NEXT_LOCK_OWNER: AtomicU64 = AtomicU64::new(PROCESS_OWNER + 1);
         Ok(Self {
+            locker: FileLocker {
+                node: node.clone(),
+                cap: access_cap,
+                owner: NEXT_LOCK_OWNER.fetch_add(1, Ordering::Relaxed),
+            },
             node: WithCap::new(node, access_cap),
             is_append: opts.append,
             offset: 0,
@@ -240,6 +266,134 @@ impl File {
     pub fn get_attr(&self) -> AxResult<FileAttr> {
         self.node.access(Cap::empty())?.get_attr()
     }
+
+    /// Returns the handle to place advisory locks on behalf of this file.
+    pub fn locker(&self) -> &FileLocker {
+        &self.locker
+    }
+
+    /// Places an advisory lock on the whole file, see [`FileLocker::lock`].
+    pub fn lock(&self, kind: LockKind, wait: bool) -> AxResult {
+        self.locker.lock(kind, wait)
+    }
+
+    /// Removes the advisory lock on the whole file placed by [`File::lock`].
+    pub fn unlock(&self) -> AxResult {
+        self.locker.unlock()
+    }
+
+    /// Places an advisory lock on the byte range `start..end` of the file, see
+    /// [`FileLocker::lock_range`].
+    pub fn lock_range(&self, kind: LockKind, start: u64, end: u64, wait: bool) -> AxResult {
+        self.locker.lock_range(kind, start, end, wait)
+    }
+
+    /// Removes the advisory locks held by this file in the byte range
+    /// `start..end`, like `fcntl(2)` with `F_UNLCK`.
+    pub fn unlock_range(&self, start: u64, end: u64) -> AxResult {
+        self.locker.unlock_range(start, end)
+    }
+
+    /// Returns a lock held by other opened files which prevents placing the
+    /// given lock, see [`FileLocker::test_lock_range`].
+    pub fn test_lock_range(
+        &self,
+        kind: LockKind,
+        start: u64,
+        end: u64,
+    ) -> AxResult<Option<FileLock>> {
+        self.locker.test_lock_range(kind, start, end)
+    }
+}
+
+impl FileLocker {
+    /// Returns a handle to place advisory locks on behalf of the process
+    /// instead of the opened file, like the POSIX record locks of `fcntl(2)`.
+    ///
+    /// Locks of the process can be changed through any opened file of the same
+    /// file, and are all released when one of them is closed.
+    pub fn process_locker(&self) -> FileLocker {
+        FileLocker {
+            owner: PROCESS_OWNER,
+            ..self.clone()
+        }
+    }
+
+    /// Places an advisory lock on the whole file, or converts the existing
+    /// lock held by this file, like `flock(2)`.
+    ///
+    /// If the lock is held by other opened files, it blocks until they release
+    /// it if `wait` is `true`, otherwise returns [`WouldBlock`](axerrno::AxError::WouldBlock).
+    pub fn lock(&self, kind: LockKind, wait: bool) -> AxResult {
+        let lock = FileLock {
+            kind,
+            start: 0,
+            end: u64::MAX,
+            owner: self.owner,
+        };
+        WHOLE_FILE_LOCKS.lock(&self.node, lock, wait)
+    }
+
+    /// Removes the advisory lock on the whole file placed by [`FileLocker::lock`].
+    pub fn unlock(&self) -> AxResult {
+        WHOLE_FILE_LOCKS.unlock_all(&self.node, self.owner);
+        Ok(())
+    }
+
+    fn range_lock(&self, kind: LockKind, start: u64, end: u64) -> AxResult<FileLock> {
+        if start >= end {
+            return ax_err!(InvalidInput);
+        }
+        let cap = match kind {
+            LockKind::Shared => Cap::READ,
+            LockKind::Exclusive => Cap::WRITE,
+        };
+        if !self.cap.contains(cap) {
+            return ax_err!(PermissionDenied);
+        }
+        Ok(FileLock {
+            kind,
+            start,
+            end,
+            owner: self.owner,
+        })
+    }
+
+    /// Places an advisory lock on the byte range `start..end` of the file, like
+    /// `fcntl(2)` with `F_SETLK` or `F_SETLKW`. Locks held by this file in the
+    /// range are replaced.
+    ///
+    /// A shared lock requires the file to be opened for reading, and an
+    /// exclusive lock requires it to be opened for writing. If the range is
+    /// locked by other opened files, it blocks until they release it if `wait`
+    /// is `true`, otherwise returns [`WouldBlock`](axerrno::AxError::WouldBlock).
+    pub fn lock_range(&self, kind: LockKind, start: u64, end: u64, wait: bool) -> AxResult {
+        let lock = self.range_lock(kind, start, end)?;
+        RANGE_LOCKS.lock(&self.node, lock, wait)
+    }
+
+    /// Removes the advisory locks held by this file in the byte range
+    /// `start..end`, like `fcntl(2)` with `F_UNLCK`.
+    pub fn unlock_range(&self, start: u64, end: u64) -> AxResult {
+        if start >= end {
+            return ax_err!(InvalidInput);
+        }
+        RANGE_LOCKS.unlock(&self.node, self.owner, start, end);
+        Ok(())
+    }
+
+    /// Returns a lock held by other opened files which prevents placing the
+    /// given lock, like `fcntl(2)` with `F_GETLK`. Returns `None` if the lock
+    /// can be placed.
+    pub fn test_lock_range(
+        &self,
+        kind: LockKind,
+        start: u64,
+        end: u64,
+    ) -> AxResult<Option<FileLock>> {
+        let lock = self.range_lock(kind, start, end)?;
+        Ok(RANGE_LOCKS.test(&self.node, &lock))
+    }
 }
 
 impl Directory {
@@ -340,6 +494,10 @@ impl Directory {
 
 impl Drop for File {
     fn drop(&mut self) {
+        for owner in [self.locker.owner, PROCESS_OWNER] {
+            WHOLE_FILE_LOCKS.unlock_all(&self.locker.node, owner);
+            RANGE_LOCKS.unlock_all(&self.locker.node, owner);
+        }
         unsafe { self.node.access_unchecked().release().ok() };
     }
 }
@@ -0,0 +1,220 @@
+//! Read-only root filesystem that serves the files of a `newc` format cpio
+//! archive, which is embedded in the kernel image at build time.
+//!
+//! The path of the archive is given by the `AX_INITRAMFS` environment variable,
+//! the archive is empty if it's not set. No node can be created, written or
+//! removed.
+
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use axerrno::{AxError, AxResult};
+use axfs_devfs::{DeviceFileSystem, DirNode};
+use axfs_vfs::VfsResult;
+use axfs_vfs::{VfsError, VfsNodeAttr, VfsNodeOps, VfsNodePerm, VfsNodeRef, VfsNodeType};
+
+/// The archive, whose path is resolved by the build script.
+static INITRAMFS: &[u8] = include_bytes!(env!("AX_INITRAMFS_PATH"));
+
+const HEADER_LEN: usize = 110;
+const TRAILER: &str = "TRAILER!!!";
+
+const S_IFMT: u32 = 0o170000;
+const S_IFDIR: u32 = 0o040000;
+const S_IFREG: u32 = 0o100000;
+
+/// An entry of the cpio archive.
+struct Entry<'a> {
+    path: &'a str,
+    mode: u32,
+    data: &'a [u8],
+}
+
+/// Iterates over the entries of a `newc` format cpio archive.
+struct CpioReader<'a> {
+    archive: &'a [u8],
+    pos: usize,
+}
+
+const fn align4(n: usize) -> usize {
+    (n + 3) & !3
+}
+
+fn parse_hex(field: &[u8]) -> AxResult<usize> {
+    core::str::from_utf8(field)
+        .ok()
+        .and_then(|s| usize::from_str_radix(s, 16).ok())
+        .ok_or(AxError::InvalidData)
+}
+
+impl<'a> CpioReader<'a> {
+    const fn new(archive: &'a [u8]) -> Self {
+        Self { archive, pos: 0 }
+    }
+
+    fn read_entry(&mut self) -> AxResult<Option<Entry<'a>>> {
+        if self.archive.is_empty() {
+            return Ok(None); // no trailer in the empty archive
+        }
+        let header = self
+            .archive
+            .get(self.pos..self.pos + HEADER_LEN)
+            .ok_or(AxError::InvalidData)?;
+        if &header[..6] != b"070701" && &header[..6] != b"070702" {
+            return Err(AxError::InvalidData);
+        }
+        let field = |i: usize| parse_hex(&header[6 + i * 8..6 + (i + 1) * 8]);
+        let mode = field(1)? as u32;
+        let file_size = field(6)?;
+        let name_size = field(11)?;
+
+        // `name_size` includes the trailing NUL
+        let name_start = self.pos + HEADER_LEN;
+        let name = self
+            .archive
+            .get(name_start..name_start + name_size.max(1) - 1)
+            .and_then(|name| core::str::from_utf8(name).ok())
+            .ok_or(AxError::InvalidData)?;
+        let data_start = align4(name_start + name_size);
+        let data = self
+            .archive
+            .get(data_start..data_start + file_size)
+            .ok_or(AxError::InvalidData)?;
+        self.pos = align4(data_start + file_size);
+
+        if name == TRAILER {
+            Ok(None)
+        } else {
+            Ok(Some(Entry {
+                path: name.trim_start_matches("./").trim_start_matches('/'),
+                mode,
+                data,
+            }))
+        }
+    }
+}
+
+/// A regular file in the initramfs, whose content is served from the archive
+/// directly.
+struct InitramfsFile {
+    data: &'static [u8],
+    perm: VfsNodePerm,
+}
+
+impl VfsNodeOps for InitramfsFile {
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        let size = self.data.len() as u64;
+        Ok(VfsNodeAttr::new(
+            self.perm,
+            VfsNodeType::File,
+            size,
+            size.div_ceil(512),
+        ))
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        let start = self.data.len().min(offset as usize);
+        let end = self.data.len().min(start + buf.len());
+        let src = &self.data[start..end];
+        buf[..src.len()].copy_from_slice(src);
+        Ok(src.len())
+    }
+
+    fn write_at(&self, _offset: u64, _buf: &[u8]) -> VfsResult<usize> {
+        Err(VfsError::PermissionDenied)
+    }
+
+    fn truncate(&self, _size: u64) -> VfsResult {
+        Err(VfsError::PermissionDenied)
+    }
+
+    axfs_vfs::impl_vfs_non_dir_default! {}
+}
+
+/// Builds the directory tree of the initramfs.
+struct TreeBuilder {
+    fs: DeviceFileSystem,
+    dirs: BTreeMap<&'static str, Arc<DirNode>>,
+}
+
+impl TreeBuilder {
+    fn new() -> Self {
+        Self {
+            fs: DeviceFileSystem::new(),
+            dirs: BTreeMap::new(),
+        }
+    }
+
+    /// Creates the directory at `path` and its parents if they don't exist.
+    fn mkdir(&mut self, path: &'static str) -> Arc<DirNode> {
+        if let Some(dir) = self.dirs.get(path) {
+            return dir.clone();
+        }
+        let dir = match path.rsplit_once('/') {
+            Some((parent, name)) => self.mkdir(parent).mkdir(name),
+            None => self.fs.mkdir(path),
+        };
+        self.dirs.insert(path, dir.clone());
+        dir
+    }
+
+    fn add(&mut self, path: &'static str, node: VfsNodeRef) {
+        match path.rsplit_once('/') {
+            Some((parent, name)) => self.mkdir(parent).add(name, node),
+            None => self.fs.add(path, node),
+        }
+    }
+}
+
+/// Builds the tree from the archive into `builder`, returns the number of
+/// files and directories.
+fn build(archive: &'static [u8], builder: &mut TreeBuilder) -> AxResult<usize> {
+    let mut reader = CpioReader::new(archive);
+    let mut count = 0;
+    while let Some(entry) = reader.read_entry()? {
+        let path = entry.path.trim_end_matches('/');
+        if path.is_empty() || path == "." {
+            continue;
+        }
+        match entry.mode & S_IFMT {
+            S_IFDIR => {
+                builder.mkdir(path);
+            }
+            S_IFREG => {
+                // all files are read-only
+                let perm = VfsNodePerm::from_bits_truncate((entry.mode & 0o555) as u16);
+                let file = Arc::new(InitramfsFile {
+                    data: entry.data,
+                    perm,
+                });
+                builder.add(path, file);
+            }
+            ty => {
+                warn!("initramfs: skip {:?} with unsupported type {:#o}", path, ty);
+                continue;
+            }
+        }
+        debug!("initramfs: add {:?}", path);
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Creates the read-only root filesystem from the embedded archive.
+///
+/// Directories for the other filesystems to be mounted on are created if the
+/// archive does not contain them, as no node can be created later.
+pub fn new_initramfs() -> Arc<DeviceFileSystem> {
+    let mut builder = TreeBuilder::new();
+    match build(INITRAMFS, &mut builder) {
+        Ok(n) => info!(
+            "  loaded {} entries from initramfs ({} bytes)",
+            n,
+            INITRAMFS.len()
+        ),
+        Err(e) => warn!("  failed to load initramfs: {:?}", e),
+    }
+    for mount_point in ["dev", "tmp", "proc", "sys"] {
+        builder.mkdir(mount_point);
+    }
+    Arc::new(builder.fs)
+}
@@ -7,9 +7,19 @@
 //! - `fatfs`: Use [FAT] as the main filesystem and mount it on `/`. This feature
 //!    is **enabled** by default.
 //! - `devfs`: Mount [`axfs_devfs::DeviceFileSystem`] on `/dev`. This feature is
-//!    **enabled** by default.
-//! - `ramfs`: Mount [`axfs_ramfs::RamFileSystem`] on `/tmp`. This feature is
-//!    **enabled** by default.
+//!    **enabled** by default. Nodes of other devices can be added by
+//!    [`register_device`].
+//! - `display`: Add the framebuffer device node `/dev/fb0` to devfs.
+//! - `ramfs`: Mount [`axfs_ramfs::RamFileSystem`] on `/tmp` as a tmpfs, its
+//!    size limit is given by the `AX_TMPFS_SIZE` environment variable (half of
+//!    the free memory by default). This feature is **enabled** by default.
+//! - `initramfs`: Use a read-only filesystem serving the files of a `newc` cpio
+//!    archive as the main filesystem. The archive is embedded in the kernel
+//!    image, its path is given by the `AX_INITRAMFS` environment variable at
+//!    build time (an empty archive if not set). Block devices are not required
+//!    in this case.
+//! - `multitask`: Tasks waiting for [advisory file locks][fops::File::lock]
+//!    are blocked in a wait queue. Otherwise, they keep yielding the CPU.
 //! - `myfs`: Allow users to define their custom filesystems to override the
 //!    default. In this case, [`MyFileSystemIf`] is required to be implemented
 //!    to create and initialize other filesystems. This feature is **disabled** by
@@ -27,20 +37,48 @@ extern crate log;
 extern crate alloc;
 
 mod dev;
+#[cfg(feature = "devfs")]
+mod devices;
 mod fs;
+mod lock;
 mod mounts;
 mod root;
 
 pub mod api;
 pub mod fops;
 
+#[cfg(feature = "devfs")]
+pub use self::devices::register_device;
+
 use axdriver::{prelude::*, AxDeviceContainer};
 
 /// Initializes filesystems by block devices.
+///
+/// The first block device is used by the main filesystem, unless the
+/// `initramfs` feature is enabled.
 pub fn init_filesystems(mut blk_devs: AxDeviceContainer<AxBlockDevice>) {
     info!("Initialize filesystems...");
 
-    let dev = blk_devs.take_one().expect("No block device found!");
-    info!("  use block device 0: {:?}", dev.device_name());
-    self::root::init_rootfs(self::dev::Disk::new(dev));
+    let disk = blk_devs.take_one().map(|dev| {
+        info!("  use block device 0: {:?}", dev.device_name());
+        self::dev::Disk::new(dev)
+    });
+
+    #[cfg(feature = "devfs")]
+    {
+        if let Some(disk) = &disk {
+            self::devices::register_block_device(disk.share())
+                .expect("failed to register block device 0");
+        }
+        let mut i = 1;
+        while let Some(dev) = blk_devs.take_one() {
+            info!("  add block device {}: {:?}", i, dev.device_name());
+            if let Err(e) = self::devices::register_block_device(self::dev::Disk::new(dev)) {
+                warn!("  failed to register block device {}: {:?}", i, e);
+            }
+            i += 1;
+        }
+    }
+
+    self::root::init_rootfs(disk);
 }
@@ -0,0 +1,176 @@
+//! Advisory file locks.
+//!
+//! Two independent kinds of locks are supported, as on Linux:
+//!
+//! - whole-file locks, like `flock(2)`;
+//! - byte-range locks, like `fcntl(2)` with `F_SETLK`, `F_SETLKW` and `F_GETLK`.
+//!
+//! Locks are owned by opened [`File`](crate::fops::File)s (i.e., open file
+//! descriptions), and are released when the file is closed. Byte-range locks
+//! can also be owned by the process, like the POSIX record locks of `fcntl(2)`,
+//! see [`FileLocker::process_locker`](crate::fops::FileLocker::process_locker).
+//! Files are identified by their VFS nodes, so the locks follow a file when
+//! it's renamed.
+//! The files on a filesystem that creates a new node on each lookup don't
+//! share locks if they are opened separately.
+
+use alloc::{collections::BTreeMap, sync::Arc, vec::Vec};
+use axerrno::{ax_err, AxResult};
+use axfs_vfs::VfsNodeRef;
+use spinlock::SpinNoIrq;
+
+/// The owner of the locks held by the process instead of an opened file.
+///
+/// There is only one process, so these locks never conflict with each other.
+pub const PROCESS_OWNER: u64 = 0;
+
+/// The kind of an advisory lock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockKind {
+    /// A shared (read) lock, can be held by multiple owners.
+    Shared,
+    /// An exclusive (write) lock, can be held by only one owner.
+    Exclusive,
+}
+
+/// An advisory lock on the byte range `start..end` of a file.
+///
+/// `end` is [`u64::MAX`] if the lock extends to the end of the file, no matter
+/// how large the file grows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileLock {
+    /// The kind of the lock.
+    pub kind: LockKind,
+    /// The start offset of the locked range.
+    pub start: u64,
+    /// The end offset (exclusive) of the locked range.
+    pub end: u64,
+    /// The ID of the opened file which holds the lock, or [`PROCESS_OWNER`]
+    /// if it's held by the process.
+    pub owner: u64,
+}
+
+impl FileLock {
+    const fn overlaps(&self, start: u64, end: u64) -> bool {
+        self.start < end && start < self.end
+    }
+
+    fn conflicts_with(&self, other: &FileLock) -> bool {
+        self.owner != other.owner
+            && self.overlaps(other.start, other.end)
+            && (self.kind == LockKind::Exclusive || other.kind == LockKind::Exclusive)
+    }
+}
+
+/// Removes the part in `start..end` of the locks held by `owner`.
+fn remove_range(locks: &mut Vec<FileLock>, owner: u64, start: u64, end: u64) {
+    let mut split = Vec::new();
+    locks.retain(|l| {
+        if l.owner != owner || !l.overlaps(start, end) {
+            return true;
+        }
+        if l.start < start {
+            split.push(FileLock { end: start, ..*l });
+        }
+        if l.end > end {
+            split.push(FileLock { start: end, ..*l });
+        }
+        false
+    });
+    locks.extend(split);
+}
+
+/// Returns the key of `node` in the [`LockTable`].
+///
+/// The node can't be dropped and its address reused while it has locks, as
+/// the files holding them keep it alive.
+fn node_key(node: &VfsNodeRef) -> usize {
+    Arc::as_ptr(node) as *const () as usize
+}
+
+/// Locks of all files, indexed by the address of the VFS node.
+pub(crate) struct LockTable {
+    locks: SpinNoIrq<BTreeMap<usize, Vec<FileLock>>>,
+    #[cfg(feature = "multitask")]
+    wq: axtask::WaitQueue,
+}
+
+impl LockTable {
+    pub const fn new() -> Self {
+        Self {
+            locks: SpinNoIrq::new(BTreeMap::new()),
+            #[cfg(feature = "multitask")]
+            wq: axtask::WaitQueue::new(),
+        }
+    }
+
+    /// Returns the first lock held by other owners that conflicts with `lock`.
+    pub fn test(&self, node: &VfsNodeRef, lock: &FileLock) -> Option<FileLock> {
+        let locks = self.locks.lock();
+        locks
+            .get(&node_key(node))
+            .and_then(|list| list.iter().find(|l| l.conflicts_with(lock)).copied())
+    }
+
+    fn try_lock(&self, key: usize, lock: &FileLock) -> bool {
+        let mut locks = self.locks.lock();
+        if let Some(list) = locks.get(&key) {
+            if list.iter().any(|l| l.conflicts_with(lock)) {
+                return false;
+            }
+        }
+        let list = locks.entry(key).or_default();
+        // the new lock replaces the existing locks of the same owner
+        remove_range(list, lock.owner, lock.start, lock.end);
+        list.push(*lock);
+        true
+    }
+
+    /// Acquires the lock, or converts the existing locks of the same owner in
+    /// the range to it.
+    ///
+    /// If the lock conflicts with the locks of other owners, it blocks until
+    /// they are released if `wait` is `true`, otherwise returns
+    /// [`WouldBlock`](axerrno::AxError::WouldBlock).
+    pub fn lock(&self, node: &VfsNodeRef, lock: FileLock, wait: bool) -> AxResult {
+        let key = node_key(node);
+        if self.try_lock(key, &lock) {
+            return Ok(());
+        } else if !wait {
+            return ax_err!(WouldBlock);
+        }
+        #[cfg(feature = "multitask")]
+        self.wq.wait_until(|| self.try_lock(key, &lock));
+        #[cfg(not(feature = "multitask"))]
+        while !self.try_lock(key, &lock) {
+            axtask::yield_now();
+        }
+        Ok(())
+    }
+
+    /// Releases the part in `start..end` of the locks held by `owner`.
+    pub fn unlock(&self, node: &VfsNodeRef, owner: u64, start: u64, end: u64) {
+        let key = node_key(node);
+        let mut locks = self.locks.lock();
+        if let Some(list) = locks.get_mut(&key) {
+            remove_range(list, owner, start, end);
+            if list.is_empty() {
+                locks.remove(&key);
+            }
+        }
+        drop(locks);
+        #[cfg(feature = "multitask")]
+        self.wq.notify_all(false);
+    }
+
+    /// Releases all locks held by `owner`.
+    pub fn unlock_all(&self, node: &VfsNodeRef, owner: u64) {
+        self.unlock(node, owner, 0, u64::MAX);
+    }
+}
+
+/// Whole-file locks, like `flock(2)`.
+pub(crate) static WHOLE_FILE_LOCKS: LockTable = LockTable::new();
+
+/// Byte-range locks, like `fcntl(2)`.
+pub(crate) static RANGE_LOCKS: LockTable = LockTable::new();
@@ -7,18 +7,65 @@ use crate::fs;
 pub(crate) fn devfs() -> Arc<fs::devfs::DeviceFileSystem> {
     let null = fs::devfs::NullDev;
     let zero = fs::devfs::ZeroDev;
-    let bar = fs::devfs::ZeroDev;
+    let random = Arc::new(fs::devfs::RandomDev::new(axhal::time::current_ticks()));
+    let console = Arc::new(crate::devices::ConsoleDev);
     let devfs = fs::devfs::DeviceFileSystem::new();
-    let foo_dir = devfs.mkdir("foo");
     devfs.add("null", Arc::new(null));
     devfs.add("zero", Arc::new(zero));
-    foo_dir.add("bar", Arc::new(bar));
-    Arc::new(devfs)
+    devfs.add("random", random.clone());
+    devfs.add("urandom", random);
+    devfs.add("console", console.clone());
+    devfs.add("ttyS0", console);
+    #[cfg(feature = "display")]
+    devfs.add("fb0", Arc::new(crate::devices::FrameBufferDev));
+
+    let devfs = Arc::new(devfs);
+    crate::devices::attach_devfs(devfs.clone());
+    devfs
 }
 
 #[cfg(feature = "ramfs")]
 pub(crate) fn ramfs() -> Arc<fs::ramfs::RamFileSystem> {
-    Arc::new(fs::ramfs::RamFileSystem::new())
+    use core::ptr::NonNull;
+    use fs::ramfs::{PageAlloc, PAGE_SIZE};
+
+    /// Allocates pages of tmpfs from the global page allocator.
+    struct TmpfsPageAlloc;
+
+    impl PageAlloc for TmpfsPageAlloc {
+        fn alloc_page(&self) -> Option<NonNull<u8>> {
+            let vaddr = axalloc::global_allocator().alloc_pages(1, PAGE_SIZE).ok()?;
+            NonNull::new(vaddr as *mut u8)
+        }
+
+        unsafe fn dealloc_page(&self, page: NonNull<u8>) {
+            axalloc::global_allocator().dealloc_pages(page.as_ptr() as usize, 1)
+        }
+    }
+
+    // Like Linux, limit the size to half of the free memory by default.
+    // No limit if the global allocator is not initialized (e.g., in tests).
+    let max_bytes = option_env!("AX_TMPFS_SIZE")
+        .and_then(|s| s.parse().ok())
+        .filter(|&size| size > 0)
+        .unwrap_or_else(|| match axalloc::global_allocator().available_pages() {
+            0 => usize::MAX,
+            n => n * PAGE_SIZE / 2,
+        });
+    let max_nodes = option_env!("AX_TMPFS_INODES")
+        .and_then(|s| s.parse().ok())
+        .filter(|&n| n > 0)
+        .unwrap_or(usize::MAX);
+    info!(
+        "  tmpfs size limit: {:#x} bytes, {} nodes",
+        max_bytes, max_nodes
+    );
+
+    Arc::new(fs::ramfs::RamFileSystem::new_tmpfs(
+        max_bytes,
+        max_nodes,
+        Arc::new(TmpfsPageAlloc),
+    ))
 }
 
 #[cfg(feature = "procfs")]
@@ -4,7 +4,9 @@
 
 use alloc::{string::String, sync::Arc, vec::Vec};
 use axerrno::{ax_err, AxError, AxResult};
-use axfs_vfs::{VfsNodeAttr, VfsNodeOps, VfsNodeRef, VfsNodeType, VfsOps, VfsResult};
+use axfs_vfs::{
+    FileSystemInfo, VfsNodeAttr, VfsNodeOps, VfsNodeRef, VfsNodeType, VfsOps, VfsResult,
+};
 use axsync::Mutex;
 use lazy_init::LazyInit;
 
@@ -56,8 +58,15 @@ impl RootDirectory {
             return ax_err!(InvalidInput, "mount point already exists");
         }
         // create the mount point in the main filesystem if it does not exist
-        self.main_fs.root_dir().create(path, FileType::Dir)?;
-        fs.mount(path, self.main_fs.root_dir().lookup(path)?)?;
+        let root = self.main_fs.root_dir();
+        let mount_point = match root.clone().lookup(path) {
+            Ok(node) => node,
+            Err(_) => {
+                root.create(path, FileType::Dir)?;
+                root.lookup(path)?
+            }
+        };
+        fs.mount(path, mount_point)?;
         self.mounts.push(MountPoint::new(path, fs));
         Ok(())
     }
@@ -143,11 +152,15 @@ impl VfsNodeOps for RootDirectory {
     }
 }
 
-pub(crate) fn init_rootfs(disk: crate::dev::Disk) {
+pub(crate) fn init_rootfs(disk: Option<crate::dev::Disk>) {
     cfg_if::cfg_if! {
         if #[cfg(feature = "myfs")] { // override the default filesystem
-            let main_fs = fs::myfs::new_myfs(disk);
+            let main_fs = fs::myfs::new_myfs(disk.expect("No block device found!"));
+        } else if #[cfg(feature = "initramfs")] {
+            let _ = disk; // block devices are only accessible in devfs
+            let main_fs = fs::initramfs::new_initramfs();
         } else if #[cfg(feature = "fatfs")] {
+            let disk = disk.expect("No block device found!");
             static FAT_FS: LazyInit<Arc<fs::fatfs::FatFileSystem>> = LazyInit::new();
             FAT_FS.init_by(Arc::new(fs::fatfs::FatFileSystem::new(disk)));
             FAT_FS.init();
@@ -273,6 +286,11 @@ pub(crate) fn remove_dir(dir: Option<&VfsNodeRef>, path: &str) -> AxResult {
     }
 }
 
+pub(crate) fn statfs(path: &str) -> AxResult<FileSystemInfo> {
+    let abs_path = absolute_path(path)?;
+    ROOT_DIR.lookup_mounted_fs(&abs_path, |fs, _| fs.statfs())
+}
+
 pub(crate) fn current_dir() -> AxResult<String> {
     Ok(CURRENT_DIR_PATH.lock().clone())
 }
@@ -198,6 +198,9 @@ fn test_devfs_ramfs() -> Result<()> {
         .collect::<Vec<_>>();
     assert!(dirents.contains(&"null".into()));
     assert!(dirents.contains(&"zero".into()));
+    assert!(dirents.contains(&"urandom".into()));
+    assert!(dirents.contains(&"console".into()));
+    assert!(dirents.contains(&"vda".into()));
 
     // stat /dev
     let dname = "/dev";
@@ -208,13 +211,15 @@ fn test_devfs_ramfs() -> Result<()> {
     assert!(!md.is_file());
     assert!(md.is_dir());
 
-    // stat /dev/foo/bar
-    let fname = ".//.///././/./dev///.///./foo//././bar";
-    let file = File::open(fname)?;
+    // stat and read /dev/urandom
+    let fname = ".//.///././/./dev///.///./urandom";
+    let mut file = File::open(fname)?;
     let md = file.metadata()?;
     println!("metadata of {:?}: {:?}", fname, md);
     assert_eq!(md.file_type(), FileType::CharDevice);
     assert!(!md.is_dir());
+    assert_eq!(file.read(&mut buf)?, N);
+    assert_ne!(buf, [0; N]);
 
     // error cases
     assert_err!(fs::metadata("/dev/null/"), NotADirectory);
@@ -233,12 +238,14 @@ fn test_devfs_ramfs() -> Result<()> {
     assert_eq!(fs::write(".///dev//..//233//.///test.txt", "test"), Ok(()));
     assert_err!(fs::remove_file("./dev//../..//233//.///test.txt"), NotFound);
     assert_eq!(fs::remove_file("./dev//..//233//../233/./test.txt"), Ok(()));
-    assert_eq!(fs::remove_dir("dev//foo/../foo/../.././/233"), Ok(()));
+    assert_eq!(fs::remove_dir("dev//../dev/.././/233"), Ok(()));
     assert_err!(fs::remove_dir("very/../dev//"), PermissionDenied);
 
     // tests in /tmp
     assert_eq!(fs::metadata("tmp")?.file_type(), FileType::Dir);
+    let files = fs::statfs("/tmp")?.files();
     assert_eq!(fs::create_dir(".///tmp///././dir"), Ok(()));
+    assert_eq!(fs::statfs("tmp/dir")?.files(), files + 1);
     assert_eq!(fs::read_dir("tmp").unwrap().count(), 1);
     assert_eq!(fs::write(".///tmp///dir//.///test.txt", "test"), Ok(()));
     assert_eq!(fs::read("tmp//././/dir//.///test.txt"), Ok("test".into()));
@@ -252,6 +259,75 @@ fn test_devfs_ramfs() -> Result<()> {
     Ok(())
 }
 
+fn test_file_lock() -> Result<()> {
+    use axfs::fops::{self, LockKind};
+
+    let fname = "/tmp/lock.txt";
+    println!("test advisory locks on {:?}:", fname);
+
+    // whole-file locks
+    let f1 = File::create(fname)?;
+    let f2 = File::open("/tmp/../tmp/lock.txt")?;
+    f1.try_lock()?;
+    assert_err!(f2.try_lock_shared(), WouldBlock);
+    f1.lock_shared()?; // convert to a shared lock
+    f2.try_lock_shared()?;
+    assert_err!(f1.try_lock(), WouldBlock);
+    f2.unlock()?;
+    f1.try_lock()?;
+    drop(f1); // released on close
+    f2.try_lock()?;
+    drop(f2);
+
+    // byte-range locks, independent of whole-file locks
+    let mut opts = fops::OpenOptions::new();
+    opts.read(true);
+    opts.write(true);
+    let f1 = fops::File::open(fname, &opts)?;
+    let f2 = fops::File::open(fname, &opts)?;
+    f1.lock(LockKind::Exclusive, false)?;
+    f1.lock_range(LockKind::Exclusive, 0, 100, false)?;
+    f2.lock_range(LockKind::Exclusive, 100, u64::MAX, false)?;
+    assert_err!(f2.lock_range(LockKind::Shared, 50, 150, false), WouldBlock);
+    let lock = f2.test_lock_range(LockKind::Shared, 50, 150)?.unwrap();
+    assert_eq!(
+        (lock.kind, lock.start, lock.end),
+        (LockKind::Exclusive, 0, 100)
+    );
+
+    // split the lock by unlocking the middle
+    f1.unlock_range(40, 60)?;
+    f2.lock_range(LockKind::Shared, 40, 60, false)?;
+    assert!(f2.test_lock_range(LockKind::Exclusive, 30, 40)?.is_some());
+    assert!(f1.test_lock_range(LockKind::Shared, 0, 100)?.is_none());
+    assert_err!(f1.test_lock_range(LockKind::Shared, 100, 100), InvalidInput);
+    drop(f2);
+    assert!(f1
+        .test_lock_range(LockKind::Exclusive, 0, u64::MAX)?
+        .is_none());
+    drop(f1);
+
+    // record locks of the process, shared by all opened files
+    let f1 = fops::File::open(fname, &opts)?;
+    let f2 = fops::File::open(fname, &opts)?;
+    let f3 = fops::File::open(fname, &opts)?;
+    let (p1, p2) = (f1.locker().process_locker(), f2.locker().process_locker());
+    p1.lock_range(LockKind::Exclusive, 0, 100, false)?;
+    p2.lock_range(LockKind::Exclusive, 50, 150, false)?; // never conflict
+    assert_err!(f3.lock_range(LockKind::Shared, 0, 10, false), WouldBlock);
+    p2.unlock_range(0, 100)?; // unlock on another file
+    f3.lock_range(LockKind::Exclusive, 0, 100, false)?;
+    assert_err!(p1.lock_range(LockKind::Shared, 0, 10, false), WouldBlock);
+    assert!(f3.test_lock_range(LockKind::Shared, 100, 150)?.is_some());
+    drop(f2); // closing any of the files releases them
+    assert!(f3.test_lock_range(LockKind::Exclusive, 100, 150)?.is_none());
+    drop((f1, f3));
+
+    fs::remove_file(fname)?;
+    println!("test_file_lock() OK!");
+    Ok(())
+}
+
 pub fn test_all() {
     test_read_write_file().expect("test_read_write_file() failed");
     test_read_dir().expect("test_read_dir() failed");
@@ -259,4 +335,5 @@ pub fn test_all() {
     test_create_file_dir().expect("test_create_file_dir() failed");
     test_remove_file_dir().expect("test_remove_file_dir() failed");
     test_devfs_ramfs().expect("test_devfs_ramfs() failed");
+    test_file_lock().expect("test_file_lock() failed");
 }
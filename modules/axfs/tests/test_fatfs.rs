@@ -3,7 +3,9 @@
 mod test_common;
 
 use axdriver::AxDeviceContainer;
-use driver_block::ramdisk::RamDisk;
+use axfs::api::{File, FileType};
+use axio::{Read, Result};
+use driver_block::{ramdisk::RamDisk, BlockDriverOps};
 
 const IMG_PATH: &str = "resources/fat16.img";
 
@@ -15,13 +17,37 @@ fn make_disk() -> std::io::Result<RamDisk> {
     Ok(RamDisk::from(&data))
 }
 
+fn test_block_device(img_size: u64) -> Result<()> {
+    // stat and read /dev/vda, which shares the disk with the root filesystem
+    let fname = "/dev/vda";
+    let mut file = File::open(fname)?;
+    let md = file.metadata()?;
+    println!("metadata of {:?}: {:?}", fname, md);
+    assert_eq!(md.file_type(), FileType::BlockDevice);
+    assert_eq!(md.len(), img_size);
+
+    let mut sector = [0; 512];
+    assert_eq!(file.read(&mut sector[..3])?, 3);
+    assert_eq!(file.read(&mut sector[3..])?, 509);
+    assert_eq!(sector[510..], [0x55, 0xaa]); // boot sector signature
+
+    let mut sector2 = [0; 512];
+    assert_eq!(File::open(fname)?.read(&mut sector2)?, 512);
+    assert_eq!(sector, sector2);
+
+    println!("test_block_device() OK!");
+    Ok(())
+}
+
 #[test]
 fn test_fatfs() {
     println!("Testing fatfs with ramdisk ...");
 
     let disk = make_disk().expect("failed to load disk image");
+    let img_size = disk.num_blocks() * disk.block_size() as u64;
     axtask::init_scheduler(); // call this to use `axsync::Mutex`.
     axfs::init_filesystems(AxDeviceContainer::from_one(disk));
 
     test_common::test_all();
+    test_block_device(img_size).expect("test_block_device() failed");
 }
@@ -60,13 +60,15 @@ fn handle_sync_exception(tf: &mut TrapFrame) {
         Some(ESR_EL1::EC::Value::DataAbortCurrentEL)
         | Some(ESR_EL1::EC::Value::InstrAbortCurrentEL) => {
             let iss = esr.read(ESR_EL1::ISS);
-            panic!(
-                "EL1 Page Fault @ {:#x}, FAR={:#x}, ISS={:#x}:\n{:#x?}",
-                tf.elr,
-                FAR_EL1.get(),
-                iss,
-                tf,
-            );
+            let vaddr = FAR_EL1.get() as usize;
+            // the trap frame is pushed onto the kernel stack
+            let sp = tf as *const TrapFrame as usize + core::mem::size_of::<TrapFrame>();
+            if !crate::trap::handle_page_fault_extern(vaddr.into(), sp.into()) {
+                panic!(
+                    "EL1 Page Fault @ {:#x}, FAR={:#x}, ISS={:#x}:\n{:#x?}",
+                    tf.elr, vaddr, iss, tf,
+                );
+            }
         }
         _ => {
             panic!(
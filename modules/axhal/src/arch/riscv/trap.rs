@@ -1,4 +1,5 @@
 use riscv::register::scause::{self, Exception as E, Trap};
+use riscv::register::stval;
 
 use super::TrapFrame;
 
@@ -14,11 +15,26 @@ fn handle_breakpoint(sepc: &mut usize) {
     *sepc += 2
 }
 
+fn handle_page_fault(tf: &TrapFrame, cause: Trap) {
+    let vaddr = stval::read();
+    if !crate::trap::handle_page_fault_extern(vaddr.into(), tf.regs.sp.into()) {
+        panic!(
+            "Unhandled Supervisor Page Fault {:?} @ {:#x}, fault_vaddr={:#x}:\n{:#x?}",
+            cause, tf.sepc, vaddr, tf
+        );
+    }
+}
+
 #[no_mangle]
-fn riscv_trap_handler(tf: &mut TrapFrame, _from_user: bool) {
+fn riscv_trap_handler(tf: &mut TrapFrame, from_user: bool) {
     let scause = scause::read();
     match scause.cause() {
         Trap::Exception(E::Breakpoint) => handle_breakpoint(&mut tf.sepc),
+        Trap::Exception(E::LoadPageFault | E::StorePageFault | E::InstructionPageFault)
+            if !from_user =>
+        {
+            handle_page_fault(tf, scause.cause())
+        }
         Trap::Interrupt(_) => crate::trap::handle_irq_extern(scause.bits()),
         _ => {
             panic!(
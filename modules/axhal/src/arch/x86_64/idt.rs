@@ -5,6 +5,7 @@ use x86_64::structures::idt::{Entry, HandlerFunc, InterruptDescriptorTable};
 use x86_64::structures::DescriptorTablePointer;
 
 const NUM_INT: usize = 256;
+const DOUBLE_FAULT_VECTOR: usize = 8;
 
 /// A wrapper of the Interrupt Descriptor Table (IDT).
 #[repr(transparent)]
@@ -13,8 +14,15 @@ pub struct IdtStruct {
 }
 
 impl IdtStruct {
+    /// The index in the Interrupt Stack Table (IST) of the stack used by the
+    /// double fault handler.
+    pub const DOUBLE_FAULT_IST_INDEX: u16 = 0;
+
     /// Constructs a new IDT struct that filled with entries from
     /// `trap_handler_table`.
+    ///
+    /// The double fault handler runs on the stack given by the IST entry
+    /// [`DOUBLE_FAULT_IST_INDEX`](Self::DOUBLE_FAULT_IST_INDEX) of the TSS.
     #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
         extern "C" {
@@ -32,7 +40,12 @@ impl IdtStruct {
             )
         };
         for i in 0..NUM_INT {
-            entries[i].set_handler_fn(unsafe { core::mem::transmute(ENTRIES[i]) });
+            let opts = entries[i].set_handler_fn(unsafe { core::mem::transmute(ENTRIES[i]) });
+            if i == DOUBLE_FAULT_VECTOR {
+                // A stack overflow causes a double fault, as the trap frame
+                // can't be pushed, so it must be handled on another stack.
+                unsafe { opts.set_stack_index(Self::DOUBLE_FAULT_IST_INDEX) };
+            }
         }
         idt
     }
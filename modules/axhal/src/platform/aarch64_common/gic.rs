@@ -10,6 +10,9 @@ pub const MAX_IRQ_COUNT: usize = 1024;
 /// The timer IRQ number.
 pub const TIMER_IRQ_NUM: usize = translate_irq(14, InterruptType::PPI).unwrap();
 
+/// The IPI (inter-processor interrupt) number, an SGI.
+pub const IPI_IRQ_NUM: usize = translate_irq(1, InterruptType::SGI).unwrap();
+
 /// The UART IRQ number.
 pub const UART_IRQ_NUM: usize = translate_irq(axconfig::UART_IRQ, InterruptType::SPI).unwrap();
 
@@ -37,6 +40,12 @@ pub fn register_handler(irq_num: usize, handler: IrqHandler) -> bool {
     crate::irq::register_handler_common(irq_num, handler)
 }
 
+/// Sends an IPI (inter-processor interrupt) to the given CPU.
+#[cfg(feature = "smp")]
+pub fn send_ipi(dest_cpu_id: usize) {
+    GICD.lock().send_sgi(dest_cpu_id, IPI_IRQ_NUM);
+}
+
 /// Dispatches the IRQ.
 ///
 /// This function is called by the common interrupt handler. It looks
@@ -57,4 +66,6 @@ pub(crate) fn init_primary() {
 #[cfg(feature = "smp")]
 pub(crate) fn init_secondary() {
     GICC.init();
+    // SGIs and PPIs are banked for each CPU
+    GICD.lock().set_enable(IPI_IRQ_NUM, true);
 }
@@ -2,6 +2,14 @@
 
 use crate::arch::{GdtStruct, IdtStruct, TaskStateSegment};
 use lazy_init::LazyInit;
+use x86_64::VirtAddr;
+
+const DOUBLE_FAULT_STACK_SIZE: usize = 0x4000;
+
+/// Per-CPU stacks to handle double faults, which may be caused by overflows
+/// of the current stack.
+static mut DOUBLE_FAULT_STACKS: [[u8; DOUBLE_FAULT_STACK_SIZE]; axconfig::SMP] =
+    [[0; DOUBLE_FAULT_STACK_SIZE]; axconfig::SMP];
 
 static IDT: LazyInit<IdtStruct> = LazyInit::new();
 
@@ -16,7 +24,11 @@ fn init_percpu() {
         IDT.load();
         let tss = TSS.current_ref_mut_raw();
         let gdt = GDT.current_ref_mut_raw();
-        tss.init_by(TaskStateSegment::new());
+        let stack = core::ptr::addr_of!(DOUBLE_FAULT_STACKS[crate::cpu::this_cpu_id()]);
+        let mut new_tss = TaskStateSegment::new();
+        new_tss.interrupt_stack_table[IdtStruct::DOUBLE_FAULT_IST_INDEX as usize] =
+            VirtAddr::from_ptr(stack) + DOUBLE_FAULT_STACK_SIZE as u64;
+        tss.init_by(new_tss);
         gdt.init_by(GdtStruct::new(tss));
         gdt.load();
         gdt.load_tss();
@@ -1,6 +1,7 @@
 //! Trap handling.
 
 use crate_interface::{call_interface, def_interface};
+use memory_addr::VirtAddr;
 
 /// Trap handler interface.
 ///
@@ -13,7 +14,11 @@ use crate_interface::{call_interface, def_interface};
 pub trait TrapHandler {
     /// Handles interrupt requests for the given IRQ number.
     fn handle_irq(irq_num: usize);
-    // more e.g.: handle_page_fault();
+    /// Handles the page fault at `vaddr` in the kernel mode, where the stack
+    /// pointer is `sp`, such as a hit on the guard page of a task stack.
+    ///
+    /// Returns `false` if it's not handled, then the kernel panics.
+    fn handle_page_fault(vaddr: VirtAddr, sp: VirtAddr) -> bool;
 }
 
 /// Call the external IRQ handler.
@@ -21,3 +26,9 @@ pub trait TrapHandler {
 pub(crate) fn handle_irq_extern(irq_num: usize) {
     call_interface!(TrapHandler::handle_irq, irq_num);
 }
+
+/// Call the external page fault handler.
+#[allow(dead_code)]
+pub(crate) fn handle_page_fault_extern(vaddr: VirtAddr, sp: VirtAddr) -> bool {
+    call_interface!(TrapHandler::handle_page_fault, vaddr, sp)
+}
@@ -0,0 +1,362 @@
+//! Memory mappings in the memory mapping region.
+
+use alloc::collections::BTreeMap;
+use axalloc::global_allocator;
+use axerrno::{ax_err, AxResult};
+use axhal::mem::{phys_to_virt, virt_to_phys, PhysAddr, VirtAddr, PAGE_SIZE_4K};
+use axhal::paging::{MappingFlags, PageSize, PageTable};
+use memory_addr::{align_up_4k, is_aligned_4k};
+use spinlock::SpinNoIrq;
+
+use crate::{kernel_page_table, paging_err_to_ax_err};
+
+const MMAP_START: usize = axconfig::MMAP_BASE_VADDR;
+const MMAP_END: usize = axconfig::MMAP_BASE_VADDR + axconfig::MMAP_SIZE;
+
+/// The size of the guard page below each stack allocated by [`alloc_stack`].
+pub const STACK_GUARD_SIZE: usize = PAGE_SIZE_4K;
+
+/// Mapped areas in the memory mapping region, `start => end`.
+static AREAS: SpinNoIrq<BTreeMap<usize, usize>> = SpinNoIrq::new(BTreeMap::new());
+
+fn check_range(start: usize, size: usize) -> AxResult {
+    if !is_aligned_4k(start) || !is_aligned_4k(size) || size == 0 {
+        return ax_err!(InvalidInput);
+    }
+    match start.checked_add(size) {
+        Some(end) if start >= MMAP_START && end <= MMAP_END => Ok(()),
+        _ => ax_err!(InvalidInput),
+    }
+}
+
+/// Returns whether `start..end` does not overlap with any mapped areas.
+fn is_free(areas: &BTreeMap<usize, usize>, start: usize, end: usize) -> bool {
+    areas
+        .range(..end)
+        .next_back()
+        .map_or(true, |(_, &area_end)| area_end <= start)
+}
+
+/// Returns whether `start..end` is fully covered by mapped areas.
+fn is_mapped(areas: &BTreeMap<usize, usize>, start: usize, end: usize) -> bool {
+    let mut pos = start;
+    for (&area_start, &area_end) in areas.range(..end) {
+        if area_end <= pos {
+            continue;
+        }
+        if area_start > pos {
+            return false;
+        }
+        pos = area_end;
+    }
+    pos >= end
+}
+
+/// Finds a free range of `size` bytes, prefers the one starts at `hint`.
+fn find_free(areas: &BTreeMap<usize, usize>, hint: Option<usize>, size: usize) -> Option<usize> {
+    if let Some(hint) = hint {
+        if check_range(hint, size).is_ok() && is_free(areas, hint, hint + size) {
+            return Some(hint);
+        }
+    }
+    let mut pos = MMAP_START;
+    for (&area_start, &area_end) in areas.iter() {
+        if area_start - pos >= size {
+            return Some(pos);
+        }
+        pos = area_end;
+    }
+    if MMAP_END - pos >= size {
+        Some(pos)
+    } else {
+        None
+    }
+}
+
+/// Removes `start..end` from the mapped areas, splits the areas if needed.
+fn remove_area(areas: &mut BTreeMap<usize, usize>, start: usize, end: usize) {
+    let overlapped = areas
+        .range(..end)
+        .filter(|(_, &area_end)| area_end > start)
+        .map(|(&s, &e)| (s, e))
+        .collect::<alloc::vec::Vec<_>>();
+    for (area_start, area_end) in overlapped {
+        areas.remove(&area_start);
+        if area_start < start {
+            areas.insert(area_start, start);
+        }
+        if area_end > end {
+            areas.insert(end, area_end);
+        }
+    }
+}
+
+/// Allocates zeroed frames and maps them to `start..start + size`.
+fn populate(pt: &mut PageTable, start: usize, size: usize, flags: MappingFlags) -> AxResult {
+    for vaddr in (start..start + size).step_by(PAGE_SIZE_4K) {
+        let res = global_allocator()
+            .alloc_pages(1, PAGE_SIZE_4K)
+            .map_err(|_| axerrno::AxError::NoMemory)
+            .and_then(|frame| {
+                unsafe { core::ptr::write_bytes(frame as *mut u8, 0, PAGE_SIZE_4K) };
+                let paddr = virt_to_phys(frame.into());
+                pt.map(vaddr.into(), paddr, PageSize::Size4K, flags)
+                    .map_err(|e| {
+                        global_allocator().dealloc_pages(frame, 1);
+                        paging_err_to_ax_err(e)
+                    })
+            });
+        if let Err(e) = res {
+            depopulate(pt, start, vaddr - start);
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+/// Unmaps `start..start + size` and frees the frames.
+fn depopulate(pt: &mut PageTable, start: usize, size: usize) {
+    for vaddr in (start..start + size).step_by(PAGE_SIZE_4K) {
+        if let Ok((paddr, _)) = pt.unmap(vaddr.into()) {
+            global_allocator().dealloc_pages(phys_to_virt(paddr).as_usize(), 1);
+            axhal::arch::flush_tlb(Some(vaddr.into()));
+        }
+    }
+}
+
+/// Creates a mapping of `size` bytes filled with zeros, returns its start
+/// address.
+///
+/// If `fixed` is `true`, the mapping is placed at `hint` exactly, replacing
+/// the existing mappings there. Otherwise, `hint` is only a preferred start
+/// address, and a free range is chosen if it's not available.
+pub fn map_anonymous(
+    hint: Option<VirtAddr>,
+    size: usize,
+    flags: MappingFlags,
+    fixed: bool,
+) -> AxResult<VirtAddr> {
+    let size = align_up_4k(size);
+    let hint = hint.map(|v| v.as_usize());
+    let mut areas = AREAS.lock();
+    let start = if fixed {
+        let start = hint.unwrap_or_default();
+        check_range(start, size)?;
+        depopulate(&mut kernel_page_table(), start, size);
+        remove_area(&mut areas, start, start + size);
+        start
+    } else {
+        if size == 0 {
+            return ax_err!(InvalidInput);
+        }
+        find_free(&areas, hint, size).ok_or(axerrno::AxError::NoMemory)?
+    };
+    populate(&mut kernel_page_table(), start, size, flags)?;
+    areas.insert(start, start + size);
+    debug!(
+        "map anonymous: [{:#x}, {:#x}) {:?}",
+        start,
+        start + size,
+        flags
+    );
+    Ok(start.into())
+}
+
+/// Allocates a stack of `size` bytes with an unmapped guard page of
+/// [`STACK_GUARD_SIZE`] bytes below it, returns the bottom of the stack.
+///
+/// The guard page is reserved so no other mappings can be placed there, any
+/// access to it causes a page fault.
+pub fn alloc_stack(size: usize) -> AxResult<VirtAddr> {
+    let size = align_up_4k(size);
+    if size == 0 {
+        return ax_err!(InvalidInput);
+    }
+    let mut areas = AREAS.lock();
+    let start =
+        find_free(&areas, None, STACK_GUARD_SIZE + size).ok_or(axerrno::AxError::NoMemory)?;
+    let bottom = start + STACK_GUARD_SIZE;
+    populate(
+        &mut kernel_page_table(),
+        bottom,
+        size,
+        MappingFlags::READ | MappingFlags::WRITE,
+    )?;
+    areas.insert(start, bottom + size);
+    debug!(
+        "alloc stack: [{:#x}, {:#x}), guard page at {:#x}",
+        bottom,
+        bottom + size,
+        start
+    );
+    Ok(bottom.into())
+}
+
+/// Frees the stack allocated by [`alloc_stack`] and its guard page.
+pub fn dealloc_stack(bottom: VirtAddr, size: usize) {
+    let bottom = bottom.as_usize();
+    let size = align_up_4k(size);
+    let mut areas = AREAS.lock();
+    depopulate(&mut kernel_page_table(), bottom, size);
+    remove_area(&mut areas, bottom - STACK_GUARD_SIZE, bottom + size);
+    debug!("dealloc stack: [{:#x}, {:#x})", bottom, bottom + size);
+}
+
+/// Removes the mappings in `start..start + size` and frees the memory.
+///
+/// It's not an error if the range contains no mappings.
+pub fn unmap_region(start: VirtAddr, size: usize) -> AxResult {
+    let start = start.as_usize();
+    let size = align_up_4k(size);
+    check_range(start, size)?;
+    let mut areas = AREAS.lock();
+    depopulate(&mut kernel_page_table(), start, size);
+    remove_area(&mut areas, start, start + size);
+    debug!("unmap: [{:#x}, {:#x})", start, start + size);
+    Ok(())
+}
+
+/// Changes the mapping flags of `start..start + size`.
+///
+/// Returns [`NoMemory`](axerrno::AxError::NoMemory) if the range is not fully
+/// mapped.
+pub fn protect_region(start: VirtAddr, size: usize, flags: MappingFlags) -> AxResult {
+    let start = start.as_usize();
+    let size = align_up_4k(size);
+    check_range(start, size)?;
+    let areas = AREAS.lock();
+    if !is_mapped(&areas, start, start + size) {
+        return ax_err!(NoMemory);
+    }
+    let mut pt = kernel_page_table();
+    for vaddr in (start..start + size).step_by(PAGE_SIZE_4K) {
+        pt.update(vaddr.into(), None, Some(flags))
+            .map_err(paging_err_to_ax_err)?;
+        axhal::arch::flush_tlb(Some(vaddr.into()));
+    }
+    Ok(())
+}
+
+/// Expands or shrinks the mapping at `old_start`, returns its new start
+/// address.
+///
+/// If the mapping can not be expanded in place, it's moved to a new address
+/// if `may_move` is `true`, otherwise [`NoMemory`](axerrno::AxError::NoMemory)
+/// is returned. The contents are kept, and the expanded part is filled with
+/// zeros. The mapping is left unchanged if it fails.
+pub fn remap_region(
+    old_start: VirtAddr,
+    old_size: usize,
+    new_size: usize,
+    may_move: bool,
+) -> AxResult<VirtAddr> {
+    let old_start = old_start.as_usize();
+    let old_size = align_up_4k(old_size);
+    let new_size = align_up_4k(new_size);
+    check_range(old_start, old_size)?;
+    if new_size == 0 {
+        return ax_err!(InvalidInput);
+    }
+    let old_end = old_start + old_size;
+
+    let mut areas = AREAS.lock();
+    if !is_mapped(&areas, old_start, old_end) {
+        return ax_err!(BadAddress);
+    }
+    let mut pt = kernel_page_table();
+    let (_, flags, _) = pt
+        .query((old_end - PAGE_SIZE_4K).into())
+        .map_err(paging_err_to_ax_err)?;
+
+    if new_size <= old_size {
+        depopulate(&mut pt, old_start + new_size, old_size - new_size);
+        remove_area(&mut areas, old_start + new_size, old_end);
+        return Ok(old_start.into());
+    }
+
+    let grow = new_size - old_size;
+    if check_range(old_end, grow).is_ok() && is_free(&areas, old_end, old_end + grow) {
+        // expand in place
+        populate(&mut pt, old_end, grow, flags)?;
+        areas.insert(old_end, old_end + grow);
+        return Ok(old_start.into());
+    }
+    if !may_move {
+        return ax_err!(NoMemory);
+    }
+
+    let new_start = find_free(&areas, None, new_size).ok_or(axerrno::AxError::NoMemory)?;
+    populate(&mut pt, new_start + old_size, grow, flags)?;
+    // move the frames to the new address without copying, the old address is
+    // unmapped only after all of them are mapped, so nothing is changed if it
+    // fails
+    let mut moved = 0;
+    let res = (0..old_size)
+        .step_by(PAGE_SIZE_4K)
+        .try_for_each(|off| -> AxResult {
+            let (paddr, flags, _) = pt
+                .query((old_start + off).into())
+                .map_err(paging_err_to_ax_err)?;
+            pt.map((new_start + off).into(), paddr, PageSize::Size4K, flags)
+                .map_err(paging_err_to_ax_err)?;
+            moved += PAGE_SIZE_4K;
+            Ok(())
+        });
+    if let Err(e) = res {
+        for vaddr in (new_start..new_start + moved).step_by(PAGE_SIZE_4K) {
+            let _ = pt.unmap(vaddr.into());
+            axhal::arch::flush_tlb(Some(vaddr.into()));
+        }
+        depopulate(&mut pt, new_start + old_size, grow);
+        return Err(e);
+    }
+    for vaddr in (old_start..old_end).step_by(PAGE_SIZE_4K) {
+        let _ = pt.unmap(vaddr.into());
+        axhal::arch::flush_tlb(Some(vaddr.into()));
+    }
+    remove_area(&mut areas, old_start, old_end);
+    areas.insert(new_start, new_start + new_size);
+    debug!(
+        "remap: [{:#x}, {:#x}) => [{:#x}, {:#x})",
+        old_start,
+        old_end,
+        new_start,
+        new_start + new_size
+    );
+    Ok(new_start.into())
+}
+
+/// Calls `f` with the frames of `start..start + len` in order, regardless of
+/// the mapping flags.
+fn for_each_frame<F>(start: usize, len: usize, mut f: F) -> AxResult
+where
+    F: FnMut(PhysAddr, usize, usize),
+{
+    let pt = kernel_page_table();
+    let mut pos = start;
+    while pos < start + len {
+        let (paddr, _, _) = pt.query(pos.into()).map_err(paging_err_to_ax_err)?;
+        let chunk = (PAGE_SIZE_4K - pos % PAGE_SIZE_4K).min(start + len - pos);
+        f(paddr, pos - start, chunk);
+        pos += chunk;
+    }
+    Ok(())
+}
+
+/// Reads the memory of the mappings at `start` into `buf`, even if it's not
+/// readable.
+pub fn read_region(start: VirtAddr, buf: &mut [u8]) -> AxResult {
+    for_each_frame(start.as_usize(), buf.len(), |paddr, off, len| {
+        let src = phys_to_virt(paddr).as_ptr();
+        unsafe { core::ptr::copy_nonoverlapping(src, buf[off..].as_mut_ptr(), len) };
+    })
+}
+
+/// Writes `data` into the memory of the mappings at `start`, even if it's not
+/// writable.
+pub fn write_region(start: VirtAddr, data: &[u8]) -> AxResult {
+    for_each_frame(start.as_usize(), data.len(), |paddr, off, len| {
+        let dst = phys_to_virt(paddr).as_mut_ptr();
+        unsafe { core::ptr::copy_nonoverlapping(data[off..].as_ptr(), dst, len) };
+    })
+}
@@ -0,0 +1,74 @@
+//! [ArceOS](https://github.com/rcore-os/arceos) virtual memory management module.
+//!
+//! It owns the kernel page table, and manages memory mappings (like `mmap`)
+//! in a dedicated virtual address region, which is given by
+//! [`axconfig::MMAP_BASE_VADDR`] and [`axconfig::MMAP_SIZE`].
+//!
+//! All pages of a mapping are allocated and mapped when it's created, so no
+//! page faults are needed to populate them.
+//!
+//! Task stacks can also be allocated in this region by [`alloc_stack`], each
+//! with an unmapped guard page below it to catch stack overflows.
+
+#![no_std]
+
+#[macro_use]
+extern crate log;
+extern crate alloc;
+
+mod area;
+
+use axhal::mem::{memory_regions, phys_to_virt};
+use axhal::paging::{PageTable, PagingError};
+use lazy_init::LazyInit;
+use spinlock::{SpinNoIrq, SpinNoIrqGuard};
+
+pub use self::area::{
+    alloc_stack, dealloc_stack, map_anonymous, protect_region, read_region, remap_region,
+    unmap_region, write_region, STACK_GUARD_SIZE,
+};
+#[doc(no_inline)]
+pub use axhal::paging::MappingFlags;
+
+static KERNEL_PAGE_TABLE: LazyInit<SpinNoIrq<PageTable>> = LazyInit::new();
+
+/// Returns the kernel page table.
+///
+/// Mappings in the memory mapping region should not be modified by it
+/// directly, use [`map_anonymous`], [`unmap_region`], etc. instead.
+pub fn kernel_page_table() -> SpinNoIrqGuard<'static, PageTable> {
+    KERNEL_PAGE_TABLE.lock()
+}
+
+/// Creates the kernel page table which maps all memory regions, and switches
+/// to it.
+///
+/// It must be called on the primary CPU, after the global allocator is
+/// initialized.
+pub fn init_memory_management() {
+    info!("Initialize kernel page table...");
+    let mut kernel_page_table = PageTable::try_new().expect("failed to create kernel page table");
+    for r in memory_regions() {
+        kernel_page_table
+            .map_region(phys_to_virt(r.paddr), r.paddr, r.size, r.flags.into(), true)
+            .expect("failed to remap kernel memory");
+    }
+    KERNEL_PAGE_TABLE.init_by(SpinNoIrq::new(kernel_page_table));
+    init_memory_management_secondary();
+}
+
+/// Switches to the kernel page table on secondary CPUs.
+pub fn init_memory_management_secondary() {
+    unsafe { axhal::arch::write_page_table_root(KERNEL_PAGE_TABLE.lock().root_paddr()) };
+}
+
+const fn paging_err_to_ax_err(err: PagingError) -> axerrno::AxError {
+    use axerrno::AxError;
+    match err {
+        PagingError::NoMemory => AxError::NoMemory,
+        PagingError::NotAligned => AxError::InvalidInput,
+        PagingError::NotMapped => AxError::BadAddress,
+        PagingError::AlreadyMapped => AxError::AlreadyExists,
+        PagingError::MappedToHugePage => AxError::BadState,
+    }
+}
@@ -8,12 +8,45 @@
 //!
 //! - [`TcpSocket`]: A TCP socket that provides POSIX-like APIs.
 //! - [`UdpSocket`]: A UDP socket that provides POSIX-like APIs.
-//! - [`dns_query`]: Function for DNS query.
+//! - [`IcmpSocket`]: An ICMP socket for echo requests and replies (ping).
+//! - [`RawSocket`]: A raw IP socket that provides POSIX-like APIs.
+//! - [`dns_query`]: Function for DNS query. The DNS servers can be replaced
+//!   by [`set_dns_servers`].
+//! - [`interfaces`], [`add_route`] and [`remove_route`]: Functions to list
+//!   network interfaces and manage the routing table.
+//!
+//! Besides the blocking and nonblocking operations, TCP and UDP sockets provide
+//! asynchronous ones (the `poll_*` methods), which register the waker of the
+//! caller on the socket instead of blocking.
+//!
+//! Every NIC becomes a network interface named `eth0`, `eth1`, etc. The
+//! loopback interface `lo` (`127.0.0.1/8` and `::1`) is always present, even
+//! if no NIC is found. The egress interface of a socket is selected by the
+//! routing table, unless it's bound to an interface by `bind_to_device`.
+//!
+//! Both IPv4 and IPv6 are supported. The IPv4 address is obtained by DHCP if
+//! the `dhcp` feature is enabled, and falls back to the one given by the `AX_IP`
+//! and `AX_GW` environment variables at build time. The IPv6 address is given
+//! by `AX_IP6` and `AX_GW6`, or autoconfigured by SLAAC if `AX_IP6` is empty.
+//! A link-local IPv6 address is always configured. Each variable is a
+//! comma-separated list, of which the i-th item is for `eth<i>`.
 //!
 //! # Cargo Features
 //!
 //! - `smoltcp`: Use [smoltcp] as the underlying network stack. This is enabled
 //!   by default.
+//! - `dhcp`: Obtain the IPv4 address, gateway and DNS servers by DHCP.
+//! - `fs`: Look up names in `/etc/hosts`, and read the DNS servers from
+//!   `/etc/resolv.conf`.
+//! - `multitask`: Renew the DHCP lease in a background task.
+//! - `irq`: Let the background task sleep between polls instead of yielding.
+//!   Along with `multitask`, the network stack is driven by NIC interrupts:
+//!   the interfaces are polled in a background task woken by the interrupts
+//!   and timers, and blocking socket operations sleep until their sockets
+//!   become ready. It falls back to polling if some NIC has no interrupt,
+//!   which is the case for VirtIO PCI devices (their INTx routing is not
+//!   supported), and for VirtIO MMIO devices on riscv64 (PLIC is not
+//!   supported).
 //!
 //! [smoltcp]: https://github.com/smoltcp-rs/smoltcp
 
@@ -34,16 +67,25 @@ cfg_if::cfg_if! {
 
 pub use self::net_impl::TcpSocket;
 pub use self::net_impl::UdpSocket;
+pub use self::net_impl::{add_route, interfaces, remove_route, InterfaceInfo};
 pub use self::net_impl::{bench_receive, bench_transmit};
-pub use self::net_impl::{dns_query, poll_interfaces};
+pub use self::net_impl::{dns_query, dns_servers, set_dns_servers};
+pub use self::net_impl::{poll_interfaces, IcmpSocket, RawSocket};
 
+use alloc::vec::Vec;
 use axdriver::{prelude::*, AxDeviceContainer};
 
 /// Initializes the network subsystem by NIC devices.
 pub fn init_network(mut net_devs: AxDeviceContainer<AxNetDevice>) {
     info!("Initialize network subsystem...");
 
-    let dev = net_devs.take_one().expect("No NIC device found!");
-    info!("  use NIC 0: {:?}", dev.device_name());
-    net_impl::init(dev);
+    let mut devs = Vec::new();
+    while let Some(dev) = net_devs.take_one() {
+        info!("  use NIC {}: {:?}", devs.len(), dev.device_name());
+        devs.push(dev);
+    }
+    if devs.is_empty() {
+        warn!("  no NIC device found, only the loopback interface is available");
+    }
+    net_impl::init(devs);
 }
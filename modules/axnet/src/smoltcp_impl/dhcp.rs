@@ -0,0 +1,146 @@
+//! DHCPv4 client.
+//!
+//! A DHCP socket is created on each interface when the network is
+//! initialized, which waits up to [`DHCP_TIMEOUT`] for a lease. The lease is renewed by the socket when the
+//! interface is polled; if the `multitask` feature is enabled, a background
+//! task keeps polling it. If no lease is obtained, or the lease is lost, the
+//! address and gateway given at compile time are used.
+
+use alloc::{vec, vec::Vec};
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::time::Duration;
+
+use axhal::time::current_time;
+use lazy_init::LazyInit;
+use smoltcp::socket::dhcpv4::{self, Event};
+use smoltcp::wire::{IpAddress, Ipv4Address, Ipv4Cidr};
+
+use super::{dns, setup_static_ipv4, SocketHandle, IFACES, SOCKET_SET};
+
+const DHCP_TIMEOUT: Duration = Duration::from_secs(5);
+#[cfg(feature = "multitask")]
+const DHCP_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+static CLIENTS: LazyInit<Vec<DhcpClient>> = LazyInit::new();
+
+struct DhcpClient {
+    iface: usize,
+    handle: SocketHandle,
+    configured: AtomicBool,
+}
+
+impl DhcpClient {
+    fn is_configured(&self) -> bool {
+        self.configured.load(Ordering::Acquire)
+    }
+}
+
+enum DhcpEvent {
+    Configured(Ipv4Cidr, Option<Ipv4Address>, Vec<IpAddress>),
+    Deconfigured,
+}
+
+/// Starts the DHCP clients and waits for leases.
+///
+/// Returns whether each interface is configured by DHCP before timeout.
+pub fn init() -> Vec<bool> {
+    CLIENTS.init_by(
+        IFACES
+            .iter()
+            .filter(|iface| !iface.is_loopback())
+            .map(|iface| DhcpClient {
+                iface: iface.index,
+                handle: SOCKET_SET.add(dhcpv4::Socket::new(), iface.index),
+                configured: AtomicBool::new(false),
+            })
+            .collect(),
+    );
+    info!("DHCP: waiting for leases...");
+
+    let deadline = current_time() + DHCP_TIMEOUT;
+    while !CLIENTS.iter().all(DhcpClient::is_configured) && current_time() < deadline {
+        SOCKET_SET.poll_interfaces();
+        axtask::yield_now();
+    }
+
+    #[cfg(feature = "multitask")]
+    axtask::spawn(|| loop {
+        SOCKET_SET.poll_interfaces();
+        #[cfg(feature = "irq")]
+        axtask::sleep(DHCP_POLL_INTERVAL);
+        #[cfg(not(feature = "irq"))]
+        {
+            let deadline = current_time() + DHCP_POLL_INTERVAL;
+            while current_time() < deadline {
+                axtask::yield_now();
+            }
+        }
+    });
+
+    let mut configured = vec![false; IFACES.len()];
+    for client in CLIENTS.iter() {
+        configured[client.iface] = client.is_configured();
+        if !configured[client.iface] {
+            warn!(
+                "DHCP: {}: timeout, use the static configuration",
+                IFACES[client.iface].name()
+            );
+        }
+    }
+    configured
+}
+
+/// Applies the configuration changes of the DHCP sockets.
+///
+/// It must be called after the interfaces are polled.
+pub fn poll_events() {
+    let Some(clients) = CLIENTS.try_get() else {
+        return;
+    };
+    for client in clients {
+        poll_client(client);
+    }
+}
+
+fn poll_client(client: &DhcpClient) {
+    let iface = &IFACES[client.iface];
+    let event = SOCKET_SET.with_socket_mut::<dhcpv4::Socket, _, _>(client.handle, |socket| {
+        socket.poll().map(|event| match event {
+            Event::Configured(config) => DhcpEvent::Configured(
+                config.address,
+                config.router,
+                config.dns_servers.iter().map(|&s| s.into()).collect(),
+            ),
+            Event::Deconfigured => DhcpEvent::Deconfigured,
+        })
+    });
+
+    match event {
+        Some(DhcpEvent::Configured(cidr, router, dns_servers)) => {
+            iface.setup_ipv4(cidr, router);
+            if !dns_servers.is_empty() {
+                dns::set_dhcp_servers(&dns_servers);
+            }
+            client.configured.store(true, Ordering::Release);
+            info!("DHCP: {}: ip:      {}", iface.name(), cidr);
+            if let Some(router) = router {
+                info!("DHCP: {}: gateway: {}", iface.name(), router);
+            }
+            for server in dns_servers {
+                info!("DHCP: {}: DNS:     {}", iface.name(), server);
+            }
+        }
+        Some(DhcpEvent::Deconfigured) => {
+            // the socket also reports it when started, ignore it
+            if client.configured.swap(false, Ordering::AcqRel) {
+                warn!(
+                    "DHCP: {}: lease lost, use the static configuration",
+                    iface.name()
+                );
+                dns::set_dhcp_servers(&[]);
+                setup_static_ipv4(client.iface);
+            }
+        }
+        None => {}
+    }
+}
@@ -0,0 +1,193 @@
+//! Encoding DNS queries and decoding the responses ([RFC 1035]).
+//!
+//! [RFC 1035]: https://datatracker.ietf.org/doc/html/rfc1035
+
+use alloc::vec::Vec;
+use core::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use axerrno::{ax_err, ax_err_type, AxResult};
+
+const HEADER_LEN: usize = 12;
+const MAX_NAME_LEN: usize = 255;
+const MAX_LABEL_LEN: usize = 63;
+
+const FLAG_RESPONSE: u16 = 0x8000;
+const FLAG_RECURSION_DESIRED: u16 = 0x0100;
+const RCODE_MASK: u16 = 0x000f;
+const RCODE_NO_ERROR: u16 = 0;
+const RCODE_NAME_ERROR: u16 = 3;
+
+const CLASS_IN: u16 = 1;
+
+/// The type of a DNS query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryType {
+    /// IPv4 addresses.
+    A = 1,
+    /// IPv6 addresses.
+    Aaaa = 28,
+}
+
+/// The addresses in the response to a query.
+pub struct Answer {
+    pub addrs: Vec<IpAddr>,
+    /// The minimum TTL of the address records in seconds, 0 if none.
+    pub ttl: u32,
+}
+
+impl Answer {
+    pub const fn empty() -> Self {
+        Self {
+            addrs: Vec::new(),
+            ttl: 0,
+        }
+    }
+}
+
+/// Encodes a recursive query of `name`.
+pub fn encode_query(id: u16, name: &str, qtype: QueryType) -> AxResult<Vec<u8>> {
+    let name = name.strip_suffix('.').unwrap_or(name);
+    if name.len() + 2 > MAX_NAME_LEN {
+        return ax_err!(InvalidInput, "DNS query failed: too long name");
+    }
+
+    let mut buf = Vec::with_capacity(HEADER_LEN + name.len() + 6);
+    buf.extend_from_slice(&id.to_be_bytes());
+    buf.extend_from_slice(&FLAG_RECURSION_DESIRED.to_be_bytes());
+    buf.extend_from_slice(&[0, 1, 0, 0, 0, 0, 0, 0]); // one question
+    for label in name.split('.') {
+        if label.is_empty() || label.len() > MAX_LABEL_LEN {
+            return ax_err!(InvalidInput, "DNS query failed: invalid name");
+        }
+        buf.push(label.len() as u8);
+        buf.extend_from_slice(label.as_bytes());
+    }
+    buf.push(0);
+    buf.extend_from_slice(&(qtype as u16).to_be_bytes());
+    buf.extend_from_slice(&CLASS_IN.to_be_bytes());
+    Ok(buf)
+}
+
+/// Returns the ID of the message in `buf` if it's a response.
+pub fn response_id(buf: &[u8]) -> Option<u16> {
+    let mut reader = Reader::new(buf);
+    let id = reader.read_u16().ok()?;
+    let flags = reader.read_u16().ok()?;
+    (flags & FLAG_RESPONSE != 0).then_some(id)
+}
+
+/// Decodes the response in `buf` to a query of type `qtype`.
+///
+/// Returns [`Err(NotFound)`](axerrno::AxError::NotFound) if the name does not
+/// exist.
+pub fn decode_response(buf: &[u8], qtype: QueryType) -> AxResult<Answer> {
+    let malformed = || ax_err_type!(InvalidData, "DNS query failed: malformed response");
+    let mut reader = Reader::new(buf);
+    let _id = reader.read_u16().map_err(|_| malformed())?;
+    let flags = reader.read_u16().map_err(|_| malformed())?;
+    match flags & RCODE_MASK {
+        RCODE_NO_ERROR => {}
+        RCODE_NAME_ERROR => return ax_err!(NotFound, "DNS query failed: no such name"),
+        _ => return ax_err!(ConnectionRefused, "DNS query failed: server failure"),
+    }
+    let qdcount = reader.read_u16().map_err(|_| malformed())?;
+    let ancount = reader.read_u16().map_err(|_| malformed())?;
+    reader.skip(4).map_err(|_| malformed())?; // NSCOUNT and ARCOUNT
+
+    for _ in 0..qdcount {
+        reader.skip_name().map_err(|_| malformed())?;
+        reader.skip(4).map_err(|_| malformed())?; // QTYPE and QCLASS
+    }
+
+    let mut answer = Answer::empty();
+    for _ in 0..ancount {
+        let record = reader.read_record().map_err(|_| malformed())?;
+        if record.class != CLASS_IN || record.rtype != qtype as u16 {
+            // e.g. CNAME, the records of the canonical name follow it
+            continue;
+        }
+        let addr = match (qtype, record.data.len()) {
+            (QueryType::A, 4) => {
+                IpAddr::V4(Ipv4Addr::from(<[u8; 4]>::try_from(record.data).unwrap()))
+            }
+            (QueryType::Aaaa, 16) => {
+                IpAddr::V6(Ipv6Addr::from(<[u8; 16]>::try_from(record.data).unwrap()))
+            }
+            _ => return Err(malformed()),
+        };
+        answer.ttl = match answer.addrs.is_empty() {
+            true => record.ttl,
+            false => answer.ttl.min(record.ttl),
+        };
+        answer.addrs.push(addr);
+    }
+    Ok(answer)
+}
+
+struct Record<'a> {
+    rtype: u16,
+    class: u16,
+    ttl: u32,
+    data: &'a [u8],
+}
+
+/// Reads the fields of a message, fails if it's truncated.
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    const fn new(buf: &'a [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+
+    fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], ()> {
+        let bytes = self.buf.get(self.pos..self.pos + len).ok_or(())?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), ()> {
+        self.read_bytes(len).map(|_| ())
+    }
+
+    fn read_u8(&mut self) -> Result<u8, ()> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    fn read_u16(&mut self) -> Result<u16, ()> {
+        Ok(u16::from_be_bytes(self.read_bytes(2)?.try_into().unwrap()))
+    }
+
+    fn read_u32(&mut self) -> Result<u32, ()> {
+        Ok(u32::from_be_bytes(self.read_bytes(4)?.try_into().unwrap()))
+    }
+
+    /// Skips a name, which ends with an empty label or a compression pointer.
+    fn skip_name(&mut self) -> Result<(), ()> {
+        loop {
+            match self.read_u8()? {
+                0 => return Ok(()),
+                len if len & 0xc0 == 0xc0 => return self.skip(1),
+                len if len & 0xc0 == 0 => self.skip(len as usize)?,
+                _ => return Err(()),
+            }
+        }
+    }
+
+    fn read_record(&mut self) -> Result<Record<'a>, ()> {
+        self.skip_name()?;
+        let rtype = self.read_u16()?;
+        let class = self.read_u16()?;
+        let ttl = self.read_u32()?;
+        let len = self.read_u16()?;
+        let data = self.read_bytes(len as usize)?;
+        Ok(Record {
+            rtype,
+            class,
+            ttl,
+            data,
+        })
+    }
+}
@@ -0,0 +1,262 @@
+//! DNS resolver.
+//!
+//! A name is looked up in order:
+//!
+//! 1. `/etc/hosts` if the `fs` feature is enabled. `localhost` is always
+//!    resolved to the loopback addresses if it's not found there.
+//! 2. The cache of the previous queries, each entry expires by the TTL of its
+//!    records.
+//! 3. The DNS servers: the ones given by [`set_dns_servers`], otherwise the
+//!    ones in `/etc/resolv.conf` if the `fs` feature is enabled, otherwise the
+//!    ones given by DHCP, otherwise `8.8.8.8`.
+
+mod message;
+
+use alloc::collections::BTreeMap;
+use alloc::{string::String, vec::Vec};
+use core::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use core::time::Duration;
+
+use axerrno::{ax_err, AxError, AxResult};
+use axhal::time::{current_time, current_time_nanos, TimeValue};
+use axsync::Mutex;
+use smoltcp::wire::IpAddress;
+
+use self::message::{Answer, QueryType};
+use super::addr::into_core_ipaddr;
+use super::UdpSocket;
+
+const DEFAULT_DNS_SERVER: IpAddr = IpAddr::V4(Ipv4Addr::new(8, 8, 8, 8));
+const MAX_DNS_SERVERS: usize = 4;
+const DNS_PORT: u16 = 53;
+/// The maximum size of a DNS message over UDP.
+const MAX_MESSAGE_LEN: usize = 512;
+const QUERY_TIMEOUT: Duration = Duration::from_secs(2);
+const QUERY_ATTEMPTS: usize = 2;
+const MAX_CACHE_ENTRIES: usize = 64;
+
+/// DNS servers given by [`set_dns_servers`].
+static USER_SERVERS: Mutex<Vec<IpAddr>> = Mutex::new(Vec::new());
+/// DNS servers given by DHCP.
+static DHCP_SERVERS: Mutex<Vec<IpAddr>> = Mutex::new(Vec::new());
+static CACHE: Mutex<BTreeMap<String, CacheEntry>> = Mutex::new(BTreeMap::new());
+
+struct CacheEntry {
+    addrs: Vec<IpAddr>,
+    expires: TimeValue,
+}
+
+/// Replaces the DNS servers, which take precedence over the ones from
+/// `/etc/resolv.conf` and DHCP. An empty list removes them.
+///
+/// At most 4 servers are used, and the cached results are dropped.
+pub fn set_dns_servers(servers: &[IpAddr]) {
+    let n = servers.len().min(MAX_DNS_SERVERS);
+    *USER_SERVERS.lock() = servers[..n].to_vec();
+    CACHE.lock().clear();
+}
+
+/// Replaces the DNS servers given by DHCP.
+#[cfg(feature = "dhcp")]
+pub(crate) fn set_dhcp_servers(servers: &[IpAddress]) {
+    let n = servers.len().min(MAX_DNS_SERVERS);
+    *DHCP_SERVERS.lock() = servers[..n].iter().map(|&s| into_core_ipaddr(s)).collect();
+}
+
+/// Returns the DNS servers in use.
+pub fn dns_servers() -> Vec<IpAddr> {
+    let servers = USER_SERVERS.lock().clone();
+    if !servers.is_empty() {
+        return servers;
+    }
+    #[cfg(feature = "fs")]
+    {
+        let servers = resolv_conf_servers();
+        if !servers.is_empty() {
+            return servers;
+        }
+    }
+    let servers = DHCP_SERVERS.lock().clone();
+    if !servers.is_empty() {
+        return servers;
+    }
+    alloc::vec![DEFAULT_DNS_SERVER]
+}
+
+/// Returns the `nameserver`s in `/etc/resolv.conf`.
+#[cfg(feature = "fs")]
+fn resolv_conf_servers() -> Vec<IpAddr> {
+    let Ok(conf) = axfs::api::read_to_string("/etc/resolv.conf") else {
+        return Vec::new();
+    };
+    conf.lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            match fields.next() {
+                Some("nameserver") => fields.next()?.parse().ok(),
+                _ => None,
+            }
+        })
+        .take(MAX_DNS_SERVERS)
+        .collect()
+}
+
+/// Returns the addresses of `name` in `/etc/hosts`.
+#[cfg(feature = "fs")]
+fn hosts_lookup(name: &str) -> Vec<IpAddr> {
+    let Ok(hosts) = axfs::api::read_to_string("/etc/hosts") else {
+        return Vec::new();
+    };
+    hosts
+        .lines()
+        .filter_map(|line| {
+            let line = line.split('#').next().unwrap_or_default();
+            let mut fields = line.split_whitespace();
+            let addr = fields.next()?.parse().ok()?;
+            fields
+                .any(|host| host.eq_ignore_ascii_case(name))
+                .then_some(addr)
+        })
+        .collect()
+}
+
+/// Looks up `name` locally, without querying the DNS servers.
+fn local_lookup(name: &str) -> Vec<IpAddr> {
+    #[cfg(feature = "fs")]
+    {
+        let addrs = hosts_lookup(name);
+        if !addrs.is_empty() {
+            return addrs;
+        }
+    }
+    if name.eq_ignore_ascii_case("localhost") {
+        return alloc::vec![Ipv4Addr::LOCALHOST.into(), Ipv6Addr::LOCALHOST.into()];
+    }
+    Vec::new()
+}
+
+fn cache_lookup(name: &str) -> Option<Vec<IpAddr>> {
+    let mut cache = CACHE.lock();
+    let entry = cache.get(name)?;
+    if entry.expires > current_time() {
+        Some(entry.addrs.clone())
+    } else {
+        cache.remove(name);
+        None
+    }
+}
+
+fn cache_insert(name: String, addrs: Vec<IpAddr>, ttl: u32) {
+    let now = current_time();
+    let mut cache = CACHE.lock();
+    if cache.len() >= MAX_CACHE_ENTRIES {
+        cache.retain(|_, entry| entry.expires > now);
+    }
+    if cache.len() >= MAX_CACHE_ENTRIES {
+        // evict the entry that expires first
+        let first = cache
+            .iter()
+            .min_by_key(|(_, entry)| entry.expires)
+            .map(|(name, _)| name.clone());
+        if let Some(first) = first {
+            cache.remove(&first);
+        }
+    }
+    let expires = now + Duration::from_secs(ttl as u64);
+    cache.insert(name, CacheEntry { addrs, expires });
+}
+
+/// Sends the query to `server`, and waits for the response.
+fn query_server(server: IpAddr, name: &str, qtype: QueryType) -> AxResult<Answer> {
+    let local_addr = match server {
+        IpAddr::V4(_) => SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), 0),
+        IpAddr::V6(_) => SocketAddr::new(Ipv6Addr::UNSPECIFIED.into(), 0),
+    };
+    let server_addr = SocketAddr::new(server, DNS_PORT);
+    let socket = UdpSocket::new();
+    socket.bind(local_addr)?;
+    socket.set_recv_timeout(Some(QUERY_TIMEOUT))?;
+
+    let id = current_time_nanos() as u16;
+    let query = message::encode_query(id, name, qtype)?;
+    let mut buf = [0; MAX_MESSAGE_LEN];
+    for _ in 0..QUERY_ATTEMPTS {
+        socket.send_to(&query, server_addr)?;
+        let deadline = current_time() + QUERY_TIMEOUT;
+        while current_time() < deadline {
+            match socket.recv_from(&mut buf) {
+                Ok((len, from)) => {
+                    let response = &buf[..len];
+                    if from == server_addr && message::response_id(response) == Some(id) {
+                        return message::decode_response(response, qtype);
+                    }
+                    // not the response to the query, drop it
+                }
+                Err(AxError::WouldBlock) => break, // timed out
+                Err(e) => return Err(e),
+            }
+        }
+    }
+    ax_err!(WouldBlock, "DNS query failed: no response")
+}
+
+/// Queries the DNS servers in order, until one of them answers.
+fn query(name: &str, qtype: QueryType) -> AxResult<Answer> {
+    let mut last_err = AxError::WouldBlock;
+    for server in dns_servers() {
+        match query_server(server, name, qtype) {
+            Ok(answer) => return Ok(answer),
+            Err(AxError::NotFound) => return Err(AxError::NotFound),
+            Err(e) => {
+                debug!("DNS query of {:?} to {} failed: {:?}", name, server, e);
+                last_err = e;
+            }
+        }
+    }
+    Err(last_err)
+}
+
+/// Public function for DNS query.
+///
+/// IPv4 addresses (A records) come first, followed by IPv6 addresses (AAAA
+/// records). It fails only if both queries fail, or the name has no address.
+///
+/// See the [module-level documentation](self) for the lookup order.
+pub fn dns_query(name: &str) -> AxResult<Vec<IpAddr>> {
+    if let Ok(addr) = name.parse::<IpAddr>() {
+        return Ok(alloc::vec![addr]);
+    }
+    let addrs = local_lookup(name);
+    if !addrs.is_empty() {
+        return Ok(addrs);
+    }
+    let key = name.strip_suffix('.').unwrap_or(name).to_ascii_lowercase();
+    if let Some(addrs) = cache_lookup(&key) {
+        return Ok(addrs);
+    }
+
+    let v4 = query(&key, QueryType::A);
+    let v6 = query(&key, QueryType::Aaaa);
+    let answers = match (v4, v6) {
+        (Ok(v4), Ok(v6)) => [v4, v6],
+        (Ok(answer), Err(_)) | (Err(_), Ok(answer)) => [answer, Answer::empty()],
+        (Err(e), Err(_)) => return Err(e),
+    };
+    let addrs = answers
+        .iter()
+        .flat_map(|answer| answer.addrs.iter().copied())
+        .collect::<Vec<_>>();
+    if addrs.is_empty() {
+        return ax_err!(NotFound, "DNS query failed: no address");
+    }
+    let ttl = answers
+        .iter()
+        .filter(|answer| !answer.addrs.is_empty())
+        .map(|answer| answer.ttl)
+        .min()
+        .unwrap_or(0);
+    if ttl > 0 {
+        cache_insert(key, addrs.clone(), ttl);
+    }
+    Ok(addrs)
+}
@@ -0,0 +1,261 @@
+use core::net::IpAddr;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::task::Waker;
+use core::time::Duration;
+
+use axerrno::{ax_err, ax_err_type, AxError, AxResult};
+use axio::PollState;
+use axsync::Mutex;
+use spin::RwLock;
+
+use smoltcp::socket::icmp::{self, BindError, Endpoint, SendError};
+use smoltcp::wire::IpAddress;
+
+use super::addr::{from_core_ipaddr, into_core_ipaddr};
+use super::poll::SocketWaiter;
+use super::{iface_by_name, route_iface};
+use super::{SocketHandle, SocketSetWrapper, SOCKET_SET};
+
+const ICMPV4_ECHO_REQUEST: u8 = 8;
+const ICMPV6_ECHO_REQUEST: u8 = 128;
+
+/// An ICMP socket for echo requests and replies, like a ping socket on Linux
+/// (`SOCK_DGRAM` with `IPPROTO_ICMP`).
+///
+/// The socket is bound to an identifier, and receives the echo replies with
+/// it. The identifier of the echo requests sent is replaced with it, and the
+/// checksum is filled in.
+pub struct IcmpSocket {
+    handle: RwLock<SocketHandle>,
+    device: RwLock<Option<usize>>,
+    ident: RwLock<Option<u16>>,
+    nonblock: AtomicBool,
+    recv_timeout: RwLock<Option<Duration>>,
+    waiter: SocketWaiter,
+}
+
+impl IcmpSocket {
+    /// Creates a new ICMP socket.
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        let socket = SocketSetWrapper::new_icmp_socket();
+        let handle = SOCKET_SET.add(socket, 0);
+        Self {
+            handle: RwLock::new(handle),
+            device: RwLock::new(None),
+            ident: RwLock::new(None),
+            nonblock: AtomicBool::new(false),
+            recv_timeout: RwLock::new(None),
+            waiter: SocketWaiter::new(),
+        }
+    }
+
+    /// Returns the identifier the socket is bound to, or
+    /// [`Err(NotConnected)`](AxError::NotConnected) if not bound.
+    pub fn ident(&self) -> AxResult<u16> {
+        self.ident.read().ok_or(AxError::NotConnected)
+    }
+
+    /// Returns whether this socket is in nonblocking mode.
+    #[inline]
+    pub fn is_nonblocking(&self) -> bool {
+        self.nonblock.load(Ordering::Acquire)
+    }
+
+    /// Moves this ICMP socket into or out of nonblocking mode.
+    ///
+    /// This will result in `recv_from` and `send_to` operations becoming
+    /// nonblocking, i.e., immediately returning from their calls. If the IO
+    /// operation could not be completed and needs to be retried, an error with
+    /// kind [`Err(WouldBlock)`](AxError::WouldBlock) is returned.
+    #[inline]
+    pub fn set_nonblocking(&self, nonblocking: bool) {
+        self.nonblock.store(nonblocking, Ordering::Release);
+    }
+
+    /// Binds the socket to the network interface named `name`, so that it only
+    /// sends and receives packets through it. An empty name removes the binding.
+    pub fn bind_to_device(&self, name: &str) -> AxResult {
+        let mut device = self.device.write();
+        if name.is_empty() {
+            *device = None;
+        } else {
+            let index = iface_by_name(name)?;
+            self.move_to(index);
+            *device = Some(index);
+        }
+        Ok(())
+    }
+
+    /// Returns the timeout of receiving, i.e. `SO_RCVTIMEO`.
+    pub fn recv_timeout(&self) -> Option<Duration> {
+        *self.recv_timeout.read()
+    }
+
+    /// Sets the timeout of receiving, i.e. `SO_RCVTIMEO`. `None` means
+    /// blocking indefinitely.
+    ///
+    /// When timed out, [`Err(WouldBlock)`](AxError::WouldBlock) is returned.
+    pub fn set_recv_timeout(&self, timeout: Option<Duration>) -> AxResult {
+        if timeout.is_some_and(|t| t.is_zero()) {
+            return ax_err!(InvalidInput, "socket set_recv_timeout() failed");
+        }
+        *self.recv_timeout.write() = timeout;
+        Ok(())
+    }
+
+    /// Binds an unbound socket to the identifier `ident`, a free one is chosen
+    /// if it's 0.
+    ///
+    /// It's called by [`send_to`](Self::send_to) automatically if the socket
+    /// is not bound.
+    pub fn bind(&self, mut ident: u16) -> AxResult {
+        let mut self_ident = self.ident.write();
+        if self_ident.is_some() {
+            return ax_err!(InvalidInput, "socket bind() failed: already bound");
+        }
+        if ident == 0 {
+            ident = get_ephemeral_ident();
+        }
+
+        let handle = *self.handle.read();
+        SOCKET_SET.with_socket_mut::<icmp::Socket, _, _>(handle, |socket| {
+            socket.bind(Endpoint::Ident(ident)).or_else(|e| match e {
+                BindError::InvalidState => ax_err!(AlreadyExists, "socket bind() failed"),
+                BindError::Unaddressable => ax_err!(InvalidInput, "socket bind() failed"),
+            })
+        })?;
+
+        *self_ident = Some(ident);
+        debug!("ICMP socket {}: bound to ident {}", handle, ident);
+        Ok(())
+    }
+
+    /// Sends an ICMP message in `buf` to the given address, including the ICMP
+    /// header. On success, returns the number of bytes written.
+    pub fn send_to(&self, buf: &[u8], addr: IpAddr) -> AxResult<usize> {
+        if addr.is_unspecified() {
+            return ax_err!(InvalidInput, "socket send_to() failed: invalid address");
+        }
+        if buf.len() < 8 {
+            return ax_err!(InvalidInput, "socket send_to() failed: message too short");
+        }
+        if self.ident.read().is_none() {
+            self.bind(0)?;
+        }
+        let ident = self.ident()?;
+        let dst = from_core_ipaddr(addr);
+        self.route_to(dst);
+
+        self.block_on(
+            None,
+            |waker| {
+                SOCKET_SET.with_socket_mut::<icmp::Socket, _, _>(*self.handle.read(), |socket| {
+                    socket.register_send_waker(waker)
+                })
+            },
+            || {
+                SOCKET_SET.with_socket_mut::<icmp::Socket, _, _>(*self.handle.read(), |socket| {
+                    if !socket.can_send() {
+                        // tx buffer is full
+                        return Err(AxError::WouldBlock);
+                    }
+                    let data = socket.send(buf.len(), dst).map_err(|e| match e {
+                        SendError::BufferFull => AxError::WouldBlock,
+                        SendError::Unaddressable => {
+                            ax_err_type!(ConnectionRefused, "socket send_to() failed")
+                        }
+                    })?;
+                    data.copy_from_slice(buf);
+                    if is_echo_request(dst, data[0]) {
+                        data[4..6].copy_from_slice(&ident.to_be_bytes());
+                    }
+                    Ok(buf.len())
+                })
+            },
+        )
+    }
+
+    /// Receives a single ICMP message on the socket, including the ICMP
+    /// header. On success, returns the number of bytes read and the origin.
+    pub fn recv_from(&self, buf: &mut [u8]) -> AxResult<(usize, IpAddr)> {
+        if self.ident.read().is_none() {
+            return ax_err!(NotConnected, "socket recv_from() failed");
+        }
+        self.block_on(
+            self.recv_timeout(),
+            |waker| {
+                SOCKET_SET.with_socket_mut::<icmp::Socket, _, _>(*self.handle.read(), |socket| {
+                    socket.register_recv_waker(waker)
+                })
+            },
+            || {
+                SOCKET_SET.with_socket_mut::<icmp::Socket, _, _>(*self.handle.read(), |socket| {
+                    match socket.recv_slice(buf) {
+                        Ok((len, addr)) => Ok((len, into_core_ipaddr(addr))),
+                        Err(_) => Err(AxError::WouldBlock),
+                    }
+                })
+            },
+        )
+    }
+
+    /// Whether the socket is readable or writable.
+    pub fn poll(&self) -> AxResult<PollState> {
+        SOCKET_SET.with_socket_mut::<icmp::Socket, _, _>(*self.handle.read(), |socket| {
+            Ok(PollState {
+                readable: socket.can_recv(),
+                writable: socket.can_send(),
+            })
+        })
+    }
+}
+
+/// Private methods
+impl IcmpSocket {
+    /// Moves the socket to the interface `index`.
+    fn move_to(&self, index: usize) {
+        let mut handle = self.handle.write();
+        *handle = SOCKET_SET.move_socket(*handle, index);
+    }
+
+    /// Moves the socket to the interface routing to `dst`, unless it's bound
+    /// to a device.
+    fn route_to(&self, dst: IpAddress) {
+        if self.device.read().is_none() {
+            self.move_to(route_iface(dst));
+        }
+    }
+
+    fn block_on<R, F, T>(&self, timeout: Option<Duration>, register: R, f: F) -> AxResult<T>
+    where
+        R: FnMut(&Waker),
+        F: FnMut() -> AxResult<T>,
+    {
+        self.waiter
+            .block_on(self.is_nonblocking(), timeout, register, f)
+    }
+}
+
+impl Drop for IcmpSocket {
+    fn drop(&mut self) {
+        SOCKET_SET.remove(*self.handle.get_mut());
+    }
+}
+
+fn is_echo_request(dst: IpAddress, icmp_type: u8) -> bool {
+    match dst {
+        IpAddress::Ipv4(_) => icmp_type == ICMPV4_ECHO_REQUEST,
+        IpAddress::Ipv6(_) => icmp_type == ICMPV6_ECHO_REQUEST,
+    }
+}
+
+fn get_ephemeral_ident() -> u16 {
+    const IDENT_START: u16 = 0x1000;
+    static CURR: Mutex<u16> = Mutex::new(IDENT_START);
+    let mut curr = CURR.lock();
+
+    let ident = *curr;
+    *curr = curr.checked_add(1).unwrap_or(IDENT_START);
+    ident
+}
@@ -1,31 +1,42 @@
 use alloc::{boxed::Box, collections::VecDeque};
 use core::ops::{Deref, DerefMut};
+use core::task::Waker;
 
 use axerrno::{ax_err, AxError, AxResult};
 use axsync::Mutex;
-use smoltcp::iface::{SocketHandle, SocketSet};
+use smoltcp::iface::SocketSet;
 use smoltcp::socket::tcp::{self, State};
 use smoltcp::wire::{IpAddress, IpEndpoint, IpListenEndpoint};
 
-use super::{SocketSetWrapper, LISTEN_QUEUE_SIZE, SOCKET_SET};
+use super::tcp::TcpOptions;
+use super::{SocketHandle, LISTEN_QUEUE_SIZE, SOCKET_SET};
 
 const PORT_NUM: usize = 65536;
 
 struct ListenTableEntry {
     listen_endpoint: IpListenEndpoint,
+    device: Option<usize>,
     syn_queue: VecDeque<SocketHandle>,
+    opts: TcpOptions,
+    waker: Option<Waker>,
 }
 
 impl ListenTableEntry {
-    pub fn new(listen_endpoint: IpListenEndpoint) -> Self {
+    pub fn new(listen_endpoint: IpListenEndpoint, device: Option<usize>, opts: TcpOptions) -> Self {
         Self {
             listen_endpoint,
+            device,
             syn_queue: VecDeque::with_capacity(LISTEN_QUEUE_SIZE),
+            opts,
+            waker: None,
         }
     }
 
     #[inline]
-    fn can_accept(&self, dst: IpAddress) -> bool {
+    fn can_accept(&self, dst: IpAddress, iface: usize) -> bool {
+        if self.device.is_some_and(|device| device != iface) {
+            return false;
+        }
         match self.listen_endpoint.addr {
             Some(addr) => addr == dst,
             None => true,
@@ -61,12 +72,23 @@ impl ListenTable {
         self.tcp[port as usize].lock().is_none()
     }
 
-    pub fn listen(&self, listen_endpoint: IpListenEndpoint) -> AxResult {
+    /// Listens on `listen_endpoint`, only on the interface `device` if given.
+    /// The sockets of incoming connections are created with `opts`.
+    pub fn listen(
+        &self,
+        listen_endpoint: IpListenEndpoint,
+        device: Option<usize>,
+        opts: TcpOptions,
+    ) -> AxResult {
         let port = listen_endpoint.port;
         assert_ne!(port, 0);
         let mut entry = self.tcp[port as usize].lock();
         if entry.is_none() {
-            *entry = Some(Box::new(ListenTableEntry::new(listen_endpoint)));
+            *entry = Some(Box::new(ListenTableEntry::new(
+                listen_endpoint,
+                device,
+                opts,
+            )));
             Ok(())
         } else {
             ax_err!(AddrInUse, "socket listen() failed")
@@ -110,15 +132,32 @@ impl ListenTable {
         }
     }
 
+    /// Registers `waker` to be woken when a connection on `port` may become
+    /// acceptable.
+    pub fn register_waker(&self, port: u16, waker: &Waker) {
+        if let Some(entry) = self.tcp[port as usize].lock().deref_mut() {
+            for &handle in &entry.syn_queue {
+                SOCKET_SET.with_socket_mut::<tcp::Socket, _, _>(handle, |socket| {
+                    if matches!(socket.state(), State::Listen | State::SynReceived) {
+                        socket.register_recv_waker(waker);
+                    }
+                });
+            }
+            // for the sockets of later incoming connections
+            entry.waker = Some(waker.clone());
+        }
+    }
+
     pub fn incoming_tcp_packet(
         &self,
         src: IpEndpoint,
         dst: IpEndpoint,
+        iface: usize,
         sockets: &mut SocketSet<'_>,
     ) {
         if let Some(entry) = self.tcp[dst.port as usize].lock().deref_mut() {
-            if !entry.can_accept(dst.addr) {
-                // not listening on this address
+            if !entry.can_accept(dst.addr, iface) {
+                // not listening on this address or interface
                 return;
             }
             if entry.syn_queue.len() >= LISTEN_QUEUE_SIZE {
@@ -126,9 +165,12 @@ impl ListenTable {
                 warn!("SYN queue overflow!");
                 return;
             }
-            let mut socket = SocketSetWrapper::new_tcp_socket();
+            let mut socket = entry.opts.new_socket();
             if socket.listen(entry.listen_endpoint).is_ok() {
-                let handle = sockets.add(socket);
+                if let Some(waker) = &entry.waker {
+                    socket.register_recv_waker(waker);
+                }
+                let handle = SocketHandle::new(iface, sockets.add(socket));
                 debug!(
                     "TCP socket {}: prepare for connection {} -> {}",
                     handle, src, entry.listen_endpoint
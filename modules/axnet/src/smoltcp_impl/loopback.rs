@@ -0,0 +1,87 @@
+//! The loopback device of the `lo` interface.
+//!
+//! It works like smoltcp's `Loopback`, but the received packets are snooped
+//! as the ones from NICs, so that TCP listeners can accept local connections.
+
+use alloc::{collections::VecDeque, vec, vec::Vec};
+
+use smoltcp::iface::SocketSet;
+use smoltcp::phy::{Device, DeviceCapabilities, Medium, RxToken, TxToken};
+use smoltcp::time::Instant;
+
+const LOOPBACK_MTU: usize = 65535;
+
+pub struct LoopbackDevice {
+    queue: VecDeque<Vec<u8>>,
+    index: usize,
+}
+
+impl LoopbackDevice {
+    pub fn new(index: usize) -> Self {
+        Self {
+            queue: VecDeque::new(),
+            index,
+        }
+    }
+}
+
+impl Device for LoopbackDevice {
+    type RxToken<'a>
+        = LoopbackRxToken
+    where
+        Self: 'a;
+    type TxToken<'a>
+        = LoopbackTxToken<'a>
+    where
+        Self: 'a;
+
+    fn receive(&mut self, _timestamp: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+        let buf = self.queue.pop_front()?;
+        Some((
+            LoopbackRxToken(buf, self.index),
+            LoopbackTxToken(&mut self.queue),
+        ))
+    }
+
+    fn transmit(&mut self, _timestamp: Instant) -> Option<Self::TxToken<'_>> {
+        Some(LoopbackTxToken(&mut self.queue))
+    }
+
+    fn capabilities(&self) -> DeviceCapabilities {
+        let mut caps = DeviceCapabilities::default();
+        caps.max_transmission_unit = LOOPBACK_MTU;
+        caps.max_burst_size = None;
+        caps.medium = Medium::Ip;
+        caps
+    }
+}
+
+pub struct LoopbackRxToken(Vec<u8>, usize);
+pub struct LoopbackTxToken<'a>(&'a mut VecDeque<Vec<u8>>);
+
+impl RxToken for LoopbackRxToken {
+    fn preprocess(&self, sockets: &mut SocketSet<'_>) {
+        super::snoop_ip_packet(&self.0, self.1, sockets).ok();
+    }
+
+    fn consume<R, F>(mut self, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        trace!("LOOPBACK RECV {} bytes: {:02X?}", self.0.len(), self.0);
+        f(&mut self.0)
+    }
+}
+
+impl<'a> TxToken for LoopbackTxToken<'a> {
+    fn consume<R, F>(self, len: usize, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        let mut buf = vec![0; len];
+        let ret = f(&mut buf);
+        trace!("LOOPBACK SEND {} bytes: {:02X?}", len, buf);
+        self.0.push_back(buf);
+        ret
+    }
+}
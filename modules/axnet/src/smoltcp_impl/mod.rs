@@ -1,28 +1,45 @@
 mod addr;
 mod bench;
+#[cfg(feature = "dhcp")]
+mod dhcp;
 mod dns;
+mod icmp;
 mod listen_table;
+mod loopback;
+mod poll;
+mod raw;
+mod route;
+mod slaac;
 mod tcp;
 mod udp;
 
-use alloc::vec;
+use alloc::{format, string::String, vec, vec::Vec};
 use core::cell::RefCell;
+use core::net::IpAddr;
 use core::ops::DerefMut;
 
 use axdriver::prelude::*;
+use axerrno::{ax_err, AxResult};
 use axhal::time::{current_time_nanos, NANOS_PER_MICROS};
 use axsync::Mutex;
 use driver_net::{DevError, NetBufPtr};
 use lazy_init::LazyInit;
-use smoltcp::iface::{Config, Interface, SocketHandle, SocketSet};
+use smoltcp::iface::{Config, Interface, SocketSet};
 use smoltcp::phy::{Device, DeviceCapabilities, Medium, RxToken, TxToken};
 use smoltcp::socket::{self, AnySocket};
 use smoltcp::time::Instant;
 use smoltcp::wire::{EthernetAddress, HardwareAddress, IpAddress, IpCidr};
+use smoltcp::wire::{IpProtocol, IpVersion, Ipv4Address, Ipv4Cidr, Ipv6Address};
 
+use self::addr::{from_core_ipaddr, into_core_ipaddr};
 use self::listen_table::ListenTable;
+use self::loopback::LoopbackDevice;
+use self::route::Route;
+use self::slaac::Slaac;
 
-pub use self::dns::dns_query;
+pub use self::dns::{dns_query, dns_servers, set_dns_servers};
+pub use self::icmp::IcmpSocket;
+pub use self::raw::RawSocket;
 pub use self::tcp::TcpSocket;
 pub use self::udp::UdpSocket;
 
@@ -35,46 +52,94 @@ macro_rules! env_or_default {
     };
 }
 
+// Comma-separated lists, the i-th item is for the i-th interface.
 const IP: &str = env_or_default!("AX_IP");
 const GATEWAY: &str = env_or_default!("AX_GW");
-const DNS_SEVER: &str = "8.8.8.8";
+const IP6: &str = env_or_default!("AX_IP6");
+const GATEWAY6: &str = env_or_default!("AX_GW6");
 const IP_PREFIX: u8 = 24;
+const IP6_PREFIX: u8 = 64;
+
+const LOOPBACK_IP: IpAddress = IpAddress::v4(127, 0, 0, 1);
+const LOOPBACK_IP_PREFIX: u8 = 8;
+const LOOPBACK_IP6: IpAddress = IpAddress::Ipv6(Ipv6Address::LOOPBACK);
+const LOOPBACK_IP6_PREFIX: u8 = 128;
 
 const STANDARD_MTU: usize = 1500;
 
 const RANDOM_SEED: u64 = 0xA2CE_05A2_CE05_A2CE;
 
+// default sizes, can be changed by `SO_RCVBUF` and `SO_SNDBUF`
 const TCP_RX_BUF_LEN: usize = 64 * 1024;
 const TCP_TX_BUF_LEN: usize = 64 * 1024;
 const UDP_RX_BUF_LEN: usize = 64 * 1024;
 const UDP_TX_BUF_LEN: usize = 64 * 1024;
+const ICMP_RX_BUF_LEN: usize = 16 * 1024;
+const ICMP_TX_BUF_LEN: usize = 16 * 1024;
+const RAW_RX_BUF_LEN: usize = 64 * 1024;
+const RAW_TX_BUF_LEN: usize = 64 * 1024;
 const LISTEN_QUEUE_SIZE: usize = 512;
 
 static LISTEN_TABLE: LazyInit<ListenTable> = LazyInit::new();
 static SOCKET_SET: LazyInit<SocketSetWrapper> = LazyInit::new();
-static ETH0: LazyInit<InterfaceWrapper> = LazyInit::new();
+static IFACES: LazyInit<Vec<InterfaceWrapper>> = LazyInit::new();
+
+/// A handle of a socket in the socket set of an interface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct SocketHandle {
+    iface: usize,
+    inner: smoltcp::iface::SocketHandle,
+}
+
+impl SocketHandle {
+    const fn new(iface: usize, inner: smoltcp::iface::SocketHandle) -> Self {
+        Self { iface, inner }
+    }
+}
 
-struct SocketSetWrapper<'a>(Mutex<SocketSet<'a>>);
+impl core::fmt::Display for SocketHandle {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{}:{}", self.iface, self.inner)
+    }
+}
+
+/// One socket set per interface, a socket is sent out from the interface of
+/// its set.
+struct SocketSetWrapper<'a>(Vec<Mutex<SocketSet<'a>>>);
 
 struct DeviceWrapper {
     inner: RefCell<AxNetDevice>, // use `RefCell` is enough since it's wrapped in `Mutex` in `InterfaceWrapper`.
+    index: usize,
+}
+
+enum NetDevice {
+    Nic(DeviceWrapper),
+    Loopback(LoopbackDevice),
 }
 
 struct InterfaceWrapper {
-    name: &'static str,
-    ether_addr: EthernetAddress,
-    dev: Mutex<DeviceWrapper>,
+    index: usize,
+    name: String,
+    ether_addr: EthernetAddress, // all zeros for the loopback interface
+    dev: Mutex<NetDevice>,
     iface: Mutex<Interface>,
+    #[cfg(all(feature = "irq", feature = "multitask"))]
+    irq_num: Option<usize>, // `None` for the loopback interface or if the NIC can only be polled
+    slaac: Slaac,
 }
 
 impl<'a> SocketSetWrapper<'a> {
-    fn new() -> Self {
-        Self(Mutex::new(SocketSet::new(vec![])))
+    fn new(num_ifaces: usize) -> Self {
+        Self(
+            (0..num_ifaces)
+                .map(|_| Mutex::new(SocketSet::new(vec![])))
+                .collect(),
+        )
     }
 
-    pub fn new_tcp_socket() -> socket::tcp::Socket<'a> {
-        let tcp_rx_buffer = socket::tcp::SocketBuffer::new(vec![0; TCP_RX_BUF_LEN]);
-        let tcp_tx_buffer = socket::tcp::SocketBuffer::new(vec![0; TCP_TX_BUF_LEN]);
+    pub fn new_tcp_socket(rx_buf_len: usize, tx_buf_len: usize) -> socket::tcp::Socket<'a> {
+        let tcp_rx_buffer = socket::tcp::SocketBuffer::new(vec![0; rx_buf_len]);
+        let tcp_tx_buffer = socket::tcp::SocketBuffer::new(vec![0; tx_buf_len]);
         socket::tcp::Socket::new(tcp_rx_buffer, tcp_tx_buffer)
     }
 
@@ -90,23 +155,61 @@ impl<'a> SocketSetWrapper<'a> {
         socket::udp::Socket::new(udp_rx_buffer, udp_tx_buffer)
     }
 
-    pub fn new_dns_socket() -> socket::dns::Socket<'a> {
-        let server_addr = DNS_SEVER.parse().expect("invalid DNS server address");
-        socket::dns::Socket::new(&[server_addr], vec![])
+    pub fn new_icmp_socket() -> socket::icmp::Socket<'a> {
+        let icmp_rx_buffer = socket::icmp::PacketBuffer::new(
+            vec![socket::icmp::PacketMetadata::EMPTY; 8],
+            vec![0; ICMP_RX_BUF_LEN],
+        );
+        let icmp_tx_buffer = socket::icmp::PacketBuffer::new(
+            vec![socket::icmp::PacketMetadata::EMPTY; 8],
+            vec![0; ICMP_TX_BUF_LEN],
+        );
+        socket::icmp::Socket::new(icmp_rx_buffer, icmp_tx_buffer)
     }
 
-    pub fn add<T: AnySocket<'a>>(&self, socket: T) -> SocketHandle {
-        let handle = self.0.lock().add(socket);
+    pub fn new_raw_socket(version: IpVersion, protocol: IpProtocol) -> socket::raw::Socket<'a> {
+        let raw_rx_buffer = socket::raw::PacketBuffer::new(
+            vec![socket::raw::PacketMetadata::EMPTY; 8],
+            vec![0; RAW_RX_BUF_LEN],
+        );
+        let raw_tx_buffer = socket::raw::PacketBuffer::new(
+            vec![socket::raw::PacketMetadata::EMPTY; 8],
+            vec![0; RAW_TX_BUF_LEN],
+        );
+        socket::raw::Socket::new(version, protocol, raw_rx_buffer, raw_tx_buffer)
+    }
+
+    pub fn add<T: AnySocket<'a>>(&self, socket: T, iface: usize) -> SocketHandle {
+        let handle = SocketHandle::new(iface, self.0[iface].lock().add(socket));
         debug!("socket {}: created", handle);
         handle
     }
 
+    /// Moves the socket to the socket set of `iface`, returns the new handle.
+    pub fn move_socket(&self, handle: SocketHandle, iface: usize) -> SocketHandle {
+        if handle.iface == iface {
+            return handle;
+        }
+        let socket = self.0[handle.iface].lock().remove(handle.inner);
+        let mut set = self.0[iface].lock();
+        let inner = match socket {
+            socket::Socket::Udp(socket) => set.add(socket),
+            socket::Socket::Tcp(socket) => set.add(socket),
+            socket::Socket::Icmp(socket) => set.add(socket),
+            socket::Socket::Raw(socket) => set.add(socket),
+            _ => unreachable!("only TCP, UDP, ICMP and raw sockets can be moved"),
+        };
+        let new_handle = SocketHandle::new(iface, inner);
+        debug!("socket {}: moved to {}", handle, new_handle);
+        new_handle
+    }
+
     pub fn with_socket<T: AnySocket<'a>, R, F>(&self, handle: SocketHandle, f: F) -> R
     where
         F: FnOnce(&T) -> R,
     {
-        let set = self.0.lock();
-        let socket = set.get(handle);
+        let set = self.0[handle.iface].lock();
+        let socket = set.get(handle.inner);
         f(socket)
     }
 
@@ -114,33 +217,94 @@ impl<'a> SocketSetWrapper<'a> {
     where
         F: FnOnce(&mut T) -> R,
     {
-        let mut set = self.0.lock();
-        let socket = set.get_mut(handle);
+        let mut set = self.0[handle.iface].lock();
+        let socket = set.get_mut(handle.inner);
         f(socket)
     }
 
+    /// Whether a TCP socket in use has the local `port`.
+    pub fn is_tcp_port_in_use(&self, port: u16) -> bool {
+        self.0.iter().any(|set| {
+            set.lock().iter().any(|(_, socket)| match socket {
+                socket::Socket::Tcp(socket) => socket
+                    .local_endpoint()
+                    .is_some_and(|endpoint| endpoint.port == port),
+                _ => false,
+            })
+        })
+    }
+
+    /// Whether a UDP socket is bound to the local `port`.
+    pub fn is_udp_port_in_use(&self, port: u16) -> bool {
+        self.0.iter().any(|set| {
+            set.lock().iter().any(|(_, socket)| match socket {
+                socket::Socket::Udp(socket) => socket.endpoint().port == port,
+                _ => false,
+            })
+        })
+    }
+
     pub fn poll_interfaces(&self) {
-        ETH0.poll(&self.0);
+        for (iface, sockets) in IFACES.iter().zip(self.0.iter()) {
+            iface.poll(sockets);
+        }
+        #[cfg(feature = "dhcp")]
+        dhcp::poll_events();
+    }
+
+    /// Returns how long to wait before the interfaces should be polled again,
+    /// or `None` if there is nothing to do until new packets arrive.
+    #[cfg(all(feature = "irq", feature = "multitask"))]
+    pub fn poll_delay(&self) -> Option<core::time::Duration> {
+        IFACES
+            .iter()
+            .zip(self.0.iter())
+            .filter_map(|(iface, sockets)| iface.poll_delay(sockets))
+            .min()
     }
 
     pub fn remove(&self, handle: SocketHandle) {
-        self.0.lock().remove(handle);
+        self.0[handle.iface].lock().remove(handle.inner);
         debug!("socket {}: destroyed", handle);
     }
 }
 
 impl InterfaceWrapper {
-    fn new(name: &'static str, dev: AxNetDevice, ether_addr: EthernetAddress) -> Self {
+    fn new(index: usize, dev: AxNetDevice, ether_addr: EthernetAddress) -> Self {
         let mut config = Config::new(HardwareAddress::Ethernet(ether_addr));
         config.random_seed = RANDOM_SEED;
 
-        let mut dev = DeviceWrapper::new(dev);
-        let iface = Mutex::new(Interface::new(config, &mut dev, Self::current_time()));
+        #[cfg(all(feature = "irq", feature = "multitask"))]
+        let irq_num = dev.irq_num();
+        let mut dev = DeviceWrapper::new(dev, index);
+        let iface = Interface::new(config, &mut dev, Self::current_time());
         Self {
-            name,
+            index,
+            name: format!("eth{}", index),
             ether_addr,
-            dev: Mutex::new(dev),
-            iface,
+            dev: Mutex::new(NetDevice::Nic(dev)),
+            iface: Mutex::new(iface),
+            #[cfg(all(feature = "irq", feature = "multitask"))]
+            irq_num,
+            slaac: Slaac::new(),
+        }
+    }
+
+    fn new_loopback(index: usize) -> Self {
+        let mut config = Config::new(HardwareAddress::Ip);
+        config.random_seed = RANDOM_SEED;
+
+        let mut dev = LoopbackDevice::new(index);
+        let iface = Interface::new(config, &mut dev, Self::current_time());
+        Self {
+            index,
+            name: String::from("lo"),
+            ether_addr: EthernetAddress([0; 6]),
+            dev: Mutex::new(NetDevice::Loopback(dev)),
+            iface: Mutex::new(iface),
+            #[cfg(all(feature = "irq", feature = "multitask"))]
+            irq_num: None,
+            slaac: Slaac::new(),
         }
     }
 
@@ -149,13 +313,17 @@ impl InterfaceWrapper {
     }
 
     pub fn name(&self) -> &str {
-        self.name
+        &self.name
     }
 
     pub fn ethernet_address(&self) -> EthernetAddress {
         self.ether_addr
     }
 
+    pub fn is_loopback(&self) -> bool {
+        matches!(*self.dev.lock(), NetDevice::Loopback(_))
+    }
+
     pub fn setup_ip_addr(&self, ip: IpAddress, prefix_len: u8) {
         let mut iface = self.iface.lock();
         iface.update_ip_addrs(|ip_addrs| {
@@ -163,11 +331,64 @@ impl InterfaceWrapper {
         });
     }
 
-    pub fn setup_gateway(&self, gateway: IpAddress) {
+    /// Replaces the IPv4 address and the default IPv4 gateway.
+    pub fn setup_ipv4(&self, cidr: Ipv4Cidr, gateway: Option<Ipv4Address>) {
         let mut iface = self.iface.lock();
+        iface.update_ip_addrs(|ip_addrs| {
+            let others = ip_addrs
+                .iter()
+                .filter(|addr| !matches!(addr, IpCidr::Ipv4(_)))
+                .copied()
+                .collect::<Vec<_>>();
+            ip_addrs.clear();
+            ip_addrs.push(IpCidr::Ipv4(cidr)).unwrap();
+            for addr in others {
+                ip_addrs.push(addr).unwrap();
+            }
+        });
         match gateway {
-            IpAddress::Ipv4(v4) => iface.routes_mut().add_default_ipv4_route(v4).unwrap(),
+            Some(gateway) => {
+                let route = Route::default_via(IpAddress::Ipv4(gateway), self.index);
+                route::add(&mut iface, route).unwrap();
+            }
+            None => {
+                let dst = route::default_dst(IpAddress::Ipv4(Ipv4Address::UNSPECIFIED));
+                route::remove(&mut iface, self.index, dst).ok();
+            }
+        }
+    }
+
+    pub fn setup_gateway(&self, gateway: IpAddress) {
+        let mut iface = self.iface.lock();
+        route::add(&mut iface, Route::default_via(gateway, self.index)).unwrap();
+    }
+
+    /// Sends a raw Ethernet frame, it's dropped if the device is busy.
+    pub fn send_raw(&self, frame: &[u8]) {
+        let NetDevice::Nic(dev) = &mut *self.dev.lock() else {
+            return;
         };
+        match dev.transmit(Self::current_time()) {
+            Some(tx_token) => tx_token.consume(frame.len(), |buf| buf.copy_from_slice(frame)),
+            None => warn!("send_raw: device busy, frame dropped"),
+        }
+    }
+
+    /// Acknowledges the pending interrupt of the NIC.
+    #[cfg(all(feature = "irq", feature = "multitask"))]
+    pub fn ack_interrupt(&self) {
+        if let NetDevice::Nic(dev) = &*self.dev.lock() {
+            dev.inner.borrow_mut().ack_interrupt();
+        }
+    }
+
+    #[cfg(all(feature = "irq", feature = "multitask"))]
+    pub fn poll_delay(&self, sockets: &Mutex<SocketSet>) -> Option<core::time::Duration> {
+        let mut iface = self.iface.lock();
+        let sockets = sockets.lock();
+        iface
+            .poll_delay(Self::current_time(), &sockets)
+            .map(|delay| core::time::Duration::from_micros(delay.total_micros()))
     }
 
     pub fn poll(&self, sockets: &Mutex<SocketSet>) {
@@ -175,14 +396,24 @@ impl InterfaceWrapper {
         let mut iface = self.iface.lock();
         let mut sockets = sockets.lock();
         let timestamp = Self::current_time();
-        iface.poll(timestamp, dev.deref_mut(), &mut sockets);
+        match dev.deref_mut() {
+            NetDevice::Nic(dev) => iface.poll(timestamp, dev, &mut sockets),
+            NetDevice::Loopback(dev) => iface.poll(timestamp, dev, &mut sockets),
+        };
+        if let Some(router) = self.slaac.apply(&mut iface, self.ether_addr) {
+            let route = Route::default_via(IpAddress::Ipv6(router), self.index);
+            if let Err(e) = route::add(&mut iface, route) {
+                warn!("SLAAC: failed to add the default route: {:?}", e);
+            }
+        }
     }
 }
 
 impl DeviceWrapper {
-    fn new(inner: AxNetDevice) -> Self {
+    fn new(inner: AxNetDevice, index: usize) -> Self {
         Self {
             inner: RefCell::new(inner),
+            index,
         }
     }
 }
@@ -210,7 +441,10 @@ impl Device for DeviceWrapper {
                 return None;
             }
         };
-        Some((AxNetRxToken(&self.inner, rx_buf), AxNetTxToken(&self.inner)))
+        Some((
+            AxNetRxToken(&self.inner, rx_buf, self.index),
+            AxNetTxToken(&self.inner),
+        ))
     }
 
     fn transmit(&mut self, _timestamp: Instant) -> Option<Self::TxToken<'_>> {
@@ -235,12 +469,12 @@ impl Device for DeviceWrapper {
     }
 }
 
-struct AxNetRxToken<'a>(&'a RefCell<AxNetDevice>, NetBufPtr);
+struct AxNetRxToken<'a>(&'a RefCell<AxNetDevice>, NetBufPtr, usize);
 struct AxNetTxToken<'a>(&'a RefCell<AxNetDevice>);
 
 impl<'a> RxToken for AxNetRxToken<'a> {
     fn preprocess(&self, sockets: &mut SocketSet<'_>) {
-        snoop_tcp_packet(self.1.packet(), sockets).ok();
+        snoop_packet(self.1.packet(), self.2, sockets).ok();
     }
 
     fn consume<R, F>(self, f: F) -> R
@@ -273,25 +507,177 @@ impl<'a> TxToken for AxNetTxToken<'a> {
     }
 }
 
-fn snoop_tcp_packet(buf: &[u8], sockets: &mut SocketSet<'_>) -> Result<(), smoltcp::wire::Error> {
-    use smoltcp::wire::{EthernetFrame, IpProtocol, Ipv4Packet, TcpPacket};
+fn snoop_packet(
+    buf: &[u8],
+    iface: usize,
+    sockets: &mut SocketSet<'_>,
+) -> Result<(), smoltcp::wire::Error> {
+    use smoltcp::wire::{EthernetFrame, EthernetProtocol};
 
     let ether_frame = EthernetFrame::new_checked(buf)?;
-    let ipv4_packet = Ipv4Packet::new_checked(ether_frame.payload())?;
+    match ether_frame.ethertype() {
+        EthernetProtocol::Ipv4 | EthernetProtocol::Ipv6 => {
+            snoop_ip_packet(ether_frame.payload(), iface, sockets)
+        }
+        _ => Ok(()),
+    }
+}
 
-    if ipv4_packet.next_header() == IpProtocol::Tcp {
-        let tcp_packet = TcpPacket::new_checked(ipv4_packet.payload())?;
-        let src_addr = (ipv4_packet.src_addr(), tcp_packet.src_port()).into();
-        let dst_addr = (ipv4_packet.dst_addr(), tcp_packet.dst_port()).into();
+fn snoop_ip_packet(
+    buf: &[u8],
+    iface: usize,
+    sockets: &mut SocketSet<'_>,
+) -> Result<(), smoltcp::wire::Error> {
+    use smoltcp::wire::{IpProtocol, IpVersion, Ipv4Packet, Ipv6Packet, TcpPacket};
+
+    let (src_ip, dst_ip, protocol, payload) = match IpVersion::of_packet(buf)? {
+        IpVersion::Ipv4 => {
+            let packet = Ipv4Packet::new_checked(buf)?;
+            (
+                IpAddress::Ipv4(packet.src_addr()),
+                IpAddress::Ipv4(packet.dst_addr()),
+                packet.next_header(),
+                packet.payload(),
+            )
+        }
+        IpVersion::Ipv6 => {
+            let packet = Ipv6Packet::new_checked(buf)?;
+            if packet.next_header() == IpProtocol::Icmpv6 && packet.hop_limit() == 255 {
+                // neighbor discovery messages must have a hop limit of 255
+                IFACES[iface]
+                    .slaac
+                    .snoop_router_advert(packet.src_addr(), packet.payload());
+            }
+            (
+                IpAddress::Ipv6(packet.src_addr()),
+                IpAddress::Ipv6(packet.dst_addr()),
+                packet.next_header(),
+                packet.payload(),
+            )
+        }
+    };
+
+    if protocol == IpProtocol::Tcp {
+        let tcp_packet = TcpPacket::new_checked(payload)?;
+        let src_addr = (src_ip, tcp_packet.src_port()).into();
+        let dst_addr = (dst_ip, tcp_packet.dst_port()).into();
         let is_first = tcp_packet.syn() && !tcp_packet.ack();
         if is_first {
             // create a socket for the first incoming TCP packet, as the later accept() returns.
-            LISTEN_TABLE.incoming_tcp_packet(src_addr, dst_addr, sockets);
+            LISTEN_TABLE.incoming_tcp_packet(src_addr, dst_addr, iface, sockets);
         }
     }
     Ok(())
 }
 
+/// Selects the egress interface for `dst` by the routing table, the first
+/// interface is used if no route matches.
+///
+/// It must not be called with a socket set locked.
+fn route_iface(dst: IpAddress) -> usize {
+    let mut connected: Option<(u8, usize)> = None;
+    for iface in IFACES.iter() {
+        for cidr in iface.iface.lock().ip_addrs() {
+            let prefix_len = cidr.prefix_len();
+            if cidr.contains_addr(&dst) && connected.map_or(true, |(len, _)| prefix_len > len) {
+                connected = Some((prefix_len, iface.index));
+            }
+        }
+    }
+    route::lookup(dst, connected).unwrap_or(0)
+}
+
+/// Returns the address of the interface `iface` used as the source address of
+/// the packets to `dst`: the one on the same network as `dst` if any, otherwise
+/// the first one of the same IP version.
+fn source_addr(iface: usize, dst: IpAddress) -> Option<IpAddress> {
+    let iface = IFACES[iface].iface.lock();
+    let mut addrs = iface
+        .ip_addrs()
+        .iter()
+        .filter(|cidr| cidr.address().version() == dst.version());
+    let first = addrs.clone().next();
+    addrs
+        .find(|cidr| cidr.contains_addr(&dst))
+        .or(first)
+        .map(|cidr| cidr.address())
+}
+
+/// Returns the index of the interface named `name`.
+fn iface_by_name(name: &str) -> AxResult<usize> {
+    match IFACES.iter().find(|iface| iface.name() == name) {
+        Some(iface) => Ok(iface.index),
+        None => ax_err!(NotFound, "no such network interface"),
+    }
+}
+
+/// Returns the index of the interface that owns the address `addr`.
+fn iface_by_addr(addr: IpAddress) -> Option<usize> {
+    IFACES
+        .iter()
+        .find(|iface| iface.iface.lock().has_ip_addr(addr))
+        .map(|iface| iface.index)
+}
+
+/// Information of a network interface.
+#[derive(Debug, Clone)]
+pub struct InterfaceInfo {
+    /// The interface name, such as `eth0`.
+    pub name: String,
+    /// The MAC address.
+    pub mac_addr: [u8; 6],
+    /// The IP addresses and their prefix lengths.
+    pub ip_addrs: Vec<(IpAddr, u8)>,
+}
+
+/// Returns all network interfaces and their addresses.
+pub fn interfaces() -> Vec<InterfaceInfo> {
+    IFACES
+        .iter()
+        .map(|iface| InterfaceInfo {
+            name: iface.name.clone(),
+            mac_addr: iface.ether_addr.0,
+            ip_addrs: iface
+                .iface
+                .lock()
+                .ip_addrs()
+                .iter()
+                .map(|cidr| (into_core_ipaddr(cidr.address()), cidr.prefix_len()))
+                .collect(),
+        })
+        .collect()
+}
+
+fn new_cidr(addr: IpAddr, prefix_len: u8) -> AxResult<IpCidr> {
+    let max_prefix_len = if addr.is_ipv4() { 32 } else { 128 };
+    if prefix_len > max_prefix_len {
+        return ax_err!(InvalidInput, "invalid prefix length");
+    }
+    Ok(IpCidr::new(from_core_ipaddr(addr), prefix_len))
+}
+
+/// Adds a route to the network `dst/prefix_len` via `gateway` on the
+/// interface named `dev`.
+///
+/// It replaces the existing route to the same network on the interface.
+pub fn add_route(dst: IpAddr, prefix_len: u8, gateway: IpAddr, dev: &str) -> AxResult {
+    let index = iface_by_name(dev)?;
+    let route = Route {
+        dst: new_cidr(dst, prefix_len)?,
+        gateway: from_core_ipaddr(gateway),
+        iface: index,
+    };
+    route::add(&mut IFACES[index].iface.lock(), route)
+}
+
+/// Removes the route to the network `dst/prefix_len` on the interface named
+/// `dev`.
+pub fn remove_route(dst: IpAddr, prefix_len: u8, dev: &str) -> AxResult {
+    let index = iface_by_name(dev)?;
+    let dst = new_cidr(dst, prefix_len)?;
+    route::remove(&mut IFACES[index].iface.lock(), index, dst)
+}
+
 /// Poll the network stack.
 ///
 /// It may receive packets from the NIC and process them, and transmit queued
@@ -302,29 +688,110 @@ pub fn poll_interfaces() {
 
 /// Benchmark raw socket transmit bandwidth.
 pub fn bench_transmit() {
-    ETH0.dev.lock().bench_transmit_bandwidth();
+    match &mut *IFACES[0].dev.lock() {
+        NetDevice::Nic(dev) => dev.bench_transmit_bandwidth(),
+        NetDevice::Loopback(_) => warn!("bench_transmit: no NIC found"),
+    }
 }
 
 /// Benchmark raw socket receive bandwidth.
 pub fn bench_receive() {
-    ETH0.dev.lock().bench_receive_bandwidth();
+    match &mut *IFACES[0].dev.lock() {
+        NetDevice::Nic(dev) => dev.bench_receive_bandwidth(),
+        NetDevice::Loopback(_) => warn!("bench_receive: no NIC found"),
+    }
+}
+
+/// Returns the `index`-th item of a comma-separated list, or an empty string.
+fn config_item(list: &'static str, index: usize) -> &'static str {
+    list.split(',').nth(index).unwrap_or_default().trim()
+}
+
+/// Configures the IPv4 address and gateway of the interface `index` given at
+/// compile time.
+fn setup_static_ipv4(index: usize) {
+    let iface = &IFACES[index];
+    let ip = config_item(IP, index);
+    if ip.is_empty() {
+        warn!("  no IPv4 address given for {}", iface.name());
+        return;
+    }
+    let ip = ip.parse().expect("invalid IP address");
+    let gateway = match config_item(GATEWAY, index) {
+        "" => None,
+        gateway => Some(gateway.parse().expect("invalid gateway IP address")),
+    };
+    iface.setup_ipv4(Ipv4Cidr::new(ip, IP_PREFIX), gateway);
+    info!("  ip:       {}/{}", ip, IP_PREFIX);
+    if let Some(gateway) = gateway {
+        info!("  gateway:  {}", gateway);
+    }
 }
 
-pub(crate) fn init(net_dev: AxNetDevice) {
-    let ether_addr = EthernetAddress(net_dev.mac_address().0);
-    let eth0 = InterfaceWrapper::new("eth0", net_dev, ether_addr);
+/// Configures the IPv6 addresses of the interface `index`: the static address
+/// if given, otherwise it's autoconfigured.
+fn setup_ipv6(index: usize) {
+    let iface = &IFACES[index];
+    let ip6 = config_item(IP6, index);
+    let gateway6 = config_item(GATEWAY6, index);
+    if !ip6.is_empty() {
+        iface.setup_ip_addr(ip6.parse().expect("invalid IPv6 address"), IP6_PREFIX);
+        info!("  ip6:      {}/{}", ip6, IP6_PREFIX);
+        if !gateway6.is_empty() {
+            iface.setup_gateway(gateway6.parse().expect("invalid IPv6 gateway address"));
+            info!("  gateway6: {}", gateway6);
+        }
+    }
+    let link_local = IpAddress::Ipv6(slaac::link_local_addr(iface.ether_addr));
+    iface.setup_ip_addr(link_local, IP6_PREFIX);
+    info!("  ip6:      {}/{} (link-local)", link_local, IP6_PREFIX);
+    if ip6.is_empty() {
+        iface.send_raw(&iface.slaac.start(iface.ether_addr));
+    }
+}
 
-    let ip = IP.parse().expect("invalid IP address");
-    let gateway = GATEWAY.parse().expect("invalid gateway IP address");
-    eth0.setup_ip_addr(ip, IP_PREFIX);
-    eth0.setup_gateway(gateway);
+/// Configures the loopback addresses of the interface `index`.
+fn setup_loopback(index: usize) {
+    let iface = &IFACES[index];
+    iface.setup_ip_addr(LOOPBACK_IP, LOOPBACK_IP_PREFIX);
+    iface.setup_ip_addr(LOOPBACK_IP6, LOOPBACK_IP6_PREFIX);
+    info!("  ip:       {}/{}", LOOPBACK_IP, LOOPBACK_IP_PREFIX);
+    info!("  ip6:      {}/{}", LOOPBACK_IP6, LOOPBACK_IP6_PREFIX);
+}
 
-    ETH0.init_by(eth0);
-    SOCKET_SET.init_by(SocketSetWrapper::new());
+pub(crate) fn init(net_devs: Vec<AxNetDevice>) {
+    // NICs come first, so that the first NIC is the fallback of the routing.
+    let mut ifaces = net_devs
+        .into_iter()
+        .enumerate()
+        .map(|(i, dev)| {
+            let ether_addr = EthernetAddress(dev.mac_address().0);
+            InterfaceWrapper::new(i, dev, ether_addr)
+        })
+        .collect::<Vec<_>>();
+    ifaces.push(InterfaceWrapper::new_loopback(ifaces.len()));
+    SOCKET_SET.init_by(SocketSetWrapper::new(ifaces.len()));
+    IFACES.init_by(ifaces);
     LISTEN_TABLE.init_by(ListenTable::new());
 
-    info!("created net interface {:?}:", ETH0.name());
-    info!("  ether:    {}", ETH0.ethernet_address());
-    info!("  ip:       {}/{}", ip, IP_PREFIX);
-    info!("  gateway:  {}", gateway);
+    #[cfg(feature = "dhcp")]
+    let configured = dhcp::init();
+    #[cfg(not(feature = "dhcp"))]
+    let configured = vec![false; IFACES.len()];
+
+    for iface in IFACES.iter() {
+        info!("created net interface {:?}:", iface.name());
+        if iface.is_loopback() {
+            setup_loopback(iface.index);
+            continue;
+        }
+        info!("  ether:    {}", iface.ethernet_address());
+        if !configured[iface.index] {
+            setup_static_ipv4(iface.index);
+        }
+        setup_ipv6(iface.index);
+    }
+
+    #[cfg(all(feature = "irq", feature = "multitask"))]
+    poll::init();
 }
@@ -0,0 +1,303 @@
+//! Driving the network stack.
+//!
+//! By default, a blocking socket operation polls the interfaces by itself, and
+//! yields the CPU until it can make progress.
+//!
+//! If the `irq` and `multitask` features are enabled and every NIC can raise
+//! interrupts, the interfaces are polled by a background task instead. The
+//! task is woken by the NIC interrupts, and by a timer when smoltcp wants to
+//! be polled again (see `Interface::poll_delay`). A blocked socket operation
+//! sleeps on the wait queue of its socket, until the socket is woken by
+//! smoltcp as it may become ready.
+//!
+//! The asynchronous socket operations (the `poll_*` methods) are built on
+//! [`poll_socket`]: they register the waker of the caller on the sockets in
+//! smoltcp instead of sleeping.
+
+use core::task::{Context, Poll, Waker};
+use core::time::Duration;
+
+use axerrno::{AxError, AxResult};
+use axhal::time::current_time;
+
+use super::SOCKET_SET;
+
+/// A wait queue of a socket, on which its blocking operations sleep.
+pub struct SocketWaiter {
+    #[cfg(all(feature = "irq", feature = "multitask"))]
+    inner: spin::Once<alloc::sync::Arc<irq_driven::WaiterInner>>,
+}
+
+impl SocketWaiter {
+    pub const fn new() -> Self {
+        Self {
+            #[cfg(all(feature = "irq", feature = "multitask"))]
+            inner: spin::Once::new(),
+        }
+    }
+
+    /// Calls `f` until it completes or fails.
+    ///
+    /// If `nonblocking` is true, `f` is called once. Otherwise, it's called
+    /// again each time the socket is woken while it returns
+    /// [`Err(WouldBlock)`](AxError::WouldBlock), until `timeout` has elapsed.
+    /// `register` registers the given waker on the sockets in smoltcp that `f`
+    /// waits for.
+    ///
+    /// With the feature `multitask`, it returns
+    /// [`Err(Interrupted)`](AxError::Interrupted) instead of waiting if the
+    /// current task has signals to handle or is requested to be canceled,
+    /// which the caller should act on after releasing the socket.
+    #[cfg_attr(
+        not(all(feature = "irq", feature = "multitask")),
+        allow(unused_variables)
+    )]
+    pub fn block_on<R, F, T>(
+        &self,
+        nonblocking: bool,
+        timeout: Option<Duration>,
+        register: R,
+        mut f: F,
+    ) -> AxResult<T>
+    where
+        R: FnMut(&Waker),
+        F: FnMut() -> AxResult<T>,
+    {
+        #[cfg(all(feature = "irq", feature = "multitask"))]
+        if irq_driven::is_enabled() {
+            return self.block_on_irq(nonblocking, timeout, register, f);
+        }
+
+        if nonblocking {
+            return f();
+        }
+        let deadline = timeout.map(|timeout| current_time() + timeout);
+        loop {
+            SOCKET_SET.poll_interfaces();
+            match f() {
+                Ok(t) => return Ok(t),
+                Err(AxError::WouldBlock) => {
+                    if deadline.is_some_and(|deadline| current_time() >= deadline) {
+                        return Err(AxError::WouldBlock);
+                    }
+                    // let the caller act on the signals and cancellation after
+                    // releasing its locks
+                    #[cfg(feature = "multitask")]
+                    if axtask::is_interrupted() {
+                        return Err(AxError::Interrupted);
+                    }
+                    axtask::yield_now()
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    #[cfg(all(feature = "irq", feature = "multitask"))]
+    fn block_on_irq<R, F, T>(
+        &self,
+        nonblocking: bool,
+        timeout: Option<Duration>,
+        mut register: R,
+        mut f: F,
+    ) -> AxResult<T>
+    where
+        R: FnMut(&Waker),
+        F: FnMut() -> AxResult<T>,
+    {
+        let deadline = timeout.map(|timeout| current_time() + timeout);
+        let inner = self
+            .inner
+            .call_once(|| alloc::sync::Arc::new(irq_driven::WaiterInner::new()));
+        loop {
+            // clear the flag before checking, so that no wakeup is lost
+            inner.clear();
+            register(&Waker::from(inner.clone()));
+            let res = f();
+            // the socket may have packets to send
+            irq_driven::wake_poll_task();
+            match res {
+                Err(AxError::WouldBlock) if !nonblocking => {
+                    if axtask::is_interrupted() {
+                        return Err(AxError::Interrupted);
+                    }
+                    if !inner.wait(deadline) {
+                        return Err(AxError::WouldBlock); // timed out
+                    }
+                }
+                res => return res,
+            }
+        }
+    }
+}
+
+/// Calls `f` once for an asynchronous socket operation, returns
+/// [`Poll::Pending`] if it returns [`Err(WouldBlock)`](AxError::WouldBlock).
+///
+/// `register` registers the waker of `cx` on the sockets in smoltcp that `f`
+/// waits for, so that the caller is woken when they may become ready. If the
+/// interfaces are not polled by the background task, they are polled here,
+/// and the caller is woken at once to poll again.
+pub fn poll_socket<R, F, T>(cx: &mut Context<'_>, mut register: R, f: F) -> Poll<AxResult<T>>
+where
+    R: FnMut(&Waker),
+    F: FnOnce() -> AxResult<T>,
+{
+    let irq_driven = is_irq_driven();
+    if !irq_driven {
+        SOCKET_SET.poll_interfaces();
+    }
+    register(cx.waker());
+    let res = f();
+    #[cfg(all(feature = "irq", feature = "multitask"))]
+    if irq_driven {
+        // the socket may have packets to send
+        irq_driven::wake_poll_task();
+    }
+    match res {
+        Err(AxError::WouldBlock) => {
+            if !irq_driven {
+                cx.waker().wake_by_ref();
+            }
+            Poll::Pending
+        }
+        res => Poll::Ready(res),
+    }
+}
+
+/// Whether the interfaces are polled by the background task.
+fn is_irq_driven() -> bool {
+    #[cfg(all(feature = "irq", feature = "multitask"))]
+    return irq_driven::is_enabled();
+    #[cfg(not(all(feature = "irq", feature = "multitask")))]
+    return false;
+}
+
+/// Starts the poll task if the NICs support interrupts.
+#[cfg(all(feature = "irq", feature = "multitask"))]
+pub fn init() {
+    irq_driven::init();
+}
+
+#[cfg(all(feature = "irq", feature = "multitask"))]
+mod irq_driven {
+    use alloc::{sync::Arc, task::Wake};
+    use core::sync::atomic::{AtomicBool, Ordering};
+    use core::time::Duration;
+
+    use axhal::time::TimeValue;
+    use axtask::WaitQueue;
+
+    use super::super::{IFACES, SOCKET_SET};
+
+    /// The maximum number of NICs that can be driven by interrupts.
+    const MAX_IRQ_NICS: usize = 4;
+    const IRQ_HANDLERS: [fn(); MAX_IRQ_NICS] = [
+        nic_irq_handler::<0>,
+        nic_irq_handler::<1>,
+        nic_irq_handler::<2>,
+        nic_irq_handler::<3>,
+    ];
+    /// The poll task wakes up at least once in this duration.
+    const MAX_POLL_DELAY: Duration = Duration::from_secs(1);
+
+    static ENABLED: AtomicBool = AtomicBool::new(false);
+    static POLL_PENDING: AtomicBool = AtomicBool::new(false);
+    static POLL_WQ: WaitQueue = WaitQueue::new();
+
+    pub struct WaiterInner {
+        wq: WaitQueue,
+        ready: AtomicBool,
+    }
+
+    impl WaiterInner {
+        pub const fn new() -> Self {
+            Self {
+                wq: WaitQueue::new(),
+                ready: AtomicBool::new(false),
+            }
+        }
+
+        pub fn clear(&self) {
+            self.ready.store(false, Ordering::Release);
+        }
+
+        /// Waits until woken, returns `false` if `deadline` has passed.
+        ///
+        /// It can be woken up early by signals and cancellation.
+        pub fn wait(&self, deadline: Option<TimeValue>) -> bool {
+            let ready = || self.ready.load(Ordering::Acquire);
+            !self.wq.wait_until_interruptible(ready, deadline)
+        }
+    }
+
+    impl Wake for WaiterInner {
+        fn wake(self: Arc<Self>) {
+            self.wake_by_ref();
+        }
+
+        fn wake_by_ref(self: &Arc<Self>) {
+            self.ready.store(true, Ordering::Release);
+            self.wq.notify_all(false);
+        }
+    }
+
+    pub fn is_enabled() -> bool {
+        ENABLED.load(Ordering::Acquire)
+    }
+
+    pub fn wake_poll_task() {
+        POLL_PENDING.store(true, Ordering::Release);
+        POLL_WQ.notify_one(false);
+    }
+
+    /// The handler of the NIC interrupts of the interface `I`.
+    ///
+    /// The IRQ is masked until the poll task acknowledges the interrupt.
+    fn nic_irq_handler<const I: usize>() {
+        if let Some(irq_num) = IFACES[I].irq_num {
+            axhal::irq::set_enable(irq_num, false);
+        }
+        wake_poll_task();
+    }
+
+    pub fn init() {
+        let nics = || IFACES.iter().filter(|iface| !iface.is_loopback());
+        if nics().any(|iface| iface.irq_num.is_none() || iface.index >= MAX_IRQ_NICS) {
+            info!("  some NIC cannot raise interrupts, poll in blocking operations");
+            return;
+        }
+        for iface in nics() {
+            let irq_num = iface.irq_num.unwrap();
+            if !axhal::irq::register_handler(irq_num, IRQ_HANDLERS[iface.index]) {
+                warn!("  failed to register IRQ {} for {}", irq_num, iface.name());
+                return;
+            }
+            info!("  {}: IRQ {}", iface.name(), irq_num);
+        }
+        ENABLED.store(true, Ordering::Release);
+        axtask::spawn(poll_task);
+    }
+
+    fn poll_task() {
+        loop {
+            POLL_PENDING.store(false, Ordering::Release);
+            for iface in IFACES.iter() {
+                iface.ack_interrupt();
+            }
+            SOCKET_SET.poll_interfaces();
+            for irq_num in IFACES.iter().filter_map(|iface| iface.irq_num) {
+                axhal::irq::set_enable(irq_num, true);
+            }
+
+            let delay = SOCKET_SET
+                .poll_delay()
+                .map_or(MAX_POLL_DELAY, |delay| delay.min(MAX_POLL_DELAY));
+            if delay.is_zero() {
+                axtask::yield_now();
+            } else {
+                POLL_WQ.wait_timeout_until(delay, || POLL_PENDING.load(Ordering::Acquire));
+            }
+        }
+    }
+}
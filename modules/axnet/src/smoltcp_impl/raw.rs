@@ -0,0 +1,249 @@
+use core::net::IpAddr;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::task::Waker;
+use core::time::Duration;
+
+use axerrno::{ax_err, ax_err_type, AxError, AxResult};
+use axio::PollState;
+use spin::RwLock;
+
+use smoltcp::phy::ChecksumCapabilities;
+use smoltcp::socket::raw;
+use smoltcp::wire::{IpAddress, IpProtocol, IpVersion};
+use smoltcp::wire::{Ipv4Packet, Ipv4Repr, Ipv6Packet, Ipv6Repr};
+
+use super::addr::{from_core_ipaddr, into_core_ipaddr};
+use super::poll::SocketWaiter;
+use super::{iface_by_name, route_iface, source_addr};
+use super::{SocketHandle, SocketSetWrapper, SOCKET_SET};
+
+const DEFAULT_HOP_LIMIT: u8 = 64;
+
+/// A raw IP socket that provides POSIX-like APIs, for one IP version and one
+/// protocol above IP.
+///
+/// Like Linux (without `IP_HDRINCL`), the IP header is filled in on sending,
+/// and the received packets include the IPv4 header but not the IPv6 header.
+pub struct RawSocket {
+    handle: RwLock<SocketHandle>,
+    device: RwLock<Option<usize>>,
+    ipv6: bool,
+    protocol: IpProtocol,
+    nonblock: AtomicBool,
+    recv_timeout: RwLock<Option<Duration>>,
+    waiter: SocketWaiter,
+}
+
+impl RawSocket {
+    /// Creates a new raw socket of IPv6 if `ipv6` is true, or IPv4 otherwise,
+    /// for the IP protocol number `protocol`.
+    pub fn new(ipv6: bool, protocol: u8) -> Self {
+        let version = if ipv6 {
+            IpVersion::Ipv6
+        } else {
+            IpVersion::Ipv4
+        };
+        let protocol = IpProtocol::from(protocol);
+        let socket = SocketSetWrapper::new_raw_socket(version, protocol);
+        let handle = SOCKET_SET.add(socket, 0);
+        Self {
+            handle: RwLock::new(handle),
+            device: RwLock::new(None),
+            ipv6,
+            protocol,
+            nonblock: AtomicBool::new(false),
+            recv_timeout: RwLock::new(None),
+            waiter: SocketWaiter::new(),
+        }
+    }
+
+    /// Returns whether this socket is in nonblocking mode.
+    #[inline]
+    pub fn is_nonblocking(&self) -> bool {
+        self.nonblock.load(Ordering::Acquire)
+    }
+
+    /// Moves this raw socket into or out of nonblocking mode.
+    ///
+    /// This will result in `recv_from` and `send_to` operations becoming
+    /// nonblocking, i.e., immediately returning from their calls. If the IO
+    /// operation could not be completed and needs to be retried, an error with
+    /// kind [`Err(WouldBlock)`](AxError::WouldBlock) is returned.
+    #[inline]
+    pub fn set_nonblocking(&self, nonblocking: bool) {
+        self.nonblock.store(nonblocking, Ordering::Release);
+    }
+
+    /// Binds the socket to the network interface named `name`, so that it only
+    /// sends and receives packets through it. An empty name removes the binding.
+    pub fn bind_to_device(&self, name: &str) -> AxResult {
+        let mut device = self.device.write();
+        if name.is_empty() {
+            *device = None;
+        } else {
+            let index = iface_by_name(name)?;
+            self.move_to(index);
+            *device = Some(index);
+        }
+        Ok(())
+    }
+
+    /// Returns the timeout of receiving, i.e. `SO_RCVTIMEO`.
+    pub fn recv_timeout(&self) -> Option<Duration> {
+        *self.recv_timeout.read()
+    }
+
+    /// Sets the timeout of receiving, i.e. `SO_RCVTIMEO`. `None` means
+    /// blocking indefinitely.
+    ///
+    /// When timed out, [`Err(WouldBlock)`](AxError::WouldBlock) is returned.
+    pub fn set_recv_timeout(&self, timeout: Option<Duration>) -> AxResult {
+        if timeout.is_some_and(|t| t.is_zero()) {
+            return ax_err!(InvalidInput, "socket set_recv_timeout() failed");
+        }
+        *self.recv_timeout.write() = timeout;
+        Ok(())
+    }
+
+    /// Sends `buf` as the payload of an IP packet to the given address. On
+    /// success, returns the number of bytes written.
+    pub fn send_to(&self, buf: &[u8], addr: IpAddr) -> AxResult<usize> {
+        if addr.is_unspecified() || addr.is_ipv6() != self.ipv6 {
+            return ax_err!(InvalidInput, "socket send_to() failed: invalid address");
+        }
+        let dst = from_core_ipaddr(addr);
+        let iface = self.route_to(dst);
+        let src = source_addr(iface, dst)
+            .ok_or_else(|| ax_err_type!(AddrNotAvailable, "socket send_to() failed"))?;
+
+        self.block_on(
+            None,
+            |waker| {
+                SOCKET_SET.with_socket_mut::<raw::Socket, _, _>(*self.handle.read(), |socket| {
+                    socket.register_send_waker(waker)
+                })
+            },
+            || {
+                SOCKET_SET.with_socket_mut::<raw::Socket, _, _>(*self.handle.read(), |socket| {
+                    if !socket.can_send() {
+                        // tx buffer is full
+                        return Err(AxError::WouldBlock);
+                    }
+                    match (src, dst) {
+                        (IpAddress::Ipv4(src_addr), IpAddress::Ipv4(dst_addr)) => {
+                            let repr = Ipv4Repr {
+                                src_addr,
+                                dst_addr,
+                                next_header: self.protocol,
+                                payload_len: buf.len(),
+                                hop_limit: DEFAULT_HOP_LIMIT,
+                            };
+                            let header_len = repr.buffer_len();
+                            let data = socket
+                                .send(header_len + buf.len())
+                                .map_err(|_| AxError::WouldBlock)?;
+                            let checksum_caps = ChecksumCapabilities::default();
+                            repr.emit(&mut Ipv4Packet::new_unchecked(&mut *data), &checksum_caps);
+                            data[header_len..].copy_from_slice(buf);
+                        }
+                        (IpAddress::Ipv6(src_addr), IpAddress::Ipv6(dst_addr)) => {
+                            let repr = Ipv6Repr {
+                                src_addr,
+                                dst_addr,
+                                next_header: self.protocol,
+                                payload_len: buf.len(),
+                                hop_limit: DEFAULT_HOP_LIMIT,
+                            };
+                            let header_len = repr.buffer_len();
+                            let data = socket
+                                .send(header_len + buf.len())
+                                .map_err(|_| AxError::WouldBlock)?;
+                            repr.emit(&mut Ipv6Packet::new_unchecked(&mut *data));
+                            data[header_len..].copy_from_slice(buf);
+                        }
+                        _ => unreachable!("the source address has the same version"),
+                    }
+                    Ok(buf.len())
+                })
+            },
+        )
+    }
+
+    /// Receives a single IP packet on the socket. On success, returns the
+    /// number of bytes read and the origin.
+    ///
+    /// The packet is truncated if `buf` is too small.
+    pub fn recv_from(&self, buf: &mut [u8]) -> AxResult<(usize, IpAddr)> {
+        self.block_on(
+            self.recv_timeout(),
+            |waker| {
+                SOCKET_SET.with_socket_mut::<raw::Socket, _, _>(*self.handle.read(), |socket| {
+                    socket.register_recv_waker(waker)
+                })
+            },
+            || {
+                SOCKET_SET.with_socket_mut::<raw::Socket, _, _>(*self.handle.read(), |socket| {
+                    let packet = socket.recv().map_err(|_| AxError::WouldBlock)?;
+                    // the header is emitted by smoltcp, no need to check it
+                    let (src, data) = if self.ipv6 {
+                        let packet = Ipv6Packet::new_unchecked(packet);
+                        (IpAddress::Ipv6(packet.src_addr()), packet.payload())
+                    } else {
+                        let src = Ipv4Packet::new_unchecked(packet).src_addr();
+                        (IpAddress::Ipv4(src), packet)
+                    };
+                    let len = data.len().min(buf.len());
+                    buf[..len].copy_from_slice(&data[..len]);
+                    Ok((len, into_core_ipaddr(src)))
+                })
+            },
+        )
+    }
+
+    /// Whether the socket is readable or writable.
+    pub fn poll(&self) -> AxResult<PollState> {
+        SOCKET_SET.with_socket_mut::<raw::Socket, _, _>(*self.handle.read(), |socket| {
+            Ok(PollState {
+                readable: socket.can_recv(),
+                writable: socket.can_send(),
+            })
+        })
+    }
+}
+
+/// Private methods
+impl RawSocket {
+    /// Moves the socket to the interface `index`.
+    fn move_to(&self, index: usize) {
+        let mut handle = self.handle.write();
+        *handle = SOCKET_SET.move_socket(*handle, index);
+    }
+
+    /// Moves the socket to the interface routing to `dst`, unless it's bound
+    /// to a device. Returns the interface of the socket.
+    fn route_to(&self, dst: IpAddress) -> usize {
+        match *self.device.read() {
+            Some(index) => index,
+            None => {
+                let index = route_iface(dst);
+                self.move_to(index);
+                index
+            }
+        }
+    }
+
+    fn block_on<R, F, T>(&self, timeout: Option<Duration>, register: R, f: F) -> AxResult<T>
+    where
+        R: FnMut(&Waker),
+        F: FnMut() -> AxResult<T>,
+    {
+        self.waiter
+            .block_on(self.is_nonblocking(), timeout, register, f)
+    }
+}
+
+impl Drop for RawSocket {
+    fn drop(&mut self) {
+        SOCKET_SET.remove(*self.handle.get_mut());
+    }
+}
@@ -0,0 +1,125 @@
+//! The routing table, which selects the egress interface for a destination.
+//!
+//! Each interface is a route to the networks of its addresses (connected
+//! routes), other routes are via gateways, such as the default routes. The
+//! route with the longest matching prefix is selected, and connected routes
+//! win ties.
+//!
+//! Routes via gateways are also added to the smoltcp interface, so that
+//! packets are sent to the gateway.
+
+use alloc::vec::Vec;
+use axerrno::{ax_err, AxResult};
+use axsync::Mutex;
+use smoltcp::iface::{Interface, Route as SmolRoute};
+use smoltcp::wire::{IpAddress, IpCidr, Ipv6Address};
+
+/// A route via a gateway.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Route {
+    /// The destination network.
+    pub dst: IpCidr,
+    /// The gateway, must be reachable from the interface.
+    pub gateway: IpAddress,
+    /// The index of the egress interface.
+    pub iface: usize,
+}
+
+impl Route {
+    /// Creates the default route via `gateway` on the interface `iface`.
+    pub fn default_via(gateway: IpAddress, iface: usize) -> Self {
+        Self {
+            dst: default_dst(gateway),
+            gateway,
+            iface,
+        }
+    }
+}
+
+static ROUTES: Mutex<Vec<Route>> = Mutex::new(Vec::new());
+
+/// Returns the destination of the default route via `gateway`.
+pub fn default_dst(gateway: IpAddress) -> IpCidr {
+    match gateway {
+        IpAddress::Ipv4(_) => IpCidr::new(IpAddress::v4(0, 0, 0, 0), 0),
+        IpAddress::Ipv6(_) => IpCidr::new(IpAddress::Ipv6(Ipv6Address::UNSPECIFIED), 0),
+    }
+}
+
+/// Adds a route, replaces the existing one to the same network on the same
+/// interface.
+///
+/// `iface` is the smoltcp interface of `route.iface`.
+pub fn add(iface: &mut Interface, route: Route) -> AxResult {
+    let same_family = matches!(
+        (route.dst.address(), route.gateway),
+        (IpAddress::Ipv4(_), IpAddress::Ipv4(_)) | (IpAddress::Ipv6(_), IpAddress::Ipv6(_))
+    );
+    if !same_family {
+        return ax_err!(InvalidInput, "route: address family mismatch");
+    }
+
+    let mut routes = ROUTES.lock();
+    let mut res = Ok(());
+    iface.routes_mut().update(|table| {
+        if let Some(pos) = table.iter().position(|r| r.cidr == route.dst) {
+            table.swap_remove(pos);
+        }
+        let new_route = SmolRoute {
+            cidr: route.dst,
+            via_router: route.gateway,
+            preferred_until: None,
+            expires_at: None,
+        };
+        if table.push(new_route).is_err() {
+            res = ax_err!(NoMemory, "route: too many routes");
+        }
+    });
+    res?;
+
+    routes.retain(|r| r.iface != route.iface || r.dst != route.dst);
+    routes.push(route);
+    debug!(
+        "route added: {} via {} on {}",
+        route.dst, route.gateway, route.iface
+    );
+    Ok(())
+}
+
+/// Removes the route to `dst` on the interface `index`.
+///
+/// `iface` is the smoltcp interface of `index`.
+pub fn remove(iface: &mut Interface, index: usize, dst: IpCidr) -> AxResult {
+    let mut routes = ROUTES.lock();
+    let Some(pos) = routes.iter().position(|r| r.iface == index && r.dst == dst) else {
+        return ax_err!(NotFound, "route: no such route");
+    };
+    routes.remove(pos);
+    iface.routes_mut().update(|table| {
+        if let Some(pos) = table.iter().position(|r| r.cidr == dst) {
+            table.swap_remove(pos);
+        }
+    });
+    debug!("route removed: {} on {}", dst, index);
+    Ok(())
+}
+
+/// Returns all routes via gateways.
+pub fn routes() -> Vec<Route> {
+    ROUTES.lock().clone()
+}
+
+/// Selects the egress interface for `dst`.
+///
+/// `connected` is the longest matching prefix among the interface addresses
+/// and its interface index, if any.
+pub fn lookup(dst: IpAddress, connected: Option<(u8, usize)>) -> Option<usize> {
+    let mut best = connected;
+    for route in ROUTES.lock().iter() {
+        let prefix_len = route.dst.prefix_len();
+        if route.dst.contains_addr(&dst) && best.map_or(true, |(len, _)| prefix_len > len) {
+            best = Some((prefix_len, route.iface));
+        }
+    }
+    best.map(|(_, iface)| iface)
+}
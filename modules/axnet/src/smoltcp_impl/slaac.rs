@@ -0,0 +1,164 @@
+//! IPv6 stateless address autoconfiguration (SLAAC, RFC 4862).
+//!
+//! A router solicitation is sent when the interface is created. When the
+//! first router advertisement with an autonomous `/64` prefix is received, an
+//! address is formed from the prefix and the interface identifier derived
+//! from the MAC address (EUI-64), and the router becomes the default gateway.
+//! Later advertisements are ignored.
+
+use alloc::{vec, vec::Vec};
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use axsync::Mutex;
+use smoltcp::iface::Interface;
+use smoltcp::wire::{EthernetAddress, IpAddress, IpCidr, Ipv6Address};
+
+const ICMPV6_ROUTER_ADVERT: u8 = 134;
+const NDISC_OPT_PREFIX_INFO: u8 = 3;
+const PREFIX_FLAG_AUTONOMOUS: u8 = 0x40;
+const SLAAC_PREFIX_LEN: u8 = 64;
+
+fn with_interface_id(prefix: Ipv6Address, ether_addr: EthernetAddress) -> Ipv6Address {
+    let mac = ether_addr.0;
+    let mut bytes = prefix.0;
+    bytes[8..11].copy_from_slice(&mac[..3]);
+    bytes[8] ^= 0x02; // flip the universal/local bit
+    bytes[11..13].copy_from_slice(&[0xff, 0xfe]);
+    bytes[13..].copy_from_slice(&mac[3..]);
+    Ipv6Address(bytes)
+}
+
+fn is_link_local(cidr: &IpCidr) -> bool {
+    matches!(cidr, IpCidr::Ipv6(cidr) if cidr.address().is_link_local())
+}
+
+/// Returns the link-local address (`fe80::/64`) of the interface.
+pub fn link_local_addr(ether_addr: EthernetAddress) -> Ipv6Address {
+    with_interface_id(Ipv6Address::new(0xfe80, 0, 0, 0, 0, 0, 0, 0), ether_addr)
+}
+
+/// The autoconfiguration state of an interface.
+pub struct Slaac {
+    /// Whether we are waiting for a router advertisement.
+    enabled: AtomicBool,
+    /// The received prefix and router, to be applied after the interface is
+    /// polled.
+    pending: Mutex<Option<(Ipv6Address, Ipv6Address)>>,
+}
+
+impl Slaac {
+    pub const fn new() -> Self {
+        Self {
+            enabled: AtomicBool::new(false),
+            pending: Mutex::new(None),
+        }
+    }
+
+    /// Starts autoconfiguration, returns the router solicitation frame to send.
+    pub fn start(&self, ether_addr: EthernetAddress) -> Vec<u8> {
+        use smoltcp::phy::ChecksumCapabilities;
+        use smoltcp::wire::{
+            EthernetFrame, EthernetProtocol, EthernetRepr, Icmpv6Packet, Icmpv6Repr,
+        };
+        use smoltcp::wire::{IpProtocol, Ipv6Packet, Ipv6Repr, NdiscRepr, RawHardwareAddress};
+
+        self.enabled.store(true, Ordering::Release);
+
+        let src_addr = link_local_addr(ether_addr);
+        let dst_addr = Ipv6Address::LINK_LOCAL_ALL_ROUTERS;
+        let icmp_repr = Icmpv6Repr::Ndisc(NdiscRepr::RouterSolicit {
+            lladdr: Some(RawHardwareAddress::from_bytes(ether_addr.as_bytes())),
+        });
+        let ip_repr = Ipv6Repr {
+            src_addr,
+            dst_addr,
+            next_header: IpProtocol::Icmpv6,
+            payload_len: icmp_repr.buffer_len(),
+            hop_limit: 255,
+        };
+        let eth_repr = EthernetRepr {
+            src_addr: ether_addr,
+            dst_addr: EthernetAddress([0x33, 0x33, 0, 0, 0, 2]),
+            ethertype: EthernetProtocol::Ipv6,
+        };
+
+        let mut buf =
+            vec![0; eth_repr.buffer_len() + ip_repr.buffer_len() + icmp_repr.buffer_len()];
+        let mut frame = EthernetFrame::new_unchecked(&mut buf[..]);
+        eth_repr.emit(&mut frame);
+        let mut ip_packet = Ipv6Packet::new_unchecked(frame.payload_mut());
+        ip_repr.emit(&mut ip_packet);
+        let mut icmp_packet = Icmpv6Packet::new_unchecked(ip_packet.payload_mut());
+        icmp_repr.emit(
+            &src_addr.into(),
+            &dst_addr.into(),
+            &mut icmp_packet,
+            &ChecksumCapabilities::default(),
+        );
+        buf
+    }
+
+    /// Checks whether the ICMPv6 message `icmp` from `src_addr` is a router
+    /// advertisement that can be used for autoconfiguration.
+    pub fn snoop_router_advert(&self, src_addr: Ipv6Address, icmp: &[u8]) {
+        // type, code, checksum, hop limit, flags, router lifetime, reachable time, retrans timer
+        const RA_HEADER_LEN: usize = 16;
+        if !self.enabled.load(Ordering::Acquire)
+            || icmp.len() < RA_HEADER_LEN
+            || icmp[0] != ICMPV6_ROUTER_ADVERT
+        {
+            return;
+        }
+        if u16::from_be_bytes([icmp[6], icmp[7]]) == 0 {
+            // not a default router
+            return;
+        }
+
+        let mut opts = &icmp[RA_HEADER_LEN..];
+        while opts.len() >= 8 {
+            let (ty, len) = (opts[0], opts[1] as usize * 8);
+            if len == 0 || len > opts.len() {
+                break;
+            }
+            if ty == NDISC_OPT_PREFIX_INFO
+                && len == 32
+                && opts[2] == SLAAC_PREFIX_LEN
+                && opts[3] & PREFIX_FLAG_AUTONOMOUS != 0
+            {
+                if self.enabled.swap(false, Ordering::AcqRel) {
+                    let prefix = Ipv6Address::from_bytes(&opts[16..32]);
+                    *self.pending.lock() = Some((prefix, src_addr));
+                }
+                return;
+            }
+            opts = &opts[len..];
+        }
+    }
+
+    /// Configures the address if a router advertisement has been received,
+    /// returns the router to be used as the default gateway.
+    pub fn apply(&self, iface: &mut Interface, ether_addr: EthernetAddress) -> Option<Ipv6Address> {
+        let (prefix, router) = self.pending.lock().take()?;
+        let cidr = IpCidr::new(
+            IpAddress::Ipv6(with_interface_id(prefix, ether_addr)),
+            SLAAC_PREFIX_LEN,
+        );
+        iface.update_ip_addrs(|ip_addrs| {
+            // keep link-local addresses at the end, so that the global address is
+            // preferred as the source address.
+            let old = ip_addrs.iter().copied().collect::<Vec<_>>();
+            ip_addrs.clear();
+            for addr in old.iter().filter(|a| !is_link_local(a)) {
+                ip_addrs.push(*addr).unwrap();
+            }
+            if ip_addrs.push(cidr).is_err() {
+                warn!("SLAAC: too many IP addresses, {} is ignored", cidr);
+            }
+            for addr in old.iter().filter(|a| is_link_local(a)) {
+                ip_addrs.push(*addr).unwrap();
+            }
+        });
+        info!("SLAAC: ip: {}, gateway: {}", cidr, router);
+        Some(router)
+    }
+}
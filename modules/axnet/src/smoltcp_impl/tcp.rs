@@ -1,17 +1,20 @@
 use core::cell::UnsafeCell;
 use core::net::SocketAddr;
 use core::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use core::task::{Context, Poll, Waker};
+use core::time::Duration;
 
 use axerrno::{ax_err, ax_err_type, AxError, AxResult};
 use axio::PollState;
 use axsync::Mutex;
 
-use smoltcp::iface::SocketHandle;
 use smoltcp::socket::tcp::{self, ConnectError, State};
 use smoltcp::wire::{IpEndpoint, IpListenEndpoint};
 
 use super::addr::{from_core_sockaddr, into_core_sockaddr, is_unspecified, UNSPECIFIED_ENDPOINT};
-use super::{SocketSetWrapper, ETH0, LISTEN_TABLE, SOCKET_SET};
+use super::poll::{poll_socket, SocketWaiter};
+use super::{iface_by_name, route_iface, SocketHandle, SocketSetWrapper};
+use super::{IFACES, LISTEN_TABLE, SOCKET_SET, TCP_RX_BUF_LEN, TCP_TX_BUF_LEN};
 
 // State transitions:
 // CLOSED -(connect)-> BUSY -> CONNECTING -> CONNECTED -(shutdown)-> BUSY -> CLOSED
@@ -25,12 +28,62 @@ const STATE_CONNECTING: u8 = 2;
 const STATE_CONNECTED: u8 = 3;
 const STATE_LISTENING: u8 = 4;
 
+const DEFAULT_KEEP_IDLE: Duration = Duration::from_secs(7200);
+
+/// Options of a TCP socket.
+///
+/// A socket accepted by a listener inherits the options of the listener.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct TcpOptions {
+    reuse_addr: bool,
+    recv_buf_size: usize,
+    send_buf_size: usize,
+    nodelay: bool,
+    keep_alive: bool,
+    keep_idle: Duration,
+    recv_timeout: Option<Duration>,
+    send_timeout: Option<Duration>,
+}
+
+impl TcpOptions {
+    const fn new() -> Self {
+        Self {
+            reuse_addr: false,
+            recv_buf_size: TCP_RX_BUF_LEN,
+            send_buf_size: TCP_TX_BUF_LEN,
+            nodelay: false,
+            keep_alive: false,
+            keep_idle: DEFAULT_KEEP_IDLE,
+            recv_timeout: None,
+            send_timeout: None,
+        }
+    }
+
+    /// Creates a socket in smoltcp with the options applied.
+    pub fn new_socket<'a>(&self) -> tcp::Socket<'a> {
+        let mut socket = SocketSetWrapper::new_tcp_socket(self.recv_buf_size, self.send_buf_size);
+        self.apply(&mut socket);
+        socket
+    }
+
+    /// Applies the options handled by smoltcp to `socket`. The buffer sizes
+    /// can't be changed after the socket is created.
+    fn apply(&self, socket: &mut tcp::Socket) {
+        socket.set_nagle_enabled(!self.nodelay);
+        socket.set_keep_alive(self.keep_alive.then(|| self.keep_idle.into()));
+    }
+}
+
 /// A TCP socket that provides POSIX-like APIs.
 ///
 /// - [`connect`] is for TCP clients.
 /// - [`bind`], [`listen`], and [`accept`] are for TCP servers.
 /// - Other methods are for both TCP clients and servers.
 ///
+/// The `poll_*` methods are the asynchronous versions of them, which never
+/// block and ignore the timeouts. They register the waker of the given context
+/// and return [`Poll::Pending`] if the operation can't complete yet.
+///
 /// [`connect`]: TcpSocket::connect
 /// [`bind`]: TcpSocket::bind
 /// [`listen`]: TcpSocket::listen
@@ -40,7 +93,11 @@ pub struct TcpSocket {
     handle: UnsafeCell<Option<SocketHandle>>,
     local_addr: UnsafeCell<IpEndpoint>,
     peer_addr: UnsafeCell<IpEndpoint>,
+    device: UnsafeCell<Option<usize>>,
     nonblock: AtomicBool,
+    opts: Mutex<TcpOptions>,
+    error: Mutex<Option<AxError>>,
+    waiter: SocketWaiter,
 }
 
 unsafe impl Sync for TcpSocket {}
@@ -53,7 +110,11 @@ impl TcpSocket {
             handle: UnsafeCell::new(None),
             local_addr: UnsafeCell::new(UNSPECIFIED_ENDPOINT),
             peer_addr: UnsafeCell::new(UNSPECIFIED_ENDPOINT),
+            device: UnsafeCell::new(None),
             nonblock: AtomicBool::new(false),
+            opts: Mutex::new(TcpOptions::new()),
+            error: Mutex::new(None),
+            waiter: SocketWaiter::new(),
         }
     }
 
@@ -62,13 +123,18 @@ impl TcpSocket {
         handle: SocketHandle,
         local_addr: IpEndpoint,
         peer_addr: IpEndpoint,
+        opts: TcpOptions,
     ) -> Self {
         Self {
             state: AtomicU8::new(STATE_CONNECTED),
             handle: UnsafeCell::new(Some(handle)),
             local_addr: UnsafeCell::new(local_addr),
             peer_addr: UnsafeCell::new(peer_addr),
+            device: UnsafeCell::new(None),
             nonblock: AtomicBool::new(false),
+            opts: Mutex::new(opts),
+            error: Mutex::new(None),
+            waiter: SocketWaiter::new(),
         }
     }
 
@@ -115,75 +181,202 @@ impl TcpSocket {
         self.nonblock.store(nonblocking, Ordering::Release);
     }
 
-    /// Connects to the given address and port.
+    /// Binds the socket to the network interface named `name`, so that it only
+    /// sends and receives packets through it. An empty name removes the binding.
     ///
-    /// The local port is generated automatically.
-    pub fn connect(&self, remote_addr: SocketAddr) -> AxResult {
-        self.update_state(STATE_CLOSED, STATE_CONNECTING, || {
-            // SAFETY: no other threads can read or write these fields.
-            let handle = unsafe { self.handle.get().read() }
-                .unwrap_or_else(|| SOCKET_SET.add(SocketSetWrapper::new_tcp_socket()));
-
-            // TODO: check remote addr unreachable
-            let remote_endpoint = from_core_sockaddr(remote_addr);
-            let bound_endpoint = self.bound_endpoint()?;
-            let iface = &ETH0.iface;
-            let (local_endpoint, remote_endpoint) = SOCKET_SET
-                .with_socket_mut::<tcp::Socket, _, _>(handle, |socket| {
-                    socket
-                        .connect(iface.lock().context(), remote_endpoint, bound_endpoint)
-                        .or_else(|e| match e {
-                            ConnectError::InvalidState => {
-                                ax_err!(BadState, "socket connect() failed")
-                            }
-                            ConnectError::Unaddressable => {
-                                ax_err!(ConnectionRefused, "socket connect() failed")
-                            }
-                        })?;
-                    Ok((
-                        socket.local_endpoint().unwrap(),
-                        socket.remote_endpoint().unwrap(),
-                    ))
-                })?;
-            unsafe {
-                // SAFETY: no other threads can read or write these fields as we
-                // have changed the state to `BUSY`.
-                self.local_addr.get().write(local_endpoint);
-                self.peer_addr.get().write(remote_endpoint);
-                self.handle.get().write(Some(handle));
-            }
+    /// It's must be called before [`connect`](Self::connect) or
+    /// [`listen`](Self::listen).
+    pub fn bind_to_device(&self, name: &str) -> AxResult {
+        let device = if name.is_empty() {
+            None
+        } else {
+            Some(iface_by_name(name)?)
+        };
+        self.update_state(STATE_CLOSED, STATE_CLOSED, || {
+            // SAFETY: no other threads can read or write `self.device` as we
+            // have changed the state to `BUSY`.
+            unsafe { self.device.get().write(device) };
             Ok(())
         })
-        .unwrap_or_else(|_| ax_err!(AlreadyExists, "socket connect() failed: already connected"))?; // EISCONN
+        .unwrap_or_else(|_| {
+            ax_err!(
+                InvalidInput,
+                "socket bind_to_device() failed: already in use"
+            )
+        })
+    }
+
+    /// Returns whether the local address can be reused, i.e. `SO_REUSEADDR`.
+    pub fn reuse_addr(&self) -> bool {
+        self.opts.lock().reuse_addr
+    }
 
+    /// Allows to [`bind`](Self::bind) a port used by other connections, i.e.
+    /// `SO_REUSEADDR`.
+    pub fn set_reuse_addr(&self, reuse_addr: bool) {
+        self.opts.lock().reuse_addr = reuse_addr;
+    }
+
+    /// Returns the size of the receive buffer, i.e. `SO_RCVBUF`.
+    pub fn recv_buffer_size(&self) -> usize {
+        self.opts.lock().recv_buf_size
+    }
+
+    /// Sets the size of the receive buffer, i.e. `SO_RCVBUF`.
+    ///
+    /// It takes effect on the next [`connect`](Self::connect) or
+    /// [`listen`](Self::listen).
+    pub fn set_recv_buffer_size(&self, size: usize) -> AxResult {
+        if size == 0 {
+            return ax_err!(InvalidInput, "socket set_recv_buffer_size() failed");
+        }
+        self.opts.lock().recv_buf_size = size;
+        Ok(())
+    }
+
+    /// Returns the size of the send buffer, i.e. `SO_SNDBUF`.
+    pub fn send_buffer_size(&self) -> usize {
+        self.opts.lock().send_buf_size
+    }
+
+    /// Sets the size of the send buffer, i.e. `SO_SNDBUF`.
+    ///
+    /// It takes effect on the next [`connect`](Self::connect) or
+    /// [`listen`](Self::listen).
+    pub fn set_send_buffer_size(&self, size: usize) -> AxResult {
+        if size == 0 {
+            return ax_err!(InvalidInput, "socket set_send_buffer_size() failed");
+        }
+        self.opts.lock().send_buf_size = size;
+        Ok(())
+    }
+
+    /// Returns whether the Nagle's algorithm is disabled, i.e. `TCP_NODELAY`.
+    pub fn nodelay(&self) -> bool {
+        self.opts.lock().nodelay
+    }
+
+    /// Disables or enables the Nagle's algorithm, i.e. `TCP_NODELAY`.
+    pub fn set_nodelay(&self, nodelay: bool) {
+        self.update_opts(|opts| opts.nodelay = nodelay);
+    }
+
+    /// Returns whether keep-alive packets are sent, i.e. `SO_KEEPALIVE`.
+    pub fn keep_alive(&self) -> bool {
+        self.opts.lock().keep_alive
+    }
+
+    /// Enables or disables sending keep-alive packets on an idle connection,
+    /// i.e. `SO_KEEPALIVE`.
+    pub fn set_keep_alive(&self, keep_alive: bool) {
+        self.update_opts(|opts| opts.keep_alive = keep_alive);
+    }
+
+    /// Returns how long the connection is idle before sending keep-alive
+    /// packets, i.e. `TCP_KEEPIDLE`.
+    pub fn keep_idle(&self) -> Duration {
+        self.opts.lock().keep_idle
+    }
+
+    /// Sets how long the connection is idle before sending keep-alive packets,
+    /// i.e. `TCP_KEEPIDLE`. It's two hours by default.
+    pub fn set_keep_idle(&self, idle: Duration) -> AxResult {
+        if idle.is_zero() {
+            return ax_err!(InvalidInput, "socket set_keep_idle() failed");
+        }
+        self.update_opts(|opts| opts.keep_idle = idle);
+        Ok(())
+    }
+
+    /// Returns the timeout of receiving, i.e. `SO_RCVTIMEO`.
+    pub fn recv_timeout(&self) -> Option<Duration> {
+        self.opts.lock().recv_timeout
+    }
+
+    /// Sets the timeout of [`recv`](Self::recv) and [`accept`](Self::accept),
+    /// i.e. `SO_RCVTIMEO`. `None` means blocking indefinitely.
+    ///
+    /// When timed out, [`Err(WouldBlock)`](AxError::WouldBlock) is returned.
+    pub fn set_recv_timeout(&self, timeout: Option<Duration>) -> AxResult {
+        if timeout.is_some_and(|t| t.is_zero()) {
+            return ax_err!(InvalidInput, "socket set_recv_timeout() failed");
+        }
+        self.opts.lock().recv_timeout = timeout;
+        Ok(())
+    }
+
+    /// Returns the timeout of sending, i.e. `SO_SNDTIMEO`.
+    pub fn send_timeout(&self) -> Option<Duration> {
+        self.opts.lock().send_timeout
+    }
+
+    /// Sets the timeout of [`send`](Self::send) and
+    /// [`connect`](Self::connect), i.e. `SO_SNDTIMEO`. `None` means blocking
+    /// indefinitely.
+    ///
+    /// When timed out, [`Err(WouldBlock)`](AxError::WouldBlock) is returned.
+    pub fn set_send_timeout(&self, timeout: Option<Duration>) -> AxResult {
+        if timeout.is_some_and(|t| t.is_zero()) {
+            return ax_err!(InvalidInput, "socket set_send_timeout() failed");
+        }
+        self.opts.lock().send_timeout = timeout;
+        Ok(())
+    }
+
+    /// Returns and clears the pending error of the socket, i.e. `SO_ERROR`.
+    ///
+    /// It's set when a nonblocking [`connect`](Self::connect) fails.
+    pub fn take_error(&self) -> Option<AxError> {
+        self.error.lock().take()
+    }
+
+    /// Connects to the given address and port.
+    ///
+    /// The local port is generated automatically. The egress interface is
+    /// selected by the routing table, unless the socket is bound to a device.
+    pub fn connect(&self, remote_addr: SocketAddr) -> AxResult {
+        self.start_connect(remote_addr)?;
         // Here our state must be `CONNECTING`, and only one thread can run here.
-        if self.is_nonblocking() {
-            Err(AxError::WouldBlock)
-        } else {
-            self.block_on(|| {
-                let PollState { writable, .. } = self.poll_connect()?;
-                if !writable {
-                    Err(AxError::WouldBlock)
-                } else if self.get_state() == STATE_CONNECTED {
-                    Ok(())
-                } else {
-                    ax_err!(ConnectionRefused, "socket connect() failed")
-                }
-            })
+        self.block_on(
+            self.send_timeout(),
+            |waker| self.register_recv_waker(waker),
+            || self.finish_connect(),
+        )
+    }
+
+    /// Connects to the given address and port asynchronously.
+    ///
+    /// The connection is started by the first call, and `Ok` is returned once
+    /// it's established. See [`connect`](Self::connect) for details.
+    pub fn poll_connect(&self, cx: &mut Context<'_>, remote_addr: SocketAddr) -> Poll<AxResult> {
+        if !self.is_connecting() {
+            self.start_connect(remote_addr)?;
         }
+        poll_socket(
+            cx,
+            |waker| self.register_recv_waker(waker),
+            || self.finish_connect(),
+        )
     }
 
     /// Binds an unbound socket to the given address and port.
     ///
-    /// If the given port is 0, it generates one automatically.
+    /// If the given port is 0, it generates one automatically. A port used by
+    /// other connections can be bound only if
+    /// [`set_reuse_addr`](Self::set_reuse_addr) is enabled, and a port used
+    /// by a listener can't be bound.
     ///
     /// It's must be called before [`listen`](Self::listen) and
     /// [`accept`](Self::accept).
     pub fn bind(&self, mut local_addr: SocketAddr) -> AxResult {
         self.update_state(STATE_CLOSED, STATE_CLOSED, || {
-            // TODO: check addr is available
-            if local_addr.port() == 0 {
+            let port = local_addr.port();
+            if port == 0 {
                 local_addr.set_port(get_ephemeral_port()?);
+            } else if !LISTEN_TABLE.can_listen(port)
+                || (!self.reuse_addr() && SOCKET_SET.is_tcp_port_in_use(port))
+            {
+                return ax_err!(AddrInUse, "socket bind() failed");
             }
             // SAFETY: no other threads can read or write `self.local_addr` as we
             // have changed the state to `BUSY`.
@@ -209,7 +402,9 @@ impl TcpSocket {
             unsafe {
                 (*self.local_addr.get()).port = bound_endpoint.port;
             }
-            LISTEN_TABLE.listen(bound_endpoint)?;
+            // SAFETY: no other threads can read or write `self.device`.
+            let device = unsafe { self.device.get().read() };
+            LISTEN_TABLE.listen(bound_endpoint, device, *self.opts.lock())?;
             debug!("TCP socket listening on {}", bound_endpoint);
             Ok(())
         })
@@ -223,17 +418,24 @@ impl TcpSocket {
     ///
     /// It's must be called after [`bind`](Self::bind) and [`listen`](Self::listen).
     pub fn accept(&self) -> AxResult<TcpSocket> {
-        if !self.is_listening() {
-            return ax_err!(InvalidInput, "socket accept() failed: not listen");
-        }
+        let local_port = self.listening_port()?;
+        self.block_on(
+            self.recv_timeout(),
+            |waker| LISTEN_TABLE.register_waker(local_port, waker),
+            || self.try_accept(local_port),
+        )
+    }
 
-        // SAFETY: `self.local_addr` should be initialized after `bind()`.
-        let local_port = unsafe { self.local_addr.get().read().port };
-        self.block_on(|| {
-            let (handle, (local_addr, peer_addr)) = LISTEN_TABLE.accept(local_port)?;
-            debug!("TCP socket accepted a new connection {}", peer_addr);
-            Ok(TcpSocket::new_connected(handle, local_addr, peer_addr))
-        })
+    /// Accepts a new connection asynchronously.
+    ///
+    /// See [`accept`](Self::accept) for details.
+    pub fn poll_accept(&self, cx: &mut Context<'_>) -> Poll<AxResult<TcpSocket>> {
+        let local_port = self.listening_port()?;
+        poll_socket(
+            cx,
+            |waker| LISTEN_TABLE.register_waker(local_port, waker),
+            || self.try_accept(local_port),
+        )
     }
 
     /// Close the connection.
@@ -279,27 +481,28 @@ impl TcpSocket {
 
         // SAFETY: `self.handle` should be initialized in a connected socket.
         let handle = unsafe { self.handle.get().read().unwrap() };
-        self.block_on(|| {
-            SOCKET_SET.with_socket_mut::<tcp::Socket, _, _>(handle, |socket| {
-                if !socket.is_active() {
-                    // not open
-                    ax_err!(ConnectionRefused, "socket recv() failed")
-                } else if !socket.may_recv() {
-                    // connection closed
-                    Ok(0)
-                } else if socket.recv_queue() > 0 {
-                    // data available
-                    // TODO: use socket.recv(|buf| {...})
-                    let len = socket
-                        .recv_slice(buf)
-                        .map_err(|_| ax_err_type!(BadState, "socket recv() failed"))?;
-                    Ok(len)
-                } else {
-                    // no more data
-                    Err(AxError::WouldBlock)
-                }
-            })
-        })
+        self.block_on(
+            self.recv_timeout(),
+            |waker| self.register_recv_waker(waker),
+            || try_recv(handle, buf),
+        )
+    }
+
+    /// Receives data from the socket asynchronously.
+    ///
+    /// See [`recv`](Self::recv) for details.
+    pub fn poll_recv(&self, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<AxResult<usize>> {
+        if !self.is_connected() {
+            return Poll::Ready(ax_err!(NotConnected, "socket recv() failed"));
+        }
+
+        // SAFETY: `self.handle` should be initialized in a connected socket.
+        let handle = unsafe { self.handle.get().read().unwrap() };
+        poll_socket(
+            cx,
+            |waker| self.register_recv_waker(waker),
+            || try_recv(handle, buf),
+        )
     }
 
     /// Transmits data in the given buffer.
@@ -312,30 +515,34 @@ impl TcpSocket {
 
         // SAFETY: `self.handle` should be initialized in a connected socket.
         let handle = unsafe { self.handle.get().read().unwrap() };
-        self.block_on(|| {
-            SOCKET_SET.with_socket_mut::<tcp::Socket, _, _>(handle, |socket| {
-                if !socket.is_active() || !socket.may_send() {
-                    // closed by remote
-                    ax_err!(ConnectionReset, "socket send() failed")
-                } else if socket.can_send() {
-                    // connected, and the tx buffer is not full
-                    // TODO: use socket.send(|buf| {...})
-                    let len = socket
-                        .send_slice(buf)
-                        .map_err(|_| ax_err_type!(BadState, "socket send() failed"))?;
-                    Ok(len)
-                } else {
-                    // tx buffer is full
-                    Err(AxError::WouldBlock)
-                }
-            })
-        })
+        self.block_on(
+            self.send_timeout(),
+            |waker| self.register_send_waker(waker),
+            || try_send(handle, buf),
+        )
+    }
+
+    /// Transmits data in the given buffer asynchronously.
+    ///
+    /// See [`send`](Self::send) for details.
+    pub fn poll_send(&self, cx: &mut Context<'_>, buf: &[u8]) -> Poll<AxResult<usize>> {
+        if !self.is_connected() {
+            return Poll::Ready(ax_err!(NotConnected, "socket send() failed"));
+        }
+
+        // SAFETY: `self.handle` should be initialized in a connected socket.
+        let handle = unsafe { self.handle.get().read().unwrap() };
+        poll_socket(
+            cx,
+            |waker| self.register_send_waker(waker),
+            || try_send(handle, buf),
+        )
     }
 
     /// Whether the socket is readable or writable.
     pub fn poll(&self) -> AxResult<PollState> {
         match self.get_state() {
-            STATE_CONNECTING => self.poll_connect(),
+            STATE_CONNECTING => self.poll_connecting(),
             STATE_CONNECTED => self.poll_stream(),
             STATE_LISTENING => self.poll_listener(),
             _ => Ok(PollState {
@@ -419,7 +626,88 @@ impl TcpSocket {
         Ok(IpListenEndpoint { addr, port })
     }
 
-    fn poll_connect(&self) -> AxResult<PollState> {
+    /// Starts connecting to `remote_addr`, changes the state to `CONNECTING`.
+    fn start_connect(&self, remote_addr: SocketAddr) -> AxResult {
+        self.update_state(STATE_CLOSED, STATE_CONNECTING, || {
+            // TODO: check remote addr unreachable
+            let remote_endpoint = from_core_sockaddr(remote_addr);
+            // SAFETY: no other threads can read or write these fields.
+            let index = unsafe { self.device.get().read() }
+                .unwrap_or_else(|| route_iface(remote_endpoint.addr));
+            let opts = *self.opts.lock();
+            let handle = match unsafe { self.handle.get().read() } {
+                Some(handle) => SOCKET_SET.move_socket(handle, index),
+                None => SOCKET_SET.add(opts.new_socket(), index),
+            };
+            unsafe { self.handle.get().write(Some(handle)) };
+
+            let bound_endpoint = self.bound_endpoint()?;
+            let iface = &IFACES[index].iface;
+            let (local_endpoint, remote_endpoint) = SOCKET_SET
+                .with_socket_mut::<tcp::Socket, _, _>(handle, |socket| {
+                    opts.apply(socket);
+                    socket
+                        .connect(iface.lock().context(), remote_endpoint, bound_endpoint)
+                        .or_else(|e| match e {
+                            ConnectError::InvalidState => {
+                                ax_err!(BadState, "socket connect() failed")
+                            }
+                            ConnectError::Unaddressable => {
+                                ax_err!(ConnectionRefused, "socket connect() failed")
+                            }
+                        })?;
+                    Ok((
+                        socket.local_endpoint().unwrap(),
+                        socket.remote_endpoint().unwrap(),
+                    ))
+                })?;
+            unsafe {
+                // SAFETY: no other threads can read or write these fields as we
+                // have changed the state to `BUSY`.
+                self.local_addr.get().write(local_endpoint);
+                self.peer_addr.get().write(remote_endpoint);
+            }
+            Ok(())
+        })
+        .unwrap_or_else(|_| ax_err!(AlreadyExists, "socket connect() failed: already connected"))?; // EISCONN
+        Ok(())
+    }
+
+    /// Checks whether the connection started by
+    /// [`start_connect`](Self::start_connect) is established.
+    fn finish_connect(&self) -> AxResult {
+        let PollState { writable, .. } = self.poll_connecting()?;
+        if !writable {
+            Err(AxError::WouldBlock)
+        } else if self.get_state() == STATE_CONNECTED {
+            Ok(())
+        } else {
+            self.take_error(); // reported here instead
+            ax_err!(ConnectionRefused, "socket connect() failed")
+        }
+    }
+
+    /// Returns the local port of a listening socket.
+    fn listening_port(&self) -> AxResult<u16> {
+        if !self.is_listening() {
+            return ax_err!(InvalidInput, "socket accept() failed: not listen");
+        }
+        // SAFETY: `self.local_addr` should be initialized after `bind()`.
+        Ok(unsafe { self.local_addr.get().read().port })
+    }
+
+    fn try_accept(&self, local_port: u16) -> AxResult<TcpSocket> {
+        let (handle, (local_addr, peer_addr)) = LISTEN_TABLE.accept(local_port)?;
+        debug!("TCP socket accepted a new connection {}", peer_addr);
+        // the options may be changed after listening
+        let opts = *self.opts.lock();
+        SOCKET_SET.with_socket_mut::<tcp::Socket, _, _>(handle, |socket| opts.apply(socket));
+        Ok(TcpSocket::new_connected(
+            handle, local_addr, peer_addr, opts,
+        ))
+    }
+
+    fn poll_connecting(&self) -> AxResult<PollState> {
         // SAFETY: `self.handle` should be initialized above.
         let handle = unsafe { self.handle.get().read().unwrap() };
         let writable =
@@ -439,6 +727,7 @@ impl TcpSocket {
                         self.local_addr.get().write(UNSPECIFIED_ENDPOINT);
                         self.peer_addr.get().write(UNSPECIFIED_ENDPOINT);
                     }
+                    *self.error.lock() = Some(AxError::ConnectionRefused);
                     self.set_state(STATE_CLOSED); // connection failed
                     true
                 }
@@ -469,26 +758,49 @@ impl TcpSocket {
         })
     }
 
+    fn register_recv_waker(&self, waker: &Waker) {
+        // SAFETY: `self.handle` should be initialized above.
+        let handle = unsafe { self.handle.get().read().unwrap() };
+        SOCKET_SET.with_socket_mut::<tcp::Socket, _, _>(handle, |socket| {
+            socket.register_recv_waker(waker)
+        });
+    }
+
+    fn register_send_waker(&self, waker: &Waker) {
+        // SAFETY: `self.handle` should be initialized above.
+        let handle = unsafe { self.handle.get().read().unwrap() };
+        SOCKET_SET.with_socket_mut::<tcp::Socket, _, _>(handle, |socket| {
+            socket.register_send_waker(waker)
+        });
+    }
+
     /// Block the current thread until the given function completes or fails.
     ///
     /// If the socket is non-blocking, it calls the function once and returns
     /// immediately. Otherwise, it may call the function multiple times if it
-    /// returns [`Err(WouldBlock)`](AxError::WouldBlock).
-    fn block_on<F, T>(&self, mut f: F) -> AxResult<T>
+    /// returns [`Err(WouldBlock)`](AxError::WouldBlock), and `register` is
+    /// called before each call to wake the thread when it may make progress.
+    /// [`Err(WouldBlock)`](AxError::WouldBlock) is also returned if `timeout`
+    /// has elapsed.
+    fn block_on<R, F, T>(&self, timeout: Option<Duration>, register: R, f: F) -> AxResult<T>
     where
+        R: FnMut(&Waker),
         F: FnMut() -> AxResult<T>,
     {
-        if self.is_nonblocking() {
-            f()
-        } else {
-            loop {
-                SOCKET_SET.poll_interfaces();
-                match f() {
-                    Ok(t) => return Ok(t),
-                    Err(AxError::WouldBlock) => axtask::yield_now(),
-                    Err(e) => return Err(e),
-                }
-            }
+        self.waiter
+            .block_on(self.is_nonblocking(), timeout, register, f)
+    }
+
+    /// Updates the options, and applies them to the socket in smoltcp if it
+    /// exists.
+    fn update_opts<F: FnOnce(&mut TcpOptions)>(&self, f: F) {
+        let mut opts = self.opts.lock();
+        f(&mut opts);
+        if self.is_connecting() || self.is_connected() {
+            // SAFETY: `self.handle` should be initialized in a connecting or
+            // connected socket.
+            let handle = unsafe { self.handle.get().read().unwrap() };
+            SOCKET_SET.with_socket_mut::<tcp::Socket, _, _>(handle, |socket| opts.apply(socket));
         }
     }
 }
@@ -503,6 +815,47 @@ impl Drop for TcpSocket {
     }
 }
 
+fn try_recv(handle: SocketHandle, buf: &mut [u8]) -> AxResult<usize> {
+    SOCKET_SET.with_socket_mut::<tcp::Socket, _, _>(handle, |socket| {
+        if !socket.is_active() {
+            // not open
+            ax_err!(ConnectionRefused, "socket recv() failed")
+        } else if !socket.may_recv() {
+            // connection closed
+            Ok(0)
+        } else if socket.recv_queue() > 0 {
+            // data available
+            // TODO: use socket.recv(|buf| {...})
+            let len = socket
+                .recv_slice(buf)
+                .map_err(|_| ax_err_type!(BadState, "socket recv() failed"))?;
+            Ok(len)
+        } else {
+            // no more data
+            Err(AxError::WouldBlock)
+        }
+    })
+}
+
+fn try_send(handle: SocketHandle, buf: &[u8]) -> AxResult<usize> {
+    SOCKET_SET.with_socket_mut::<tcp::Socket, _, _>(handle, |socket| {
+        if !socket.is_active() || !socket.may_send() {
+            // closed by remote
+            ax_err!(ConnectionReset, "socket send() failed")
+        } else if socket.can_send() {
+            // connected, and the tx buffer is not full
+            // TODO: use socket.send(|buf| {...})
+            let len = socket
+                .send_slice(buf)
+                .map_err(|_| ax_err_type!(BadState, "socket send() failed"))?;
+            Ok(len)
+        } else {
+            // tx buffer is full
+            Err(AxError::WouldBlock)
+        }
+    })
+}
+
 fn get_ephemeral_port() -> AxResult<u16> {
     const PORT_START: u16 = 0xc000;
     const PORT_END: u16 = 0xffff;
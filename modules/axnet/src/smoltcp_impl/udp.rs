@@ -1,24 +1,41 @@
 use core::net::SocketAddr;
 use core::sync::atomic::{AtomicBool, Ordering};
+use core::task::{Context, Poll, Waker};
+use core::time::Duration;
 
 use axerrno::{ax_err, ax_err_type, AxError, AxResult};
 use axio::PollState;
 use axsync::Mutex;
 use spin::RwLock;
 
-use smoltcp::iface::SocketHandle;
 use smoltcp::socket::udp::{self, BindError, SendError};
-use smoltcp::wire::{IpEndpoint, IpListenEndpoint};
+use smoltcp::wire::{IpAddress, IpEndpoint, IpListenEndpoint};
 
 use super::addr::{from_core_sockaddr, into_core_sockaddr, is_unspecified, UNSPECIFIED_ENDPOINT};
-use super::{SocketSetWrapper, SOCKET_SET};
+use super::poll::{poll_socket, SocketWaiter};
+use super::{iface_by_addr, iface_by_name, route_iface};
+use super::{SocketHandle, SocketSetWrapper, SOCKET_SET};
 
 /// A UDP socket that provides POSIX-like APIs.
+///
+/// The socket sends and receives packets through one interface: the one it's
+/// bound to by [`bind_to_device`](Self::bind_to_device), or the one owning
+/// its bound address, otherwise the one routing to its last destination.
+///
+/// The `poll_*` methods are the asynchronous versions of the sending and
+/// receiving methods, which never block and ignore the timeouts. They register
+/// the waker of the given context and return [`Poll::Pending`] if the operation
+/// can't complete yet.
 pub struct UdpSocket {
-    handle: SocketHandle,
+    handle: RwLock<SocketHandle>,
+    device: RwLock<Option<usize>>,
     local_addr: RwLock<Option<IpEndpoint>>,
     peer_addr: RwLock<Option<IpEndpoint>>,
     nonblock: AtomicBool,
+    reuse_addr: AtomicBool,
+    recv_timeout: RwLock<Option<Duration>>,
+    send_timeout: RwLock<Option<Duration>>,
+    waiter: SocketWaiter,
 }
 
 impl UdpSocket {
@@ -26,12 +43,17 @@ impl UdpSocket {
     #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
         let socket = SocketSetWrapper::new_udp_socket();
-        let handle = SOCKET_SET.add(socket);
+        let handle = SOCKET_SET.add(socket, 0);
         Self {
-            handle,
+            handle: RwLock::new(handle),
+            device: RwLock::new(None),
             local_addr: RwLock::new(None),
             peer_addr: RwLock::new(None),
             nonblock: AtomicBool::new(false),
+            reuse_addr: AtomicBool::new(false),
+            recv_timeout: RwLock::new(None),
+            send_timeout: RwLock::new(None),
+            waiter: SocketWaiter::new(),
         }
     }
 
@@ -69,8 +91,70 @@ impl UdpSocket {
         self.nonblock.store(nonblocking, Ordering::Release);
     }
 
+    /// Binds the socket to the network interface named `name`, so that it only
+    /// sends and receives packets through it. An empty name removes the binding.
+    pub fn bind_to_device(&self, name: &str) -> AxResult {
+        let mut device = self.device.write();
+        if name.is_empty() {
+            *device = None;
+        } else {
+            let index = iface_by_name(name)?;
+            self.move_to(index);
+            *device = Some(index);
+        }
+        Ok(())
+    }
+
+    /// Returns whether the local address can be reused, i.e. `SO_REUSEADDR`.
+    pub fn reuse_addr(&self) -> bool {
+        self.reuse_addr.load(Ordering::Acquire)
+    }
+
+    /// Allows to [`bind`](Self::bind) a port used by other sockets, i.e.
+    /// `SO_REUSEADDR`.
+    pub fn set_reuse_addr(&self, reuse_addr: bool) {
+        self.reuse_addr.store(reuse_addr, Ordering::Release);
+    }
+
+    /// Returns the timeout of receiving, i.e. `SO_RCVTIMEO`.
+    pub fn recv_timeout(&self) -> Option<Duration> {
+        *self.recv_timeout.read()
+    }
+
+    /// Sets the timeout of receiving, i.e. `SO_RCVTIMEO`. `None` means
+    /// blocking indefinitely.
+    ///
+    /// When timed out, [`Err(WouldBlock)`](AxError::WouldBlock) is returned.
+    pub fn set_recv_timeout(&self, timeout: Option<Duration>) -> AxResult {
+        if timeout.is_some_and(|t| t.is_zero()) {
+            return ax_err!(InvalidInput, "socket set_recv_timeout() failed");
+        }
+        *self.recv_timeout.write() = timeout;
+        Ok(())
+    }
+
+    /// Returns the timeout of sending, i.e. `SO_SNDTIMEO`.
+    pub fn send_timeout(&self) -> Option<Duration> {
+        *self.send_timeout.read()
+    }
+
+    /// Sets the timeout of sending, i.e. `SO_SNDTIMEO`. `None` means blocking
+    /// indefinitely.
+    ///
+    /// When timed out, [`Err(WouldBlock)`](AxError::WouldBlock) is returned.
+    pub fn set_send_timeout(&self, timeout: Option<Duration>) -> AxResult {
+        if timeout.is_some_and(|t| t.is_zero()) {
+            return ax_err!(InvalidInput, "socket set_send_timeout() failed");
+        }
+        *self.send_timeout.write() = timeout;
+        Ok(())
+    }
+
     /// Binds an unbound socket to the given address and port.
     ///
+    /// A port used by other sockets can be bound only if
+    /// [`set_reuse_addr`](Self::set_reuse_addr) is enabled.
+    ///
     /// It's must be called before [`send_to`](Self::send_to) and
     /// [`recv_from`](Self::recv_from).
     pub fn bind(&self, mut local_addr: SocketAddr) -> AxResult {
@@ -78,6 +162,8 @@ impl UdpSocket {
 
         if local_addr.port() == 0 {
             local_addr.set_port(get_ephemeral_port()?);
+        } else if !self.reuse_addr() && SOCKET_SET.is_udp_port_in_use(local_addr.port()) {
+            return ax_err!(AddrInUse, "socket bind() failed");
         }
         if self_local_addr.is_some() {
             return ax_err!(InvalidInput, "socket bind() failed: already bound");
@@ -88,7 +174,13 @@ impl UdpSocket {
             addr: (!is_unspecified(local_endpoint.addr)).then_some(local_endpoint.addr),
             port: local_endpoint.port,
         };
-        SOCKET_SET.with_socket_mut::<udp::Socket, _, _>(self.handle, |socket| {
+        if self.device.read().is_none() {
+            if let Some(index) = endpoint.addr.and_then(iface_by_addr) {
+                self.move_to(index);
+            }
+        }
+        let handle = *self.handle.read();
+        SOCKET_SET.with_socket_mut::<udp::Socket, _, _>(handle, |socket| {
             socket.bind(endpoint).or_else(|e| match e {
                 BindError::InvalidState => ax_err!(AlreadyExists, "socket bind() failed"),
                 BindError::Unaddressable => ax_err!(InvalidInput, "socket bind() failed"),
@@ -96,17 +188,28 @@ impl UdpSocket {
         })?;
 
         *self_local_addr = Some(local_endpoint);
-        debug!("UDP socket {}: bound on {}", self.handle, endpoint);
+        debug!("UDP socket {}: bound on {}", handle, endpoint);
         Ok(())
     }
 
     /// Sends data on the socket to the given address. On success, returns the
     /// number of bytes written.
     pub fn send_to(&self, buf: &[u8], remote_addr: SocketAddr) -> AxResult<usize> {
-        if remote_addr.port() == 0 || remote_addr.ip().is_unspecified() {
-            return ax_err!(InvalidInput, "socket send_to() failed: invalid address");
-        }
-        self.send_impl(buf, from_core_sockaddr(remote_addr))
+        let remote_endpoint = self.route_to_addr(remote_addr)?;
+        self.send_impl(buf, remote_endpoint)
+    }
+
+    /// Sends data on the socket to the given address asynchronously.
+    ///
+    /// See [`send_to`](Self::send_to) for details.
+    pub fn poll_send_to(
+        &self,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+        remote_addr: SocketAddr,
+    ) -> Poll<AxResult<usize>> {
+        let remote_endpoint = self.route_to_addr(remote_addr)?;
+        self.poll_send_impl(cx, buf, remote_endpoint)
     }
 
     /// Receives a single datagram message on the socket. On success, returns
@@ -118,6 +221,20 @@ impl UdpSocket {
         })
     }
 
+    /// Receives a single datagram message on the socket asynchronously.
+    ///
+    /// See [`recv_from`](Self::recv_from) for details.
+    pub fn poll_recv_from(
+        &self,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<AxResult<(usize, SocketAddr)>> {
+        self.poll_recv_impl(cx, |socket| match socket.recv_slice(buf) {
+            Ok((len, meta)) => Ok((len, into_core_sockaddr(meta.endpoint))),
+            Err(_) => ax_err!(BadState, "socket recv_from() failed"),
+        })
+    }
+
     /// Receives a single datagram message on the socket, without removing it from
     /// the queue. On success, returns the number of bytes read and the origin.
     pub fn peek_from(&self, buf: &mut [u8]) -> AxResult<(usize, SocketAddr)> {
@@ -141,8 +258,10 @@ impl UdpSocket {
             self.bind(into_core_sockaddr(UNSPECIFIED_ENDPOINT))?;
         }
 
-        *self_peer_addr = Some(from_core_sockaddr(addr));
-        debug!("UDP socket {}: connected to {}", self.handle, addr);
+        let remote_endpoint = from_core_sockaddr(addr);
+        self.route_to(remote_endpoint.addr);
+        *self_peer_addr = Some(remote_endpoint);
+        debug!("UDP socket {}: connected to {}", *self.handle.read(), addr);
         Ok(())
     }
 
@@ -152,28 +271,36 @@ impl UdpSocket {
         self.send_impl(buf, remote_endpoint)
     }
 
+    /// Sends data on the socket to the remote address to which it is connected
+    /// asynchronously.
+    ///
+    /// See [`send`](Self::send) for details.
+    pub fn poll_send(&self, cx: &mut Context<'_>, buf: &[u8]) -> Poll<AxResult<usize>> {
+        let remote_endpoint = self.remote_endpoint()?;
+        self.poll_send_impl(cx, buf, remote_endpoint)
+    }
+
     /// Receives a single datagram message on the socket from the remote address
     /// to which it is connected. On success, returns the number of bytes read.
     pub fn recv(&self, buf: &mut [u8]) -> AxResult<usize> {
         let remote_endpoint = self.remote_endpoint()?;
-        self.recv_impl(|socket| {
-            let (len, meta) = socket
-                .recv_slice(buf)
-                .map_err(|_| ax_err_type!(BadState, "socket recv() failed"))?;
-            if !is_unspecified(remote_endpoint.addr) && remote_endpoint.addr != meta.endpoint.addr {
-                return Err(AxError::WouldBlock);
-            }
-            if remote_endpoint.port != 0 && remote_endpoint.port != meta.endpoint.port {
-                return Err(AxError::WouldBlock);
-            }
-            Ok(len)
-        })
+        self.recv_impl(|socket| recv_from_peer(socket, buf, remote_endpoint))
+    }
+
+    /// Receives a single datagram message on the socket from the remote address
+    /// to which it is connected asynchronously.
+    ///
+    /// See [`recv`](Self::recv) for details.
+    pub fn poll_recv(&self, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<AxResult<usize>> {
+        let remote_endpoint = self.remote_endpoint()?;
+        self.poll_recv_impl(cx, |socket| recv_from_peer(socket, buf, remote_endpoint))
     }
 
     /// Close the socket.
     pub fn shutdown(&self) -> AxResult {
-        SOCKET_SET.with_socket_mut::<udp::Socket, _, _>(self.handle, |socket| {
-            debug!("UDP socket {}: shutting down", self.handle);
+        let handle = *self.handle.read();
+        SOCKET_SET.with_socket_mut::<udp::Socket, _, _>(handle, |socket| {
+            debug!("UDP socket {}: shutting down", handle);
             socket.close();
         });
         SOCKET_SET.poll_interfaces();
@@ -188,7 +315,7 @@ impl UdpSocket {
                 writable: false,
             });
         }
-        SOCKET_SET.with_socket_mut::<udp::Socket, _, _>(self.handle, |socket| {
+        SOCKET_SET.with_socket_mut::<udp::Socket, _, _>(*self.handle.read(), |socket| {
             Ok(PollState {
                 readable: socket.can_recv(),
                 writable: socket.can_send(),
@@ -199,6 +326,28 @@ impl UdpSocket {
 
 /// Private methods
 impl UdpSocket {
+    /// Moves the socket to the interface `index`.
+    fn move_to(&self, index: usize) {
+        let mut handle = self.handle.write();
+        *handle = SOCKET_SET.move_socket(*handle, index);
+    }
+
+    /// Moves the socket to the interface routing to `dst`, unless it's bound
+    /// to a device or a specific address.
+    fn route_to(&self, dst: IpAddress) {
+        if self.device.read().is_some() {
+            return;
+        }
+        if self
+            .local_addr
+            .read()
+            .is_some_and(|addr| !is_unspecified(addr.addr))
+        {
+            return;
+        }
+        self.move_to(route_iface(dst));
+    }
+
     fn remote_endpoint(&self) -> AxResult<IpEndpoint> {
         match self.peer_addr.try_read() {
             Some(addr) => addr.ok_or(AxError::NotConnected),
@@ -206,28 +355,61 @@ impl UdpSocket {
         }
     }
 
+    /// Checks the destination address of `send_to`, and routes to it.
+    fn route_to_addr(&self, remote_addr: SocketAddr) -> AxResult<IpEndpoint> {
+        if remote_addr.port() == 0 || remote_addr.ip().is_unspecified() {
+            return ax_err!(InvalidInput, "socket send_to() failed: invalid address");
+        }
+        let remote_endpoint = from_core_sockaddr(remote_addr);
+        self.route_to(remote_endpoint.addr);
+        Ok(remote_endpoint)
+    }
+
     fn send_impl(&self, buf: &[u8], remote_endpoint: IpEndpoint) -> AxResult<usize> {
         if self.local_addr.read().is_none() {
             return ax_err!(NotConnected, "socket send() failed");
         }
 
-        self.block_on(|| {
-            SOCKET_SET.with_socket_mut::<udp::Socket, _, _>(self.handle, |socket| {
-                if socket.can_send() {
-                    socket
-                        .send_slice(buf, remote_endpoint)
-                        .map_err(|e| match e {
-                            SendError::BufferFull => AxError::WouldBlock,
-                            SendError::Unaddressable => {
-                                ax_err_type!(ConnectionRefused, "socket send() failed")
-                            }
-                        })?;
-                    Ok(buf.len())
-                } else {
-                    // tx buffer is full
-                    Err(AxError::WouldBlock)
-                }
-            })
+        self.block_on(
+            self.send_timeout(),
+            |waker| self.register_send_waker(waker),
+            || self.try_send(buf, remote_endpoint),
+        )
+    }
+
+    fn poll_send_impl(
+        &self,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+        remote_endpoint: IpEndpoint,
+    ) -> Poll<AxResult<usize>> {
+        if self.local_addr.read().is_none() {
+            return Poll::Ready(ax_err!(NotConnected, "socket send() failed"));
+        }
+
+        poll_socket(
+            cx,
+            |waker| self.register_send_waker(waker),
+            || self.try_send(buf, remote_endpoint),
+        )
+    }
+
+    fn try_send(&self, buf: &[u8], remote_endpoint: IpEndpoint) -> AxResult<usize> {
+        SOCKET_SET.with_socket_mut::<udp::Socket, _, _>(*self.handle.read(), |socket| {
+            if socket.can_send() {
+                socket
+                    .send_slice(buf, remote_endpoint)
+                    .map_err(|e| match e {
+                        SendError::BufferFull => AxError::WouldBlock,
+                        SendError::Unaddressable => {
+                            ax_err_type!(ConnectionRefused, "socket send() failed")
+                        }
+                    })?;
+                Ok(buf.len())
+            } else {
+                // tx buffer is full
+                Err(AxError::WouldBlock)
+            }
         })
     }
 
@@ -239,43 +421,89 @@ impl UdpSocket {
             return ax_err!(NotConnected, "socket send() failed");
         }
 
-        self.block_on(|| {
-            SOCKET_SET.with_socket_mut::<udp::Socket, _, _>(self.handle, |socket| {
-                if socket.can_recv() {
-                    // data available
-                    op(socket)
-                } else {
-                    // no more data
-                    Err(AxError::WouldBlock)
-                }
-            })
+        self.block_on(
+            self.recv_timeout(),
+            |waker| self.register_recv_waker(waker),
+            || self.try_recv(&mut op),
+        )
+    }
+
+    fn poll_recv_impl<F, T>(&self, cx: &mut Context<'_>, mut op: F) -> Poll<AxResult<T>>
+    where
+        F: FnMut(&mut udp::Socket) -> AxResult<T>,
+    {
+        if self.local_addr.read().is_none() {
+            return Poll::Ready(ax_err!(NotConnected, "socket send() failed"));
+        }
+
+        poll_socket(
+            cx,
+            |waker| self.register_recv_waker(waker),
+            || self.try_recv(&mut op),
+        )
+    }
+
+    fn try_recv<F, T>(&self, op: &mut F) -> AxResult<T>
+    where
+        F: FnMut(&mut udp::Socket) -> AxResult<T>,
+    {
+        SOCKET_SET.with_socket_mut::<udp::Socket, _, _>(*self.handle.read(), |socket| {
+            if socket.can_recv() {
+                // data available
+                op(socket)
+            } else {
+                // no more data
+                Err(AxError::WouldBlock)
+            }
+        })
+    }
+
+    fn register_send_waker(&self, waker: &Waker) {
+        SOCKET_SET.with_socket_mut::<udp::Socket, _, _>(*self.handle.read(), |socket| {
+            socket.register_send_waker(waker)
+        })
+    }
+
+    fn register_recv_waker(&self, waker: &Waker) {
+        SOCKET_SET.with_socket_mut::<udp::Socket, _, _>(*self.handle.read(), |socket| {
+            socket.register_recv_waker(waker)
         })
     }
 
-    fn block_on<F, T>(&self, mut f: F) -> AxResult<T>
+    fn block_on<R, F, T>(&self, timeout: Option<Duration>, register: R, f: F) -> AxResult<T>
     where
+        R: FnMut(&Waker),
         F: FnMut() -> AxResult<T>,
     {
-        if self.is_nonblocking() {
-            f()
-        } else {
-            loop {
-                SOCKET_SET.poll_interfaces();
-                match f() {
-                    Ok(t) => return Ok(t),
-                    Err(AxError::WouldBlock) => axtask::yield_now(),
-                    Err(e) => return Err(e),
-                }
-            }
-        }
+        self.waiter
+            .block_on(self.is_nonblocking(), timeout, register, f)
     }
 }
 
 impl Drop for UdpSocket {
     fn drop(&mut self) {
         self.shutdown().ok();
-        SOCKET_SET.remove(self.handle);
+        SOCKET_SET.remove(*self.handle.get_mut());
+    }
+}
+
+/// Receives a datagram from `remote_endpoint`, the ones from other addresses
+/// are dropped.
+fn recv_from_peer(
+    socket: &mut udp::Socket,
+    buf: &mut [u8],
+    remote_endpoint: IpEndpoint,
+) -> AxResult<usize> {
+    let (len, meta) = socket
+        .recv_slice(buf)
+        .map_err(|_| ax_err_type!(BadState, "socket recv() failed"))?;
+    if !is_unspecified(remote_endpoint.addr) && remote_endpoint.addr != meta.endpoint.addr {
+        return Err(AxError::WouldBlock);
+    }
+    if remote_endpoint.port != 0 && remote_endpoint.port != meta.endpoint.port {
+        return Err(AxError::WouldBlock);
     }
+    Ok(len)
 }
 
 fn get_ephemeral_port() -> AxResult<u16> {
@@ -1,3 +1,5 @@
+use axhal::mem::VirtAddr;
+
 struct TrapHandlerImpl;
 
 #[crate_interface::impl_interface]
@@ -10,4 +12,19 @@ impl axhal::trap::TrapHandler for TrapHandlerImpl {
             drop(guard); // rescheduling may occur when preemption is re-enabled.
         }
     }
+
+    fn handle_page_fault(_vaddr: VirtAddr, _sp: VirtAddr) -> bool {
+        #[cfg(all(feature = "multitask", feature = "paging", target_arch = "x86_64"))]
+        if let Some(curr) = axtask::current_may_uninit() {
+            if curr.is_stack_guard(_vaddr) {
+                panic!(
+                    "stack overflow in {}: sp={:#x}, fault_vaddr={:#x}",
+                    curr.id_name(),
+                    _sp,
+                    _vaddr
+                );
+            }
+        }
+        false
+    }
 }
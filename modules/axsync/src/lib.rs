@@ -2,27 +2,77 @@
 //!
 //! Currently supported primitives:
 //!
-//! - [`Mutex`]: A mutual exclusion primitive.
+//! - [`Mutex`]: A mutual exclusion primitive, with priority inheritance.
+//! - [`Condvar`]: A condition variable, used with [`Mutex`].
+//! - [`RwLock`]: A writer-preferring reader-writer lock.
+//! - [`Semaphore`]: A counting semaphore.
+//! - [`Barrier`]: A barrier to synchronize a number of tasks.
 //! - mod [`spin`](spinlock): spin-locks.
 //!
 //! # Cargo Features
 //!
 //! - `multitask`: For use in the multi-threaded environments. If the feature is
-//!   not enabled, [`Mutex`] will be an alias of [`spin::SpinNoIrq`]. This
-//!   feature is enabled by default.
+//!   not enabled, [`Mutex`] will be an alias of [`spin::SpinNoIrq`], and other
+//!   sleeping primitives are not available. This feature is enabled by default.
+//! - `irq`: Interrupts are enabled. If this feature is not enabled, the
+//!   timeouts of the timed waiting methods are ignored, such as
+//!   [`Condvar::wait_timeout`] and [`Semaphore::acquire_timeout`].
 
 #![cfg_attr(not(test), no_std)]
 #![feature(doc_cfg)]
 
 pub use spinlock as spin;
 
+#[cfg(feature = "multitask")]
+mod barrier;
+#[cfg(feature = "multitask")]
+mod condvar;
 #[cfg(feature = "multitask")]
 mod mutex;
+#[cfg(feature = "multitask")]
+mod rwlock;
+#[cfg(feature = "multitask")]
+mod semaphore;
 
+#[cfg(test)]
+mod tests;
+
+#[cfg(feature = "multitask")]
+#[doc(cfg(feature = "multitask"))]
+pub use self::barrier::{Barrier, BarrierWaitResult};
+#[cfg(feature = "multitask")]
+#[doc(cfg(feature = "multitask"))]
+pub use self::condvar::{Condvar, WaitTimeoutResult};
 #[cfg(feature = "multitask")]
 #[doc(cfg(feature = "multitask"))]
 pub use self::mutex::{Mutex, MutexGuard};
+#[cfg(feature = "multitask")]
+#[doc(cfg(feature = "multitask"))]
+pub use self::rwlock::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+#[cfg(feature = "multitask")]
+#[doc(cfg(feature = "multitask"))]
+pub use self::semaphore::Semaphore;
 
 #[cfg(not(feature = "multitask"))]
 #[doc(cfg(not(feature = "multitask")))]
 pub use spinlock::{SpinNoIrq as Mutex, SpinNoIrqGuard as MutexGuard};
+
+/// Blocks the current task in `wq` until the `condition` returns `true` or
+/// the given duration has elapsed, returns whether it's timed out.
+#[cfg(all(feature = "multitask", feature = "irq"))]
+fn wait_timeout_until<F>(wq: &axtask::WaitQueue, dur: core::time::Duration, condition: F) -> bool
+where
+    F: Fn() -> bool,
+{
+    wq.wait_timeout_until(dur, condition)
+}
+
+/// The timeout is ignored without the `irq` feature.
+#[cfg(all(feature = "multitask", not(feature = "irq")))]
+fn wait_timeout_until<F>(wq: &axtask::WaitQueue, _dur: core::time::Duration, condition: F) -> bool
+where
+    F: Fn() -> bool,
+{
+    wq.wait_until(condition);
+    false
+}
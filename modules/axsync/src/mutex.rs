@@ -11,8 +11,20 @@ use axtask::{current, WaitQueue};
 /// [`std::sync::Mutex`](https://doc.rust-lang.org/std/sync/struct.Mutex.html).
 ///
 /// When the mutex is locked, the current task will block and be put into the
-/// wait queue. When the mutex is unlocked, all tasks waiting on the queue
-/// will be woken up.
+/// wait queue. When the mutex is unlocked, one task waiting on the queue will
+/// be woken up.
+///
+/// It supports priority inheritance: a waiting task lends its priority to the
+/// owner of the mutex if it's higher than the owner's (see
+/// [`axtask::inherit_priority`]), so the owner can't be delayed indefinitely
+/// by the tasks with priorities between them. The owner restores its own
+/// priority when it unlocks the mutex, and if it was raised, all waiting tasks
+/// are woken up to lend their priorities to the next owner. The whole
+/// inherited priority is dropped at that time, so the owner of nested mutexes
+/// may lose the priority inherited from the waiters of other mutexes early.
+///
+/// It has the same layout as the mutex without priority inheritance, which is
+/// relied on by `pthread_mutex_t` in `axlibc`.
 pub struct Mutex<T: ?Sized> {
     wq: WaitQueue,
     owner_id: AtomicU64,
@@ -87,6 +99,13 @@ impl<T: ?Sized> Mutex<T> {
                         "{} tried to acquire mutex it already owns.",
                         current().id_name()
                     );
+                    if owner_id != 0 {
+                        // Don't raise the priority of the owner if it has
+                        // unlocked the mutex, as it has restored its priority.
+                        axtask::inherit_priority(owner_id, || {
+                            self.owner_id.load(Ordering::Acquire) == owner_id
+                        });
+                    }
                     // Wait until the lock looks unlocked before retrying
                     self.wq.wait_until(|| !self.is_locked());
                 }
@@ -133,7 +152,28 @@ impl<T: ?Sized> Mutex<T> {
             "{} tried to release mutex it doesn't own",
             current().id_name()
         );
-        self.wq.notify_one(true);
+        // It must be after the owner is cleared, see `lock`.
+        if axtask::restore_priority() {
+            // Let the waiters lend their priorities to the next owner.
+            self.wq.notify_all(true);
+        } else {
+            self.wq.notify_one(true);
+        }
+    }
+
+    /// Makes a guard of the [`Mutex`] that is already locked by the current
+    /// task, without locking it again.
+    ///
+    /// # Safety
+    ///
+    /// The lock must be held by the current task and not be owned by any
+    /// other guard, e.g. the guard was forgotten. Like [`Mutex::force_unlock`],
+    /// this can be useful for exposing the lock to FFI.
+    pub unsafe fn make_guard_unchecked(&self) -> MutexGuard<T> {
+        MutexGuard {
+            lock: self,
+            data: unsafe { &mut *self.data.get() },
+        }
     }
 
     /// Returns a mutable reference to the underlying data.
@@ -167,6 +207,13 @@ impl<T: ?Sized + fmt::Debug> fmt::Debug for Mutex<T> {
     }
 }
 
+impl<'a, T: ?Sized> MutexGuard<'a, T> {
+    /// Returns the [`Mutex`] locked by the guard.
+    pub(crate) fn mutex(guard: &Self) -> &'a Mutex<T> {
+        guard.lock
+    }
+}
+
 impl<'a, T: ?Sized> Deref for MutexGuard<'a, T> {
     type Target = T;
     #[inline(always)]
@@ -201,9 +248,6 @@ impl<'a, T: ?Sized> Drop for MutexGuard<'a, T> {
 mod tests {
     use crate::Mutex;
     use axtask as thread;
-    use std::sync::Once;
-
-    static INIT: Once = Once::new();
 
     fn may_interrupt() {
         // simulate interrupts
@@ -214,7 +258,7 @@ mod tests {
 
     #[test]
     fn lots_and_lots() {
-        INIT.call_once(thread::init_scheduler);
+        let _lock = crate::tests::init();
 
         const NUM_TASKS: u32 = 10;
         const NUM_ITERS: u32 = 10_000;
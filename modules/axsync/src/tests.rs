@@ -0,0 +1,136 @@
+use core::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex as StdMutex, MutexGuard as StdMutexGuard, Once};
+
+use axtask as thread;
+
+use crate::{Barrier, Condvar, Mutex, RwLock, Semaphore};
+
+static INIT: Once = Once::new();
+static SERIAL: StdMutex<()> = StdMutex::new(());
+
+/// Initializes the scheduler, and serializes the tests as they share it.
+pub(crate) fn init() -> StdMutexGuard<'static, ()> {
+    let guard = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+    INIT.call_once(thread::init_scheduler);
+    guard
+}
+
+fn wait_for(counter: &AtomicUsize, n: usize) {
+    while counter.load(Ordering::Acquire) < n {
+        thread::yield_now();
+    }
+}
+
+#[test]
+fn test_condvar() {
+    let _lock = init();
+
+    const NUM_TASKS: usize = 10;
+    static M: Mutex<usize> = Mutex::new(0);
+    static CV: Condvar = Condvar::new();
+
+    // each task waits for its turn
+    for i in (0..NUM_TASKS).rev() {
+        thread::spawn(move || {
+            let mut turn = CV.wait_while(M.lock(), |turn| *turn != i);
+            *turn += 1;
+            CV.notify_all();
+        });
+    }
+
+    let turn = CV.wait_while(M.lock(), |turn| *turn < NUM_TASKS);
+    assert_eq!(*turn, NUM_TASKS);
+    println!("Condvar test OK");
+}
+
+#[test]
+fn test_rwlock() {
+    let _lock = init();
+
+    const NUM_READERS: usize = 10;
+    const NUM_WRITERS: usize = 5;
+    const NUM_ITERS: usize = 100;
+    static RW: RwLock<usize> = RwLock::new(0);
+    static FINISHED: AtomicUsize = AtomicUsize::new(0);
+
+    for _ in 0..NUM_WRITERS {
+        thread::spawn(|| {
+            for _ in 0..NUM_ITERS {
+                let mut val = RW.write();
+                *val += 1;
+                thread::yield_now();
+                *val += 1;
+            }
+            FINISHED.fetch_add(1, Ordering::Release);
+        });
+    }
+    for _ in 0..NUM_READERS {
+        thread::spawn(|| {
+            for _ in 0..NUM_ITERS {
+                let val = RW.read();
+                // never see the intermediate state of writers
+                assert_eq!(*val % 2, 0);
+                thread::yield_now();
+            }
+            FINISHED.fetch_add(1, Ordering::Release);
+        });
+    }
+
+    wait_for(&FINISHED, NUM_READERS + NUM_WRITERS);
+    assert_eq!(*RW.read(), NUM_WRITERS * NUM_ITERS * 2);
+    assert!(RW.try_write().is_some());
+    println!("RwLock test OK");
+}
+
+#[test]
+fn test_semaphore() {
+    let _lock = init();
+
+    const NUM_TASKS: usize = 10;
+    const NUM_PERMITS: usize = 3;
+    static SEM: Semaphore = Semaphore::new(NUM_PERMITS);
+    static INSIDE: AtomicUsize = AtomicUsize::new(0);
+    static FINISHED: AtomicUsize = AtomicUsize::new(0);
+
+    for _ in 0..NUM_TASKS {
+        thread::spawn(|| {
+            SEM.acquire();
+            let n = INSIDE.fetch_add(1, Ordering::AcqRel) + 1;
+            assert!(n <= NUM_PERMITS);
+            thread::yield_now();
+            INSIDE.fetch_sub(1, Ordering::AcqRel);
+            SEM.release();
+            FINISHED.fetch_add(1, Ordering::Release);
+        });
+    }
+
+    wait_for(&FINISHED, NUM_TASKS);
+    assert_eq!(SEM.available(), NUM_PERMITS);
+    println!("Semaphore test OK");
+}
+
+#[test]
+fn test_barrier() {
+    let _lock = init();
+
+    const NUM_TASKS: usize = 10;
+    static BARRIER: Barrier = Barrier::new(NUM_TASKS);
+    static ARRIVED: AtomicUsize = AtomicUsize::new(0);
+    static LEADERS: AtomicUsize = AtomicUsize::new(0);
+    static FINISHED: AtomicUsize = AtomicUsize::new(0);
+
+    for _ in 0..NUM_TASKS {
+        thread::spawn(|| {
+            ARRIVED.fetch_add(1, Ordering::AcqRel);
+            if BARRIER.wait().is_leader() {
+                LEADERS.fetch_add(1, Ordering::AcqRel);
+            }
+            assert_eq!(ARRIVED.load(Ordering::Acquire), NUM_TASKS);
+            FINISHED.fetch_add(1, Ordering::Release);
+        });
+    }
+
+    wait_for(&FINISHED, NUM_TASKS);
+    assert_eq!(LEADERS.load(Ordering::Acquire), 1);
+    println!("Barrier test OK");
+}
@@ -2,12 +2,17 @@
 
 use alloc::{string::String, sync::Arc};
 
-pub(crate) use crate::run_queue::{AxRunQueue, RUN_QUEUE};
+pub(crate) use crate::run_queue::{current_run_queue, AxRunQueue};
 
+#[doc(cfg(feature = "multitask"))]
+pub use crate::cpumask::CpuMask;
 #[doc(cfg(feature = "multitask"))]
 pub use crate::task::{CurrentTask, TaskId, TaskInner};
 #[doc(cfg(feature = "multitask"))]
 pub use crate::wait_queue::WaitQueue;
+#[cfg(feature = "sched_class")]
+#[doc(cfg(feature = "sched_class"))]
+pub use scheduler::SchedPolicy;
 
 /// The reference type of a task.
 pub type AxTaskRef = Arc<AxTask>;
@@ -17,6 +22,10 @@ cfg_if::cfg_if! {
         const MAX_TIME_SLICE: usize = 5;
         pub(crate) type AxTask = scheduler::RRTask<TaskInner, MAX_TIME_SLICE>;
         pub(crate) type Scheduler = scheduler::RRScheduler<TaskInner, MAX_TIME_SLICE>;
+    } else if #[cfg(feature = "sched_class")] {
+        const MAX_TIME_SLICE: usize = 5;
+        pub(crate) type AxTask = scheduler::ClassTask<TaskInner, MAX_TIME_SLICE>;
+        pub(crate) type Scheduler = scheduler::ClassScheduler<TaskInner, MAX_TIME_SLICE>;
     } else if #[cfg(feature = "sched_cfs")] {
         pub(crate) type AxTask = scheduler::CFSTask<TaskInner>;
         pub(crate) type Scheduler = scheduler::CFScheduler<TaskInner>;
@@ -75,6 +84,8 @@ pub fn init_scheduler() {
 /// Initializes the task scheduler for secondary CPUs.
 pub fn init_scheduler_secondary() {
     crate::run_queue::init_secondary();
+    #[cfg(feature = "irq")]
+    crate::timers::init();
 }
 
 /// Handles periodic timer ticks for the task manager.
@@ -84,7 +95,17 @@ pub fn init_scheduler_secondary() {
 #[doc(cfg(feature = "irq"))]
 pub fn on_timer_tick() {
     crate::timers::check_events();
-    RUN_QUEUE.lock().scheduler_timer_tick();
+    current_run_queue().scheduler_timer_tick();
+}
+
+/// Handles the inter-processor interrupt for remote wakeup.
+///
+/// The tasks woken up by other CPUs are added to the run queue of the current
+/// CPU, and the current task may be preempted.
+#[cfg(feature = "smp")]
+#[doc(cfg(feature = "smp"))]
+pub fn on_ipi() {
+    crate::run_queue::on_ipi();
 }
 
 /// Spawns a new task with the given parameters.
@@ -95,10 +116,50 @@ where
     F: FnOnce() + Send + 'static,
 {
     let task = TaskInner::new(f, name, stack_size);
-    RUN_QUEUE.lock().add_task(task.clone());
+    current_run_queue().add_task(task.clone());
     task
 }
 
+/// Spawns a new task with the given parameters, which is only allowed to run
+/// on the CPUs in `cpumask`.
+///
+/// Returns the task reference.
+///
+/// # Panics
+///
+/// Panics if `cpumask` is empty.
+pub fn spawn_raw_with_affinity<F>(
+    f: F,
+    name: String,
+    stack_size: usize,
+    cpumask: CpuMask,
+) -> AxTaskRef
+where
+    F: FnOnce() + Send + 'static,
+{
+    assert!(!cpumask.is_empty(), "empty cpumask");
+    let task = TaskInner::new(f, name, stack_size);
+    task.set_cpumask(cpumask);
+    current_run_queue().add_task(task.clone());
+    task
+}
+
+/// Spawns a new task with the default parameters, which is pinned to the CPU
+/// `cpu_id`.
+///
+/// Returns the task reference.
+///
+/// # Panics
+///
+/// Panics if `cpu_id` is not less than [`axconfig::SMP`].
+pub fn spawn_on<F>(cpu_id: usize, f: F) -> AxTaskRef
+where
+    F: FnOnce() + Send + 'static,
+{
+    let cpumask = CpuMask::one_shot(cpu_id);
+    spawn_raw_with_affinity(f, "".into(), axconfig::TASK_STACK_SIZE, cpumask)
+}
+
 /// Spawns a new task with the default parameters.
 ///
 /// The default task name is an empty string. The default task stack size is
@@ -122,13 +183,80 @@ where
 ///
 /// [CFS]: https://en.wikipedia.org/wiki/Completely_Fair_Scheduler
 pub fn set_priority(prio: isize) -> bool {
-    RUN_QUEUE.lock().set_current_priority(prio)
+    current_run_queue().set_current_priority(prio)
+}
+
+/// Raises the priority of the task with ID `task_id` to the priority of the
+/// current task, if the latter is higher and `cond` returns `true`.
+///
+/// It's used for priority inheritance of locks: the current task is going to
+/// wait for a lock held by the task, which should not be delayed by the tasks
+/// with lower priorities than the waiter. `cond` checks whether the task still
+/// holds the lock. It's called with the run queue of the task locked, so the
+/// task can't call [`restore_priority`] between the check and the raise if it
+/// releases the lock before that. The inherited priority is kept until the
+/// task calls [`restore_priority`].
+///
+/// Returns `true` if the priority is raised. It always returns `false` if the
+/// scheduler has no priorities.
+pub fn inherit_priority<F>(task_id: u64, cond: F) -> bool
+where
+    F: FnOnce() -> bool,
+{
+    let Some(task) = crate::task::task_by_id(task_id) else {
+        return false;
+    };
+    let curr = current();
+    crate::run_queue::with_task_run_queue(&task, |rq| {
+        cond() && rq.inherit_priority(&task, Some(curr.as_task_ref()))
+    })
+}
+
+/// Drops the priority that the current task inherited from other tasks by
+/// [`inherit_priority`].
+///
+/// Returns `true` if the current task had an inherited priority.
+pub fn restore_priority() -> bool {
+    current_run_queue().inherit_priority(current().as_task_ref(), None)
+}
+
+/// Sets the scheduling policy of the task, which also decides its scheduling
+/// class (real-time, fair or idle).
+///
+/// It takes effect immediately if the task is ready. A running task is
+/// preempted at the next timer tick if there is a ready task in a higher
+/// class after the change.
+///
+/// Returns `false` if the parameters of the policy are invalid.
+#[cfg(feature = "sched_class")]
+#[doc(cfg(feature = "sched_class"))]
+pub fn set_policy(task: &AxTaskRef, policy: SchedPolicy) -> bool {
+    crate::run_queue::with_task_run_queue(task, |rq| rq.set_task_policy(task, policy))
+}
+
+/// Sets the CPUs that the task is allowed to run on.
+///
+/// If it's the current task and the current CPU is not allowed, it's moved to
+/// an allowed CPU immediately. Other tasks are moved when they are scheduled
+/// or woken up next time.
+///
+/// Returns `false` if `cpumask` is empty.
+pub fn set_affinity(task: &AxTaskRef, cpumask: CpuMask) -> bool {
+    if cpumask.is_empty() {
+        return false;
+    }
+    task.set_cpumask(cpumask);
+    let curr = current();
+    if curr.ptr_eq(task) && !cpumask.get(axhal::cpu::this_cpu_id()) {
+        current_run_queue().yield_current();
+    }
+    true
 }
 
 /// Current task gives up the CPU time voluntarily, and switches to another
 /// ready task.
 pub fn yield_now() {
-    RUN_QUEUE.lock().yield_current();
+    current_run_queue().yield_current();
 }
 
 /// Current task is going to sleep for the given duration.
@@ -138,19 +266,32 @@ pub fn sleep(dur: core::time::Duration) {
     sleep_until(axhal::time::current_time() + dur);
 }
 
-/// Current task is going to sleep, it will be woken up at the given deadline.
+/// Current task is going to sleep, it will be woken up at the given deadline,
+/// or earlier by a signal that is not blocked or a cancellation request.
 ///
-/// If the feature `irq` is not enabled, it uses busy-wait instead.
+/// They are not acted on here, see [`is_interrupted`]. If the feature `irq`
+/// is not enabled, it uses busy-wait instead.
 pub fn sleep_until(deadline: axhal::time::TimeValue) {
     #[cfg(feature = "irq")]
-    RUN_QUEUE.lock().sleep_until(deadline);
+    current_run_queue().sleep_until(deadline);
     #[cfg(not(feature = "irq"))]
     axhal::time::busy_wait_until(deadline);
 }
 
+/// Whether the current task has signals to handle, or is requested to be
+/// canceled.
+///
+/// The interruptible waits return early in this case, and the caller should
+/// stop blocking and return to a point where it holds no locks, to handle the
+/// signals by [`handle_pending_signals`](crate::signal::handle_pending_signals)
+/// and the cancellation by [`testcancel`](crate::testcancel).
+pub fn is_interrupted() -> bool {
+    current().has_pending_interrupt()
+}
+
 /// Exits the current task.
 pub fn exit(exit_code: i32) -> ! {
-    RUN_QUEUE.lock().exit_current(exit_code)
+    current_run_queue().exit_current(exit_code)
 }
 
 /// The idle task routine.
@@ -161,6 +302,10 @@ pub fn run_idle() -> ! {
         yield_now();
         debug!("idle task: waiting for IRQs...");
         #[cfg(feature = "irq")]
-        axhal::arch::wait_for_irqs();
+        {
+            crate::run_queue::set_current_cpu_idle(true);
+            axhal::arch::wait_for_irqs();
+            crate::run_queue::set_current_cpu_idle(false);
+        }
     }
 }
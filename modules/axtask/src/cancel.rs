@@ -0,0 +1,71 @@
+//! Deferred cancellation of tasks.
+//!
+//! A task requested to be canceled by [`cancel`] exits when it calls
+//! [`testcancel`] with cancellation enabled. The task exits without unwinding,
+//! so it must hold no locks or other resources to release there, such as at
+//! the beginning or the end of the blocking calls of libc.
+//!
+//! The task blocked in [`sleep`](crate::sleep) or
+//! [`WaitQueue::wait_until_interruptible`](crate::WaitQueue::wait_until_interruptible)
+//! is woken up early by the request, so that it can return an error such as
+//! `AxError::Interrupted` to such a point (see
+//! [`is_interrupted`](crate::is_interrupted)). Other waits are not interrupted.
+
+use spinlock::SpinNoIrq;
+
+use crate::AxTaskRef;
+
+/// The exit code of the canceled tasks.
+pub const CANCELED_EXIT_CODE: i32 = -1;
+
+static CANCEL_HANDLER: SpinNoIrq<Option<fn()>> = SpinNoIrq::new(None);
+
+/// Requests cancellation of the given task.
+///
+/// It returns immediately without waiting for the task to exit. The task is
+/// canceled at the next cancellation point after its cancellation is enabled.
+pub fn cancel(task: &AxTaskRef) {
+    debug!("task cancel: {}", task.id_name());
+    task.request_cancel();
+    if task.cancel_pending() {
+        crate::run_queue::interrupt_task(task);
+    }
+}
+
+/// Enables or disables cancellation of the current task, and returns whether
+/// it was enabled.
+///
+/// Cancellation is enabled for new tasks.
+pub fn set_cancel_enabled(enabled: bool) -> bool {
+    crate::current().set_cancel_enabled(enabled)
+}
+
+/// Sets the function that a canceled task calls before it exits, such as to
+/// run the cleanup handlers.
+pub fn set_cancel_handler(handler: fn()) {
+    *CANCEL_HANDLER.lock() = Some(handler);
+}
+
+/// Whether the current task is requested to be canceled and cancellation is
+/// enabled, so it should return to a cancellation point.
+pub fn cancel_pending() -> bool {
+    crate::current().cancel_pending()
+}
+
+/// A cancellation point: the current task exits with [`CANCELED_EXIT_CODE`]
+/// if it's requested to be canceled and cancellation is enabled.
+///
+/// It must be called only where the current task holds no locks.
+pub fn testcancel() {
+    let curr = crate::current();
+    if curr.cancel_pending() {
+        debug!("task canceled: {}", curr.id_name());
+        // the handler may reach cancellation points again
+        curr.set_cancel_enabled(false);
+        let handler = *CANCEL_HANDLER.lock();
+        if let Some(handler) = handler {
+            handler();
+        }
+        crate::exit(CANCELED_EXIT_CODE);
+    }
+}
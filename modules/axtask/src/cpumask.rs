@@ -0,0 +1,65 @@
+use core::fmt;
+
+/// A set of CPUs that a task is allowed to run on, one bit for each CPU.
+///
+/// Only the first [`axconfig::SMP`] bits are valid.
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub struct CpuMask(usize);
+
+impl CpuMask {
+    const VALID_BITS: usize = if axconfig::SMP >= usize::BITS as usize {
+        usize::MAX
+    } else {
+        (1 << axconfig::SMP) - 1
+    };
+
+    /// Creates a mask with all CPUs.
+    pub const fn full() -> Self {
+        Self(Self::VALID_BITS)
+    }
+
+    /// Creates a mask with only the given CPU, or an empty mask if the CPU ID
+    /// is invalid.
+    pub const fn one_shot(cpu_id: usize) -> Self {
+        if cpu_id < axconfig::SMP {
+            Self(1 << cpu_id)
+        } else {
+            Self(0)
+        }
+    }
+
+    /// Creates a mask from raw bits, the invalid bits are ignored.
+    pub const fn from_raw_bits(bits: usize) -> Self {
+        Self(bits & Self::VALID_BITS)
+    }
+
+    /// Returns the raw bits of the mask.
+    pub const fn bits(&self) -> usize {
+        self.0
+    }
+
+    /// Whether the given CPU is in the mask.
+    pub const fn get(&self, cpu_id: usize) -> bool {
+        cpu_id < axconfig::SMP && self.0 & (1 << cpu_id) != 0
+    }
+
+    /// Whether the mask contains no CPU.
+    pub const fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns the first CPU in the mask, or `None` if the mask is empty.
+    pub const fn first(&self) -> Option<usize> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(self.0.trailing_zeros() as usize)
+        }
+    }
+}
+
+impl fmt::Debug for CpuMask {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "CpuMask({:#b})", self.0)
+    }
+}
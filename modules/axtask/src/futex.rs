@@ -0,0 +1,69 @@
+//! Futex-style waiting and waking on addresses.
+//!
+//! Tasks waiting on a futex are put into one of the wait queues in a hash
+//! table, keyed by the address of the futex, so no kernel object is needed
+//! for each futex. Addresses that share a wait queue are distinguished by
+//! the address recorded in each waiting task.
+
+use core::sync::atomic::{AtomicU32, Ordering};
+use core::time::Duration;
+
+use crate::WaitQueue;
+
+/// The number of wait queues in the hash table, must be a power of two.
+const FUTEX_HASH_SIZE: usize = 64;
+
+const EMPTY_QUEUE: WaitQueue = WaitQueue::new();
+
+static FUTEX_QUEUES: [WaitQueue; FUTEX_HASH_SIZE] = [EMPTY_QUEUE; FUTEX_HASH_SIZE];
+
+/// The result of [`futex_wait`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum FutexWaitResult {
+    /// Woken up by [`futex_wake`].
+    Woken,
+    /// The value of the futex is not the expected one, so it didn't block.
+    Mismatch,
+    /// The timeout has elapsed before it's woken up.
+    TimedOut,
+}
+
+fn futex_queue(addr: usize) -> &'static WaitQueue {
+    // futexes are 4-byte aligned, mix the higher bits in
+    let hash = (addr >> 2) ^ (addr >> 12);
+    &FUTEX_QUEUES[hash & (FUTEX_HASH_SIZE - 1)]
+}
+
+/// Blocks the current task on the futex at `addr` if its value is `expected`,
+/// until it's woken up by [`futex_wake`] on the same address, or the given
+/// `timeout` has elapsed (if specified).
+///
+/// The value is checked atomically with respect to [`futex_wake`], so a
+/// wakeup after the value is changed is never missed. The timeout is ignored
+/// if the feature `irq` is not enabled.
+pub fn futex_wait(addr: &AtomicU32, expected: u32, timeout: Option<Duration>) -> FutexWaitResult {
+    let key = addr as *const _ as usize;
+    let curr = crate::current();
+    debug!(
+        "task futex_wait: {}, addr={:#x}, expected={}",
+        curr.id_name(),
+        key,
+        expected
+    );
+    curr.set_futex_addr(key);
+    let deadline = timeout.map(|dur| axhal::time::current_time() + dur);
+    match futex_queue(key).wait_if(|| addr.load(Ordering::Acquire) == expected, deadline) {
+        None => FutexWaitResult::Mismatch,
+        Some(true) => FutexWaitResult::TimedOut,
+        Some(false) => FutexWaitResult::Woken,
+    }
+}
+
+/// Wakes up at most `count` tasks waiting on the futex at `addr`.
+///
+/// Returns the number of tasks woken up.
+pub fn futex_wake(addr: &AtomicU32, count: usize) -> usize {
+    let key = addr as *const _ as usize;
+    trace!("futex_wake: addr={:#x}, count={}", key, count);
+    futex_queue(key).notify_matching(count, true, |task| task.futex_addr() == key)
+}
@@ -13,6 +13,17 @@
 //!    APIs can be used, such as [`sleep`], [`sleep_until`], and
 //!    [`WaitQueue::wait_timeout`].
 //! - `preempt`: Enable preemptive scheduling.
+//! - `paging`: Allocate task stacks by `axmm`, each with an unmapped guard
+//!   page below it, so that stack overflows cause page faults. This is only
+//!   implemented on x86_64, which handles the fault on a separate double fault
+//!   stack. On aarch64 and riscv64, the trap frame would be saved on the
+//!   overflowed stack, so task stacks still come from the heap with canaries
+//!   at their bottoms, as without this feature. A canary only detects an
+//!   overflow when the task is switched out, after the neighbouring memory
+//!   may have been corrupted.
+//! - `smp`: Enable SMP (symmetric multiprocessing) support. Each CPU has its
+//!   own run queue and timer list, idle CPUs steal ready tasks from others,
+//!   and tasks blocked on other CPUs are woken up by IPIs.
 //! - `sched_fifo`: Use the [FIFO cooperative scheduler][1]. It also enables the
 //!   `multitask` feature if it is enabled. This feature is enabled by default,
 //!   and it can be overriden by other scheduler features.
@@ -20,10 +31,14 @@
 //!   the `multitask` and `preempt` features if it is enabled.
 //! - `sched_cfs`: Use the [Completely Fair Scheduler][3]. It also enables the
 //!   the `multitask` and `preempt` features if it is enabled.
+//! - `sched_class`: Use the [hierarchical scheduler][4], where each task has
+//!   its own policy that can be changed by [`set_policy`]. It also enables the
+//!   `multitask` and `preempt` features if it is enabled.
 //!
 //! [1]: scheduler::FifoScheduler
 //! [2]: scheduler::RRScheduler
 //! [3]: scheduler::CFScheduler
+//! [4]: scheduler::ClassScheduler
 
 #![cfg_attr(not(test), no_std)]
 #![feature(doc_cfg)]
@@ -35,6 +50,9 @@ cfg_if::cfg_if! {
         extern crate log;
         extern crate alloc;
 
+        mod cancel;
+        mod cpumask;
+        mod futex;
         mod run_queue;
         mod task;
         mod api;
@@ -43,8 +61,18 @@ cfg_if::cfg_if! {
         #[cfg(feature = "irq")]
         mod timers;
 
+        #[doc(cfg(feature = "multitask"))]
+        pub mod signal;
+
         #[doc(cfg(feature = "multitask"))]
         pub use self::api::*;
+        #[doc(cfg(feature = "multitask"))]
+        pub use self::cancel::{
+            cancel, cancel_pending, set_cancel_enabled, set_cancel_handler, testcancel,
+            CANCELED_EXIT_CODE,
+        };
+        #[doc(cfg(feature = "multitask"))]
+        pub use self::futex::{futex_wait, futex_wake, FutexWaitResult};
         pub use self::api::{sleep, sleep_until, yield_now};
     } else {
         mod api_s;
@@ -1,5 +1,18 @@
+//! Per-CPU run queues.
+//!
+//! Each CPU schedules the tasks in its own run queue, which is locked by
+//! [`current_run_queue`]. A task runs on the CPU of its run queue, until it's
+//! stolen by an idle CPU when it's ready. A blocked task is woken up on its
+//! CPU: if the waker runs on another CPU, the task is pushed to the wake list
+//! of the CPU, which is notified by an IPI if IRQs are enabled.
+
 use alloc::collections::VecDeque;
 use alloc::sync::Arc;
+use core::cell::UnsafeCell;
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use kernel_guard::{BaseGuard, NoPreemptIrqSave};
 use lazy_init::LazyInit;
 use scheduler::BaseScheduler;
 use spinlock::SpinNoIrq;
@@ -7,33 +20,210 @@ use spinlock::SpinNoIrq;
 use crate::task::{CurrentTask, TaskState};
 use crate::{AxTaskRef, Scheduler, TaskInner, WaitQueue};
 
-// TODO: per-CPU
-pub(crate) static RUN_QUEUE: LazyInit<SpinNoIrq<AxRunQueue>> = LazyInit::new();
+#[percpu::def_percpu]
+static RUN_QUEUE: LazyInit<RunQueueLock> = LazyInit::new();
+
+/// The tasks woken up by other CPUs, to be added to the run queue.
+#[percpu::def_percpu]
+static WAKE_LIST: SpinNoIrq<VecDeque<AxTaskRef>> = SpinNoIrq::new(VecDeque::new());
 
-// TODO: per-CPU
+#[percpu::def_percpu]
 static EXITED_TASKS: SpinNoIrq<VecDeque<AxTaskRef>> = SpinNoIrq::new(VecDeque::new());
 
+#[percpu::def_percpu]
 static WAIT_FOR_EXIT: WaitQueue = WaitQueue::new();
 
 #[percpu::def_percpu]
 static IDLE_TASK: LazyInit<AxTaskRef> = LazyInit::new();
 
+/// The previous task that is not allowed to run on the current CPU, to be
+/// moved to another CPU after it's switched out.
+#[cfg(feature = "smp")]
+#[percpu::def_percpu]
+static MIGRATING_TASK: Option<AxTaskRef> = None;
+
+/// A run queue with its lock.
+///
+/// The lock may be released on another CPU than the one acquiring it, as the
+/// task holding it can be switched out and resumed on another CPU, so it's
+/// not implemented by a RAII guard.
+struct RunQueueLock {
+    locked: AtomicBool,
+    rq: UnsafeCell<AxRunQueue>,
+}
+
+unsafe impl Sync for RunQueueLock {}
+
+impl RunQueueLock {
+    fn new(rq: AxRunQueue) -> Self {
+        Self {
+            locked: AtomicBool::new(false),
+            rq: UnsafeCell::new(rq),
+        }
+    }
+
+    fn lock(&self) {
+        while !self.try_lock() {
+            while self.locked.load(Ordering::Relaxed) {
+                core::hint::spin_loop();
+            }
+        }
+    }
+
+    fn try_lock(&self) -> bool {
+        self.locked
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_ok()
+    }
+
+    /// # Safety
+    ///
+    /// The lock must be held.
+    unsafe fn unlock(&self) {
+        self.locked.store(false, Ordering::Release);
+    }
+
+    /// # Safety
+    ///
+    /// The lock must be held.
+    #[allow(clippy::mut_from_ref)]
+    unsafe fn get_mut(&self) -> &mut AxRunQueue {
+        &mut *self.rq.get()
+    }
+}
+
+/// Returns the run queue lock of the current CPU.
+///
+/// # Safety
+///
+/// Preemption must be disabled, and the run queue must be initialized.
+unsafe fn this_run_queue() -> &'static RunQueueLock {
+    RUN_QUEUE.current_ref_raw().get_unchecked()
+}
+
+/// Returns the run queue lock of the given CPU, or `None` if it's not
+/// initialized yet.
+#[cfg(feature = "smp")]
+fn remote_run_queue(cpu_id: usize) -> Option<&'static RunQueueLock> {
+    unsafe { RUN_QUEUE.remote_ref_raw(cpu_id) }.try_get()
+}
+
+/// The locked run queue of the current CPU, with IRQs and preemption
+/// disabled.
+///
+/// If the current task is switched out while holding it, the lock is released
+/// by the next task, and acquired again when it's resumed, maybe on another
+/// CPU. So it always refers to the run queue of the current CPU.
+pub(crate) struct CurrentRunQueueRef {
+    irq_state: <NoPreemptIrqSave as BaseGuard>::State,
+}
+
+/// Locks the run queue of the current CPU.
+pub(crate) fn current_run_queue() -> CurrentRunQueueRef {
+    let irq_state = NoPreemptIrqSave::acquire();
+    unsafe { this_run_queue() }.lock();
+    CurrentRunQueueRef { irq_state }
+}
+
+/// Releases the run queue lock held by the previous task on the current CPU,
+/// when a new task starts running.
+///
+/// # Safety
+///
+/// It must be called only once at the beginning of a new task.
+pub(crate) unsafe fn force_unlock_current_run_queue() {
+    this_run_queue().unlock();
+}
+
+/// Locks the run queue of the CPU that `task` belongs to, and calls `f` with
+/// it.
+///
+/// A ready task can only be in the run queue of the CPU given by
+/// [`TaskInner::cpu_id`], which is checked again after locking in case the
+/// task is migrated at the same time.
+pub(crate) fn with_task_run_queue<F, R>(task: &AxTaskRef, f: F) -> R
+where
+    F: FnOnce(&mut AxRunQueue) -> R,
+{
+    let irq_state = NoPreemptIrqSave::acquire();
+    let ret = loop {
+        let cpu_id = task.cpu_id();
+        #[cfg(feature = "smp")]
+        let rq = remote_run_queue(cpu_id).expect("run queue is not initialized");
+        #[cfg(not(feature = "smp"))]
+        let rq = unsafe { this_run_queue() };
+        rq.lock();
+        if task.cpu_id() == cpu_id {
+            let ret = f(unsafe { rq.get_mut() });
+            unsafe { rq.unlock() };
+            break ret;
+        }
+        unsafe { rq.unlock() };
+    };
+    NoPreemptIrqSave::release(irq_state);
+    ret
+}
+
+/// Wakes up `task` if it's blocked interruptibly, to handle signals or
+/// cancellation.
+pub(crate) fn interrupt_task(task: &AxTaskRef) {
+    with_task_run_queue(task, |rq| {
+        if task.take_interruptible() {
+            rq.unblock_task(task.clone(), true);
+        }
+    });
+}
+
+impl Deref for CurrentRunQueueRef {
+    type Target = AxRunQueue;
+    fn deref(&self) -> &Self::Target {
+        unsafe { this_run_queue().get_mut() }
+    }
+}
+
+impl DerefMut for CurrentRunQueueRef {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { this_run_queue().get_mut() }
+    }
+}
+
+impl Drop for CurrentRunQueueRef {
+    fn drop(&mut self) {
+        unsafe { this_run_queue().unlock() };
+        NoPreemptIrqSave::release(self.irq_state);
+    }
+}
+
 pub(crate) struct AxRunQueue {
+    cpu_id: usize,
     scheduler: Scheduler,
 }
 
 impl AxRunQueue {
-    pub fn new() -> SpinNoIrq<Self> {
-        let gc_task = TaskInner::new(gc_entry, "gc".into(), axconfig::TASK_STACK_SIZE);
+    fn new(cpu_id: usize) -> Self {
+        let gc_task = TaskInner::new(
+            move || gc_entry(cpu_id),
+            "gc".into(),
+            axconfig::TASK_STACK_SIZE,
+        );
+        gc_task.set_cpu_id(cpu_id);
         let mut scheduler = Scheduler::new();
         scheduler.add_task(gc_task);
-        SpinNoIrq::new(Self { scheduler })
+        Self { cpu_id, scheduler }
     }
 
     pub fn add_task(&mut self, task: AxTaskRef) {
         debug!("task spawn: {}", task.id_name());
         assert!(task.is_ready());
+        task.set_cpu_id(task.select_cpu(self.cpu_id));
+        #[cfg(feature = "smp")]
+        if task.cpu_id() != self.cpu_id {
+            wake_remote(task.cpu_id(), task);
+            return;
+        }
         self.scheduler.add_task(task);
+        #[cfg(all(feature = "smp", feature = "irq"))]
+        kick_idle_cpu(self.cpu_id);
     }
 
     #[cfg(feature = "irq")]
@@ -57,13 +247,22 @@ impl AxRunQueue {
             .set_priority(crate::current().as_task_ref(), prio)
     }
 
+    pub fn inherit_priority(&mut self, task: &AxTaskRef, donor: Option<&AxTaskRef>) -> bool {
+        self.scheduler.inherit_priority(task, donor)
+    }
+
+    #[cfg(feature = "sched_class")]
+    pub fn set_task_policy(&mut self, task: &AxTaskRef, policy: scheduler::SchedPolicy) -> bool {
+        self.scheduler.set_policy(task, policy)
+    }
+
     #[cfg(feature = "preempt")]
     pub fn preempt_resched(&mut self) {
         let curr = crate::current();
         assert!(curr.is_running());
 
         // When we get the mutable reference of the run queue, we must
-        // have held the `SpinNoIrq` lock with both IRQs and preemption
+        // have held the run queue lock with both IRQs and preemption
         // disabled. So we need to set `current_disable_count` to 1 in
         // `can_preempt()` to obtain the preemption permission before
         //  locking the run queue.
@@ -87,13 +286,13 @@ impl AxRunQueue {
         assert!(curr.is_running());
         assert!(!curr.is_idle());
         if curr.is_init() {
-            EXITED_TASKS.lock().clear();
+            EXITED_TASKS.with_current(|tasks| tasks.lock().clear());
             axhal::misc::terminate();
         } else {
             curr.set_state(TaskState::Exited);
             curr.notify_exit(exit_code, self);
-            EXITED_TASKS.lock().push_back(curr.clone());
-            WAIT_FOR_EXIT.notify_one_locked(false, self);
+            EXITED_TASKS.with_current(|tasks| tasks.lock().push_back(curr.clone()));
+            unsafe { WAIT_FOR_EXIT.current_ref_raw() }.notify_one_locked(false, self);
             self.resched(false);
         }
         unreachable!("task exited!");
@@ -117,10 +316,38 @@ impl AxRunQueue {
         self.resched(false);
     }
 
+    /// Blocks the current task like [`block_current`](Self::block_current),
+    /// but it can be woken up early by signals or cancellation.
+    pub fn block_current_interruptible<F>(&mut self, wait_queue_push: F)
+    where
+        F: FnOnce(AxTaskRef),
+    {
+        let curr = crate::current();
+        debug!("task block interruptibly: {}", curr.id_name());
+        assert!(curr.is_running());
+        assert!(!curr.is_idle());
+        #[cfg(feature = "preempt")]
+        assert!(curr.can_preempt(1));
+
+        curr.set_state(TaskState::Blocked);
+        wait_queue_push(curr.clone());
+        self.resched_interruptible();
+    }
+
+    /// Wakes up a blocked task on its CPU.
+    ///
+    /// If its affinity has changed, it's moved by that CPU in
+    /// [`pick_next_task`](Self::pick_next_task), as the CPU may be still
+    /// saving its context.
     pub fn unblock_task(&mut self, task: AxTaskRef, resched: bool) {
         debug!("task unblock: {}", task.id_name());
-        if task.is_blocked() {
-            task.set_state(TaskState::Ready);
+        // other CPUs may wake up the task at the same time
+        if task.transition_state(TaskState::Blocked, TaskState::Ready) {
+            #[cfg(feature = "smp")]
+            if task.cpu_id() != self.cpu_id {
+                wake_remote(task.cpu_id(), task);
+                return;
+            }
             self.scheduler.add_task(task); // TODO: priority
             if resched {
                 #[cfg(feature = "preempt")]
@@ -140,8 +367,25 @@ impl AxRunQueue {
         if now < deadline {
             crate::timers::set_alarm_wakeup(deadline, curr.clone());
             curr.set_state(TaskState::Blocked);
-            self.resched(false);
+            self.resched_interruptible();
+            if curr.in_timer_list() {
+                // interrupted before the deadline
+                crate::timers::cancel_alarm(curr.as_task_ref());
+            }
+        }
+    }
+
+    /// Adds the tasks woken up by other CPUs.
+    ///
+    /// Returns `true` if any task is added.
+    pub fn drain_wake_list(&mut self) -> bool {
+        let tasks = WAKE_LIST.with_current(|list| core::mem::take(&mut *list.lock()));
+        let woken = !tasks.is_empty();
+        for task in tasks {
+            trace!("task woken by another CPU: {}", task.id_name());
+            self.scheduler.add_task(task);
         }
+        woken
     }
 }
 
@@ -149,20 +393,97 @@ impl AxRunQueue {
     /// Common reschedule subroutine. If `preempt`, keep current task's time
     /// slice, otherwise reset it.
     fn resched(&mut self, preempt: bool) {
+        self.drain_wake_list();
         let prev = crate::current();
         if prev.is_running() {
             prev.set_state(TaskState::Ready);
             if !prev.is_idle() {
-                self.scheduler.put_prev_task(prev.clone(), preempt);
+                self.put_prev_task(prev.clone(), preempt);
             }
         }
-        let next = self.scheduler.pick_next_task().unwrap_or_else(|| unsafe {
+        let next = self.pick_next_task().unwrap_or_else(|| unsafe {
             // Safety: IRQs must be disabled at this time.
             IDLE_TASK.current_ref_raw().get_unchecked().clone()
         });
         self.switch_to(prev, next);
     }
 
+    /// Reschedules after the current task is blocked, unless it has been
+    /// interrupted already.
+    fn resched_interruptible(&mut self) {
+        let curr = crate::current();
+        // interrupts from others are serialized by the run queue lock
+        curr.set_interruptible(true);
+        if curr.has_pending_interrupt() && curr.take_interruptible() {
+            curr.set_state(TaskState::Running);
+        } else {
+            self.resched(false);
+        }
+        curr.set_interruptible(false);
+    }
+
+    fn put_prev_task(&mut self, prev: AxTaskRef, preempt: bool) {
+        #[cfg(feature = "smp")]
+        if !prev.cpumask().get(self.cpu_id) {
+            // Its affinity has changed, it can be moved only after the
+            // context is saved.
+            prev.set_cpu_id(prev.select_cpu(self.cpu_id));
+            MIGRATING_TASK.with_current(|task| *task = Some(prev));
+            return;
+        }
+        self.scheduler.put_prev_task(prev, preempt);
+    }
+
+    #[cfg(not(feature = "smp"))]
+    fn pick_next_task(&mut self) -> Option<AxTaskRef> {
+        self.scheduler.pick_next_task()
+    }
+
+    #[cfg(feature = "smp")]
+    fn pick_next_task(&mut self) -> Option<AxTaskRef> {
+        while let Some(task) = self.scheduler.pick_next_task() {
+            if task.cpumask().get(self.cpu_id) {
+                return Some(task);
+            }
+            // Its affinity has changed while it's ready.
+            task.set_cpu_id(task.select_cpu(self.cpu_id));
+            wake_remote(task.cpu_id(), task);
+        }
+        self.steal_task()
+    }
+
+    /// Takes a ready task from the run queue of another CPU.
+    ///
+    /// The run queues that are locked by others are skipped to avoid
+    /// deadlocks.
+    #[cfg(feature = "smp")]
+    fn steal_task(&mut self) -> Option<AxTaskRef> {
+        for i in 1..axconfig::SMP {
+            let cpu_id = (self.cpu_id + i) % axconfig::SMP;
+            let Some(rq) = remote_run_queue(cpu_id) else {
+                continue;
+            };
+            if !rq.try_lock() {
+                continue;
+            }
+            let remote_rq = unsafe { rq.get_mut() };
+            let task = match remote_rq.scheduler.pick_next_task() {
+                Some(task) if !task.cpumask().get(self.cpu_id) => {
+                    remote_rq.scheduler.put_prev_task(task, true);
+                    None
+                }
+                task => task,
+            };
+            unsafe { rq.unlock() };
+            if let Some(task) = task {
+                debug!("task stolen from CPU {}: {}", cpu_id, task.id_name());
+                task.set_cpu_id(self.cpu_id);
+                return Some(task);
+            }
+        }
+        None
+    }
+
     fn switch_to(&mut self, prev_task: CurrentTask, next_task: AxTaskRef) {
         trace!(
             "context switch: {} -> {}",
@@ -175,6 +496,8 @@ impl AxRunQueue {
         if prev_task.ptr_eq(&next_task) {
             return;
         }
+        #[cfg(not(all(feature = "paging", target_arch = "x86_64")))]
+        prev_task.check_stack_canary();
 
         unsafe {
             let prev_ctx_ptr = prev_task.ctx_mut_ptr();
@@ -188,16 +511,82 @@ impl AxRunQueue {
             CurrentTask::set_current(prev_task, next_task);
             (*prev_ctx_ptr).switch_to(&*next_ctx_ptr);
         }
+        #[cfg(feature = "smp")]
+        finish_task_switch();
+    }
+}
+
+/// Moves the previous task to another CPU if needed, after switching to the
+/// current task.
+#[cfg(feature = "smp")]
+pub(crate) fn finish_task_switch() {
+    if let Some(task) = MIGRATING_TASK.with_current(|task| task.take()) {
+        debug!("task migrate to CPU {}: {}", task.cpu_id(), task.id_name());
+        wake_remote(task.cpu_id(), task);
+    }
+}
+
+/// Pushes `task` to the wake list of CPU `cpu_id`, and notifies it.
+#[cfg(feature = "smp")]
+fn wake_remote(cpu_id: usize, task: AxTaskRef) {
+    trace!("task wake up on CPU {}: {}", cpu_id, task.id_name());
+    unsafe { WAKE_LIST.remote_ref_raw(cpu_id) }
+        .lock()
+        .push_back(task);
+    #[cfg(feature = "irq")]
+    axhal::irq::send_ipi(cpu_id);
+}
+
+/// The CPUs running the idle task, one bit for each CPU.
+#[cfg(all(feature = "smp", feature = "irq"))]
+static IDLE_CPUS: core::sync::atomic::AtomicUsize = core::sync::atomic::AtomicUsize::new(0);
+
+/// Marks the current CPU as idle or not, so that it can be notified when
+/// there are tasks to steal.
+#[cfg(feature = "irq")]
+pub(crate) fn set_current_cpu_idle(_idle: bool) {
+    #[cfg(feature = "smp")]
+    {
+        let mask = 1 << axhal::cpu::this_cpu_id();
+        if _idle {
+            IDLE_CPUS.fetch_or(mask, Ordering::Release);
+        } else {
+            IDLE_CPUS.fetch_and(!mask, Ordering::Release);
+        }
     }
 }
 
-fn gc_entry() {
+/// Notifies an idle CPU other than `cpu_id` to steal the new ready task.
+#[cfg(all(feature = "smp", feature = "irq"))]
+fn kick_idle_cpu(cpu_id: usize) {
+    let idle_cpus = IDLE_CPUS.load(Ordering::Acquire) & !(1 << cpu_id);
+    if idle_cpus != 0 {
+        let target = idle_cpus.trailing_zeros() as usize;
+        // only notify once, until it becomes idle again
+        IDLE_CPUS.fetch_and(!(1 << target), Ordering::Release);
+        axhal::irq::send_ipi(target);
+    }
+}
+
+/// Handles the IPI of remote wakeup on the current CPU.
+#[cfg(feature = "smp")]
+pub(crate) fn on_ipi() {
+    let mut rq = current_run_queue();
+    if rq.drain_wake_list() {
+        #[cfg(feature = "preempt")]
+        crate::current().set_preempt_pending(true);
+    }
+}
+
+fn gc_entry(cpu_id: usize) {
+    let exited_tasks = unsafe { EXITED_TASKS.remote_ref_raw(cpu_id) };
+    let wait_for_exit = unsafe { WAIT_FOR_EXIT.remote_ref_raw(cpu_id) };
     loop {
         // Drop all exited tasks and recycle resources.
-        let n = EXITED_TASKS.lock().len();
+        let n = exited_tasks.lock().len();
         for _ in 0..n {
             // Do not do the slow drops in the critical section.
-            let task = EXITED_TASKS.lock().pop_front();
+            let task = exited_tasks.lock().pop_front();
             if let Some(task) = task {
                 if Arc::strong_count(&task) == 1 {
                     // If I'm the last holder of the task, drop it immediately.
@@ -205,29 +594,36 @@ fn gc_entry() {
                 } else {
                     // Otherwise (e.g, `switch_to` is not compeleted, held by the
                     // joiner, etc), push it back and wait for them to drop first.
-                    EXITED_TASKS.lock().push_back(task);
+                    exited_tasks.lock().push_back(task);
                 }
             }
         }
-        WAIT_FOR_EXIT.wait();
+        wait_for_exit.wait();
     }
 }
 
 pub(crate) fn init() {
     const IDLE_TASK_STACK_SIZE: usize = 4096;
+    let cpu_id = axhal::cpu::this_cpu_id();
     let idle_task = TaskInner::new(|| crate::run_idle(), "idle".into(), IDLE_TASK_STACK_SIZE);
+    idle_task.set_cpu_id(cpu_id);
     IDLE_TASK.with_current(|i| i.init_by(idle_task.clone()));
 
     let main_task = TaskInner::new_init("main".into());
+    main_task.set_cpu_id(cpu_id);
     main_task.set_state(TaskState::Running);
 
-    RUN_QUEUE.init_by(AxRunQueue::new());
+    RUN_QUEUE.with_current(|rq| rq.init_by(RunQueueLock::new(AxRunQueue::new(cpu_id))));
     unsafe { CurrentTask::init_current(main_task) }
 }
 
 pub(crate) fn init_secondary() {
+    let cpu_id = axhal::cpu::this_cpu_id();
     let idle_task = TaskInner::new_init("idle".into());
+    idle_task.set_cpu_id(cpu_id);
     idle_task.set_state(TaskState::Running);
     IDLE_TASK.with_current(|i| i.init_by(idle_task.clone()));
+
+    RUN_QUEUE.with_current(|rq| rq.init_by(RunQueueLock::new(AxRunQueue::new(cpu_id))));
     unsafe { CurrentTask::init_current(idle_task) }
 }
@@ -0,0 +1,366 @@
+//! Task-level signals.
+//!
+//! Each task has its own sets of pending and blocked signals, while the
+//! signal actions are shared by all tasks as they run in the same address
+//! space. The handler runs in the context of the target task, on its own
+//! stack.
+//!
+//! The kernel can't tell whether a task holds locks, so a pending signal that
+//! is not blocked is only delivered at the explicit points: when the target
+//! task calls [`handle_pending_signals`], such as at the end of the blocking
+//! calls of libc after they release their locks, when it sends the signal to
+//! itself, or when it unblocks the signal by [`set_signal_mask`]. A task
+//! running without reaching these points never handles the signal.
+//!
+//! A task blocked in [`WaitQueue::wait_until_interruptible`] or
+//! [`sleep`](crate::sleep) is woken up early by the signal, so that it can
+//! return to such a point (see [`is_interrupted`]). Other waits, such as for
+//! locks, are not interrupted.
+//!
+//! [`WaitQueue::wait_until_interruptible`]: crate::WaitQueue::wait_until_interruptible
+//! [`is_interrupted`]: crate::is_interrupted
+
+use core::sync::atomic::{AtomicU64, Ordering};
+use core::time::Duration;
+
+use spinlock::SpinNoIrq;
+
+use crate::AxTaskRef;
+
+/// The number of signals, which are numbered from 1 to `NSIG`.
+pub const NSIG: usize = 64;
+
+pub const SIGHUP: usize = 1;
+pub const SIGINT: usize = 2;
+pub const SIGQUIT: usize = 3;
+pub const SIGILL: usize = 4;
+pub const SIGTRAP: usize = 5;
+pub const SIGABRT: usize = 6;
+pub const SIGBUS: usize = 7;
+pub const SIGFPE: usize = 8;
+pub const SIGKILL: usize = 9;
+pub const SIGUSR1: usize = 10;
+pub const SIGSEGV: usize = 11;
+pub const SIGUSR2: usize = 12;
+pub const SIGPIPE: usize = 13;
+pub const SIGALRM: usize = 14;
+pub const SIGTERM: usize = 15;
+pub const SIGCHLD: usize = 17;
+pub const SIGCONT: usize = 18;
+pub const SIGSTOP: usize = 19;
+pub const SIGTSTP: usize = 20;
+pub const SIGTTIN: usize = 21;
+pub const SIGTTOU: usize = 22;
+pub const SIGURG: usize = 23;
+pub const SIGXCPU: usize = 24;
+pub const SIGXFSZ: usize = 25;
+pub const SIGWINCH: usize = 28;
+pub const SIGSYS: usize = 31;
+
+/// Signals that can be neither caught, ignored nor blocked.
+const UNBLOCKABLE: u64 = sig_bit(SIGKILL) | sig_bit(SIGSTOP);
+
+/// Returns the bit of the signal `signo` in a signal set.
+#[inline]
+pub const fn sig_bit(signo: usize) -> u64 {
+    1 << (signo - 1)
+}
+
+/// The disposition of a signal.
+#[derive(Debug, Clone, Copy)]
+pub enum SignalHandler {
+    /// Perform the default action: ignore it, terminate the task, or
+    /// terminate the whole system.
+    Default,
+    /// Ignore the signal.
+    Ignore,
+    /// Call the handler function with the signal number.
+    Handler(extern "C" fn(i32)),
+}
+
+/// The action taken when a signal is delivered.
+#[derive(Debug, Clone, Copy)]
+pub struct SignalAction {
+    /// How to handle the signal.
+    pub handler: SignalHandler,
+    /// Signals to block while the handler runs.
+    pub mask: u64,
+    /// Don't block the signal itself while the handler runs (`SA_NODEFER`).
+    pub nodefer: bool,
+    /// Reset the action to default once the signal is delivered
+    /// (`SA_RESETHAND`).
+    pub oneshot: bool,
+}
+
+impl SignalAction {
+    /// The default action of signals.
+    pub const DEFAULT: Self = Self {
+        handler: SignalHandler::Default,
+        mask: 0,
+        nodefer: false,
+        oneshot: false,
+    };
+}
+
+static SIGNAL_ACTIONS: SpinNoIrq<[SignalAction; NSIG]> =
+    SpinNoIrq::new([SignalAction::DEFAULT; NSIG]);
+
+/// The signal states of a task.
+pub(crate) struct TaskSignals {
+    pending: AtomicU64,
+    blocked: AtomicU64,
+}
+
+impl TaskSignals {
+    pub const fn new() -> Self {
+        Self {
+            pending: AtomicU64::new(0),
+            blocked: AtomicU64::new(0),
+        }
+    }
+
+    /// Whether there are pending signals that are not blocked.
+    pub fn has_deliverable(&self) -> bool {
+        self.pending.load(Ordering::Acquire) & !self.blocked.load(Ordering::Acquire) != 0
+    }
+
+    /// Removes the lowest numbered deliverable signal from the pending set.
+    fn take_deliverable(&self) -> Option<usize> {
+        loop {
+            let pending = self.pending.load(Ordering::Acquire);
+            let deliverable = pending & !self.blocked.load(Ordering::Acquire);
+            if deliverable == 0 {
+                return None;
+            }
+            let signo = deliverable.trailing_zeros() as usize + 1;
+            let old = self.pending.fetch_and(!sig_bit(signo), Ordering::AcqRel);
+            if old & sig_bit(signo) != 0 {
+                return Some(signo);
+            }
+        }
+    }
+}
+
+/// Returns the action of the signal `signo`, or [`None`] if `signo` is not a
+/// valid signal number.
+pub fn signal_action(signo: usize) -> Option<SignalAction> {
+    if !(1..=NSIG).contains(&signo) {
+        return None;
+    }
+    Some(SIGNAL_ACTIONS.lock()[signo - 1])
+}
+
+/// Changes the action of the signal `signo`, and returns the old one.
+///
+/// Returns [`None`] if `signo` is not a valid signal number, or its action
+/// cannot be changed (`SIGKILL` and `SIGSTOP`).
+pub fn set_signal_action(signo: usize, action: SignalAction) -> Option<SignalAction> {
+    if !(1..=NSIG).contains(&signo) || sig_bit(signo) & UNBLOCKABLE != 0 {
+        return None;
+    }
+    debug!("set signal action: {}, {:?}", signo, action.handler);
+    let mut actions = SIGNAL_ACTIONS.lock();
+    Some(core::mem::replace(&mut actions[signo - 1], action))
+}
+
+/// Sends the signal `signo` to the given task.
+///
+/// If the task is the current task, the signal is handled before returning
+/// unless it's blocked, so the caller must hold no locks then. A `signo` of 0
+/// only checks whether the signal number is valid.
+///
+/// Returns `false` if `signo` is not a valid signal number.
+pub fn send_signal(task: &AxTaskRef, signo: usize) -> bool {
+    if signo == 0 {
+        return true;
+    } else if signo > NSIG {
+        return false;
+    }
+    signal_task(task, signo);
+    if crate::current().ptr_eq(task) {
+        handle_pending_signals();
+    }
+    true
+}
+
+/// Returns the signals that are blocked by the current task.
+pub fn signal_mask() -> u64 {
+    crate::current().signals().blocked.load(Ordering::Acquire)
+}
+
+/// Sets the signals blocked by the current task, and returns the old mask.
+///
+/// `SIGKILL` and `SIGSTOP` cannot be blocked. The signals unblocked and
+/// pending are handled before returning, so the caller must hold no locks.
+pub fn set_signal_mask(mask: u64) -> u64 {
+    let curr = crate::current();
+    let old = curr
+        .signals()
+        .blocked
+        .swap(mask & !UNBLOCKABLE, Ordering::AcqRel);
+    handle_pending_signals();
+    old
+}
+
+/// Returns the signals that are pending on the current task.
+pub fn pending_signals() -> u64 {
+    crate::current().signals().pending.load(Ordering::Acquire)
+}
+
+/// Marks the signal `signo` as pending on `task`, and wakes it up if it's
+/// blocked interruptibly. It never handles the signal in place, so it can be
+/// called in the IRQ context.
+fn signal_task(task: &AxTaskRef, signo: usize) {
+    debug!("send signal {} to {}", signo, task.id_name());
+    let signals = task.signals();
+    signals.pending.fetch_or(sig_bit(signo), Ordering::AcqRel);
+    if signals.has_deliverable() {
+        crate::run_queue::interrupt_task(task);
+    }
+}
+
+/// Handles the deliverable signals of the current task.
+///
+/// It must be called only where the current task holds no locks, as the
+/// handlers may block or never return, and the default action may exit the
+/// task.
+pub fn handle_pending_signals() {
+    let curr = crate::current();
+    if curr.is_idle() {
+        return;
+    }
+    while let Some(signo) = curr.signals().take_deliverable() {
+        let action = {
+            let mut actions = SIGNAL_ACTIONS.lock();
+            let action = actions[signo - 1];
+            if action.oneshot {
+                actions[signo - 1] = SignalAction::DEFAULT;
+            }
+            action
+        };
+        debug!("task {} handle signal {}", curr.id_name(), signo);
+        match action.handler {
+            SignalHandler::Ignore => {}
+            SignalHandler::Default => default_action(signo),
+            SignalHandler::Handler(handler) => {
+                let mut mask = action.mask;
+                if !action.nodefer {
+                    mask |= sig_bit(signo);
+                }
+                let blocked = &curr.signals().blocked;
+                let old = blocked.fetch_or(mask & !UNBLOCKABLE, Ordering::AcqRel);
+                handler(signo as i32);
+                blocked.store(old, Ordering::Release);
+            }
+        }
+    }
+}
+
+fn default_action(signo: usize) {
+    match signo {
+        SIGCHLD | SIGCONT | SIGURG | SIGWINCH => {}
+        SIGSTOP | SIGTSTP | SIGTTIN | SIGTTOU => {
+            warn!("stopping tasks by signal {} is not supported", signo);
+        }
+        SIGQUIT | SIGILL | SIGTRAP | SIGABRT | SIGBUS | SIGFPE | SIGSEGV | SIGXCPU | SIGXFSZ
+        | SIGSYS => {
+            error!(
+                "{} killed by signal {}, shutting down...",
+                crate::current().id_name(),
+                signo
+            );
+            axhal::misc::terminate();
+        }
+        _ => {
+            info!("{} killed by signal {}", crate::current().id_name(), signo);
+            crate::exit(128 + signo as i32);
+        }
+    }
+}
+
+#[cfg(feature = "irq")]
+struct AlarmTimer {
+    task: Option<AxTaskRef>,
+    deadline: axhal::time::TimeValue,
+    interval: Duration,
+    /// The CPU of the timer list that the alarm event is in.
+    cpu_id: usize,
+}
+
+#[cfg(feature = "irq")]
+static ALARM: SpinNoIrq<AlarmTimer> = SpinNoIrq::new(AlarmTimer {
+    task: None,
+    deadline: Duration::ZERO,
+    interval: Duration::ZERO,
+    cpu_id: 0,
+});
+
+/// Arms the alarm timer to send `SIGALRM` to `task` after `value`, and then
+/// every `interval` if it's not zero. A zero `value` disarms the timer.
+///
+/// Returns the remaining time and the interval of the previous alarm. The
+/// alarm never fires if the feature `irq` is not enabled.
+pub fn set_alarm(task: &AxTaskRef, value: Duration, interval: Duration) -> (Duration, Duration) {
+    #[cfg(feature = "irq")]
+    {
+        let now = axhal::time::current_time();
+        let mut alarm = ALARM.lock();
+        let old = if alarm.task.take().is_some() {
+            crate::timers::cancel_signal_alarm(alarm.cpu_id);
+            (alarm.deadline.saturating_sub(now), alarm.interval)
+        } else {
+            (Duration::ZERO, Duration::ZERO)
+        };
+        if !value.is_zero() {
+            alarm.task = Some(task.clone());
+            alarm.deadline = now + value;
+            alarm.interval = interval;
+            alarm.cpu_id = crate::timers::set_signal_alarm(alarm.deadline);
+        }
+        old
+    }
+    #[cfg(not(feature = "irq"))]
+    {
+        let _ = (task, value, interval);
+        warn!("set_alarm: the alarm never fires without the `irq` feature");
+        (Duration::ZERO, Duration::ZERO)
+    }
+}
+
+/// Returns the remaining time and the interval of the alarm timer.
+pub fn alarm_remaining() -> (Duration, Duration) {
+    #[cfg(feature = "irq")]
+    {
+        let alarm = ALARM.lock();
+        if alarm.task.is_some() {
+            let now = axhal::time::current_time();
+            return (alarm.deadline.saturating_sub(now), alarm.interval);
+        }
+    }
+    (Duration::ZERO, Duration::ZERO)
+}
+
+/// Called when the alarm event expires on the timer list of the current CPU.
+#[cfg(feature = "irq")]
+pub(crate) fn on_alarm(now: axhal::time::TimeValue) {
+    let task = {
+        let mut alarm = ALARM.lock();
+        if alarm.task.is_none() || now < alarm.deadline {
+            return; // it has been disarmed or rearmed
+        }
+        if alarm.interval.is_zero() {
+            alarm.task.take()
+        } else {
+            alarm.deadline += alarm.interval;
+            if alarm.deadline <= now {
+                // missed some periods, don't fire them all at once
+                alarm.deadline = now + alarm.interval;
+            }
+            alarm.cpu_id = crate::timers::set_signal_alarm(alarm.deadline);
+            alarm.task.clone()
+        }
+    };
+    if let Some(task) = task {
+        signal_task(&task, SIGALRM);
+    }
+}
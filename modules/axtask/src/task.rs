@@ -1,18 +1,27 @@
-use alloc::{boxed::Box, string::String, sync::Arc};
+use alloc::collections::BTreeMap;
+use alloc::sync::{Arc, Weak};
+use alloc::{boxed::Box, string::String};
 use core::ops::Deref;
-use core::sync::atomic::{AtomicBool, AtomicI32, AtomicU64, AtomicU8, Ordering};
+use core::sync::atomic::{AtomicBool, AtomicI32, AtomicU64, AtomicU8, AtomicUsize, Ordering};
 use core::{alloc::Layout, cell::UnsafeCell, fmt, ptr::NonNull};
 
-#[cfg(feature = "preempt")]
-use core::sync::atomic::AtomicUsize;
-
 #[cfg(feature = "tls")]
 use axhal::tls::TlsArea;
 
 use axhal::arch::TaskContext;
 use memory_addr::{align_up_4k, VirtAddr};
+use spinlock::SpinNoIrq;
+
+use crate::signal::TaskSignals;
+use crate::{AxRunQueue, AxTask, AxTaskRef, CpuMask, WaitQueue};
+
+/// All tasks that are not dropped, indexed by their IDs.
+static TASKS: SpinNoIrq<BTreeMap<u64, Weak<AxTask>>> = SpinNoIrq::new(BTreeMap::new());
 
-use crate::{AxRunQueue, AxTask, AxTaskRef, WaitQueue};
+/// Returns the task with the given ID, or [`None`] if it's dropped.
+pub(crate) fn task_by_id(id: u64) -> Option<AxTaskRef> {
+    TASKS.lock().get(&id).and_then(Weak::upgrade)
+}
 
 /// A unique identifier for a thread.
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
@@ -37,16 +46,32 @@ pub struct TaskInner {
 
     entry: Option<*mut dyn FnOnce()>,
     state: AtomicU8,
+    /// The CPU of the run queue that the task belongs to.
+    cpu_id: AtomicUsize,
+    /// The CPUs that the task is allowed to run on.
+    cpumask: AtomicUsize,
 
     in_wait_queue: AtomicBool,
+    /// Whether the task is blocked interruptibly, and can be woken up by
+    /// signals or cancellation.
+    interruptible: AtomicBool,
+    /// The address of the futex that the task waits on.
+    futex_addr: AtomicUsize,
     #[cfg(feature = "irq")]
     in_timer_list: AtomicBool,
+    /// The CPU of the timer list that the task is in.
+    #[cfg(feature = "irq")]
+    timer_cpu_id: AtomicUsize,
 
     #[cfg(feature = "preempt")]
     need_resched: AtomicBool,
     #[cfg(feature = "preempt")]
     preempt_disable_count: AtomicUsize,
 
+    signals: TaskSignals,
+    cancel_requested: AtomicBool,
+    cancel_disabled: AtomicBool,
+
     exit_code: AtomicI32,
     wait_for_exit: WaitQueue,
 
@@ -101,6 +126,11 @@ impl TaskInner {
         alloc::format!("Task({}, {:?})", self.id.as_u64(), self.name)
     }
 
+    /// Gets the CPUs that the task is allowed to run on.
+    pub fn cpumask(&self) -> CpuMask {
+        CpuMask::from_raw_bits(self.cpumask.load(Ordering::Acquire))
+    }
+
     /// Wait for the task to exit, and return the exit code.
     ///
     /// It will return immediately if the task has already exited (but not dropped).
@@ -109,10 +139,27 @@ impl TaskInner {
             .wait_until(|| self.state() == TaskState::Exited);
         Some(self.exit_code.load(Ordering::Acquire))
     }
+
+    /// Whether `vaddr` is in the guard page below the kernel stack of the
+    /// task, i.e., an access to it is a stack overflow.
+    #[cfg(all(feature = "paging", target_arch = "x86_64"))]
+    pub fn is_stack_guard(&self, vaddr: VirtAddr) -> bool {
+        self.kstack
+            .as_ref()
+            .map_or(false, |kstack| kstack.guard_contains(vaddr))
+    }
 }
 
 // private methods
 impl TaskInner {
+    fn register(t: Self) -> AxTaskRef {
+        let task = Arc::new(AxTask::new(t));
+        TASKS
+            .lock()
+            .insert(task.id().as_u64(), Arc::downgrade(&task));
+        task
+    }
+
     fn new_common(id: TaskId, name: String) -> Self {
         Self {
             id,
@@ -121,13 +168,22 @@ impl TaskInner {
             is_init: false,
             entry: None,
             state: AtomicU8::new(TaskState::Ready as u8),
+            cpu_id: AtomicUsize::new(0),
+            cpumask: AtomicUsize::new(CpuMask::full().bits()),
             in_wait_queue: AtomicBool::new(false),
+            interruptible: AtomicBool::new(false),
+            futex_addr: AtomicUsize::new(0),
             #[cfg(feature = "irq")]
             in_timer_list: AtomicBool::new(false),
+            #[cfg(feature = "irq")]
+            timer_cpu_id: AtomicUsize::new(0),
             #[cfg(feature = "preempt")]
             need_resched: AtomicBool::new(false),
             #[cfg(feature = "preempt")]
             preempt_disable_count: AtomicUsize::new(0),
+            signals: TaskSignals::new(),
+            cancel_requested: AtomicBool::new(false),
+            cancel_disabled: AtomicBool::new(false),
             exit_code: AtomicI32::new(0),
             wait_for_exit: WaitQueue::new(),
             kstack: None,
@@ -157,7 +213,7 @@ impl TaskInner {
         if t.name == "idle" {
             t.is_idle = true;
         }
-        Arc::new(AxTask::new(t))
+        Self::register(t)
     }
 
     /// Creates an "init task" using the current CPU states, to use as the
@@ -174,7 +230,7 @@ impl TaskInner {
         if t.name == "idle" {
             t.is_idle = true;
         }
-        Arc::new(AxTask::new(t))
+        Self::register(t)
     }
 
     #[inline]
@@ -187,6 +243,41 @@ impl TaskInner {
         self.state.store(state as u8, Ordering::Release)
     }
 
+    /// Changes the state from `from` to `to`, returns `false` if the current
+    /// state is not `from`.
+    #[inline]
+    pub(crate) fn transition_state(&self, from: TaskState, to: TaskState) -> bool {
+        self.state
+            .compare_exchange(from as u8, to as u8, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok()
+    }
+
+    #[inline]
+    pub(crate) fn cpu_id(&self) -> usize {
+        self.cpu_id.load(Ordering::Acquire)
+    }
+
+    #[inline]
+    pub(crate) fn set_cpu_id(&self, cpu_id: usize) {
+        self.cpu_id.store(cpu_id, Ordering::Release)
+    }
+
+    #[inline]
+    pub(crate) fn set_cpumask(&self, cpumask: CpuMask) {
+        self.cpumask.store(cpumask.bits(), Ordering::Release)
+    }
+
+    /// Returns a CPU allowed by the cpumask, prefers `cpu_id` if it's allowed.
+    #[inline]
+    pub(crate) fn select_cpu(&self, cpu_id: usize) -> usize {
+        let cpumask = self.cpumask();
+        if cpumask.get(cpu_id) {
+            cpu_id
+        } else {
+            cpumask.first().unwrap_or(cpu_id)
+        }
+    }
+
     #[inline]
     pub(crate) fn is_running(&self) -> bool {
         matches!(self.state(), TaskState::Running)
@@ -197,11 +288,6 @@ impl TaskInner {
         matches!(self.state(), TaskState::Ready)
     }
 
-    #[inline]
-    pub(crate) fn is_blocked(&self) -> bool {
-        matches!(self.state(), TaskState::Blocked)
-    }
-
     #[inline]
     pub(crate) const fn is_init(&self) -> bool {
         self.is_init
@@ -222,6 +308,57 @@ impl TaskInner {
         self.in_wait_queue.store(in_wait_queue, Ordering::Release);
     }
 
+    #[inline]
+    pub(crate) fn set_interruptible(&self, interruptible: bool) {
+        self.interruptible.store(interruptible, Ordering::Release);
+    }
+
+    /// Clears the interruptible flag, returns `true` if it was set, so that
+    /// the caller is responsible for waking up the task.
+    #[inline]
+    pub(crate) fn take_interruptible(&self) -> bool {
+        self.interruptible.swap(false, Ordering::AcqRel)
+    }
+
+    /// Whether there are signals or cancellation requests to interrupt the
+    /// blocking of the task.
+    #[inline]
+    pub(crate) fn has_pending_interrupt(&self) -> bool {
+        self.signals.has_deliverable() || self.cancel_pending()
+    }
+
+    #[inline]
+    pub(crate) fn request_cancel(&self) {
+        self.cancel_requested.store(true, Ordering::Release);
+    }
+
+    #[inline]
+    pub(crate) fn cancel_enabled(&self) -> bool {
+        !self.cancel_disabled.load(Ordering::Acquire)
+    }
+
+    #[inline]
+    pub(crate) fn set_cancel_enabled(&self, enabled: bool) -> bool {
+        !self.cancel_disabled.swap(!enabled, Ordering::AcqRel)
+    }
+
+    /// Whether the task is requested to be canceled, with cancellation
+    /// enabled.
+    #[inline]
+    pub(crate) fn cancel_pending(&self) -> bool {
+        self.cancel_requested.load(Ordering::Acquire) && self.cancel_enabled()
+    }
+
+    #[inline]
+    pub(crate) fn futex_addr(&self) -> usize {
+        self.futex_addr.load(Ordering::Acquire)
+    }
+
+    #[inline]
+    pub(crate) fn set_futex_addr(&self, addr: usize) {
+        self.futex_addr.store(addr, Ordering::Release);
+    }
+
     #[inline]
     #[cfg(feature = "irq")]
     pub(crate) fn in_timer_list(&self) -> bool {
@@ -234,6 +371,18 @@ impl TaskInner {
         self.in_timer_list.store(in_timer_list, Ordering::Release);
     }
 
+    #[inline]
+    #[cfg(feature = "irq")]
+    pub(crate) fn timer_cpu_id(&self) -> usize {
+        self.timer_cpu_id.load(Ordering::Acquire)
+    }
+
+    #[inline]
+    #[cfg(feature = "irq")]
+    pub(crate) fn set_timer_cpu_id(&self, cpu_id: usize) {
+        self.timer_cpu_id.store(cpu_id, Ordering::Release);
+    }
+
     #[inline]
     #[cfg(feature = "preempt")]
     pub(crate) fn set_preempt_pending(&self, pending: bool) {
@@ -265,13 +414,18 @@ impl TaskInner {
     fn current_check_preempt_pending() {
         let curr = crate::current();
         if curr.need_resched.load(Ordering::Acquire) && curr.can_preempt(0) {
-            let mut rq = crate::RUN_QUEUE.lock();
+            let mut rq = crate::run_queue::current_run_queue();
             if curr.need_resched.load(Ordering::Acquire) {
                 rq.preempt_resched();
             }
         }
     }
 
+    #[inline]
+    pub(crate) const fn signals(&self) -> &TaskSignals {
+        &self.signals
+    }
+
     pub(crate) fn notify_exit(&self, exit_code: i32, rq: &mut AxRunQueue) {
         self.exit_code.store(exit_code, Ordering::Release);
         self.wait_for_exit.notify_all_locked(false, rq);
@@ -281,6 +435,20 @@ impl TaskInner {
     pub(crate) const unsafe fn ctx_mut_ptr(&self) -> *mut TaskContext {
         self.ctx.get()
     }
+
+    /// Panics if the canary at the bottom of the kernel stack is overwritten.
+    #[cfg(not(all(feature = "paging", target_arch = "x86_64")))]
+    pub(crate) fn check_stack_canary(&self) {
+        if let Some(kstack) = &self.kstack {
+            if !kstack.canary_intact() {
+                panic!(
+                    "stack overflow in {}: the canary at {:#x} is overwritten",
+                    self.id_name(),
+                    kstack.ptr.as_ptr() as usize
+                );
+            }
+        }
+    }
 }
 
 impl fmt::Debug for TaskInner {
@@ -296,9 +464,24 @@ impl fmt::Debug for TaskInner {
 impl Drop for TaskInner {
     fn drop(&mut self) {
         debug!("task drop: {}", self.id_name());
+        TASKS.lock().remove(&self.id.as_u64());
     }
 }
 
+/// The value placed at the bottom of the task stacks to detect overflows, if
+/// guard pages are not available.
+#[cfg(not(all(feature = "paging", target_arch = "x86_64")))]
+const STACK_CANARY: u64 = 0x5afe_57ac_c0de_ca11;
+
+/// The kernel stack of a task.
+///
+/// With the feature `paging` on x86_64, it has an unmapped guard page below
+/// it, so an overflow causes a page fault, which is handled on the double
+/// fault stack. Guard pages are not implemented on aarch64 and riscv64, as
+/// they have no separate exception stack to handle the fault on, so there and
+/// without `paging`, it's allocated from the heap with a canary at its bottom.
+/// The canary is checked when the task is switched out, which detects an
+/// overflow only after it may have corrupted the neighbouring allocations.
 struct TaskStack {
     ptr: NonNull<u8>,
     layout: Layout,
@@ -307,20 +490,47 @@ struct TaskStack {
 impl TaskStack {
     pub fn alloc(size: usize) -> Self {
         let layout = Layout::from_size_align(size, 16).unwrap();
-        Self {
-            ptr: NonNull::new(unsafe { alloc::alloc::alloc(layout) }).unwrap(),
-            layout,
-        }
+        #[cfg(all(feature = "paging", target_arch = "x86_64"))]
+        let ptr = axmm::alloc_stack(size)
+            .expect("failed to allocate the task stack")
+            .as_mut_ptr();
+        #[cfg(not(all(feature = "paging", target_arch = "x86_64")))]
+        let ptr = unsafe { alloc::alloc::alloc(layout) };
+        let ptr = NonNull::new(ptr).unwrap();
+        #[cfg(not(all(feature = "paging", target_arch = "x86_64")))]
+        unsafe {
+            ptr.as_ptr().cast::<u64>().write(STACK_CANARY)
+        };
+        Self { ptr, layout }
     }
 
     pub const fn top(&self) -> VirtAddr {
         unsafe { core::mem::transmute(self.ptr.as_ptr().add(self.layout.size())) }
     }
+
+    #[cfg(all(feature = "paging", target_arch = "x86_64"))]
+    pub fn guard_contains(&self, vaddr: VirtAddr) -> bool {
+        let bottom = self.ptr.as_ptr() as usize;
+        (bottom - axmm::STACK_GUARD_SIZE..bottom).contains(&vaddr.as_usize())
+    }
+
+    #[cfg(not(all(feature = "paging", target_arch = "x86_64")))]
+    pub fn canary_intact(&self) -> bool {
+        unsafe { self.ptr.as_ptr().cast::<u64>().read() == STACK_CANARY }
+    }
 }
 
 impl Drop for TaskStack {
     fn drop(&mut self) {
-        unsafe { alloc::alloc::dealloc(self.ptr.as_ptr(), self.layout) }
+        #[cfg(all(feature = "paging", target_arch = "x86_64"))]
+        axmm::dealloc_stack(
+            VirtAddr::from(self.ptr.as_ptr() as usize),
+            self.layout.size(),
+        );
+        #[cfg(not(all(feature = "paging", target_arch = "x86_64")))]
+        unsafe {
+            alloc::alloc::dealloc(self.ptr.as_ptr(), self.layout)
+        }
     }
 }
 
@@ -380,7 +590,9 @@ impl Deref for CurrentTask {
 
 extern "C" fn task_entry() -> ! {
     // release the lock that was implicitly held across the reschedule
-    unsafe { crate::RUN_QUEUE.force_unlock() };
+    #[cfg(feature = "smp")]
+    crate::run_queue::finish_task_switch();
+    unsafe { crate::run_queue::force_unlock_current_run_queue() };
     #[cfg(feature = "irq")]
     axhal::arch::enable_irqs();
     let task = crate::current();
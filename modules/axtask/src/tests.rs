@@ -1,7 +1,8 @@
-use core::sync::atomic::{AtomicUsize, Ordering};
+use core::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
 use std::sync::{Mutex, Once};
 
-use crate::{self as axtask, current, WaitQueue};
+use crate::signal::{self, SignalAction, SignalHandler};
+use crate::{self as axtask, current, CpuMask, FutexWaitResult, WaitQueue};
 
 static INIT: Once = Once::new();
 static SERIAL: Mutex<()> = Mutex::new(());
@@ -128,3 +129,168 @@ fn test_task_join() {
         assert_eq!(tasks[i].join(), Some(i as _));
     }
 }
+
+#[test]
+fn test_affinity() {
+    let _lock = SERIAL.lock();
+    INIT.call_once(axtask::init_scheduler);
+
+    let cpumask = CpuMask::one_shot(0);
+    assert!(cpumask.get(0));
+    assert_eq!(cpumask.first(), Some(0));
+    assert!(CpuMask::one_shot(axconfig::SMP).is_empty());
+
+    let task = axtask::spawn_on(0, || {
+        let curr = current();
+        assert_eq!(curr.cpumask(), CpuMask::one_shot(0));
+        assert!(!axtask::set_affinity(
+            curr.as_task_ref(),
+            CpuMask::from_raw_bits(0)
+        ));
+        assert!(axtask::set_affinity(curr.as_task_ref(), CpuMask::full()));
+        axtask::yield_now();
+        axtask::exit(7);
+    });
+    assert_eq!(task.join(), Some(7));
+    assert_eq!(task.cpumask(), CpuMask::full());
+}
+
+#[test]
+fn test_futex() {
+    let _lock = SERIAL.lock();
+    INIT.call_once(axtask::init_scheduler);
+
+    const NUM_TASKS: usize = 5;
+    const ZERO: AtomicU32 = AtomicU32::new(0);
+    // the first and the last ones are likely in the same wait queue
+    static FUTEXES: [AtomicU32; 65] = [ZERO; 65];
+    static WOKEN: AtomicUsize = AtomicUsize::new(0);
+
+    assert_eq!(
+        axtask::futex_wait(&FUTEXES[0], 1, None),
+        FutexWaitResult::Mismatch
+    );
+
+    for i in 0..NUM_TASKS {
+        let futex = &FUTEXES[if i % 2 == 0 { 0 } else { 64 }];
+        axtask::spawn(move || {
+            assert_eq!(axtask::futex_wait(futex, 0, None), FutexWaitResult::Woken);
+            WOKEN.fetch_add(1, Ordering::Relaxed);
+        });
+    }
+    for _ in 0..NUM_TASKS {
+        axtask::yield_now();
+    }
+
+    // only the tasks waiting on the same address are woken up
+    assert_eq!(axtask::futex_wake(&FUTEXES[64], usize::MAX), 2);
+    while WOKEN.load(Ordering::Relaxed) < 2 {
+        axtask::yield_now();
+    }
+    assert_eq!(axtask::futex_wake(&FUTEXES[0], 1), 1);
+    assert_eq!(axtask::futex_wake(&FUTEXES[0], usize::MAX), 2);
+    while WOKEN.load(Ordering::Relaxed) < NUM_TASKS {
+        axtask::yield_now();
+    }
+    assert_eq!(axtask::futex_wake(&FUTEXES[0], 1), 0);
+}
+
+#[test]
+fn test_signal() {
+    let _lock = SERIAL.lock();
+    INIT.call_once(axtask::init_scheduler);
+
+    static HANDLED: AtomicUsize = AtomicUsize::new(0);
+    extern "C" fn handler(signo: i32) {
+        assert_eq!(signo as usize, signal::SIGUSR1);
+        // the signal itself is blocked while the handler runs
+        assert_ne!(signal::signal_mask() & signal::sig_bit(signal::SIGUSR1), 0);
+        HANDLED.fetch_add(1, Ordering::Relaxed);
+    }
+
+    let action = SignalAction {
+        handler: SignalHandler::Handler(handler),
+        ..SignalAction::DEFAULT
+    };
+    assert!(signal::set_signal_action(signal::SIGKILL, action).is_none());
+    assert!(signal::set_signal_action(signal::SIGUSR1, action).is_some());
+
+    // handled immediately if sent to the current task
+    let curr = current();
+    assert!(signal::send_signal(curr.as_task_ref(), signal::SIGUSR1));
+    assert_eq!(HANDLED.load(Ordering::Relaxed), 1);
+
+    // handled after unblocked
+    signal::set_signal_mask(signal::sig_bit(signal::SIGUSR1));
+    assert!(signal::send_signal(curr.as_task_ref(), signal::SIGUSR1));
+    assert_eq!(HANDLED.load(Ordering::Relaxed), 1);
+    assert_eq!(signal::pending_signals(), signal::sig_bit(signal::SIGUSR1));
+    signal::set_signal_mask(0);
+    assert_eq!(HANDLED.load(Ordering::Relaxed), 2);
+    assert_eq!(signal::pending_signals(), 0);
+
+    // other tasks are woken up from interruptible waits, and handle it at
+    // the explicit points
+    static WQ: WaitQueue = WaitQueue::new();
+    let task = axtask::spawn(|| loop {
+        WQ.wait_until_interruptible(|| false, None);
+        assert!(axtask::is_interrupted());
+        signal::handle_pending_signals();
+    });
+    assert!(signal::send_signal(&task, signal::SIGUSR1));
+    while HANDLED.load(Ordering::Relaxed) < 3 {
+        axtask::yield_now();
+    }
+
+    // the default action of `SIGTERM` terminates the task
+    assert!(signal::send_signal(&task, signal::SIGTERM));
+    assert_eq!(task.join(), Some(128 + signal::SIGTERM as i32));
+
+    signal::set_signal_action(signal::SIGUSR1, SignalAction::DEFAULT);
+}
+
+#[test]
+fn test_cancel() {
+    let _lock = SERIAL.lock();
+    INIT.call_once(axtask::init_scheduler);
+
+    static WQ: WaitQueue = WaitQueue::new();
+    static CLEANED: AtomicUsize = AtomicUsize::new(0);
+    axtask::set_cancel_handler(|| {
+        CLEANED.fetch_add(1, Ordering::Relaxed);
+    });
+
+    // woken up from the wait queue by the cancellation request, and acted on
+    // at the cancellation point
+    let task = axtask::spawn(|| loop {
+        WQ.wait_until_interruptible(|| false, None);
+        assert!(axtask::cancel_pending());
+        axtask::testcancel();
+    });
+    axtask::yield_now();
+    axtask::cancel(&task);
+    assert_eq!(task.join(), Some(axtask::CANCELED_EXIT_CODE));
+    assert_eq!(CLEANED.load(Ordering::Relaxed), 1);
+
+    // canceled after cancellation is enabled again
+    static STEP: AtomicUsize = AtomicUsize::new(0);
+    let task = axtask::spawn(|| {
+        assert!(axtask::set_cancel_enabled(false));
+        STEP.store(1, Ordering::Relaxed);
+        while STEP.load(Ordering::Relaxed) < 2 {
+            axtask::yield_now();
+        }
+        axtask::testcancel(); // disabled, no effect
+        assert!(!axtask::set_cancel_enabled(true));
+        axtask::testcancel();
+        unreachable!();
+    });
+    while STEP.load(Ordering::Relaxed) < 1 {
+        axtask::yield_now();
+    }
+    axtask::cancel(&task);
+    STEP.store(2, Ordering::Relaxed);
+    assert_eq!(task.join(), Some(axtask::CANCELED_EXIT_CODE));
+    assert_eq!(CLEANED.load(Ordering::Relaxed), 2);
+    assert!(!WQ.notify_one(false)); // the canceled task is removed from it
+}
@@ -4,37 +4,72 @@ use lazy_init::LazyInit;
 use spinlock::SpinNoIrq;
 use timer_list::{TimeValue, TimerEvent, TimerList};
 
-use crate::{AxTaskRef, RUN_QUEUE};
+use crate::run_queue::current_run_queue;
+use crate::AxTaskRef;
 
-// TODO: per-CPU
-static TIMER_LIST: LazyInit<SpinNoIrq<TimerList<TaskWakeupEvent>>> = LazyInit::new();
+#[percpu::def_percpu]
+static TIMER_LIST: LazyInit<SpinNoIrq<TimerList<TaskTimerEvent>>> = LazyInit::new();
 
-struct TaskWakeupEvent(AxTaskRef);
+enum TaskTimerEvent {
+    /// Wakes up the sleeping task.
+    Wakeup(AxTaskRef),
+    /// Sends `SIGALRM` by the alarm timer.
+    Alarm,
+}
 
-impl TimerEvent for TaskWakeupEvent {
-    fn callback(self, _now: TimeValue) {
-        let mut rq = RUN_QUEUE.lock();
-        self.0.set_in_timer_list(false);
-        rq.unblock_task(self.0, true);
+impl TimerEvent for TaskTimerEvent {
+    fn callback(self, now: TimeValue) {
+        match self {
+            Self::Wakeup(task) => {
+                let mut rq = current_run_queue();
+                task.set_in_timer_list(false);
+                rq.unblock_task(task, true);
+            }
+            Self::Alarm => crate::signal::on_alarm(now),
+        }
     }
 }
 
+/// Sets an alarm on the timer list of the current CPU.
 pub fn set_alarm_wakeup(deadline: TimeValue, task: AxTaskRef) {
-    let mut timers = TIMER_LIST.lock();
-    task.set_in_timer_list(true);
-    timers.set(deadline, TaskWakeupEvent(task));
+    TIMER_LIST.with_current(|timer_list| {
+        let mut timers = timer_list.lock();
+        task.set_in_timer_list(true);
+        task.set_timer_cpu_id(axhal::cpu::this_cpu_id());
+        timers.set(deadline, TaskTimerEvent::Wakeup(task));
+    })
 }
 
+/// Cancels the alarm of the task, which may be on the timer list of another
+/// CPU if the task has migrated.
 pub fn cancel_alarm(task: &AxTaskRef) {
-    let mut timers = TIMER_LIST.lock();
+    let timer_list = unsafe { TIMER_LIST.remote_ref_raw(task.timer_cpu_id()) };
+    let mut timers = timer_list.lock();
     task.set_in_timer_list(false);
-    timers.cancel(|t| Arc::ptr_eq(&t.0, task));
+    timers.cancel(|e| matches!(e, TaskTimerEvent::Wakeup(t) if Arc::ptr_eq(t, task)));
+}
+
+/// Sets the alarm event of signals on the timer list of the current CPU.
+///
+/// Returns the ID of the current CPU.
+pub fn set_signal_alarm(deadline: TimeValue) -> usize {
+    TIMER_LIST.with_current(|timer_list| timer_list.lock().set(deadline, TaskTimerEvent::Alarm));
+    axhal::cpu::this_cpu_id()
+}
+
+/// Cancels the alarm event of signals on the timer list of CPU `cpu_id`.
+pub fn cancel_signal_alarm(cpu_id: usize) {
+    let timer_list = unsafe { TIMER_LIST.remote_ref_raw(cpu_id) };
+    timer_list
+        .lock()
+        .cancel(|t| matches!(t, TaskTimerEvent::Alarm));
 }
 
+/// Fires the expired events on the timer list of the current CPU.
 pub fn check_events() {
     loop {
         let now = current_time();
-        let event = TIMER_LIST.lock().expire_one(now);
+        let event = TIMER_LIST.with_current(|timer_list| timer_list.lock().expire_one(now));
         if let Some((_deadline, event)) = event {
             event.callback(now);
         } else {
@@ -44,5 +79,5 @@ pub fn check_events() {
 }
 
 pub fn init() {
-    TIMER_LIST.init_by(SpinNoIrq::new(TimerList::new()));
+    TIMER_LIST.with_current(|timer_list| timer_list.init_by(SpinNoIrq::new(TimerList::new())));
 }
@@ -2,7 +2,8 @@ use alloc::collections::VecDeque;
 use alloc::sync::Arc;
 use spinlock::SpinRaw;
 
-use crate::{AxRunQueue, AxTaskRef, CurrentTask, RUN_QUEUE};
+use crate::run_queue::{current_run_queue, AxRunQueue};
+use crate::{AxTaskRef, CurrentTask};
 
 /// A queue to store sleeping tasks.
 ///
@@ -27,7 +28,7 @@ use crate::{AxRunQueue, AxTaskRef, CurrentTask, RUN_QUEUE};
 /// assert_eq!(VALUE.load(Ordering::Relaxed), 1);
 /// ```
 pub struct WaitQueue {
-    queue: SpinRaw<VecDeque<AxTaskRef>>, // we already disabled IRQs when lock the `RUN_QUEUE`
+    queue: SpinRaw<VecDeque<AxTaskRef>>, // we already disabled IRQs when lock the run queue
 }
 
 impl WaitQueue {
@@ -50,7 +51,7 @@ impl WaitQueue {
         // the event from another queue.
         if curr.in_wait_queue() {
             // wake up by timer (timeout).
-            // `RUN_QUEUE` is not locked here, so disable IRQs.
+            // The run queue is not locked here, so disable IRQs.
             let _guard = kernel_guard::IrqSave::new();
             self.queue.lock().retain(|t| !curr.ptr_eq(t));
             curr.set_in_wait_queue(false);
@@ -65,7 +66,7 @@ impl WaitQueue {
     /// Blocks the current task and put it into the wait queue, until other task
     /// notifies it.
     pub fn wait(&self) {
-        RUN_QUEUE.lock().block_current(|task| {
+        current_run_queue().block_current(|task| {
             task.set_in_wait_queue(true);
             self.queue.lock().push_back(task)
         });
@@ -82,13 +83,16 @@ impl WaitQueue {
         F: Fn() -> bool,
     {
         loop {
-            let mut rq = RUN_QUEUE.lock();
+            let mut rq = current_run_queue();
+            // Hold the wait queue lock until the task is pushed, in case it's
+            // notified by another CPU after the condition is checked.
+            let mut wq = self.queue.lock();
             if condition() {
                 break;
             }
-            rq.block_current(|task| {
+            rq.block_current(move |task| {
                 task.set_in_wait_queue(true);
-                self.queue.lock().push_back(task);
+                wq.push_back(task);
             });
         }
         self.cancel_events(crate::current());
@@ -107,7 +111,7 @@ impl WaitQueue {
         );
         crate::timers::set_alarm_wakeup(deadline, curr.clone());
 
-        RUN_QUEUE.lock().block_current(|task| {
+        current_run_queue().block_current(|task| {
             task.set_in_wait_queue(true);
             self.queue.lock().push_back(task)
         });
@@ -137,26 +141,135 @@ impl WaitQueue {
 
         let mut timeout = true;
         while axhal::time::current_time() < deadline {
-            let mut rq = RUN_QUEUE.lock();
+            let mut rq = current_run_queue();
+            let mut wq = self.queue.lock();
             if condition() {
                 timeout = false;
                 break;
             }
-            rq.block_current(|task| {
+            rq.block_current(move |task| {
                 task.set_in_wait_queue(true);
-                self.queue.lock().push_back(task);
+                wq.push_back(task);
             });
         }
         self.cancel_events(curr);
         timeout
     }
 
+    /// Blocks the current task and put it into the wait queue if the given
+    /// `condition` is true, until other tasks notify it, or the `deadline` is
+    /// reached (if specified, and the feature `irq` is enabled).
+    ///
+    /// The condition is checked with the wait queue locked, so the notification
+    /// right after the condition is checked won't be missed.
+    ///
+    /// Returns [`None`] without blocking if the condition is false, otherwise
+    /// returns whether it's timed out.
+    pub(crate) fn wait_if<F>(
+        &self,
+        condition: F,
+        _deadline: Option<axhal::time::TimeValue>,
+    ) -> Option<bool>
+    where
+        F: FnOnce() -> bool,
+    {
+        let curr = crate::current();
+        {
+            let mut rq = current_run_queue();
+            let mut wq = self.queue.lock();
+            if !condition() {
+                return None;
+            }
+            #[cfg(feature = "irq")]
+            if let Some(deadline) = _deadline {
+                crate::timers::set_alarm_wakeup(deadline, curr.clone());
+            }
+            rq.block_current(move |task| {
+                task.set_in_wait_queue(true);
+                wq.push_back(task);
+            });
+        }
+        let timeout = curr.in_wait_queue(); // still in the wait queue, must have timed out
+        self.cancel_events(curr);
+        Some(timeout)
+    }
+
+    /// Blocks the current task and put it into the wait queue, until the given
+    /// `condition` becomes true, the `deadline` is reached (if specified, and
+    /// the feature `irq` is enabled), or it's interrupted.
+    ///
+    /// Unlike [`WaitQueue::wait_until`], it can be woken up early by signals
+    /// or cancellation. They are not acted on here, the caller should check
+    /// [`is_interrupted`](crate::is_interrupted) and return to a point where
+    /// it holds no locks to handle them.
+    ///
+    /// Returns `true` if it's timed out.
+    pub fn wait_until_interruptible<F>(
+        &self,
+        condition: F,
+        deadline: Option<axhal::time::TimeValue>,
+    ) -> bool
+    where
+        F: Fn() -> bool,
+    {
+        let curr = crate::current();
+        #[cfg(feature = "irq")]
+        if let Some(deadline) = deadline {
+            crate::timers::set_alarm_wakeup(deadline, curr.clone());
+        }
+        let timeout = loop {
+            let mut rq = current_run_queue();
+            let mut wq = self.queue.lock();
+            if condition() {
+                break false;
+            }
+            if deadline.map_or(false, |d| axhal::time::current_time() >= d) {
+                break true;
+            }
+            if curr.has_pending_interrupt() {
+                break false;
+            }
+            // if it's still in the wait queue after woken up, it must have
+            // timed out or been interrupted, and it will leave the loop.
+            rq.block_current_interruptible(move |task| {
+                task.set_in_wait_queue(true);
+                wq.push_back(task);
+            });
+        };
+        self.cancel_events(curr);
+        timeout
+    }
+
+    /// Wakes up at most `count` tasks in the wait queue that satisfy `pred`,
+    /// in the order they were put into the queue.
+    ///
+    /// Returns the number of tasks woken up.
+    pub(crate) fn notify_matching<F>(&self, count: usize, resched: bool, pred: F) -> usize
+    where
+        F: Fn(&AxTaskRef) -> bool,
+    {
+        let mut rq = current_run_queue();
+        let mut wq = self.queue.lock();
+        let mut woken = 0;
+        wq.retain(|task| {
+            if woken < count && pred(task) {
+                woken += 1;
+                task.set_in_wait_queue(false);
+                rq.unblock_task(task.clone(), resched);
+                false
+            } else {
+                true
+            }
+        });
+        woken
+    }
+
     /// Wakes up one task in the wait queue, usually the first one.
     ///
     /// If `resched` is true, the current task will be preempted when the
     /// preemption is enabled.
     pub fn notify_one(&self, resched: bool) -> bool {
-        let mut rq = RUN_QUEUE.lock();
+        let mut rq = current_run_queue();
         if !self.queue.lock().is_empty() {
             self.notify_one_locked(resched, &mut rq)
         } else {
@@ -170,14 +283,14 @@ impl WaitQueue {
     /// preemption is enabled.
     pub fn notify_all(&self, resched: bool) {
         loop {
-            let mut rq = RUN_QUEUE.lock();
+            let mut rq = current_run_queue();
             if let Some(task) = self.queue.lock().pop_front() {
                 task.set_in_wait_queue(false);
                 rq.unblock_task(task, resched);
             } else {
                 break;
             }
-            drop(rq); // we must unlock `RUN_QUEUE` after unlocking `self.queue`.
+            drop(rq); // we must unlock the run queue after unlocking `self.queue`.
         }
     }
 
@@ -186,7 +299,7 @@ impl WaitQueue {
     /// If `resched` is true, the current task will be preempted when the
     /// preemption is enabled.
     pub fn notify_task(&mut self, resched: bool, task: &AxTaskRef) -> bool {
-        let mut rq = RUN_QUEUE.lock();
+        let mut rq = current_run_queue();
         let mut wq = self.queue.lock();
         if let Some(index) = wq.iter().position(|t| Arc::ptr_eq(t, task)) {
             task.set_in_wait_queue(false);
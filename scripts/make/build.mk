@@ -31,7 +31,12 @@ else ifeq ($(filter $(MAKECMDGOALS),clippy unittest unittest_no_fail_fast),) # n
   endif
 endif
 
-_cargo_build:
+ifneq ($(INITRAMFS),)
+  INITRAMFS_IMG := $(abspath $(OUT_DIR)/initramfs.cpio)
+  export AX_INITRAMFS=$(INITRAMFS_IMG)
+endif
+
+_cargo_build: $(INITRAMFS_IMG)
 	@printf "    $(GREEN_C)Building$(END_C) App: $(APP_NAME), Arch: $(ARCH), Platform: $(PLATFORM_NAME), App type: $(APP_TYPE)\n"
 ifeq ($(APP_TYPE), rust)
 	$(call cargo_build,--manifest-path $(APP)/Cargo.toml,$(AX_FEAT) $(LIB_FEAT) $(APP_FEAT))
@@ -46,4 +51,9 @@ $(OUT_DIR):
 $(OUT_BIN): _cargo_build $(OUT_ELF)
 	$(call run_cmd,$(OBJCOPY),$(OUT_ELF) --strip-all -O binary $@)
 
-.PHONY: _cargo_build
+ifneq ($(INITRAMFS),)
+$(INITRAMFS_IMG): $(OUT_DIR)
+	$(call make_initramfs,$(INITRAMFS),$@)
+endif
+
+.PHONY: _cargo_build $(INITRAMFS_IMG)
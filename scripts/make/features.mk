@@ -14,7 +14,7 @@
 ifeq ($(APP_TYPE),c)
   ax_feat_prefix := axfeat/
   lib_feat_prefix := axlibc/
-  lib_features := fp_simd alloc multitask fs net fd pipe select epoll
+  lib_features := fp_simd alloc mmap multitask fs net fd pipe select epoll
 else
   # TODO: it's better to use `axfeat/` as `ax_feat_prefix`, but all apps need to have `axfeat` as a dependency
   ax_feat_prefix := axstd/
@@ -33,6 +33,14 @@ ifeq ($(APP_TYPE), c)
   endif
 endif
 
+ifneq ($(INITRAMFS),)
+  override FEATURES += initramfs
+endif
+
+ifeq ($(DHCP), y)
+  override FEATURES += dhcp
+endif
+
 override FEATURES := $(strip $(FEATURES))
 
 ax_feat :=
@@ -21,3 +21,8 @@ endef
 define make_disk_image
   $(if $(filter $(1),fat32), $(call make_disk_image_fat32,$(2)))
 endef
+
+define make_initramfs
+  @printf "    $(GREEN_C)Creating$(END_C) initramfs \"$(2)\" from \"$(1)\" ...\n"
+  @cd $(1) && find . | cpio -o -H newc --quiet > $(2)
+endef
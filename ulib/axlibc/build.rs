@@ -69,6 +69,13 @@ typedef struct {{
             "epoll_event",
             "iovec",
             "tm",
+            "flock",
+            "cpu_set_t",
+            "sem_t",
+            "sigaction",
+            "sigset_t",
+            "itimerval",
+            "__ptcb",
         ];
         let allow_vars = [
             "O_.*",
@@ -77,11 +84,24 @@ typedef struct {{
             "IPPROTO_.*",
             "FD_.*",
             "F_.*",
+            "LOCK_.*",
+            "SEEK_.*",
             "_SC_.*",
             "SO_.*",
             "SOL_.*",
+            "TCP_.*",
+            "AI_.*",
             "EPOLL_CTL_.*",
             "EPOLL.*",
+            "PROT_.*",
+            "MAP_.*",
+            "MREMAP_.*",
+            "MS_.*",
+            "MADV_.*",
+            "SA_.*",
+            "SIG_.*",
+            "ITIMER_.*",
+            "PTHREAD_CANCEL_.*",
         ];
 
         #[derive(Debug)]
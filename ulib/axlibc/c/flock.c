@@ -1,9 +1,11 @@
-#include <stdio.h>
+#include <axlibc.h>
 #include <sys/file.h>
 
-// TODO
+#ifdef AX_CONFIG_FS
+
 int flock(int __fd, int __operation)
 {
-    unimplemented();
-    return 0;
+    return ax_flock(__fd, __operation);
 }
+
+#endif // AX_CONFIG_FS
@@ -1,11 +1,56 @@
+#include <axlibc.h>
+#include <stdarg.h>
 #include <stddef.h>
 #include <stdio.h>
+#include <sys/mman.h>
+
+#ifdef AX_CONFIG_MMAP
+
+void *mmap(void *addr, size_t len, int prot, int flags, int fildes, off_t off)
+{
+    return ax_mmap(addr, len, prot, flags, fildes, off);
+}
+
+int munmap(void *addr, size_t length)
+{
+    return ax_munmap(addr, length);
+}
+
+void *mremap(void *old_address, size_t old_size, size_t new_size, int flags,
+             ... /* void *new_address */)
+{
+    void *new_address = NULL;
+    if (flags & MREMAP_FIXED) {
+        va_list ap;
+        va_start(ap, flags);
+        new_address = va_arg(ap, void *);
+        va_end(ap);
+    }
+    return ax_mremap(old_address, old_size, new_size, flags, new_address);
+}
+
+int mprotect(void *addr, size_t len, int prot)
+{
+    return ax_mprotect(addr, len, prot);
+}
+
+int madvise(void *addr, size_t len, int advice)
+{
+    return ax_madvise(addr, len, advice);
+}
+
+int msync(void *addr, size_t length, int flags)
+{
+    return ax_msync(addr, length, flags);
+}
+
+#else // AX_CONFIG_MMAP
 
 // TODO:
 void *mmap(void *addr, size_t len, int prot, int flags, int fildes, off_t off)
 {
     unimplemented();
-    return NULL;
+    return MAP_FAILED;
 }
 
 // TODO:
@@ -20,7 +65,7 @@ void *mremap(void *old_address, size_t old_size, size_t new_size, int flags,
              ... /* void *new_address */)
 {
     unimplemented();
-    return NULL;
+    return MAP_FAILED;
 }
 
 // TODO
@@ -36,3 +81,12 @@ int madvise(void *addr, size_t len, int advice)
     unimplemented();
     return 0;
 }
+
+// TODO
+int msync(void *addr, size_t length, int flags)
+{
+    unimplemented();
+    return 0;
+}
+
+#endif // AX_CONFIG_MMAP
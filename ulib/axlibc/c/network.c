@@ -14,28 +14,72 @@
 
 int h_errno;
 
-/*Only IPv4. Ports are always 0. Ignore service and hint. Results' ai_flags, ai_socktype,
- * ai_protocol and ai_canonname are 0 or NULL.  */
+/* `ai_flags` (only AI_NUMERICHOST and AI_PASSIVE), `ai_family`, `ai_socktype` and `ai_protocol` in
+ * hints are used. Each address is returned for both TCP and UDP unless the socket type or the protocol
+ * is given. Results' ai_flags and ai_canonname are 0 or NULL. */
 int getaddrinfo(const char *__restrict node, const char *__restrict service,
                 const struct addrinfo *__restrict hints, struct addrinfo **__restrict res)
 {
-    struct sockaddr *addrs = (struct sockaddr *)malloc(MAXADDRS * sizeof(struct sockaddr));
-    int res_len = ax_getaddrinfo(node, service, addrs, MAXADDRS);
-    if (res_len < 0)
-        return EAI_FAIL;
-    if (res_len == 0)
-        return EAI_NONAME;
-    struct addrinfo *_res = (struct addrinfo *)calloc(res_len, sizeof(struct addrinfo));
+    static const struct {
+        int socktype;
+        int protocol;
+    } types[] = {{SOCK_STREAM, IPPROTO_TCP}, {SOCK_DGRAM, IPPROTO_UDP}};
+    const int ntypes = sizeof(types) / sizeof(types[0]);
+
+    int flags = hints ? hints->ai_flags : 0;
+    int family = hints ? hints->ai_family : AF_UNSPEC;
+    int socktype = hints ? hints->ai_socktype : 0;
+    int protocol = hints ? hints->ai_protocol : 0;
+    if (family != AF_UNSPEC && family != AF_INET && family != AF_INET6)
+        return EAI_FAMILY;
+    if (socktype != 0 && socktype != SOCK_STREAM && socktype != SOCK_DGRAM)
+        return EAI_SOCKTYPE;
+    struct sockaddr_storage *addrs =
+        (struct sockaddr_storage *)malloc(MAXADDRS * sizeof(struct sockaddr_storage));
+    int res_len = ax_getaddrinfo(node, service, flags, addrs, MAXADDRS);
+    if (res_len < 0) {
+        free(addrs);
+        switch (errno) {
+        case ENOENT:
+            return EAI_NONAME;
+        case EAGAIN:
+            return EAI_AGAIN;
+        default:
+            return EAI_FAIL;
+        }
+    }
+    struct addrinfo *_res =
+        (struct addrinfo *)calloc(res_len ? res_len * ntypes : 1, sizeof(struct addrinfo));
+    int naddrs = 0, n = 0;
     for (int i = 0; i < res_len; i++) {
-        (_res + i)->ai_family = AF_INET;
-        (_res + i)->ai_addrlen = sizeof(struct sockaddr);
-        (_res + i)->ai_addr = (addrs + i);
-        (_res + i)->ai_next = (_res + i + 1);
-        // TODO: This is a hard-code part, only return TCP parameters
-        (_res + i)->ai_socktype = SOCK_STREAM;
-        (_res + i)->ai_protocol = IPPROTO_TCP;
+        if (family != AF_UNSPEC && addrs[i].ss_family != family)
+            continue;
+        // keep the addresses contiguous, so that `freeaddrinfo` frees them all
+        if (naddrs != i)
+            addrs[naddrs] = addrs[i];
+        for (int t = 0; t < ntypes; t++) {
+            if ((socktype && socktype != types[t].socktype) ||
+                (protocol && protocol != types[t].protocol))
+                continue;
+            // the results of the same address share the `ai_addr`
+            (_res + n)->ai_family = addrs[naddrs].ss_family;
+            (_res + n)->ai_socktype = types[t].socktype;
+            (_res + n)->ai_protocol = types[t].protocol;
+            (_res + n)->ai_addrlen = addrs[naddrs].ss_family == AF_INET6
+                                         ? sizeof(struct sockaddr_in6)
+                                         : sizeof(struct sockaddr_in);
+            (_res + n)->ai_addr = (struct sockaddr *)(addrs + naddrs);
+            (_res + n)->ai_next = (_res + n + 1);
+            n++;
+        }
+        naddrs++;
+    }
+    if (n == 0) {
+        free(addrs);
+        free(_res);
+        return naddrs ? EAI_SOCKTYPE : EAI_NONAME;
     }
-    (_res + res_len - 1)->ai_next = NULL;
+    (_res + n - 1)->ai_next = NULL;
     *res = _res;
     return 0;
 }
@@ -29,28 +29,36 @@ int pthread_join(pthread_t t, void **res)
 
 int pthread_setcancelstate(int new, int *old)
 {
-    unimplemented();
-    return 0;
+    return ax_pthread_setcancelstate(new, old) < 0 ? errno : 0;
 }
 
 int pthread_setcanceltype(int new, int *old)
 {
-    unimplemented();
-    return 0;
+    return ax_pthread_setcanceltype(new, old) < 0 ? errno : 0;
 }
 
-// TODO
 void pthread_testcancel(void)
 {
-    unimplemented();
-    return;
+    ax_pthread_testcancel();
 }
 
-// TODO
 int pthread_cancel(pthread_t t)
 {
-    unimplemented();
-    return 0;
+    return ax_pthread_cancel(t) < 0 ? errno : 0;
+}
+
+void _pthread_cleanup_push(struct __ptcb *cb, void (*f)(void *), void *x)
+{
+    cb->__f = f;
+    cb->__x = x;
+    ax_pthread_cleanup_push(cb);
+}
+
+void _pthread_cleanup_pop(struct __ptcb *cb, int run)
+{
+    ax_pthread_cleanup_pop(cb);
+    if (run)
+        cb->__f(cb->__x);
 }
 
 int pthread_mutex_init(pthread_mutex_t *restrict m, const pthread_mutexattr_t *restrict a)
@@ -82,36 +90,99 @@ int pthread_setname_np(pthread_t thread, const char *name)
     return 0;
 }
 
+int pthread_setaffinity_np(pthread_t t, size_t cpusetsize, const cpu_set_t *cpuset)
+{
+    return ax_pthread_setaffinity_np(t, cpusetsize, cpuset) < 0 ? errno : 0;
+}
+
+int pthread_getaffinity_np(pthread_t t, size_t cpusetsize, cpu_set_t *cpuset)
+{
+    return ax_pthread_getaffinity_np(t, cpusetsize, cpuset) < 0 ? errno : 0;
+}
+
 int pthread_cond_init(pthread_cond_t *restrict c, const pthread_condattr_t *restrict a)
 {
-    *c = (pthread_cond_t){0};
-    if (a) {
-        c->_c_clock = a->__attr & 0x7fffffff;
-        if (a->__attr >> 31)
-            c->_c_shared = (void *)-1;
-    }
-    return 0;
+    return ax_pthread_cond_init(c, a) < 0 ? errno : 0;
 }
 
-// TODO
-int pthread_cond_signal(pthread_cond_t *__cond)
+int pthread_cond_destroy(pthread_cond_t *c)
 {
-    unimplemented();
-    return 0;
+    return ax_pthread_cond_destroy(c) < 0 ? errno : 0;
 }
 
-// TODO
-int pthread_cond_wait(pthread_cond_t *__restrict__ __cond, pthread_mutex_t *__restrict__ __mutex)
+int pthread_cond_signal(pthread_cond_t *c)
 {
-    unimplemented();
-    return 0;
+    return ax_pthread_cond_signal(c) < 0 ? errno : 0;
+}
+
+int pthread_cond_wait(pthread_cond_t *restrict c, pthread_mutex_t *restrict m)
+{
+    return ax_pthread_cond_wait(c, m) < 0 ? errno : 0;
+}
+
+int pthread_cond_timedwait(pthread_cond_t *restrict c, pthread_mutex_t *restrict m,
+                           const struct timespec *restrict ts)
+{
+    return ax_pthread_cond_timedwait(c, m, ts) < 0 ? errno : 0;
 }
 
-// TODO
 int pthread_cond_broadcast(pthread_cond_t *c)
 {
-    unimplemented();
-    return 0;
+    return ax_pthread_cond_broadcast(c) < 0 ? errno : 0;
+}
+
+int pthread_rwlock_init(pthread_rwlock_t *restrict rw, const pthread_rwlockattr_t *restrict a)
+{
+    return ax_pthread_rwlock_init(rw, a) < 0 ? errno : 0;
+}
+
+int pthread_rwlock_destroy(pthread_rwlock_t *rw)
+{
+    return ax_pthread_rwlock_destroy(rw) < 0 ? errno : 0;
+}
+
+int pthread_rwlock_rdlock(pthread_rwlock_t *rw)
+{
+    return ax_pthread_rwlock_rdlock(rw) < 0 ? errno : 0;
+}
+
+int pthread_rwlock_tryrdlock(pthread_rwlock_t *rw)
+{
+    return ax_pthread_rwlock_tryrdlock(rw) < 0 ? errno : 0;
+}
+
+int pthread_rwlock_wrlock(pthread_rwlock_t *rw)
+{
+    return ax_pthread_rwlock_wrlock(rw) < 0 ? errno : 0;
+}
+
+int pthread_rwlock_trywrlock(pthread_rwlock_t *rw)
+{
+    return ax_pthread_rwlock_trywrlock(rw) < 0 ? errno : 0;
+}
+
+int pthread_rwlock_unlock(pthread_rwlock_t *rw)
+{
+    return ax_pthread_rwlock_unlock(rw) < 0 ? errno : 0;
+}
+
+int pthread_barrier_init(pthread_barrier_t *restrict b, const pthread_barrierattr_t *restrict a,
+                         unsigned count)
+{
+    return ax_pthread_barrier_init(b, a, count) < 0 ? errno : 0;
+}
+
+int pthread_barrier_destroy(pthread_barrier_t *b)
+{
+    return ax_pthread_barrier_destroy(b) < 0 ? errno : 0;
+}
+
+int pthread_barrier_wait(pthread_barrier_t *b)
+{
+    int ret = ax_pthread_barrier_wait(b);
+    if (ret < 0)
+        return errno;
+    return ret ? PTHREAD_BARRIER_SERIAL_THREAD : 0;
 }
 
 #define DEFAULT_STACK_SIZE 131072
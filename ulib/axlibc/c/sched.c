@@ -1,9 +1,34 @@
 #include <sched.h>
 #include <stdio.h>
 
+#ifdef AX_CONFIG_MULTITASK
+
+#include <axlibc.h>
+
+int sched_setaffinity(pid_t pid, size_t cpusetsize, const cpu_set_t *cpuset)
+{
+    return ax_sched_setaffinity(pid, cpusetsize, cpuset);
+}
+
+int sched_getaffinity(pid_t pid, size_t cpusetsize, cpu_set_t *cpuset)
+{
+    return ax_sched_getaffinity(pid, cpusetsize, cpuset);
+}
+
+#else
+
 // TODO
 int sched_setaffinity(pid_t __pid, size_t __cpusetsize, const cpu_set_t *__cpuset)
 {
     unimplemented();
     return 0;
 }
+
+// TODO
+int sched_getaffinity(pid_t __pid, size_t __cpusetsize, cpu_set_t *__cpuset)
+{
+    unimplemented();
+    return 0;
+}
+
+#endif // AX_CONFIG_MULTITASK
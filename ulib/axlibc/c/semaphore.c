@@ -0,0 +1,41 @@
+#ifdef AX_CONFIG_MULTITASK
+
+#include <axlibc.h>
+#include <semaphore.h>
+
+int sem_init(sem_t *sem, int pshared, unsigned value)
+{
+    return ax_sem_init(sem, pshared, value);
+}
+
+int sem_destroy(sem_t *sem)
+{
+    return ax_sem_destroy(sem);
+}
+
+int sem_wait(sem_t *sem)
+{
+    return ax_sem_wait(sem);
+}
+
+int sem_trywait(sem_t *sem)
+{
+    return ax_sem_trywait(sem);
+}
+
+int sem_timedwait(sem_t *restrict sem, const struct timespec *restrict at)
+{
+    return ax_sem_timedwait(sem, at);
+}
+
+int sem_post(sem_t *sem)
+{
+    return ax_sem_post(sem);
+}
+
+int sem_getvalue(sem_t *restrict sem, int *restrict sval)
+{
+    return ax_sem_getvalue(sem, sval);
+}
+
+#endif // AX_CONFIG_MULTITASK
@@ -1,36 +1,59 @@
+#include <axlibc.h>
 #include <errno.h>
 #include <signal.h>
 #include <stddef.h>
 #include <stdio.h>
 
-int sigaction_helper(int signum, const struct sigaction *act, struct sigaction *oldact,
-                     size_t sigsetsize)
+#ifdef AX_CONFIG_MULTITASK
+
+int sigaction(int sig, const struct sigaction *restrict act, struct sigaction *restrict oact)
 {
-    if (signum == SIGKILL || signum == SIGSTOP)
-        return -EINVAL;
+    return ax_sigaction(sig, act, oact);
+}
 
-    if (oldact)
-        *oldact = (struct sigaction){0};
+int kill(pid_t pid, int sig)
+{
+    return ax_kill(pid, sig);
+}
 
+int raise(int sig)
+{
+    int ret = pthread_kill(pthread_self(), sig);
+    if (ret) {
+        errno = ret;
+        return -1;
+    }
     return 0;
 }
 
-void (*signal(int signum, void (*handler)(int)))(int)
+int sigprocmask(int how, const sigset_t *restrict set, sigset_t *restrict oldset)
 {
-    struct sigaction old;
-    struct sigaction act = {
-        .sa_handler = handler, .sa_flags = SA_RESTART, /* BSD signal semantics */
-    };
+    return ax_sigprocmask(how, set, oldset);
+}
 
-    if (sigaction_helper(signum, &act, &old, sizeof(sigset_t)) < 0)
-        return SIG_ERR;
+int pthread_sigmask(int how, const sigset_t *restrict set, sigset_t *restrict oldset)
+{
+    return ax_sigprocmask(how, set, oldset) < 0 ? errno : 0;
+}
 
-    return (old.sa_flags & SA_SIGINFO) ? NULL : old.sa_handler;
+int pthread_kill(pthread_t t, int sig)
+{
+    return ax_pthread_kill(t, sig) < 0 ? errno : 0;
 }
 
+#else
+
 int sigaction(int sig, const struct sigaction *restrict act, struct sigaction *restrict oact)
 {
-    return sigaction_helper(sig, act, oact, sizeof(sigset_t));
+    if (sig == SIGKILL || sig == SIGSTOP) {
+        errno = EINVAL;
+        return -1;
+    }
+
+    if (oact)
+        *oact = (struct sigaction){0};
+
+    return 0;
 }
 
 // TODO
@@ -40,6 +63,35 @@ int kill(pid_t __pid, int __sig)
     return 0;
 }
 
+// TODO
+int sigprocmask(int __how, const sigset_t *restrict __set, sigset_t *restrict __oldset)
+{
+    unimplemented();
+    return 0;
+}
+
+// TODO
+int pthread_sigmask(int __how, const sigset_t *restrict __newmask, sigset_t *restrict __oldmask)
+{
+    unimplemented();
+    return 0;
+}
+
+#endif
+
+void (*signal(int signum, void (*handler)(int)))(int)
+{
+    struct sigaction old;
+    struct sigaction act = {
+        .sa_handler = handler, .sa_flags = SA_RESTART, /* BSD signal semantics */
+    };
+
+    if (sigaction(signum, &act, &old) < 0)
+        return SIG_ERR;
+
+    return (old.sa_flags & SA_SIGINFO) ? NULL : old.sa_handler;
+}
+
 int sigemptyset(sigset_t *set)
 {
     set->__bits[0] = 0;
@@ -52,13 +104,6 @@ int sigemptyset(sigset_t *set)
     return 0;
 }
 
-// TODO
-int raise(int __sig)
-{
-    unimplemented();
-    return 0;
-}
-
 int sigaddset(sigset_t *set, int sig)
 {
     unsigned s = sig - 1;
@@ -69,19 +114,3 @@ int sigaddset(sigset_t *set, int sig)
     set->__bits[s / 8 / sizeof *set->__bits] |= 1UL << (s & (8 * sizeof *set->__bits - 1));
     return 0;
 }
-
-// TODO
-int pthread_sigmask(int __how, const sigset_t *restrict __newmask, sigset_t *restrict __oldmask)
-{
-    unimplemented();
-    return 0;
-}
-
-#ifdef AX_CONFIG_MULTITASK
-// TODO
-int pthread_kill(pthread_t t, int sig)
-{
-    unimplemented();
-    return 0;
-}
-#endif
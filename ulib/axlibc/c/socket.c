@@ -86,15 +86,12 @@ ssize_t recvfrom(int fd, void *restrict buf, size_t n, int flags, struct sockadd
 
 int getsockopt(int fd, int level, int optname, void *restrict optval, socklen_t *restrict optlen)
 {
-    unimplemented();
-    return -1;
+    return ax_getsockopt(fd, level, optname, optval, optlen);
 }
 
 int setsockopt(int fd, int level, int optname, const void *optval, socklen_t optlen)
 {
-    unimplemented("fd: %d, level: %d, optname: %d, optval: %d, optlen: %d", fd, level, optname,
-                  *(int *)optval, optlen);
-    return 0;
+    return ax_setsockopt(fd, level, optname, optval, optlen);
 }
 
 int getsockname(int sockfd, struct sockaddr *restrict addr, socklen_t *restrict addrlen)
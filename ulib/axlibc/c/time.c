@@ -195,12 +195,24 @@ void tzset()
     return;
 }
 
+#ifdef AX_CONFIG_MULTITASK
+int setitimer(int which, const struct itimerval *restrict new, struct itimerval *restrict old)
+{
+    return ax_setitimer(which, new, old);
+}
+
+int getitimer(int which, struct itimerval *curr)
+{
+    return ax_getitimer(which, curr);
+}
+#else
 // TODO
 int setitimer(int _which, const struct itimerval *restrict _new, struct itimerval *restrict _old)
 {
     unimplemented();
     return 0;
 }
+#endif
 
 // TODO
 char *ctime_r(const time_t *t, char *buf)
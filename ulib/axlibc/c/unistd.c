@@ -63,6 +63,15 @@ int usleep(unsigned useconds)
     return nanosleep(&tv, &tv);
 }
 
+#ifdef AX_CONFIG_MULTITASK
+unsigned alarm(unsigned seconds)
+{
+    struct itimerval it = {.it_value.tv_sec = seconds}, old = {0};
+    setitimer(ITIMER_REAL, &it, &old);
+    return old.it_value.tv_sec + !!old.it_value.tv_usec;
+}
+#endif
+
 long sysconf(int name)
 {
     return ax_sysconf(name);
@@ -168,6 +168,7 @@ int sigaction(int, const struct sigaction *__restrict, struct sigaction *__restr
 int sigemptyset(sigset_t *);
 int raise(int);
 int sigaddset(sigset_t *, int);
+int sigprocmask(int, const sigset_t *__restrict, sigset_t *__restrict);
 int pthread_sigmask(int, const sigset_t *__restrict, sigset_t *__restrict);
 
 int kill(pid_t, int);
@@ -47,6 +47,11 @@ pub fn close_file_like(fd: c_int) -> LinuxResult {
         .write()
         .remove(fd as usize)
         .ok_or(LinuxError::EBADF)?;
+    // closing any fd of a file releases the locks of the process on it
+    #[cfg(feature = "fs")]
+    if let Ok(file) = f.clone().into_any().downcast::<crate::file::File>() {
+        file.unlock_process_locks();
+    }
     drop(f);
     Ok(())
 }
@@ -158,7 +163,7 @@ pub unsafe extern "C" fn ax_dup3(old_fd: c_int, new_fd: c_int, flags: c_int) ->
 
 /// Fcntl implementation
 ///
-/// TODO: `SET/GET` command is ignored
+/// TODO: `SET/GET` commands of fd flags are ignored
 #[no_mangle]
 pub unsafe extern "C" fn ax_fcntl(fd: c_int, cmd: c_int, arg: usize) -> c_int {
     debug!("ax_fcntl <= fd: {} cmd: {} arg: {}", fd, cmd, arg);
@@ -173,6 +178,10 @@ pub unsafe extern "C" fn ax_fcntl(fd: c_int, cmd: c_int, arg: usize) -> c_int {
                 get_file_like(fd)?.set_nonblocking(arg & (ctypes::O_NONBLOCK as usize) > 0)?;
                 Ok(0)
             }
+            #[cfg(feature = "fs")]
+            ctypes::F_GETLK | ctypes::F_SETLK | ctypes::F_SETLKW => {
+                crate::file::fcntl_lock(fd, cmd as u32, arg as *mut ctypes::flock)
+            }
             _ => {
                 warn!("unsupported fcntl parameters: cmd {}", cmd);
                 Ok(0)
@@ -1,9 +1,9 @@
 use alloc::sync::Arc;
 use core::ffi::{c_char, c_int};
 
-use axerrno::{LinuxError, LinuxResult};
+use axerrno::{AxError, LinuxError, LinuxResult};
 use axio::{prelude::*, PollState, SeekFrom};
-use axstd::fs::OpenOptions;
+use axstd::fs::{LockKind, OpenOptions};
 use axstd::sync::Mutex;
 
 use crate::{ctypes, fd_ops::FileLike, utils::char_ptr_to_str};
@@ -19,12 +19,22 @@ impl File {
         super::fd_ops::add_file_like(Arc::new(self))
     }
 
-    fn from_fd(fd: c_int) -> LinuxResult<Arc<Self>> {
+    pub(crate) fn from_fd(fd: c_int) -> LinuxResult<Arc<Self>> {
         let f = super::fd_ops::get_file_like(fd)?;
         f.into_any()
             .downcast::<Self>()
             .map_err(|_| LinuxError::EINVAL)
     }
+
+    pub(crate) fn inner(&self) -> &Mutex<axstd::fs::File> {
+        &self.0
+    }
+
+    /// Releases the byte-range locks held by the process on the file.
+    pub(crate) fn unlock_process_locks(&self) {
+        let locker = self.0.lock().process_locker();
+        locker.unlock_range(0, u64::MAX).ok();
+    }
 }
 
 impl FileLike for File {
@@ -206,3 +216,101 @@ pub unsafe extern "C" fn ax_rename(old: *const c_char, new: *const c_char) -> c_
         Ok(0)
     })
 }
+
+/// Apply or remove an advisory lock on the whole file indicated by `fd`.
+///
+/// Return 0 if success.
+#[no_mangle]
+pub unsafe extern "C" fn ax_flock(fd: c_int, operation: c_int) -> c_int {
+    debug!("ax_flock <= {} {:#x}", fd, operation);
+    ax_call_body!(ax_flock, {
+        let file = File::from_fd(fd)?;
+        // don't block other operations on the file while waiting for the lock
+        let locker = file.0.lock().locker();
+        let op = operation as u32;
+        let wait = op & ctypes::LOCK_NB == 0;
+        match op & !ctypes::LOCK_NB {
+            ctypes::LOCK_SH => locker.lock(LockKind::Shared, wait)?,
+            ctypes::LOCK_EX => locker.lock(LockKind::Exclusive, wait)?,
+            ctypes::LOCK_UN => locker.unlock()?,
+            _ => return Err(LinuxError::EINVAL),
+        }
+        Ok(0)
+    })
+}
+
+/// Handle the `F_GETLK`, `F_SETLK` and `F_SETLKW` commands of `fcntl`.
+///
+/// Byte-range locks are owned by the process as POSIX requires, so they never
+/// conflict between threads, and closing any fd of the file releases them.
+pub(crate) fn fcntl_lock(fd: c_int, cmd: u32, flock: *mut ctypes::flock) -> LinuxResult<c_int> {
+    if flock.is_null() {
+        return Err(LinuxError::EFAULT);
+    }
+    let flock = unsafe { &mut *flock };
+    let file = File::from_fd(fd)?;
+    let mut inner = file.0.lock();
+    let base = match flock.l_whence as u32 {
+        ctypes::SEEK_SET => 0,
+        ctypes::SEEK_CUR => inner.stream_position()? as i64,
+        ctypes::SEEK_END => inner.metadata()?.len() as i64,
+        _ => return Err(LinuxError::EINVAL),
+    };
+    // don't block other operations on the file while waiting for the lock
+    let locker = inner.process_locker();
+    drop(inner);
+
+    let start = base
+        .checked_add(flock.l_start)
+        .ok_or(LinuxError::EOVERFLOW)?;
+    // a zero length means the lock extends to the end of the file, and a
+    // negative length means the range `start + len..start`
+    let (start, end) = match flock.l_len {
+        0 => (start, None),
+        len if len > 0 => (
+            start,
+            Some(start.checked_add(len).ok_or(LinuxError::EOVERFLOW)?),
+        ),
+        len => (start + len, Some(start)),
+    };
+    if start < 0 {
+        return Err(LinuxError::EINVAL);
+    }
+    let (start, end) = (start as u64, end.map_or(u64::MAX, |end| end as u64));
+
+    let kind = match flock.l_type as u32 {
+        ctypes::F_RDLCK => Some(LockKind::Shared),
+        ctypes::F_WRLCK => Some(LockKind::Exclusive),
+        ctypes::F_UNLCK => None,
+        _ => return Err(LinuxError::EINVAL),
+    };
+    match (cmd, kind) {
+        (ctypes::F_GETLK, Some(kind)) => match locker.test_lock_range(kind, start, end)? {
+            Some(lock) => {
+                flock.l_type = match lock.kind {
+                    LockKind::Shared => ctypes::F_RDLCK,
+                    LockKind::Exclusive => ctypes::F_WRLCK,
+                } as _;
+                flock.l_whence = ctypes::SEEK_SET as _;
+                flock.l_start = lock.start as _;
+                flock.l_len = if lock.end == u64::MAX {
+                    0
+                } else {
+                    (lock.end - lock.start) as _
+                };
+                flock.l_pid = -1;
+            }
+            None => flock.l_type = ctypes::F_UNLCK as _,
+        },
+        (ctypes::F_GETLK, None) => return Err(LinuxError::EINVAL),
+        (_, Some(kind)) => locker
+            .lock_range(kind, start, end, cmd == ctypes::F_SETLKW)
+            .map_err(|e| match e {
+                // the file is not opened for reading or writing as required
+                AxError::PermissionDenied => LinuxError::EBADF,
+                e => e.into(),
+            })?,
+        (_, None) => locker.unlock_range(start, end)?,
+    }
+    Ok(0)
+}
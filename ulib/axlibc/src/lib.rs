@@ -43,10 +43,18 @@ mod file;
 mod io_mpx;
 #[cfg(feature = "alloc")]
 mod malloc;
+#[cfg(feature = "mmap")]
+mod mmap;
 #[cfg(feature = "pipe")]
 mod pipe;
 #[cfg(feature = "multitask")]
 mod pthread;
+#[cfg(feature = "multitask")]
+mod sched;
+#[cfg(feature = "multitask")]
+mod semaphore;
+#[cfg(feature = "multitask")]
+mod signal;
 #[cfg(feature = "net")]
 mod socket;
 #[cfg(feature = "alloc")]
@@ -83,26 +91,63 @@ pub use self::malloc::{ax_free, ax_malloc};
 #[cfg(feature = "alloc")]
 pub use self::strftime::ax_strftime;
 
+#[cfg(feature = "mmap")]
+pub use self::mmap::{ax_madvise, ax_mmap, ax_mprotect, ax_mremap, ax_msync, ax_munmap};
+
 #[cfg(feature = "fd")]
 pub use self::fd_ops::{ax_close, ax_dup, ax_dup3, ax_fcntl, ax_fstat, ax_read, ax_write};
 #[cfg(feature = "fd")]
 pub use self::uio::ax_writev;
 
 #[cfg(feature = "fs")]
-pub use self::file::{ax_getcwd, ax_lseek, ax_lstat, ax_open, ax_stat};
+pub use self::file::{ax_flock, ax_getcwd, ax_lseek, ax_lstat, ax_open, ax_stat};
 
 #[cfg(feature = "net")]
 pub use self::socket::{
-    ax_accept, ax_bind, ax_connect, ax_getaddrinfo, ax_getpeername, ax_getsockname, ax_listen,
-    ax_recv, ax_recvfrom, ax_send, ax_sendto, ax_shutdown, ax_socket,
+    ax_accept, ax_bind, ax_connect, ax_getaddrinfo, ax_getpeername, ax_getsockname,
+    ax_getsockopt, ax_listen, ax_recv, ax_recvfrom, ax_send, ax_sendto, ax_setsockopt,
+    ax_shutdown, ax_socket,
 };
 
+#[cfg(feature = "multitask")]
+pub use self::pthread::barrier::{
+    ax_pthread_barrier_destroy, ax_pthread_barrier_init, ax_pthread_barrier_wait,
+};
+#[cfg(feature = "multitask")]
+pub use self::pthread::condvar::{
+    ax_pthread_cond_broadcast, ax_pthread_cond_destroy, ax_pthread_cond_init,
+    ax_pthread_cond_signal, ax_pthread_cond_timedwait, ax_pthread_cond_wait,
+};
 #[cfg(feature = "multitask")]
 pub use self::pthread::mutex::{
     ax_pthread_mutex_init, ax_pthread_mutex_lock, ax_pthread_mutex_unlock,
 };
 #[cfg(feature = "multitask")]
-pub use self::pthread::{ax_getpid, ax_pthread_create, ax_pthread_exit, ax_pthread_join};
+pub use self::pthread::rwlock::{
+    ax_pthread_rwlock_destroy, ax_pthread_rwlock_init, ax_pthread_rwlock_rdlock,
+    ax_pthread_rwlock_tryrdlock, ax_pthread_rwlock_trywrlock, ax_pthread_rwlock_unlock,
+    ax_pthread_rwlock_wrlock,
+};
+#[cfg(feature = "multitask")]
+pub use self::pthread::{
+    ax_getpid, ax_pthread_create, ax_pthread_exit, ax_pthread_join, ax_pthread_kill,
+};
+#[cfg(feature = "multitask")]
+pub use self::pthread::{
+    ax_pthread_cancel, ax_pthread_cleanup_pop, ax_pthread_cleanup_push,
+    ax_pthread_setcancelstate, ax_pthread_setcanceltype, ax_pthread_testcancel,
+};
+#[cfg(feature = "multitask")]
+pub use self::pthread::{ax_pthread_getaffinity_np, ax_pthread_setaffinity_np};
+#[cfg(feature = "multitask")]
+pub use self::sched::{ax_sched_getaffinity, ax_sched_setaffinity};
+#[cfg(feature = "multitask")]
+pub use self::semaphore::{
+    ax_sem_destroy, ax_sem_getvalue, ax_sem_init, ax_sem_post, ax_sem_timedwait, ax_sem_trywait,
+    ax_sem_wait,
+};
+#[cfg(feature = "multitask")]
+pub use self::signal::{ax_getitimer, ax_kill, ax_setitimer, ax_sigaction, ax_sigprocmask};
 
 #[cfg(feature = "pipe")]
 pub use self::pipe::ax_pipe;
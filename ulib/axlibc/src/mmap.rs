@@ -0,0 +1,347 @@
+//! Memory mappings.
+//!
+//! All mappings are placed in the kernel address space and populated when they
+//! are created. File-backed mappings are loaded from the file at that time,
+//! and `MAP_SHARED` ones are written back on `msync` and `munmap`.
+
+use core::ffi::{c_int, c_void};
+
+use axerrno::{LinuxError, LinuxResult};
+use axhal::mem::{VirtAddr, PAGE_SIZE_4K};
+use axmm::MappingFlags;
+
+use crate::ctypes;
+
+fn prot_to_flags(prot: c_int) -> MappingFlags {
+    let prot = prot as u32;
+    let mut flags = MappingFlags::empty();
+    if prot & ctypes::PROT_READ != 0 {
+        flags |= MappingFlags::READ;
+    }
+    if prot & ctypes::PROT_WRITE != 0 {
+        flags |= MappingFlags::WRITE;
+    }
+    if prot & ctypes::PROT_EXEC != 0 {
+        flags |= MappingFlags::EXECUTE;
+    }
+    flags
+}
+
+const fn is_aligned(n: usize) -> bool {
+    n % PAGE_SIZE_4K == 0
+}
+
+#[cfg(feature = "fs")]
+mod file_backed {
+    use alloc::{collections::BTreeMap, sync::Arc, vec};
+    use axerrno::LinuxResult;
+    use axhal::mem::{VirtAddr, PAGE_SIZE_4K};
+    use axio::{prelude::*, SeekFrom};
+    use axstd::sync::Mutex;
+
+    use crate::file::File;
+
+    /// A `MAP_SHARED` file-backed mapping.
+    pub struct SharedMapping {
+        file: Arc<File>,
+        offset: u64,
+        len: usize,
+    }
+
+    /// All `MAP_SHARED` file-backed mappings, indexed by the start address.
+    static SHARED_MAPPINGS: Mutex<BTreeMap<usize, SharedMapping>> = Mutex::new(BTreeMap::new());
+
+    /// Reads the file at `offset` into the mapping at `start`, stops at the
+    /// end of the file.
+    pub fn load(file: &File, offset: u64, start: usize, len: usize) -> LinuxResult {
+        let mut file = file.inner().lock();
+        let pos = file.stream_position()?;
+        file.seek(SeekFrom::Start(offset))?;
+        let mut buf = vec![0; PAGE_SIZE_4K];
+        let mut loaded = 0;
+        let res = (|| -> LinuxResult {
+            while loaded < len {
+                let n = file.read(&mut buf[..PAGE_SIZE_4K.min(len - loaded)])?;
+                if n == 0 {
+                    break;
+                }
+                axmm::write_region(VirtAddr::from(start + loaded), &buf[..n])?;
+                loaded += n;
+            }
+            Ok(())
+        })();
+        file.seek(SeekFrom::Start(pos))?;
+        res
+    }
+
+    /// Writes the mapping at `start` back to the file at `offset`, stops at
+    /// the end of the file.
+    fn store(file: &File, offset: u64, start: usize, len: usize) -> LinuxResult {
+        let mut file = file.inner().lock();
+        let size = file.metadata()?.len();
+        let len = len.min(size.saturating_sub(offset) as usize);
+        let pos = file.stream_position()?;
+        file.seek(SeekFrom::Start(offset))?;
+        let mut buf = vec![0; PAGE_SIZE_4K];
+        let mut stored = 0;
+        let res = (|| -> LinuxResult {
+            while stored < len {
+                let n = PAGE_SIZE_4K.min(len - stored);
+                axmm::read_region(VirtAddr::from(start + stored), &mut buf[..n])?;
+                file.write_all(&buf[..n])?;
+                stored += n;
+            }
+            file.flush()?;
+            Ok(())
+        })();
+        file.seek(SeekFrom::Start(pos))?;
+        res
+    }
+
+    pub fn add(start: usize, len: usize, file: Arc<File>, offset: u64) {
+        SHARED_MAPPINGS
+            .lock()
+            .insert(start, SharedMapping { file, offset, len });
+    }
+
+    /// Writes back the mappings in `start..start + len`.
+    pub fn sync(start: usize, len: usize) -> LinuxResult {
+        let end = start + len;
+        let mappings = SHARED_MAPPINGS.lock();
+        for (&m_start, m) in mappings.range(..end) {
+            let m_end = m_start + m.len;
+            if m_end <= start {
+                continue;
+            }
+            let (s, e) = (m_start.max(start), m_end.min(end));
+            store(&m.file, m.offset + (s - m_start) as u64, s, e - s)?;
+        }
+        Ok(())
+    }
+
+    /// Writes back and removes the mappings in `start..start + len`, splits
+    /// the mappings if needed.
+    pub fn remove(start: usize, len: usize) -> LinuxResult {
+        sync(start, len)?;
+        let end = start + len;
+        let mut mappings = SHARED_MAPPINGS.lock();
+        let overlapped = mappings
+            .range(..end)
+            .filter(|(&m_start, m)| m_start + m.len > start)
+            .map(|(&m_start, _)| m_start)
+            .collect::<alloc::vec::Vec<_>>();
+        for m_start in overlapped {
+            let m = mappings.remove(&m_start).unwrap();
+            let m_end = m_start + m.len;
+            if m_start < start {
+                mappings.insert(
+                    m_start,
+                    SharedMapping {
+                        file: m.file.clone(),
+                        offset: m.offset,
+                        len: start - m_start,
+                    },
+                );
+            }
+            if m_end > end {
+                mappings.insert(
+                    end,
+                    SharedMapping {
+                        file: m.file,
+                        offset: m.offset + (end - m_start) as u64,
+                        len: m_end - end,
+                    },
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Updates the mapping at `old_start` after it's moved or resized.
+    pub fn remap(old_start: usize, new_start: usize, new_len: usize) {
+        let mut mappings = SHARED_MAPPINGS.lock();
+        if let Some(mut m) = mappings.remove(&old_start) {
+            m.len = new_len;
+            mappings.insert(new_start, m);
+        }
+    }
+}
+
+fn do_mmap(
+    addr: *mut c_void,
+    len: usize,
+    prot: c_int,
+    flags: c_int,
+    fd: c_int,
+    off: ctypes::off_t,
+) -> LinuxResult<*mut c_void> {
+    let map_flags = flags as u32;
+    let map_type = map_flags & ctypes::MAP_TYPE;
+    if len == 0 || (map_type != ctypes::MAP_SHARED && map_type != ctypes::MAP_PRIVATE) {
+        return Err(LinuxError::EINVAL);
+    }
+    let fixed = map_flags & ctypes::MAP_FIXED != 0;
+    let hint = (!addr.is_null()).then(|| VirtAddr::from(addr as usize));
+    if fixed {
+        if addr.is_null() || !is_aligned(addr as usize) {
+            return Err(LinuxError::EINVAL);
+        }
+        // the old mappings are replaced
+        #[cfg(feature = "fs")]
+        file_backed::remove(addr as usize, len)?;
+    }
+
+    if map_flags & ctypes::MAP_ANONYMOUS != 0 {
+        let start = axmm::map_anonymous(hint, len, prot_to_flags(prot), fixed)?;
+        return Ok(start.as_usize() as _);
+    }
+
+    #[cfg(feature = "fs")]
+    {
+        if off < 0 || !is_aligned(off as usize) {
+            return Err(LinuxError::EINVAL);
+        }
+        let file = crate::file::File::from_fd(fd)?;
+        let start = axmm::map_anonymous(hint, len, prot_to_flags(prot), fixed)?;
+        let start = start.as_usize();
+        let res = file_backed::load(&file, off as u64, start, len);
+        if let Err(e) = res {
+            axmm::unmap_region(start.into(), len)?;
+            return Err(e);
+        }
+        if map_type == ctypes::MAP_SHARED {
+            file_backed::add(start, len, file, off as u64);
+        }
+        Ok(start as _)
+    }
+    #[cfg(not(feature = "fs"))]
+    {
+        let _ = (fd, off);
+        Err(LinuxError::ENODEV)
+    }
+}
+
+/// Create a new mapping of `len` bytes.
+///
+/// Return the start address of the mapping, or `MAP_FAILED` on error.
+#[no_mangle]
+pub unsafe extern "C" fn ax_mmap(
+    addr: *mut c_void,
+    len: usize,
+    prot: c_int,
+    flags: c_int,
+    fd: c_int,
+    off: ctypes::off_t,
+) -> *mut c_void {
+    debug!(
+        "ax_mmap <= {:#x} {:#x} {:#x} {:#x} {} {}",
+        addr as usize, len, prot, flags, fd, off
+    );
+    ax_call_body!(ax_mmap, do_mmap(addr, len, prot, flags, fd, off))
+}
+
+/// Remove the mappings in the range `[addr, addr + len)`.
+///
+/// `MAP_SHARED` file-backed mappings are written back first.
+#[no_mangle]
+pub unsafe extern "C" fn ax_munmap(addr: *mut c_void, len: usize) -> c_int {
+    debug!("ax_munmap <= {:#x} {:#x}", addr as usize, len);
+    ax_call_body!(ax_munmap, {
+        if !is_aligned(addr as usize) || len == 0 {
+            return Err(LinuxError::EINVAL);
+        }
+        #[cfg(feature = "fs")]
+        file_backed::remove(addr as usize, len)?;
+        axmm::unmap_region(VirtAddr::from(addr as usize), len)?;
+        Ok(0)
+    })
+}
+
+/// Expand or shrink the mapping at `old_addr`, possibly moving it.
+///
+/// The removed part of a `MAP_SHARED` file-backed mapping is written back
+/// before shrinking.
+///
+/// `MREMAP_FIXED` is not supported, `new_addr` is ignored.
+#[no_mangle]
+pub unsafe extern "C" fn ax_mremap(
+    old_addr: *mut c_void,
+    old_size: usize,
+    new_size: usize,
+    flags: c_int,
+    new_addr: *mut c_void,
+) -> *mut c_void {
+    debug!(
+        "ax_mremap <= {:#x} {:#x} {:#x} {:#x} {:#x}",
+        old_addr as usize, old_size, new_size, flags, new_addr as usize
+    );
+    ax_call_body!(ax_mremap, {
+        let flags = flags as u32;
+        if !is_aligned(old_addr as usize) || flags & ctypes::MREMAP_FIXED != 0 {
+            return Err(LinuxError::EINVAL);
+        }
+        let may_move = flags & ctypes::MREMAP_MAYMOVE != 0;
+        // write back the part to be removed
+        #[cfg(feature = "fs")]
+        if new_size < old_size {
+            file_backed::sync(old_addr as usize + new_size, old_size - new_size)?;
+        }
+        let new_start = axmm::remap_region(
+            VirtAddr::from(old_addr as usize),
+            old_size,
+            new_size,
+            may_move,
+        )?;
+        #[cfg(feature = "fs")]
+        file_backed::remap(old_addr as usize, new_start.as_usize(), new_size);
+        Ok(new_start.as_usize() as *mut c_void)
+    })
+}
+
+/// Change the access protections of the mappings in `[addr, addr + len)`.
+#[no_mangle]
+pub unsafe extern "C" fn ax_mprotect(addr: *mut c_void, len: usize, prot: c_int) -> c_int {
+    debug!("ax_mprotect <= {:#x} {:#x} {:#x}", addr as usize, len, prot);
+    ax_call_body!(ax_mprotect, {
+        if !is_aligned(addr as usize) {
+            return Err(LinuxError::EINVAL);
+        }
+        axmm::protect_region(VirtAddr::from(addr as usize), len, prot_to_flags(prot))?;
+        Ok(0)
+    })
+}
+
+/// Write back the `MAP_SHARED` file-backed mappings in `[addr, addr + len)`.
+#[no_mangle]
+pub unsafe extern "C" fn ax_msync(addr: *mut c_void, len: usize, flags: c_int) -> c_int {
+    debug!("ax_msync <= {:#x} {:#x} {:#x}", addr as usize, len, flags);
+    ax_call_body!(ax_msync, {
+        if !is_aligned(addr as usize) {
+            return Err(LinuxError::EINVAL);
+        }
+        #[cfg(feature = "fs")]
+        file_backed::sync(addr as usize, len)?;
+        Ok(0)
+    })
+}
+
+/// Give advice about the use of the memory in `[addr, addr + len)`.
+///
+/// Only `MADV_DONTNEED` takes effect, which fills the memory with zeros.
+#[no_mangle]
+pub unsafe extern "C" fn ax_madvise(addr: *mut c_void, len: usize, advice: c_int) -> c_int {
+    debug!("ax_madvise <= {:#x} {:#x} {}", addr as usize, len, advice);
+    ax_call_body!(ax_madvise, {
+        if !is_aligned(addr as usize) {
+            return Err(LinuxError::EINVAL);
+        }
+        if advice as u32 == ctypes::MADV_DONTNEED {
+            let zeros = [0; PAGE_SIZE_4K];
+            for off in (0..len).step_by(PAGE_SIZE_4K) {
+                let n = PAGE_SIZE_4K.min(len - off);
+                axmm::write_region(VirtAddr::from(addr as usize + off), &zeros[..n])?;
+            }
+        }
+        Ok(0)
+    })
+}
@@ -0,0 +1,61 @@
+use crate::{ctypes, utils::check_null_mut_ptr};
+use axerrno::LinuxError;
+use axstd::sync::Barrier;
+use core::ffi::{c_int, c_uint};
+use core::mem::size_of;
+
+static_assertions::const_assert!(
+    size_of::<PthreadBarrier>() <= size_of::<ctypes::pthread_barrier_t>()
+);
+
+#[repr(C)]
+pub struct PthreadBarrier(Barrier);
+
+/// Initialize a barrier for `count` threads.
+#[no_mangle]
+pub unsafe extern "C" fn ax_pthread_barrier_init(
+    barrier: *mut ctypes::pthread_barrier_t,
+    _attr: *const ctypes::pthread_barrierattr_t,
+    count: c_uint,
+) -> c_int {
+    debug!(
+        "ax_pthread_barrier_init <= {:#x}, {}",
+        barrier as usize, count
+    );
+    ax_call_body!(ax_pthread_barrier_init, {
+        check_null_mut_ptr(barrier)?;
+        if count == 0 {
+            return Err(LinuxError::EINVAL);
+        }
+        barrier
+            .cast::<PthreadBarrier>()
+            .write(PthreadBarrier(Barrier::new(count as usize)));
+        Ok(0)
+    })
+}
+
+/// Destroy a barrier.
+#[no_mangle]
+pub unsafe extern "C" fn ax_pthread_barrier_destroy(
+    barrier: *mut ctypes::pthread_barrier_t,
+) -> c_int {
+    debug!("ax_pthread_barrier_destroy <= {:#x}", barrier as usize);
+    ax_call_body!(ax_pthread_barrier_destroy, {
+        check_null_mut_ptr(barrier)?;
+        barrier.cast::<PthreadBarrier>().drop_in_place();
+        Ok(0)
+    })
+}
+
+/// Wait on a barrier until all threads arrive.
+///
+/// Returns 1 in one of the threads, and 0 in others.
+#[no_mangle]
+pub unsafe extern "C" fn ax_pthread_barrier_wait(barrier: *mut ctypes::pthread_barrier_t) -> c_int {
+    debug!("ax_pthread_barrier_wait <= {:#x}", barrier as usize);
+    ax_call_body!(ax_pthread_barrier_wait, {
+        check_null_mut_ptr(barrier)?;
+        let res = (*barrier.cast::<PthreadBarrier>()).0.wait();
+        Ok(res.is_leader() as c_int)
+    })
+}
@@ -0,0 +1,127 @@
+use crate::ctypes;
+use crate::utils::{check_null_mut_ptr, timeout_from_abstime};
+use axerrno::{LinuxError, LinuxResult};
+use axstd::sync::Condvar;
+use core::ffi::c_int;
+use core::mem::{size_of, ManuallyDrop};
+use core::time::Duration;
+
+use super::mutex::PthreadMutex;
+
+static_assertions::const_assert!(size_of::<PthreadCond>() <= size_of::<ctypes::pthread_cond_t>());
+
+#[repr(C)]
+pub struct PthreadCond(Condvar);
+
+impl PthreadCond {
+    const fn new() -> Self {
+        Self(Condvar::new())
+    }
+
+    /// Waits with the mutex unlocked, which is locked again before returning.
+    fn wait(&self, mutex: &PthreadMutex, timeout: Option<Duration>) -> LinuxResult {
+        // The mutex is locked by the caller without a guard.
+        let guard = unsafe { mutex.0.make_guard_unchecked() };
+        let (guard, timed_out) = match timeout {
+            Some(dur) => {
+                let (guard, res) = self.0.wait_timeout(guard, dur);
+                (guard, res.timed_out())
+            }
+            None => (self.0.wait(guard), false),
+        };
+        let _guard = ManuallyDrop::new(guard);
+        if timed_out {
+            Err(LinuxError::ETIMEDOUT)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Initialize a condition variable.
+#[no_mangle]
+pub unsafe extern "C" fn ax_pthread_cond_init(
+    cond: *mut ctypes::pthread_cond_t,
+    _attr: *const ctypes::pthread_condattr_t,
+) -> c_int {
+    debug!("ax_pthread_cond_init <= {:#x}", cond as usize);
+    ax_call_body!(ax_pthread_cond_init, {
+        check_null_mut_ptr(cond)?;
+        cond.cast::<PthreadCond>().write(PthreadCond::new());
+        Ok(0)
+    })
+}
+
+/// Destroy a condition variable.
+#[no_mangle]
+pub unsafe extern "C" fn ax_pthread_cond_destroy(cond: *mut ctypes::pthread_cond_t) -> c_int {
+    debug!("ax_pthread_cond_destroy <= {:#x}", cond as usize);
+    ax_call_body!(ax_pthread_cond_destroy, {
+        check_null_mut_ptr(cond)?;
+        cond.cast::<PthreadCond>().drop_in_place();
+        Ok(0)
+    })
+}
+
+/// Wait on a condition variable with the given mutex locked.
+#[no_mangle]
+pub unsafe extern "C" fn ax_pthread_cond_wait(
+    cond: *mut ctypes::pthread_cond_t,
+    mutex: *mut ctypes::pthread_mutex_t,
+) -> c_int {
+    debug!(
+        "ax_pthread_cond_wait <= {:#x}, {:#x}",
+        cond as usize, mutex as usize
+    );
+    ax_call_body!(ax_pthread_cond_wait, {
+        axtask::testcancel();
+        check_null_mut_ptr(cond)?;
+        check_null_mut_ptr(mutex)?;
+        (*cond.cast::<PthreadCond>()).wait(&*mutex.cast::<PthreadMutex>(), None)?;
+        Ok(0)
+    })
+}
+
+/// Wait on a condition variable with the given mutex locked, until the
+/// absolute time `abstime` since boot.
+#[no_mangle]
+pub unsafe extern "C" fn ax_pthread_cond_timedwait(
+    cond: *mut ctypes::pthread_cond_t,
+    mutex: *mut ctypes::pthread_mutex_t,
+    abstime: *const ctypes::timespec,
+) -> c_int {
+    debug!(
+        "ax_pthread_cond_timedwait <= {:#x}, {:#x}",
+        cond as usize, mutex as usize
+    );
+    ax_call_body!(ax_pthread_cond_timedwait, {
+        axtask::testcancel();
+        check_null_mut_ptr(cond)?;
+        check_null_mut_ptr(mutex)?;
+        let timeout = timeout_from_abstime(abstime)?;
+        (*cond.cast::<PthreadCond>()).wait(&*mutex.cast::<PthreadMutex>(), Some(timeout))?;
+        Ok(0)
+    })
+}
+
+/// Wake up one task waiting on the condition variable.
+#[no_mangle]
+pub unsafe extern "C" fn ax_pthread_cond_signal(cond: *mut ctypes::pthread_cond_t) -> c_int {
+    debug!("ax_pthread_cond_signal <= {:#x}", cond as usize);
+    ax_call_body!(ax_pthread_cond_signal, {
+        check_null_mut_ptr(cond)?;
+        (*cond.cast::<PthreadCond>()).0.notify_one();
+        Ok(0)
+    })
+}
+
+/// Wake up all tasks waiting on the condition variable.
+#[no_mangle]
+pub unsafe extern "C" fn ax_pthread_cond_broadcast(cond: *mut ctypes::pthread_cond_t) -> c_int {
+    debug!("ax_pthread_cond_broadcast <= {:#x}", cond as usize);
+    ax_call_body!(ax_pthread_cond_broadcast, {
+        check_null_mut_ptr(cond)?;
+        (*cond.cast::<PthreadCond>()).0.notify_all();
+        Ok(0)
+    })
+}
@@ -1,6 +1,7 @@
 use alloc::{boxed::Box, collections::BTreeMap, sync::Arc};
 use core::cell::UnsafeCell;
 use core::ffi::{c_int, c_void};
+use core::sync::atomic::{AtomicI32, AtomicPtr, Ordering};
 
 use axerrno::{LinuxError, LinuxResult};
 use axtask::AxTaskRef;
@@ -8,7 +9,13 @@ use spin::RwLock;
 
 use super::ctypes;
 
+pub mod barrier;
+pub mod condvar;
 pub mod mutex;
+pub mod rwlock;
+
+/// The return value of canceled threads, same as `PTHREAD_CANCELED` in C.
+const PTHREAD_CANCELED: *mut c_void = -1isize as _;
 
 lazy_static::lazy_static! {
     static ref TID_TO_PTHREAD: RwLock<BTreeMap<u64, ForceSendSync<ctypes::pthread_t>>> = {
@@ -20,6 +27,8 @@ lazy_static::lazy_static! {
             retval: Arc::new(Packet {
                 result: UnsafeCell::new(core::ptr::null_mut()),
             }),
+            cleanup: AtomicPtr::new(core::ptr::null_mut()),
+            cancel_type: AtomicI32::new(ctypes::PTHREAD_CANCEL_DEFERRED as _),
         };
         let ptr = Box::into_raw(Box::new(main_thread)) as *mut c_void;
         map.insert(main_tid, ForceSendSync(ptr));
@@ -37,6 +46,9 @@ unsafe impl<T> Sync for Packet<T> {}
 pub struct Pthread {
     inner: AxTaskRef,
     retval: Arc<Packet<*mut c_void>>,
+    /// The most recently pushed cleanup handler.
+    cleanup: AtomicPtr<ctypes::__ptcb>,
+    cancel_type: AtomicI32,
 }
 
 impl Pthread {
@@ -64,6 +76,8 @@ impl Pthread {
         let thread = Pthread {
             inner: task_inner,
             retval: my_packet,
+            cleanup: AtomicPtr::new(core::ptr::null_mut()),
+            cancel_type: AtomicI32::new(ctypes::PTHREAD_CANCEL_DEFERRED as _),
         };
         let ptr = Box::into_raw(Box::new(thread)) as *mut c_void;
         TID_TO_PTHREAD.write().insert(tid, ForceSendSync(ptr));
@@ -82,12 +96,44 @@ impl Pthread {
         unsafe { core::ptr::NonNull::new(Self::current_ptr()).map(|ptr| ptr.as_ref()) }
     }
 
+    /// Returns the task of the thread with the given ID.
+    pub(crate) fn task_by_tid(tid: u64) -> Option<AxTaskRef> {
+        TID_TO_PTHREAD
+            .read()
+            .get(&tid)
+            .map(|ptr| unsafe { &*(ptr.0 as *const Pthread) }.inner.clone())
+    }
+
+    /// Pops and runs all the cleanup handlers, the most recently pushed first.
+    fn run_cleanup(&self) {
+        loop {
+            let cb = self.cleanup.load(Ordering::Acquire);
+            if cb.is_null() {
+                break;
+            }
+            let cb = unsafe { &*cb };
+            self.cleanup.store(cb.__next, Ordering::Release);
+            if let Some(f) = cb.__f {
+                unsafe { f(cb.__x) };
+            }
+        }
+    }
+
     fn exit_current(retval: *mut c_void) -> ! {
         let thread = Self::current().expect("fail to get current thread");
+        thread.run_cleanup();
         unsafe { *thread.retval.result.get() = retval };
         axtask::exit(0);
     }
 
+    /// Called by the canceled thread before it exits.
+    fn on_cancel() {
+        if let Some(thread) = Self::current() {
+            thread.run_cleanup();
+            unsafe { *thread.retval.result.get() = PTHREAD_CANCELED };
+        }
+    }
+
     fn join(ptr: ctypes::pthread_t) -> LinuxResult<*mut c_void> {
         if core::ptr::eq(ptr, Self::current_ptr() as _) {
             return Err(LinuxError::EDEADLK);
@@ -155,6 +201,7 @@ pub unsafe extern "C" fn ax_pthread_join(
 ) -> c_int {
     debug!("ax_pthread_join <= {:#x}", retval as usize);
     ax_call_body!(ax_pthread_join, {
+        axtask::testcancel();
         let ret = Pthread::join(thread)?;
         if !retval.is_null() {
             unsafe { core::ptr::write(retval, ret) };
@@ -163,6 +210,135 @@ pub unsafe extern "C" fn ax_pthread_join(
     })
 }
 
+/// Send the signal `sig` to the given thread.
+#[no_mangle]
+pub unsafe extern "C" fn ax_pthread_kill(thread: ctypes::pthread_t, sig: c_int) -> c_int {
+    debug!("ax_pthread_kill <= {:#x}, {}", thread as usize, sig);
+    ax_call_body!(ax_pthread_kill, {
+        let thread = unsafe { &*(thread as *const Pthread) };
+        crate::signal::send_signal(&thread.inner, sig)?;
+        Ok(0)
+    })
+}
+
+/// Request cancellation of the given thread.
+///
+/// Only deferred cancellation is supported: the thread is canceled when it
+/// reaches a cancellation point, such as `pthread_testcancel`, `sleep`,
+/// `pthread_join`, `pthread_cond_wait`, `sem_wait`, or blocking socket calls.
+#[no_mangle]
+pub unsafe extern "C" fn ax_pthread_cancel(thread: ctypes::pthread_t) -> c_int {
+    debug!("ax_pthread_cancel <= {:#x}", thread as usize);
+    ax_call_body!(ax_pthread_cancel, {
+        let thread = unsafe { &*(thread as *const Pthread) };
+        axtask::set_cancel_handler(Pthread::on_cancel);
+        axtask::cancel(&thread.inner);
+        Ok(0)
+    })
+}
+
+/// Enable or disable cancellation of the current thread.
+#[no_mangle]
+pub unsafe extern "C" fn ax_pthread_setcancelstate(state: c_int, oldstate: *mut c_int) -> c_int {
+    debug!("ax_pthread_setcancelstate <= {}", state);
+    ax_call_body!(ax_pthread_setcancelstate, {
+        let enabled = match state as u32 {
+            ctypes::PTHREAD_CANCEL_ENABLE => true,
+            ctypes::PTHREAD_CANCEL_DISABLE => false,
+            _ => return Err(LinuxError::EINVAL),
+        };
+        let old = axtask::set_cancel_enabled(enabled);
+        if !oldstate.is_null() {
+            let old = if old {
+                ctypes::PTHREAD_CANCEL_ENABLE
+            } else {
+                ctypes::PTHREAD_CANCEL_DISABLE
+            };
+            unsafe { *oldstate = old as c_int };
+        }
+        Ok(0)
+    })
+}
+
+/// Set the cancellation type of the current thread.
+///
+/// Asynchronous cancellation is not supported, threads with this type are
+/// still canceled at cancellation points.
+#[no_mangle]
+pub unsafe extern "C" fn ax_pthread_setcanceltype(ty: c_int, oldtype: *mut c_int) -> c_int {
+    debug!("ax_pthread_setcanceltype <= {}", ty);
+    ax_call_body!(ax_pthread_setcanceltype, {
+        if !matches!(
+            ty as u32,
+            ctypes::PTHREAD_CANCEL_DEFERRED | ctypes::PTHREAD_CANCEL_ASYNCHRONOUS
+        ) {
+            return Err(LinuxError::EINVAL);
+        }
+        let thread = Pthread::current().ok_or(LinuxError::ESRCH)?;
+        let old = thread.cancel_type.swap(ty, Ordering::AcqRel);
+        if !oldtype.is_null() {
+            unsafe { *oldtype = old };
+        }
+        Ok(0)
+    })
+}
+
+/// A cancellation point: exits the current thread if it's requested to be
+/// canceled and cancellation is enabled.
+#[no_mangle]
+pub unsafe extern "C" fn ax_pthread_testcancel() {
+    axtask::testcancel();
+}
+
+/// Push the cleanup handler `cb` of the current thread, which is filled in by
+/// the caller.
+#[no_mangle]
+pub unsafe extern "C" fn ax_pthread_cleanup_push(cb: *mut ctypes::__ptcb) {
+    let thread = Pthread::current().expect("fail to get current thread");
+    unsafe { (*cb).__next = thread.cleanup.load(Ordering::Acquire) };
+    thread.cleanup.store(cb, Ordering::Release);
+}
+
+/// Pop the cleanup handler `cb` of the current thread without running it.
+#[no_mangle]
+pub unsafe extern "C" fn ax_pthread_cleanup_pop(cb: *mut ctypes::__ptcb) {
+    let thread = Pthread::current().expect("fail to get current thread");
+    thread
+        .cleanup
+        .store(unsafe { (*cb).__next }, Ordering::Release);
+}
+
+/// Set the CPU affinity mask of the given thread.
+#[no_mangle]
+pub unsafe extern "C" fn ax_pthread_setaffinity_np(
+    thread: ctypes::pthread_t,
+    cpusetsize: usize,
+    cpuset: *const ctypes::cpu_set_t,
+) -> c_int {
+    debug!("ax_pthread_setaffinity_np <= {:#x}", thread as usize);
+    ax_call_body!(ax_pthread_setaffinity_np, {
+        let cpumask = crate::sched::cpuset_to_mask(cpusetsize, cpuset)?;
+        let thread = unsafe { &*(thread as *const Pthread) };
+        axtask::set_affinity(&thread.inner, cpumask);
+        Ok(0)
+    })
+}
+
+/// Get the CPU affinity mask of the given thread.
+#[no_mangle]
+pub unsafe extern "C" fn ax_pthread_getaffinity_np(
+    thread: ctypes::pthread_t,
+    cpusetsize: usize,
+    cpuset: *mut ctypes::cpu_set_t,
+) -> c_int {
+    debug!("ax_pthread_getaffinity_np <= {:#x}", thread as usize);
+    ax_call_body!(ax_pthread_getaffinity_np, {
+        let thread = unsafe { &*(thread as *const Pthread) };
+        crate::sched::mask_to_cpuset(thread.inner.cpumask(), cpusetsize, cpuset)?;
+        Ok(0)
+    })
+}
+
 #[derive(Clone, Copy)]
 struct ForceSendSync<T>(T);
 
@@ -0,0 +1,133 @@
+use crate::{ctypes, utils::check_null_mut_ptr};
+use axerrno::{LinuxError, LinuxResult};
+use axstd::sync::RwLock;
+use core::ffi::c_int;
+use core::mem::{size_of, ManuallyDrop};
+
+static_assertions::const_assert!(
+    size_of::<PthreadRwLock>() <= size_of::<ctypes::pthread_rwlock_t>()
+);
+
+#[repr(C)]
+pub struct PthreadRwLock(RwLock<()>);
+
+impl PthreadRwLock {
+    const fn new() -> Self {
+        Self(RwLock::new(()))
+    }
+
+    fn rdlock(&self) -> LinuxResult {
+        let _guard = ManuallyDrop::new(self.0.read());
+        Ok(())
+    }
+
+    fn tryrdlock(&self) -> LinuxResult {
+        let guard = self.0.try_read().ok_or(LinuxError::EBUSY)?;
+        let _guard = ManuallyDrop::new(guard);
+        Ok(())
+    }
+
+    fn wrlock(&self) -> LinuxResult {
+        let _guard = ManuallyDrop::new(self.0.write());
+        Ok(())
+    }
+
+    fn trywrlock(&self) -> LinuxResult {
+        let guard = self.0.try_write().ok_or(LinuxError::EBUSY)?;
+        let _guard = ManuallyDrop::new(guard);
+        Ok(())
+    }
+
+    fn unlock(&self) -> LinuxResult {
+        if self.0.is_locked_exclusive() {
+            unsafe { self.0.force_write_unlock() };
+        } else {
+            unsafe { self.0.force_read_unlock() };
+        }
+        Ok(())
+    }
+}
+
+/// Initialize a reader-writer lock.
+#[no_mangle]
+pub unsafe extern "C" fn ax_pthread_rwlock_init(
+    rwlock: *mut ctypes::pthread_rwlock_t,
+    _attr: *const ctypes::pthread_rwlockattr_t,
+) -> c_int {
+    debug!("ax_pthread_rwlock_init <= {:#x}", rwlock as usize);
+    ax_call_body!(ax_pthread_rwlock_init, {
+        check_null_mut_ptr(rwlock)?;
+        rwlock.cast::<PthreadRwLock>().write(PthreadRwLock::new());
+        Ok(0)
+    })
+}
+
+/// Destroy a reader-writer lock.
+#[no_mangle]
+pub unsafe extern "C" fn ax_pthread_rwlock_destroy(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    debug!("ax_pthread_rwlock_destroy <= {:#x}", rwlock as usize);
+    ax_call_body!(ax_pthread_rwlock_destroy, {
+        check_null_mut_ptr(rwlock)?;
+        rwlock.cast::<PthreadRwLock>().drop_in_place();
+        Ok(0)
+    })
+}
+
+/// Lock the given reader-writer lock for reading.
+#[no_mangle]
+pub unsafe extern "C" fn ax_pthread_rwlock_rdlock(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    debug!("ax_pthread_rwlock_rdlock <= {:#x}", rwlock as usize);
+    ax_call_body!(ax_pthread_rwlock_rdlock, {
+        check_null_mut_ptr(rwlock)?;
+        (*rwlock.cast::<PthreadRwLock>()).rdlock()?;
+        Ok(0)
+    })
+}
+
+/// Try to lock the given reader-writer lock for reading without blocking.
+#[no_mangle]
+pub unsafe extern "C" fn ax_pthread_rwlock_tryrdlock(
+    rwlock: *mut ctypes::pthread_rwlock_t,
+) -> c_int {
+    debug!("ax_pthread_rwlock_tryrdlock <= {:#x}", rwlock as usize);
+    ax_call_body!(ax_pthread_rwlock_tryrdlock, {
+        check_null_mut_ptr(rwlock)?;
+        (*rwlock.cast::<PthreadRwLock>()).tryrdlock()?;
+        Ok(0)
+    })
+}
+
+/// Lock the given reader-writer lock for writing.
+#[no_mangle]
+pub unsafe extern "C" fn ax_pthread_rwlock_wrlock(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    debug!("ax_pthread_rwlock_wrlock <= {:#x}", rwlock as usize);
+    ax_call_body!(ax_pthread_rwlock_wrlock, {
+        check_null_mut_ptr(rwlock)?;
+        (*rwlock.cast::<PthreadRwLock>()).wrlock()?;
+        Ok(0)
+    })
+}
+
+/// Try to lock the given reader-writer lock for writing without blocking.
+#[no_mangle]
+pub unsafe extern "C" fn ax_pthread_rwlock_trywrlock(
+    rwlock: *mut ctypes::pthread_rwlock_t,
+) -> c_int {
+    debug!("ax_pthread_rwlock_trywrlock <= {:#x}", rwlock as usize);
+    ax_call_body!(ax_pthread_rwlock_trywrlock, {
+        check_null_mut_ptr(rwlock)?;
+        (*rwlock.cast::<PthreadRwLock>()).trywrlock()?;
+        Ok(0)
+    })
+}
+
+/// Unlock the given reader-writer lock.
+#[no_mangle]
+pub unsafe extern "C" fn ax_pthread_rwlock_unlock(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    debug!("ax_pthread_rwlock_unlock <= {:#x}", rwlock as usize);
+    ax_call_body!(ax_pthread_rwlock_unlock, {
+        check_null_mut_ptr(rwlock)?;
+        (*rwlock.cast::<PthreadRwLock>()).unlock()?;
+        Ok(0)
+    })
+}
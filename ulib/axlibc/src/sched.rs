@@ -0,0 +1,82 @@
+use core::ffi::{c_int, c_ulong};
+use core::mem::size_of;
+
+use axerrno::{LinuxError, LinuxResult};
+use axtask::{AxTaskRef, CpuMask};
+
+use crate::{ctypes, pthread::Pthread, utils::check_null_ptr};
+
+/// Converts a `cpu_set_t` to the CPU mask, only the first word is used as
+/// ArceOS supports at most `usize::BITS` CPUs.
+pub(crate) fn cpuset_to_mask(
+    cpusetsize: usize,
+    cpuset: *const ctypes::cpu_set_t,
+) -> LinuxResult<CpuMask> {
+    check_null_ptr(cpuset)?;
+    if cpusetsize < size_of::<c_ulong>() {
+        return Err(LinuxError::EINVAL);
+    }
+    let bits = unsafe { (*cpuset).__bits[0] } as usize;
+    let cpumask = CpuMask::from_raw_bits(bits);
+    if cpumask.is_empty() {
+        return Err(LinuxError::EINVAL);
+    }
+    Ok(cpumask)
+}
+
+/// Writes the CPU mask to a `cpu_set_t`, the rest bits are cleared.
+pub(crate) fn mask_to_cpuset(
+    cpumask: CpuMask,
+    cpusetsize: usize,
+    cpuset: *mut ctypes::cpu_set_t,
+) -> LinuxResult {
+    check_null_ptr(cpuset)?;
+    if cpusetsize < size_of::<c_ulong>() {
+        return Err(LinuxError::EINVAL);
+    }
+    let size = cpusetsize.min(size_of::<ctypes::cpu_set_t>());
+    unsafe {
+        core::ptr::write_bytes(cpuset as *mut u8, 0, size);
+        (*cpuset).__bits[0] = cpumask.bits() as c_ulong;
+    }
+    Ok(())
+}
+
+/// Finds the task by the thread ID, `0` means the current thread.
+fn find_task(pid: c_int) -> LinuxResult<AxTaskRef> {
+    let curr = axtask::current();
+    if pid == 0 || pid as u64 == curr.id().as_u64() {
+        Ok(curr.as_task_ref().clone())
+    } else {
+        Pthread::task_by_tid(pid as u64).ok_or(LinuxError::ESRCH)
+    }
+}
+
+/// Set the CPU affinity mask of the thread `pid`.
+#[no_mangle]
+pub unsafe extern "C" fn ax_sched_setaffinity(
+    pid: c_int,
+    cpusetsize: usize,
+    cpuset: *const ctypes::cpu_set_t,
+) -> c_int {
+    debug!("ax_sched_setaffinity <= {} {}", pid, cpusetsize);
+    ax_call_body!(ax_sched_setaffinity, {
+        let cpumask = cpuset_to_mask(cpusetsize, cpuset)?;
+        axtask::set_affinity(&find_task(pid)?, cpumask);
+        Ok(0)
+    })
+}
+
+/// Get the CPU affinity mask of the thread `pid`.
+#[no_mangle]
+pub unsafe extern "C" fn ax_sched_getaffinity(
+    pid: c_int,
+    cpusetsize: usize,
+    cpuset: *mut ctypes::cpu_set_t,
+) -> c_int {
+    debug!("ax_sched_getaffinity <= {} {}", pid, cpusetsize);
+    ax_call_body!(ax_sched_getaffinity, {
+        mask_to_cpuset(find_task(pid)?.cpumask(), cpusetsize, cpuset)?;
+        Ok(0)
+    })
+}
@@ -0,0 +1,125 @@
+use crate::ctypes;
+use crate::utils::{check_null_mut_ptr, timeout_from_abstime};
+use axerrno::{LinuxError, LinuxResult};
+use axstd::sync::Semaphore;
+use core::ffi::{c_int, c_uint};
+use core::mem::size_of;
+
+/// The maximum value of a semaphore, same as `SEM_VALUE_MAX` in `semaphore.h`.
+const SEM_VALUE_MAX: usize = 0x7fff_ffff;
+
+static_assertions::const_assert!(size_of::<Sem>() <= size_of::<ctypes::sem_t>());
+
+#[repr(C)]
+pub struct Sem(Semaphore);
+
+impl Sem {
+    fn post(&self) -> LinuxResult {
+        if self.0.available() >= SEM_VALUE_MAX {
+            return Err(LinuxError::EOVERFLOW);
+        }
+        self.0.release();
+        Ok(())
+    }
+}
+
+/// Initialize an unnamed semaphore with the initial `value`.
+///
+/// Semaphores can always be shared between tasks, so `pshared` is ignored.
+#[no_mangle]
+pub unsafe extern "C" fn ax_sem_init(
+    sem: *mut ctypes::sem_t,
+    _pshared: c_int,
+    value: c_uint,
+) -> c_int {
+    debug!("ax_sem_init <= {:#x}, {}", sem as usize, value);
+    ax_call_body!(ax_sem_init, {
+        check_null_mut_ptr(sem)?;
+        if value as usize > SEM_VALUE_MAX {
+            return Err(LinuxError::EINVAL);
+        }
+        sem.cast::<Sem>().write(Sem(Semaphore::new(value as usize)));
+        Ok(0)
+    })
+}
+
+/// Destroy an unnamed semaphore.
+#[no_mangle]
+pub unsafe extern "C" fn ax_sem_destroy(sem: *mut ctypes::sem_t) -> c_int {
+    debug!("ax_sem_destroy <= {:#x}", sem as usize);
+    ax_call_body!(ax_sem_destroy, {
+        check_null_mut_ptr(sem)?;
+        sem.cast::<Sem>().drop_in_place();
+        Ok(0)
+    })
+}
+
+/// Decrement the semaphore, blocking until it's positive.
+#[no_mangle]
+pub unsafe extern "C" fn ax_sem_wait(sem: *mut ctypes::sem_t) -> c_int {
+    debug!("ax_sem_wait <= {:#x}", sem as usize);
+    ax_call_body!(ax_sem_wait, {
+        axtask::testcancel();
+        check_null_mut_ptr(sem)?;
+        (*sem.cast::<Sem>()).0.acquire();
+        Ok(0)
+    })
+}
+
+/// Decrement the semaphore if it's positive, or fail with `EAGAIN`.
+#[no_mangle]
+pub unsafe extern "C" fn ax_sem_trywait(sem: *mut ctypes::sem_t) -> c_int {
+    debug!("ax_sem_trywait <= {:#x}", sem as usize);
+    ax_call_body!(ax_sem_trywait, {
+        check_null_mut_ptr(sem)?;
+        if (*sem.cast::<Sem>()).0.try_acquire() {
+            Ok(0)
+        } else {
+            Err(LinuxError::EAGAIN)
+        }
+    })
+}
+
+/// Decrement the semaphore, blocking until it's positive or the absolute time
+/// `abstime` since boot.
+#[no_mangle]
+pub unsafe extern "C" fn ax_sem_timedwait(
+    sem: *mut ctypes::sem_t,
+    abstime: *const ctypes::timespec,
+) -> c_int {
+    debug!("ax_sem_timedwait <= {:#x}", sem as usize);
+    ax_call_body!(ax_sem_timedwait, {
+        axtask::testcancel();
+        check_null_mut_ptr(sem)?;
+        let sem = &*sem.cast::<Sem>();
+        // no need to check `abstime` if it can be decremented immediately
+        if sem.0.try_acquire() || sem.0.acquire_timeout(timeout_from_abstime(abstime)?) {
+            Ok(0)
+        } else {
+            Err(LinuxError::ETIMEDOUT)
+        }
+    })
+}
+
+/// Increment the semaphore, and wake up a task waiting for it.
+#[no_mangle]
+pub unsafe extern "C" fn ax_sem_post(sem: *mut ctypes::sem_t) -> c_int {
+    debug!("ax_sem_post <= {:#x}", sem as usize);
+    ax_call_body!(ax_sem_post, {
+        check_null_mut_ptr(sem)?;
+        (*sem.cast::<Sem>()).post()?;
+        Ok(0)
+    })
+}
+
+/// Get the value of the semaphore.
+#[no_mangle]
+pub unsafe extern "C" fn ax_sem_getvalue(sem: *mut ctypes::sem_t, sval: *mut c_int) -> c_int {
+    debug!("ax_sem_getvalue <= {:#x}", sem as usize);
+    ax_call_body!(ax_sem_getvalue, {
+        check_null_mut_ptr(sem)?;
+        check_null_mut_ptr(sval)?;
+        *sval = (*sem.cast::<Sem>()).0.available() as c_int;
+        Ok(0)
+    })
+}
@@ -0,0 +1,205 @@
+use core::ffi::c_int;
+use core::time::Duration;
+
+use axerrno::{LinuxError, LinuxResult};
+use axtask::signal::{self, SignalAction, SignalHandler};
+
+use crate::{ctypes, pthread::Pthread};
+
+const SIG_DFL: usize = 0;
+const SIG_IGN: usize = 1;
+
+/// Converts the signal number, which must be in `1..=NSIG`.
+fn signo_from(sig: c_int) -> LinuxResult<usize> {
+    if (1..=signal::NSIG as c_int).contains(&sig) {
+        Ok(sig as usize)
+    } else {
+        Err(LinuxError::EINVAL)
+    }
+}
+
+/// Only the first 64 signals are supported, so only the first word is used.
+fn sigset_to_mask(set: &ctypes::sigset_t) -> u64 {
+    set.__bits[0] as u64
+}
+
+fn mask_to_sigset(mask: u64) -> ctypes::sigset_t {
+    let mut set = ctypes::sigset_t::default();
+    set.__bits[0] = mask as _;
+    set
+}
+
+fn action_from(act: &ctypes::sigaction) -> LinuxResult<SignalAction> {
+    let flags = act.sa_flags as u32;
+    if flags & ctypes::SA_SIGINFO != 0 {
+        warn!("sigaction: SA_SIGINFO is not supported");
+        return Err(LinuxError::EINVAL);
+    }
+    let handler = unsafe { act.__sa_handler.sa_handler }.map_or(SIG_DFL, |f| f as usize);
+    let handler = match handler {
+        SIG_DFL => SignalHandler::Default,
+        SIG_IGN => SignalHandler::Ignore,
+        f => SignalHandler::Handler(unsafe { core::mem::transmute(f) }),
+    };
+    Ok(SignalAction {
+        handler,
+        mask: sigset_to_mask(&act.sa_mask),
+        nodefer: flags & ctypes::SA_NODEFER != 0,
+        oneshot: flags & ctypes::SA_RESETHAND != 0,
+    })
+}
+
+fn action_to(action: &SignalAction) -> ctypes::sigaction {
+    let handler = match action.handler {
+        SignalHandler::Default => SIG_DFL,
+        SignalHandler::Ignore => SIG_IGN,
+        SignalHandler::Handler(f) => f as usize,
+    };
+    let mut flags = 0;
+    if action.nodefer {
+        flags |= ctypes::SA_NODEFER;
+    }
+    if action.oneshot {
+        flags |= ctypes::SA_RESETHAND;
+    }
+    let mut act = ctypes::sigaction::default();
+    act.__sa_handler.sa_handler = unsafe { core::mem::transmute(handler) };
+    act.sa_mask = mask_to_sigset(action.mask);
+    act.sa_flags = flags as c_int;
+    act
+}
+
+/// Sends the signal `sig` to the given task, or only checks `sig` if it's 0.
+pub(crate) fn send_signal(task: &axtask::AxTaskRef, sig: c_int) -> LinuxResult {
+    if sig != 0 {
+        signo_from(sig)?;
+    }
+    signal::send_signal(task, sig as usize);
+    Ok(())
+}
+
+/// Examine and change the action of the signal `signum`.
+///
+/// `SA_SIGINFO` is not supported, and `SA_RESTART` is always assumed.
+#[no_mangle]
+pub unsafe extern "C" fn ax_sigaction(
+    signum: c_int,
+    act: *const ctypes::sigaction,
+    oldact: *mut ctypes::sigaction,
+) -> c_int {
+    debug!("ax_sigaction <= {}", signum);
+    ax_call_body!(ax_sigaction, {
+        let signo = signo_from(signum)?;
+        let old = if act.is_null() {
+            signal::signal_action(signo)
+        } else {
+            signal::set_signal_action(signo, action_from(unsafe { &*act })?)
+        };
+        let old = old.ok_or(LinuxError::EINVAL)?;
+        if !oldact.is_null() {
+            unsafe { *oldact = action_to(&old) };
+        }
+        Ok(0)
+    })
+}
+
+/// Examine and change the blocked signals of the current thread.
+#[no_mangle]
+pub unsafe extern "C" fn ax_sigprocmask(
+    how: c_int,
+    set: *const ctypes::sigset_t,
+    oldset: *mut ctypes::sigset_t,
+) -> c_int {
+    debug!("ax_sigprocmask <= {}", how);
+    ax_call_body!(ax_sigprocmask, {
+        let old = signal::signal_mask();
+        if !set.is_null() {
+            let mask = sigset_to_mask(unsafe { &*set });
+            let new = match how as u32 {
+                ctypes::SIG_BLOCK => old | mask,
+                ctypes::SIG_UNBLOCK => old & !mask,
+                ctypes::SIG_SETMASK => mask,
+                _ => return Err(LinuxError::EINVAL),
+            };
+            signal::set_signal_mask(new);
+        }
+        if !oldset.is_null() {
+            unsafe { *oldset = mask_to_sigset(old) };
+        }
+        Ok(0)
+    })
+}
+
+/// Send the signal `sig` to the thread `pid`.
+///
+/// All threads are in the same process, so the signal sent to the process
+/// (`pid` is 0 or -1) is delivered to the calling thread.
+#[no_mangle]
+pub unsafe extern "C" fn ax_kill(pid: c_int, sig: c_int) -> c_int {
+    debug!("ax_kill <= {}, {}", pid, sig);
+    ax_call_body!(ax_kill, {
+        let task = match pid {
+            0 | -1 => axtask::current().as_task_ref().clone(),
+            pid if pid > 0 => Pthread::task_by_tid(pid as u64).ok_or(LinuxError::ESRCH)?,
+            _ => return Err(LinuxError::ESRCH),
+        };
+        send_signal(&task, sig)?;
+        Ok(0)
+    })
+}
+
+/// Set the real-time interval timer, which sends `SIGALRM` to the calling
+/// thread when it expires. Only `ITIMER_REAL` is supported.
+#[no_mangle]
+pub unsafe extern "C" fn ax_setitimer(
+    which: c_int,
+    new_value: *const ctypes::itimerval,
+    old_value: *mut ctypes::itimerval,
+) -> c_int {
+    debug!("ax_setitimer <= {}", which);
+    ax_call_body!(ax_setitimer, {
+        if which as u32 != ctypes::ITIMER_REAL {
+            return Err(LinuxError::EINVAL);
+        }
+        let (value, interval) = if new_value.is_null() {
+            (Duration::ZERO, Duration::ZERO)
+        } else {
+            let new_value = unsafe { *new_value };
+            for tv in [new_value.it_value, new_value.it_interval] {
+                if tv.tv_sec < 0 || !(0..1_000_000).contains(&tv.tv_usec) {
+                    return Err(LinuxError::EINVAL);
+                }
+            }
+            (new_value.it_value.into(), new_value.it_interval.into())
+        };
+        let curr = axtask::current();
+        let (old, old_interval) = signal::set_alarm(curr.as_task_ref(), value, interval);
+        if !old_value.is_null() {
+            unsafe {
+                (*old_value).it_value = old.into();
+                (*old_value).it_interval = old_interval.into();
+            }
+        }
+        Ok(0)
+    })
+}
+
+/// Get the remaining time of the real-time interval timer.
+#[no_mangle]
+pub unsafe extern "C" fn ax_getitimer(which: c_int, curr_value: *mut ctypes::itimerval) -> c_int {
+    debug!("ax_getitimer <= {}", which);
+    ax_call_body!(ax_getitimer, {
+        if which as u32 != ctypes::ITIMER_REAL {
+            return Err(LinuxError::EINVAL);
+        }
+        if curr_value.is_null() {
+            return Err(LinuxError::EFAULT);
+        }
+        let (value, interval) = signal::alarm_remaining();
+        unsafe {
+            (*curr_value).it_value = value.into();
+            (*curr_value).it_interval = interval.into();
+        }
+        Ok(0)
+    })
+}
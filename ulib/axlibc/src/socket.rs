@@ -1,18 +1,47 @@
 use alloc::{sync::Arc, vec};
 use core::ffi::{c_char, c_int, c_void};
 use core::mem::size_of;
-use core::net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4};
+use core::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+use core::time::Duration;
 
-use axerrno::{LinuxError, LinuxResult};
+use axerrno::{AxError, LinuxError, LinuxResult};
 use axio::PollState;
-use axnet::{TcpSocket, UdpSocket};
+use axnet::{IcmpSocket, RawSocket, TcpSocket, UdpSocket};
 use axstd::sync::Mutex;
 
 use crate::{ctypes, fd_ops::FileLike, utils::char_ptr_to_str};
 
+/// Calls the blocking socket operation `f`, and handles the signals that
+/// interrupt it after `f` has released the socket.
+///
+/// As `SA_RESTART` is always assumed, `f` is called again after the signals
+/// are handled if `restart` is `true`, otherwise it fails with `EINTR`. It
+/// also fails with `EINTR` if the thread is canceled, which is acted on by
+/// `ax_call_body` once the call has released everything.
+fn interruptible<T>(restart: bool, mut f: impl FnMut() -> LinuxResult<T>) -> LinuxResult<T> {
+    loop {
+        let res = f();
+        if !matches!(res, Err(LinuxError::EINTR)) {
+            return res;
+        }
+        #[cfg(feature = "multitask")]
+        {
+            axtask::signal::handle_pending_signals();
+            if axtask::cancel_pending() {
+                return res;
+            }
+        }
+        if !restart {
+            return res;
+        }
+    }
+}
+
 pub enum Socket {
     Udp(Mutex<UdpSocket>),
     Tcp(Mutex<TcpSocket>),
+    Icmp(Mutex<IcmpSocket>),
+    Raw(Mutex<RawSocket>),
 }
 
 impl Socket {
@@ -28,23 +57,29 @@ impl Socket {
     }
 
     fn send(&self, buf: &[u8]) -> LinuxResult<usize> {
-        match self {
+        interruptible(true, || match self {
             Socket::Udp(udpsocket) => Ok(udpsocket.lock().send(buf)?),
             Socket::Tcp(tcpsocket) => Ok(tcpsocket.lock().send(buf)?),
-        }
+            // diff: ICMP and raw sockets can't be connected
+            Socket::Icmp(_) | Socket::Raw(_) => Err(LinuxError::EDESTADDRREQ),
+        })
     }
 
     fn recv(&self, buf: &mut [u8]) -> LinuxResult<usize> {
-        match self {
+        interruptible(true, || match self {
             Socket::Udp(udpsocket) => Ok(udpsocket.lock().recv_from(buf).map(|e| e.0)?),
             Socket::Tcp(tcpsocket) => Ok(tcpsocket.lock().recv(buf)?),
-        }
+            Socket::Icmp(icmpsocket) => Ok(icmpsocket.lock().recv_from(buf).map(|e| e.0)?),
+            Socket::Raw(rawsocket) => Ok(rawsocket.lock().recv_from(buf).map(|e| e.0)?),
+        })
     }
 
     pub fn poll(&self) -> LinuxResult<PollState> {
         match self {
             Socket::Udp(udpsocket) => Ok(udpsocket.lock().poll()?),
             Socket::Tcp(tcpsocket) => Ok(tcpsocket.lock().poll()?),
+            Socket::Icmp(icmpsocket) => Ok(icmpsocket.lock().poll()?),
+            Socket::Raw(rawsocket) => Ok(rawsocket.lock().poll()?),
         }
     }
 
@@ -52,6 +87,12 @@ impl Socket {
         match self {
             Socket::Udp(udpsocket) => Ok(udpsocket.lock().local_addr()?),
             Socket::Tcp(tcpsocket) => Ok(tcpsocket.lock().local_addr()?),
+            // the identifier is the port of a ping socket
+            Socket::Icmp(icmpsocket) => Ok(SocketAddr::new(
+                Ipv4Addr::UNSPECIFIED.into(),
+                icmpsocket.lock().ident()?,
+            )),
+            Socket::Raw(_) => Err(LinuxError::EOPNOTSUPP),
         }
     }
 
@@ -59,6 +100,7 @@ impl Socket {
         match self {
             Socket::Udp(udpsocket) => Ok(udpsocket.lock().peer_addr()?),
             Socket::Tcp(tcpsocket) => Ok(tcpsocket.lock().peer_addr()?),
+            Socket::Icmp(_) | Socket::Raw(_) => Err(LinuxError::ENOTCONN),
         }
     }
 
@@ -66,46 +108,149 @@ impl Socket {
         match self {
             Socket::Udp(udpsocket) => Ok(udpsocket.lock().bind(addr)?),
             Socket::Tcp(tcpsocket) => Ok(tcpsocket.lock().bind(addr)?),
+            // diff: only the identifier (port) is bound, the address is ignored
+            Socket::Icmp(icmpsocket) => Ok(icmpsocket.lock().bind(addr.port())?),
+            Socket::Raw(_) => Err(LinuxError::EOPNOTSUPP),
         }
     }
 
     fn connect(&self, addr: SocketAddr) -> LinuxResult {
-        match self {
+        // the connection continues in the background if it's interrupted
+        interruptible(false, || match self {
             Socket::Udp(udpsocket) => Ok(udpsocket.lock().connect(addr)?),
             Socket::Tcp(tcpsocket) => Ok(tcpsocket.lock().connect(addr)?),
-        }
+            Socket::Icmp(_) | Socket::Raw(_) => Err(LinuxError::EOPNOTSUPP),
+        })
     }
 
     fn sendto(&self, buf: &[u8], addr: SocketAddr) -> LinuxResult<usize> {
-        match self {
+        interruptible(true, || match self {
             // diff: must bind before sendto
             Socket::Udp(udpsocket) => Ok(udpsocket.lock().send_to(buf, addr)?),
             Socket::Tcp(_) => Err(LinuxError::EISCONN),
-        }
+            Socket::Icmp(icmpsocket) => Ok(icmpsocket.lock().send_to(buf, addr.ip())?),
+            Socket::Raw(rawsocket) => Ok(rawsocket.lock().send_to(buf, addr.ip())?),
+        })
     }
 
     fn recvfrom(&self, buf: &mut [u8]) -> LinuxResult<(usize, Option<SocketAddr>)> {
-        match self {
+        interruptible(true, || match self {
             // diff: must bind before recvfrom
             Socket::Udp(udpsocket) => Ok(udpsocket
                 .lock()
                 .recv_from(buf)
                 .map(|res| (res.0, Some(res.1)))?),
             Socket::Tcp(tcpsocket) => Ok(tcpsocket.lock().recv(buf).map(|res| (res, None))?),
-        }
+            Socket::Icmp(icmpsocket) => Ok(icmpsocket
+                .lock()
+                .recv_from(buf)
+                .map(|res| (res.0, Some(SocketAddr::new(res.1, 0))))?),
+            Socket::Raw(rawsocket) => Ok(rawsocket
+                .lock()
+                .recv_from(buf)
+                .map(|res| (res.0, Some(SocketAddr::new(res.1, 0))))?),
+        })
     }
 
     fn listen(&self) -> LinuxResult {
         match self {
-            Socket::Udp(_) => Err(LinuxError::EOPNOTSUPP),
             Socket::Tcp(tcpsocket) => Ok(tcpsocket.lock().listen()?),
+            _ => Err(LinuxError::EOPNOTSUPP),
         }
     }
 
     fn accept(&self) -> LinuxResult<TcpSocket> {
-        match self {
-            Socket::Udp(_) => Err(LinuxError::EOPNOTSUPP),
+        interruptible(true, || match self {
             Socket::Tcp(tcpsocket) => Ok(tcpsocket.lock().accept()?),
+            _ => Err(LinuxError::EOPNOTSUPP),
+        })
+    }
+
+    fn bind_to_device(&self, name: &str) -> LinuxResult {
+        let res = match self {
+            Socket::Udp(udpsocket) => udpsocket.lock().bind_to_device(name),
+            Socket::Tcp(tcpsocket) => tcpsocket.lock().bind_to_device(name),
+            Socket::Icmp(icmpsocket) => icmpsocket.lock().bind_to_device(name),
+            Socket::Raw(rawsocket) => rawsocket.lock().bind_to_device(name),
+        };
+        res.map_err(|e| match e {
+            AxError::NotFound => LinuxError::ENODEV,
+            e => e.into(),
+        })
+    }
+
+    /// Returns the TCP socket, or `ENOPROTOOPT` if it's not one, for the
+    /// options only supported by TCP.
+    fn tcp_socket(&self) -> LinuxResult<&Mutex<TcpSocket>> {
+        match self {
+            Socket::Tcp(tcpsocket) => Ok(tcpsocket),
+            _ => Err(LinuxError::ENOPROTOOPT),
+        }
+    }
+
+    fn socket_type(&self) -> u32 {
+        match self {
+            Socket::Udp(_) | Socket::Icmp(_) => ctypes::SOCK_DGRAM,
+            Socket::Tcp(_) => ctypes::SOCK_STREAM,
+            Socket::Raw(_) => ctypes::SOCK_RAW,
+        }
+    }
+
+    fn reuse_addr(&self) -> LinuxResult<bool> {
+        match self {
+            Socket::Udp(udpsocket) => Ok(udpsocket.lock().reuse_addr()),
+            Socket::Tcp(tcpsocket) => Ok(tcpsocket.lock().reuse_addr()),
+            Socket::Icmp(_) | Socket::Raw(_) => Err(LinuxError::ENOPROTOOPT),
+        }
+    }
+
+    fn set_reuse_addr(&self, reuse_addr: bool) -> LinuxResult {
+        match self {
+            Socket::Udp(udpsocket) => udpsocket.lock().set_reuse_addr(reuse_addr),
+            Socket::Tcp(tcpsocket) => tcpsocket.lock().set_reuse_addr(reuse_addr),
+            Socket::Icmp(_) | Socket::Raw(_) => return Err(LinuxError::ENOPROTOOPT),
+        }
+        Ok(())
+    }
+
+    fn recv_timeout(&self) -> Option<Duration> {
+        match self {
+            Socket::Udp(udpsocket) => udpsocket.lock().recv_timeout(),
+            Socket::Tcp(tcpsocket) => tcpsocket.lock().recv_timeout(),
+            Socket::Icmp(icmpsocket) => icmpsocket.lock().recv_timeout(),
+            Socket::Raw(rawsocket) => rawsocket.lock().recv_timeout(),
+        }
+    }
+
+    fn set_recv_timeout(&self, timeout: Option<Duration>) -> LinuxResult {
+        match self {
+            Socket::Udp(udpsocket) => Ok(udpsocket.lock().set_recv_timeout(timeout)?),
+            Socket::Tcp(tcpsocket) => Ok(tcpsocket.lock().set_recv_timeout(timeout)?),
+            Socket::Icmp(icmpsocket) => Ok(icmpsocket.lock().set_recv_timeout(timeout)?),
+            Socket::Raw(rawsocket) => Ok(rawsocket.lock().set_recv_timeout(timeout)?),
+        }
+    }
+
+    fn send_timeout(&self) -> Option<Duration> {
+        match self {
+            Socket::Udp(udpsocket) => udpsocket.lock().send_timeout(),
+            Socket::Tcp(tcpsocket) => tcpsocket.lock().send_timeout(),
+            Socket::Icmp(_) | Socket::Raw(_) => None,
+        }
+    }
+
+    fn set_send_timeout(&self, timeout: Option<Duration>) -> LinuxResult {
+        match self {
+            Socket::Udp(udpsocket) => Ok(udpsocket.lock().set_send_timeout(timeout)?),
+            Socket::Tcp(tcpsocket) => Ok(tcpsocket.lock().set_send_timeout(timeout)?),
+            Socket::Icmp(_) | Socket::Raw(_) => Err(LinuxError::ENOPROTOOPT),
+        }
+    }
+
+    fn take_error(&self) -> Option<AxError> {
+        match self {
+            Socket::Tcp(tcpsocket) => tcpsocket.lock().take_error(),
+            _ => None,
         }
     }
 
@@ -124,6 +269,8 @@ impl Socket {
                 tcpsocket.shutdown()?;
                 Ok(())
             }
+
+            Socket::Icmp(_) | Socket::Raw(_) => Err(LinuxError::ENOTCONN),
         }
     }
 }
@@ -163,6 +310,8 @@ impl FileLike for Socket {
         match self {
             Socket::Udp(udpsocket) => udpsocket.lock().set_nonblocking(nonblock),
             Socket::Tcp(tcpsocket) => tcpsocket.lock().set_nonblocking(nonblock),
+            Socket::Icmp(icmpsocket) => icmpsocket.lock().set_nonblocking(nonblock),
+            Socket::Raw(rawsocket) => rawsocket.lock().set_nonblocking(nonblock),
         }
         Ok(())
     }
@@ -192,17 +341,61 @@ impl From<ctypes::sockaddr_in> for SocketAddrV4 {
     }
 }
 
-fn into_sockaddr(addr: SocketAddr) -> (ctypes::sockaddr, ctypes::socklen_t) {
-    debug!("    Sockaddr: {}", addr);
-    match addr {
-        SocketAddr::V4(addr) => (
-            unsafe { *(&ctypes::sockaddr_in::from(addr) as *const _ as *const ctypes::sockaddr) },
-            size_of::<ctypes::sockaddr>() as _,
-        ),
-        SocketAddr::V6(_) => panic!("IPv6 is not supported"),
+impl From<SocketAddrV6> for ctypes::sockaddr_in6 {
+    fn from(addr: SocketAddrV6) -> ctypes::sockaddr_in6 {
+        let mut res: ctypes::sockaddr_in6 = unsafe { core::mem::zeroed() };
+        res.sin6_family = ctypes::AF_INET6 as u16;
+        res.sin6_port = addr.port().to_be();
+        res.sin6_flowinfo = addr.flowinfo().to_be();
+        res.sin6_addr.__in6_union.__s6_addr = addr.ip().octets();
+        res.sin6_scope_id = addr.scope_id();
+        res
     }
 }
 
+impl From<ctypes::sockaddr_in6> for SocketAddrV6 {
+    fn from(addr: ctypes::sockaddr_in6) -> SocketAddrV6 {
+        SocketAddrV6::new(
+            Ipv6Addr::from(unsafe { addr.sin6_addr.__in6_union.__s6_addr }),
+            u16::from_be(addr.sin6_port),
+            u32::from_be(addr.sin6_flowinfo),
+            addr.sin6_scope_id,
+        )
+    }
+}
+
+fn into_sockaddr(addr: SocketAddr) -> (ctypes::sockaddr_storage, ctypes::socklen_t) {
+    debug!("    Sockaddr: {}", addr);
+    let mut storage: ctypes::sockaddr_storage = unsafe { core::mem::zeroed() };
+    let len = match addr {
+        SocketAddr::V4(addr) => {
+            let dst = &mut storage as *mut _ as *mut ctypes::sockaddr_in;
+            unsafe { dst.write(addr.into()) };
+            size_of::<ctypes::sockaddr_in>()
+        }
+        SocketAddr::V6(addr) => {
+            let dst = &mut storage as *mut _ as *mut ctypes::sockaddr_in6;
+            unsafe { dst.write(addr.into()) };
+            size_of::<ctypes::sockaddr_in6>()
+        }
+    };
+    (storage, len as _)
+}
+
+/// Writes `addr` to the buffer `dst` of `*addrlen` bytes, and sets `*addrlen`
+/// to the actual size of the address. The address is truncated if the buffer
+/// is too small.
+unsafe fn write_sockaddr(
+    addr: SocketAddr,
+    dst: *mut ctypes::sockaddr,
+    addrlen: *mut ctypes::socklen_t,
+) {
+    let (storage, len) = into_sockaddr(addr);
+    let n = (*addrlen).min(len) as usize;
+    core::ptr::copy_nonoverlapping(&storage as *const _ as *const u8, dst as *mut u8, n);
+    *addrlen = len;
+}
+
 fn from_sockaddr(
     addr: *const ctypes::sockaddr,
     addrlen: ctypes::socklen_t,
@@ -210,22 +403,65 @@ fn from_sockaddr(
     if addr.is_null() {
         return Err(LinuxError::EFAULT);
     }
-    if addrlen != size_of::<ctypes::sockaddr>() as _ {
+    let family = unsafe { (*addr).sa_family } as u32;
+    let res = match family {
+        ctypes::AF_INET if addrlen as usize >= size_of::<ctypes::sockaddr_in>() => {
+            SocketAddr::V4(unsafe { *(addr as *const ctypes::sockaddr_in) }.into())
+        }
+        ctypes::AF_INET6 if addrlen as usize >= size_of::<ctypes::sockaddr_in6>() => {
+            SocketAddr::V6(unsafe { *(addr as *const ctypes::sockaddr_in6) }.into())
+        }
+        _ => return Err(LinuxError::EINVAL),
+    };
+    debug!("    load sockaddr:{:#x} => {:?}", addr as usize, res);
+    Ok(res)
+}
+
+/// Reads the option value of type `T` from the buffer `optval` of `optlen`
+/// bytes.
+unsafe fn read_optval<T: Copy>(optval: *const c_void, optlen: ctypes::socklen_t) -> LinuxResult<T> {
+    if optval.is_null() {
+        return Err(LinuxError::EFAULT);
+    }
+    if (optlen as usize) < size_of::<T>() {
         return Err(LinuxError::EINVAL);
     }
+    Ok((optval as *const T).read_unaligned())
+}
 
-    let mid = unsafe { *(addr as *const ctypes::sockaddr_in) };
-    if mid.sin_family != ctypes::AF_INET as u16 {
-        return Err(LinuxError::EINVAL);
+/// Writes the option value `val` to the buffer `optval` of `*optlen` bytes, and
+/// sets `*optlen` to the size written. The value is truncated if the buffer is
+/// too small.
+unsafe fn write_optval<T>(
+    val: T,
+    optval: *mut c_void,
+    optlen: *mut ctypes::socklen_t,
+) -> LinuxResult {
+    if optval.is_null() || optlen.is_null() {
+        return Err(LinuxError::EFAULT);
     }
+    let n = (*optlen as usize).min(size_of::<T>());
+    core::ptr::copy_nonoverlapping(&val as *const T as *const u8, optval as *mut u8, n);
+    *optlen = n as _;
+    Ok(())
+}
 
-    let res = SocketAddr::V4(mid.into());
-    debug!("    load sockaddr:{:#x} => {:?}", addr as usize, res);
-    Ok(res)
+/// Converts a `timeval` option value to a timeout, where zero means no
+/// timeout.
+fn timeval_to_timeout(tv: ctypes::timeval) -> LinuxResult<Option<Duration>> {
+    if tv.tv_sec < 0 || !(0..1_000_000).contains(&tv.tv_usec) {
+        return Err(LinuxError::EDOM);
+    }
+    let timeout = Duration::from(tv);
+    Ok((!timeout.is_zero()).then_some(timeout))
 }
 
 /// Create an socket for communication.
 ///
+/// Besides TCP and UDP sockets, ICMP sockets for echo requests and replies
+/// (`SOCK_DGRAM` with `IPPROTO_ICMP` or `IPPROTO_ICMPV6`), and raw IP sockets
+/// (`SOCK_RAW`) are supported.
+///
 /// Return the socket file descriptor.
 #[no_mangle]
 pub unsafe extern "C" fn ax_socket(domain: c_int, socktype: c_int, protocol: c_int) -> c_int {
@@ -233,14 +469,23 @@ pub unsafe extern "C" fn ax_socket(domain: c_int, socktype: c_int, protocol: c_i
     let (domain, socktype, protocol) = (domain as u32, socktype as u32, protocol as u32);
     ax_call_body!(ax_socket, {
         match (domain, socktype, protocol) {
-            (ctypes::AF_INET, ctypes::SOCK_STREAM, ctypes::IPPROTO_TCP)
-            | (ctypes::AF_INET, ctypes::SOCK_STREAM, 0) => {
+            (ctypes::AF_INET | ctypes::AF_INET6, ctypes::SOCK_STREAM, ctypes::IPPROTO_TCP)
+            | (ctypes::AF_INET | ctypes::AF_INET6, ctypes::SOCK_STREAM, 0) => {
                 Socket::Tcp(Mutex::new(TcpSocket::new())).add_to_fd_table()
             }
-            (ctypes::AF_INET, ctypes::SOCK_DGRAM, ctypes::IPPROTO_UDP)
-            | (ctypes::AF_INET, ctypes::SOCK_DGRAM, 0) => {
+            (ctypes::AF_INET | ctypes::AF_INET6, ctypes::SOCK_DGRAM, ctypes::IPPROTO_UDP)
+            | (ctypes::AF_INET | ctypes::AF_INET6, ctypes::SOCK_DGRAM, 0) => {
                 Socket::Udp(Mutex::new(UdpSocket::new())).add_to_fd_table()
             }
+            (ctypes::AF_INET, ctypes::SOCK_DGRAM, ctypes::IPPROTO_ICMP)
+            | (ctypes::AF_INET6, ctypes::SOCK_DGRAM, ctypes::IPPROTO_ICMPV6) => {
+                Socket::Icmp(Mutex::new(IcmpSocket::new())).add_to_fd_table()
+            }
+            (ctypes::AF_INET | ctypes::AF_INET6, ctypes::SOCK_RAW, 1..=255) => {
+                let ipv6 = domain == ctypes::AF_INET6;
+                let socket = RawSocket::new(ipv6, protocol as u8);
+                Socket::Raw(Mutex::new(socket)).add_to_fd_table()
+            }
             _ => Err(LinuxError::EINVAL),
         }
     })
@@ -360,9 +605,7 @@ pub unsafe extern "C" fn ax_recvfrom(
 
         let res = socket.recvfrom(buf)?;
         if let Some(addr) = res.1 {
-            unsafe {
-                (*socket_addr, *addrlen) = into_sockaddr(addr);
-            }
+            unsafe { write_sockaddr(addr, socket_addr, addrlen) };
         }
         Ok(res.0)
     })
@@ -427,9 +670,7 @@ pub unsafe extern "C" fn ax_accept(
         let new_socket = socket.accept()?;
         let addr = new_socket.peer_addr()?;
         let new_fd = Socket::add_to_fd_table(Socket::Tcp(Mutex::new(new_socket)))?;
-        unsafe {
-            (*socket_addr, *socket_len) = into_sockaddr(addr);
-        }
+        unsafe { write_sockaddr(addr, socket_addr, socket_len) };
         Ok(new_fd)
     })
 }
@@ -449,21 +690,174 @@ pub unsafe extern "C" fn ax_shutdown(
     })
 }
 
+/// Set options on the socket.
+///
+/// Supported options are `SO_BINDTODEVICE`, `SO_REUSEADDR`, `SO_RCVBUF`,
+/// `SO_SNDBUF`, `SO_KEEPALIVE`, `SO_RCVTIMEO` and `SO_SNDTIMEO` at the
+/// `SOL_SOCKET` level, and `TCP_NODELAY` and `TCP_KEEPIDLE` at the
+/// `IPPROTO_TCP` level. Other options fail with `ENOPROTOOPT`.
+///
+/// Return 0 if success.
+#[no_mangle]
+pub unsafe extern "C" fn ax_setsockopt(
+    socket_fd: c_int,
+    level: c_int,
+    optname: c_int,
+    optval: *const c_void,
+    optlen: ctypes::socklen_t,
+) -> c_int {
+    debug!(
+        "ax_setsockopt <= {} {} {} {:#x} {}",
+        socket_fd, level, optname, optval as usize, optlen
+    );
+    ax_call_body!(ax_setsockopt, {
+        let socket = Socket::from_fd(socket_fd)?;
+        let int_val = || unsafe { read_optval::<c_int>(optval, optlen) };
+        let buf_size = || usize::try_from(int_val()?).map_err(|_| LinuxError::EINVAL);
+        match (level as u32, optname as u32) {
+            (ctypes::SOL_SOCKET, ctypes::SO_BINDTODEVICE) => {
+                if optval.is_null() && optlen > 0 {
+                    return Err(LinuxError::EFAULT);
+                }
+                let name: &[u8] = if optlen == 0 {
+                    &[]
+                } else {
+                    unsafe { core::slice::from_raw_parts(optval as *const u8, optlen as usize) }
+                };
+                // the name may be NUL-terminated
+                let name = name.split(|&c| c == 0).next().unwrap_or_default();
+                let name = core::str::from_utf8(name).map_err(|_| LinuxError::EINVAL)?;
+                socket.bind_to_device(name)?;
+            }
+            (ctypes::SOL_SOCKET, ctypes::SO_REUSEADDR) => socket.set_reuse_addr(int_val()? != 0)?,
+            (ctypes::SOL_SOCKET, ctypes::SO_RCVBUF) => {
+                let size = buf_size()?;
+                socket.tcp_socket()?.lock().set_recv_buffer_size(size)?;
+            }
+            (ctypes::SOL_SOCKET, ctypes::SO_SNDBUF) => {
+                let size = buf_size()?;
+                socket.tcp_socket()?.lock().set_send_buffer_size(size)?;
+            }
+            (ctypes::SOL_SOCKET, ctypes::SO_KEEPALIVE) => {
+                let keep_alive = int_val()? != 0;
+                socket.tcp_socket()?.lock().set_keep_alive(keep_alive);
+            }
+            (ctypes::SOL_SOCKET, ctypes::SO_RCVTIMEO) => {
+                let tv = unsafe { read_optval::<ctypes::timeval>(optval, optlen)? };
+                socket.set_recv_timeout(timeval_to_timeout(tv)?)?;
+            }
+            (ctypes::SOL_SOCKET, ctypes::SO_SNDTIMEO) => {
+                let tv = unsafe { read_optval::<ctypes::timeval>(optval, optlen)? };
+                socket.set_send_timeout(timeval_to_timeout(tv)?)?;
+            }
+            (ctypes::IPPROTO_TCP, ctypes::TCP_NODELAY) => {
+                let nodelay = int_val()? != 0;
+                socket.tcp_socket()?.lock().set_nodelay(nodelay);
+            }
+            (ctypes::IPPROTO_TCP, ctypes::TCP_KEEPIDLE) => {
+                let secs = u64::try_from(int_val()?).map_err(|_| LinuxError::EINVAL)?;
+                socket
+                    .tcp_socket()?
+                    .lock()
+                    .set_keep_idle(Duration::from_secs(secs))?;
+            }
+            _ => {
+                warn!(
+                    "ax_setsockopt: unsupported option: level {}, optname {}",
+                    level, optname
+                );
+                return Err(LinuxError::ENOPROTOOPT);
+            }
+        }
+        Ok(0)
+    })
+}
+
+/// Get options on the socket.
+///
+/// Besides the options supported by [`ax_setsockopt`] (except
+/// `SO_BINDTODEVICE`), `SO_TYPE` and `SO_ERROR` are supported.
+///
+/// Return 0 if success.
+#[no_mangle]
+pub unsafe extern "C" fn ax_getsockopt(
+    socket_fd: c_int,
+    level: c_int,
+    optname: c_int,
+    optval: *mut c_void,
+    optlen: *mut ctypes::socklen_t,
+) -> c_int {
+    debug!(
+        "ax_getsockopt <= {} {} {} {:#x} {:#x}",
+        socket_fd, level, optname, optval as usize, optlen as usize
+    );
+    ax_call_body!(ax_getsockopt, {
+        let socket = Socket::from_fd(socket_fd)?;
+        let int_val: c_int = match (level as u32, optname as u32) {
+            (ctypes::SOL_SOCKET, ctypes::SO_TYPE) => socket.socket_type() as _,
+            (ctypes::SOL_SOCKET, ctypes::SO_ERROR) => socket
+                .take_error()
+                .map_or(0, |e| LinuxError::from(e).code()),
+            (ctypes::SOL_SOCKET, ctypes::SO_REUSEADDR) => socket.reuse_addr()? as _,
+            (ctypes::SOL_SOCKET, ctypes::SO_RCVBUF) => {
+                socket.tcp_socket()?.lock().recv_buffer_size() as _
+            }
+            (ctypes::SOL_SOCKET, ctypes::SO_SNDBUF) => {
+                socket.tcp_socket()?.lock().send_buffer_size() as _
+            }
+            (ctypes::SOL_SOCKET, ctypes::SO_KEEPALIVE) => {
+                socket.tcp_socket()?.lock().keep_alive() as _
+            }
+            (ctypes::SOL_SOCKET, ctypes::SO_RCVTIMEO) => {
+                let tv = ctypes::timeval::from(socket.recv_timeout().unwrap_or_default());
+                unsafe { write_optval(tv, optval, optlen)? };
+                return Ok(0);
+            }
+            (ctypes::SOL_SOCKET, ctypes::SO_SNDTIMEO) => {
+                let tv = ctypes::timeval::from(socket.send_timeout().unwrap_or_default());
+                unsafe { write_optval(tv, optval, optlen)? };
+                return Ok(0);
+            }
+            (ctypes::IPPROTO_TCP, ctypes::TCP_NODELAY) => {
+                socket.tcp_socket()?.lock().nodelay() as _
+            }
+            (ctypes::IPPROTO_TCP, ctypes::TCP_KEEPIDLE) => {
+                socket.tcp_socket()?.lock().keep_idle().as_secs() as _
+            }
+            _ => {
+                warn!(
+                    "ax_getsockopt: unsupported option: level {}, optname {}",
+                    level, optname
+                );
+                return Err(LinuxError::ENOPROTOOPT);
+            }
+        };
+        unsafe { write_optval(int_val, optval, optlen)? };
+        Ok(0)
+    })
+}
+
 /// Query addresses for a domain name.
 ///
-/// Return address number if success.
+/// The addresses are stored in `addrs` as `sockaddr_in` or `sockaddr_in6`,
+/// one `sockaddr_storage` for each. Only `AI_NUMERICHOST` and `AI_PASSIVE`
+/// in `flags` are used.
+///
+/// Return address number if success. `ENOENT` is returned if the name does
+/// not resolve.
 #[no_mangle]
 pub unsafe extern "C" fn ax_getaddrinfo(
     node: *const c_char,
     service: *const c_char,
-    addrs: *mut ctypes::sockaddr,
+    flags: c_int,
+    addrs: *mut ctypes::sockaddr_storage,
     len: ctypes::size_t,
 ) -> c_int {
     let name = char_ptr_to_str(node);
     let port = char_ptr_to_str(service);
     debug!(
-        "ax_getaddrinfo <= {:?} {:?} {:#x} {}",
-        name, port, addrs as usize, len
+        "ax_getaddrinfo <= {:?} {:?} {:#x} {:#x} {}",
+        name, port, flags, addrs as usize, len
     );
     ax_call_body!(ax_getaddrinfo, {
         if addrs.is_null() || (node.is_null() && service.is_null()) {
@@ -473,19 +867,20 @@ pub unsafe extern "C" fn ax_getaddrinfo(
         let res = if let Ok(domain) = name {
             if let Ok(a) = domain.parse::<IpAddr>() {
                 vec![a]
+            } else if flags & ctypes::AI_NUMERICHOST as c_int != 0 {
+                return Err(LinuxError::ENOENT);
             } else {
                 axnet::dns_query(domain)?
             }
+        } else if flags & ctypes::AI_PASSIVE as c_int != 0 {
+            vec![Ipv4Addr::UNSPECIFIED.into(), Ipv6Addr::UNSPECIFIED.into()]
         } else {
-            vec![Ipv4Addr::LOCALHOST.into()]
+            vec![Ipv4Addr::LOCALHOST.into(), Ipv6Addr::LOCALHOST.into()]
         };
 
+        let port = port.map_or(0, |p| p.parse::<u16>().unwrap_or(0));
         for (i, item) in res.iter().enumerate().take(len) {
-            addr_slice[i] = into_sockaddr(SocketAddr::new(
-                *item,
-                port.map_or(0, |p| p.parse::<u16>().unwrap_or(0)),
-            ))
-            .0;
+            addr_slice[i] = into_sockaddr(SocketAddr::new(*item, port)).0;
         }
         Ok(res.len().min(len))
     })
@@ -509,9 +904,7 @@ pub unsafe extern "C" fn ax_getsockname(
         if unsafe { *addrlen } < size_of::<ctypes::sockaddr>() as u32 {
             return Err(LinuxError::EINVAL);
         }
-        unsafe {
-            (*addr, *addrlen) = into_sockaddr(Socket::from_fd(sock_fd)?.local_addr()?);
-        }
+        unsafe { write_sockaddr(Socket::from_fd(sock_fd)?.local_addr()?, addr, addrlen) };
         Ok(0)
     })
 }
@@ -534,9 +927,7 @@ pub unsafe extern "C" fn ax_getpeername(
         if unsafe { *addrlen } < size_of::<ctypes::sockaddr>() as u32 {
             return Err(LinuxError::EINVAL);
         }
-        unsafe {
-            (*addr, *addrlen) = into_sockaddr(Socket::from_fd(sock_fd)?.peer_addr()?);
-        }
+        unsafe { write_sockaddr(Socket::from_fd(sock_fd)?.peer_addr()?, addr, addrlen) };
         Ok(0)
     })
 }
@@ -51,7 +51,8 @@ pub unsafe extern "C" fn ax_clock_gettime(ts: *mut ctypes::timespec) -> c_int {
 
 /// Sleep some nanoseconds
 ///
-/// TODO: should be woken by signals, and set errno
+/// It returns `EINTR` and the remaining time in `rem` if it's woken up by
+/// signals early.
 #[no_mangle]
 pub unsafe extern "C" fn ax_nanosleep(
     req: *const ctypes::timespec,
@@ -68,6 +69,9 @@ pub unsafe extern "C" fn ax_nanosleep(
         let now = Instant::now();
         axstd::thread::sleep(dur);
         let actual = now.elapsed();
+        // handle the signals that woke it up early
+        #[cfg(feature = "multitask")]
+        axtask::signal::handle_pending_signals();
 
         if let Some(diff) = dur.checked_sub(actual) {
             if !rem.is_null() {
@@ -3,6 +3,9 @@
 
 use axerrno::{LinuxError, LinuxResult};
 use core::ffi::{c_char, CStr};
+use core::time::Duration;
+
+use crate::ctypes;
 
 pub fn char_ptr_to_str<'a>(str: *const c_char) -> LinuxResult<&'a str> {
     if str.is_null() {
@@ -30,6 +33,16 @@ pub fn check_null_mut_ptr<T>(ptr: *mut T) -> LinuxResult {
     }
 }
 
+/// Converts the absolute time `abstime` since boot to the timeout from now.
+pub fn timeout_from_abstime(abstime: *const ctypes::timespec) -> LinuxResult<Duration> {
+    check_null_ptr(abstime)?;
+    let abstime = unsafe { *abstime };
+    if abstime.tv_sec < 0 || !(0..1_000_000_000).contains(&abstime.tv_nsec) {
+        return Err(LinuxError::EINVAL);
+    }
+    Ok(Duration::from(abstime).saturating_sub(axhal::time::current_time()))
+}
+
 macro_rules! ax_call_body {
     ($fn: ident, $($stmt: tt)*) => {{
         #[allow(clippy::redundant_closure_call)]
@@ -41,6 +54,12 @@ macro_rules! ax_call_body {
         match res {
             Ok(v) => v as _,
             Err(e) => {
+                // a blocking call interrupted by cancellation is a cancellation
+                // point, acted on here after it has released everything
+                #[cfg(feature = "multitask")]
+                if e == axerrno::LinuxError::EINTR {
+                    axtask::testcancel();
+                }
                 crate::errno::set_errno(e.code());
                 -1 as _
             }
@@ -0,0 +1,182 @@
+//! A minimal executor of asynchronous tasks.
+//!
+//! An [`Executor`] runs many futures in the current thread, e.g. one for each
+//! connection served by the asynchronous socket operations in `axstd::net`.
+//! When none of them can make progress, the thread sleeps until one of them is
+//! woken.
+
+use alloc::{boxed::Box, collections::VecDeque, sync::Arc, task::Wake, vec::Vec};
+use core::cell::RefCell;
+use core::future::Future;
+use core::pin::{pin, Pin};
+use core::task::{Context, Poll, Waker};
+
+use arceos_api::task as api;
+use spinlock::SpinNoIrq;
+
+/// The ID of the future given to [`Executor::block_on`].
+const MAIN_ID: usize = usize::MAX;
+
+struct Task<'a> {
+    future: Pin<Box<dyn Future<Output = ()> + 'a>>,
+    waker: Waker,
+}
+
+/// The IDs of the woken tasks.
+struct ReadyQueue {
+    ids: SpinNoIrq<VecDeque<usize>>,
+    #[cfg(feature = "multitask")]
+    wq: api::AxWaitQueueHandle,
+}
+
+impl ReadyQueue {
+    fn new() -> Self {
+        Self {
+            ids: SpinNoIrq::new(VecDeque::new()),
+            #[cfg(feature = "multitask")]
+            wq: api::AxWaitQueueHandle::new(),
+        }
+    }
+
+    fn push(&self, id: usize) {
+        self.ids.lock().push_back(id);
+        #[cfg(feature = "multitask")]
+        api::ax_wait_queue_wake(&self.wq, 1);
+    }
+
+    fn pop(&self) -> Option<usize> {
+        self.ids.lock().pop_front()
+    }
+
+    fn len(&self) -> usize {
+        self.ids.lock().len()
+    }
+
+    /// Sleeps until some task is woken.
+    fn wait(&self) {
+        #[cfg(feature = "multitask")]
+        api::ax_wait_queue_wait(&self.wq, || self.len() > 0, None);
+        #[cfg(not(feature = "multitask"))]
+        while self.len() == 0 {
+            core::hint::spin_loop();
+        }
+    }
+}
+
+struct TaskWaker {
+    id: usize,
+    ready: Arc<ReadyQueue>,
+}
+
+impl Wake for TaskWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.ready.push(self.id);
+    }
+}
+
+/// A single-threaded executor.
+///
+/// The futures are spawned by [`spawn`](Self::spawn), and run in
+/// [`block_on`](Self::block_on).
+pub struct Executor<'a> {
+    tasks: RefCell<Vec<Option<Task<'a>>>>,
+    free_ids: RefCell<Vec<usize>>,
+    ready: Arc<ReadyQueue>,
+}
+
+impl<'a> Executor<'a> {
+    /// Creates a new executor without any task.
+    pub fn new() -> Self {
+        Self {
+            tasks: RefCell::new(Vec::new()),
+            free_ids: RefCell::new(Vec::new()),
+            ready: Arc::new(ReadyQueue::new()),
+        }
+    }
+
+    /// Spawns a task running `future`.
+    ///
+    /// It can be called by the tasks of this executor, and the task starts
+    /// running in the current or next [`block_on`](Self::block_on).
+    pub fn spawn<F: Future<Output = ()> + 'a>(&self, future: F) {
+        let id = self.free_ids.borrow_mut().pop().unwrap_or_else(|| {
+            let mut tasks = self.tasks.borrow_mut();
+            tasks.push(None);
+            tasks.len() - 1
+        });
+        self.tasks.borrow_mut()[id] = Some(Task {
+            future: Box::pin(future),
+            waker: self.waker(id),
+        });
+        self.ready.push(id);
+    }
+
+    /// Runs `future` and the spawned tasks until `future` completes, and
+    /// returns its output.
+    ///
+    /// The tasks not completed yet remain in the executor.
+    pub fn block_on<F: Future>(&self, future: F) -> F::Output {
+        let mut future = pin!(future);
+        let waker = self.waker(MAIN_ID);
+        let mut cx = Context::from_waker(&waker);
+        self.ready.push(MAIN_ID);
+        loop {
+            // the tasks woken in this round are polled in the next one
+            for _ in 0..self.ready.len() {
+                let Some(id) = self.ready.pop() else {
+                    break;
+                };
+                if id != MAIN_ID {
+                    self.poll_task(id);
+                } else if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                    return output;
+                }
+            }
+            if self.ready.len() > 0 {
+                // let other threads run if the tasks keep being woken
+                api::ax_yield_now();
+            } else {
+                self.ready.wait();
+            }
+        }
+    }
+
+    fn waker(&self, id: usize) -> Waker {
+        Waker::from(Arc::new(TaskWaker {
+            id,
+            ready: self.ready.clone(),
+        }))
+    }
+
+    fn poll_task(&self, id: usize) {
+        // take the task out, so that it can spawn new tasks when polled
+        let task = self.tasks.borrow_mut().get_mut(id).and_then(Option::take);
+        let Some(mut task) = task else {
+            return; // completed already
+        };
+        let mut cx = Context::from_waker(&task.waker);
+        if task.future.as_mut().poll(&mut cx).is_pending() {
+            self.tasks.borrow_mut()[id] = Some(task);
+        } else {
+            self.free_ids.borrow_mut().push(id);
+        }
+    }
+}
+
+impl Default for Executor<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Runs `future` in the current thread until it completes, and returns its
+/// output.
+///
+/// Use an [`Executor`] to run other tasks meanwhile.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    Executor::new().block_on(future)
+}
@@ -10,11 +10,26 @@ pub type FileType = api::AxFileType;
 /// Representation of the various permissions on a file.
 pub type Permissions = api::AxFilePerm;
 
+/// The kind of an advisory file lock.
+pub type LockKind = api::AxLockKind;
+
+/// An advisory lock on a byte range of a file.
+pub type FileLock = api::AxFileLock;
+
 /// An object providing access to an open file on the filesystem.
 pub struct File {
     inner: api::AxFileHandle,
 }
 
+/// A handle to place advisory locks on behalf of a [`File`], returned by
+/// [`File::locker`] or [`File::process_locker`].
+///
+/// It can be used without borrowing the file, e.g. to block on a lock while
+/// the file is used by others. Locks placed after the file is closed are not
+/// released.
+#[derive(Clone)]
+pub struct FileLocker(api::AxFileLocker);
+
 /// Metadata information about a file.
 pub struct Metadata(api::AxFileAttr);
 
@@ -162,6 +177,121 @@ impl File {
     pub fn metadata(&self) -> Result<Metadata> {
         api::ax_file_attr(&self.inner).map(Metadata)
     }
+    /// Returns a handle to place advisory locks on behalf of this file,
+    /// which can be used without borrowing the file.
+    pub fn locker(&self) -> FileLocker {
+        FileLocker(api::ax_file_locker(&self.inner).clone())
+    }
+
+    /// Returns a handle to place advisory locks on behalf of the process
+    /// instead of this file, like the POSIX record locks of `fcntl(2)`.
+    ///
+    /// Locks of the process never conflict with each other, can be changed
+    /// through any file opened on the same path, and are all released when
+    /// one of those files is closed.
+    pub fn process_locker(&self) -> FileLocker {
+        FileLocker(api::ax_process_file_locker(&self.inner))
+    }
+
+    /// Acquires an exclusive advisory lock on the file, blocking until it can
+    /// be acquired.
+    pub fn lock(&self) -> Result<()> {
+        api::ax_lock_file(self.raw_locker(), api::AxLockKind::Exclusive, true)
+    }
+
+    /// Acquires a shared advisory lock on the file, blocking until it can be
+    /// acquired.
+    pub fn lock_shared(&self) -> Result<()> {
+        api::ax_lock_file(self.raw_locker(), api::AxLockKind::Shared, true)
+    }
+
+    /// Tries to acquire an exclusive advisory lock on the file. Returns
+    /// [`WouldBlock`](crate::io::Error::WouldBlock) if it is held by other
+    /// opened files.
+    pub fn try_lock(&self) -> Result<()> {
+        api::ax_lock_file(self.raw_locker(), api::AxLockKind::Exclusive, false)
+    }
+
+    /// Tries to acquire a shared advisory lock on the file. Returns
+    /// [`WouldBlock`](crate::io::Error::WouldBlock) if an exclusive lock is
+    /// held by other opened files.
+    pub fn try_lock_shared(&self) -> Result<()> {
+        api::ax_lock_file(self.raw_locker(), api::AxLockKind::Shared, false)
+    }
+
+    /// Releases the advisory lock on the file.
+    pub fn unlock(&self) -> Result<()> {
+        api::ax_unlock_file(self.raw_locker())
+    }
+
+    /// Places an advisory lock on the byte range `start..end` of the file, like
+    /// `fcntl(2)` with `F_SETLK` (`wait` is `false`) or `F_SETLKW` (`wait` is
+    /// `true`).
+    ///
+    /// These locks are independent of the whole-file locks placed by
+    /// [`File::lock`] and friends.
+    pub fn lock_range(&self, kind: LockKind, start: u64, end: u64, wait: bool) -> Result<()> {
+        api::ax_lock_file_range(self.raw_locker(), kind, start, end, wait)
+    }
+
+    /// Removes the advisory locks held by this file in the byte range
+    /// `start..end`.
+    pub fn unlock_range(&self, start: u64, end: u64) -> Result<()> {
+        api::ax_unlock_file_range(self.raw_locker(), start, end)
+    }
+
+    /// Returns a lock held by other opened files that prevents placing the
+    /// given lock, or `None` if it can be placed, like `fcntl(2)` with
+    /// `F_GETLK`.
+    pub fn test_lock_range(
+        &self,
+        kind: LockKind,
+        start: u64,
+        end: u64,
+    ) -> Result<Option<FileLock>> {
+        api::ax_test_lock_file_range(self.raw_locker(), kind, start, end)
+    }
+
+    fn raw_locker(&self) -> &api::AxFileLocker {
+        api::ax_file_locker(&self.inner)
+    }
+}
+
+impl FileLocker {
+    /// Places an advisory lock of `kind` on the whole file, like
+    /// [`File::lock`] (`wait` is `true`) or [`File::try_lock`] (`wait` is
+    /// `false`).
+    pub fn lock(&self, kind: LockKind, wait: bool) -> Result<()> {
+        api::ax_lock_file(&self.0, kind, wait)
+    }
+
+    /// Releases the advisory lock on the whole file, like [`File::unlock`].
+    pub fn unlock(&self) -> Result<()> {
+        api::ax_unlock_file(&self.0)
+    }
+
+    /// Places an advisory lock on the byte range `start..end` of the file, like
+    /// [`File::lock_range`].
+    pub fn lock_range(&self, kind: LockKind, start: u64, end: u64, wait: bool) -> Result<()> {
+        api::ax_lock_file_range(&self.0, kind, start, end, wait)
+    }
+
+    /// Removes the advisory locks in the byte range `start..end`, like
+    /// [`File::unlock_range`].
+    pub fn unlock_range(&self, start: u64, end: u64) -> Result<()> {
+        api::ax_unlock_file_range(&self.0, start, end)
+    }
+
+    /// Returns a lock held by other opened files that prevents placing the
+    /// given lock, like [`File::test_lock_range`].
+    pub fn test_lock_range(
+        &self,
+        kind: LockKind,
+        start: u64,
+        end: u64,
+    ) -> Result<Option<FileLock>> {
+        api::ax_test_lock_file_range(&self.0, kind, start, end)
+    }
 }
 
 impl Read for File {
@@ -9,7 +9,9 @@ use crate::io::{self, prelude::*};
 use alloc::{string::String, vec::Vec};
 
 pub use self::dir::{DirBuilder, DirEntry, ReadDir};
-pub use self::file::{File, FileType, Metadata, OpenOptions, Permissions};
+pub use self::file::{
+    File, FileLock, FileLocker, FileType, LockKind, Metadata, OpenOptions, Permissions,
+};
 
 /// Read the entire contents of a file into a bytes vector.
 #[cfg(feature = "alloc")]
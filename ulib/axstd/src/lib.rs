@@ -27,11 +27,14 @@
 //!     - `sched_fifo`: Use the FIFO cooperative scheduler.
 //!     - `sched_rr`: Use the Round-robin preemptive scheduler.
 //!     - `sched_cfs`: Use the Completely Fair Scheduler (CFS) preemptive scheduler.
+//!     - `sched_class`: Use the hierarchical preemptive scheduler with per-task policies.
 //! - Upperlayer stacks
 //!     - `fs`: Enable file system support.
 //!     - `myfs`: Allow users to define their custom filesystems to override the default.
 //!     - `net`: Enable networking support.
+//!     - `dhcp`: Configure the network interface by DHCP.
 //!     - `dns`: Enable DNS lookup support.
+//!     - `net-tls`: Enable TLS streams over TCP (`axstd::net::tls`).
 //!     - `display`: Enable graphics support.
 //! - Device drivers
 //!     - `bus-mmio`: Use device tree to probe all MMIO devices.
@@ -72,6 +75,8 @@ pub mod sync;
 pub mod thread;
 pub mod time;
 
+#[cfg(feature = "alloc")]
+pub mod executor;
 #[cfg(feature = "fs")]
 pub mod fs;
 #[cfg(feature = "net")]
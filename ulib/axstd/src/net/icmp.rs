@@ -0,0 +1,64 @@
+use super::IpAddr;
+use crate::io;
+use core::time::Duration;
+
+use arceos_api::net::{self as api, AxIcmpSocketHandle};
+
+/// An ICMP socket for echo requests and replies, like a ping socket on Linux.
+///
+/// The messages sent and received include the ICMP header. The identifier of
+/// the echo requests sent is replaced with the one the socket is bound to, and
+/// only the echo replies with it are received.
+pub struct IcmpSocket(AxIcmpSocketHandle);
+
+impl IcmpSocket {
+    /// Creates an ICMP socket bound to the given identifier. A free identifier
+    /// is chosen if it's 0.
+    pub fn bind(ident: u16) -> io::Result<IcmpSocket> {
+        let socket = api::ax_icmp_socket();
+        api::ax_icmp_bind(&socket, ident)?;
+        Ok(IcmpSocket(socket))
+    }
+
+    /// Returns the identifier this socket is bound to.
+    pub fn ident(&self) -> io::Result<u16> {
+        api::ax_icmp_ident(&self.0)
+    }
+
+    /// Binds this socket to the network interface named `name`, so that it
+    /// only sends and receives packets through it. An empty name removes the
+    /// binding.
+    pub fn bind_to_device(&self, name: &str) -> io::Result<()> {
+        api::ax_icmp_bind_to_device(&self.0, name)
+    }
+
+    /// Sends an ICMP message on the socket to the given address. On success,
+    /// returns the number of bytes written.
+    pub fn send_to(&self, buf: &[u8], addr: IpAddr) -> io::Result<usize> {
+        api::ax_icmp_send_to(&self.0, buf, addr)
+    }
+
+    /// Receives a single ICMP message on the socket. On success, returns the
+    /// number of bytes read and the origin.
+    pub fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, IpAddr)> {
+        api::ax_icmp_recv_from(&self.0, buf)
+    }
+
+    /// Moves this ICMP socket into or out of nonblocking mode.
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        api::ax_icmp_set_nonblocking(&self.0, nonblocking)
+    }
+
+    /// Sets the read timeout to the timeout specified.
+    ///
+    /// If the value specified is `None`, then read calls will block
+    /// indefinitely. An error is returned if the zero duration is passed.
+    pub fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        api::ax_icmp_set_recv_timeout(&self.0, dur)
+    }
+
+    /// Returns the read timeout of this socket.
+    pub fn read_timeout(&self) -> io::Result<Option<Duration>> {
+        api::ax_icmp_recv_timeout(&self.0)
+    }
+}
@@ -7,23 +7,62 @@
 //!
 //! * [`TcpListener`] and [`TcpStream`] provide functionality for communication over TCP
 //! * [`UdpSocket`] provides functionality for communication over UDP
+//! * [`IcmpSocket`] sends ICMP echo requests and receives the replies (ping)
 //! * [`IpAddr`] represents IP addresses of either IPv4 or IPv6; [`Ipv4Addr`] and
 //!   [`Ipv6Addr`] are respectively IPv4 and IPv6 addresses
 //! * [`SocketAddr`] represents socket addresses of either IPv4 or IPv6; [`SocketAddrV4`]
 //!   and [`SocketAddrV6`] are respectively IPv4 and IPv6 socket addresses
 //! * [`ToSocketAddrs`] is a trait that is used for generic address resolution when interacting
 //!   with networking objects like [`TcpListener`], [`TcpStream`] or [`UdpSocket`]
+//! * [`interfaces`] lists the network interfaces and their addresses
+//! * [`set_dns_servers`] and [`dns_servers`] manage the DNS servers used by
+//!   the name resolution (with the `dns` feature)
+//! * [`tls`] provides TLS streams over TCP (with the `net-tls` feature)
+//!
+//! The sockets also provide asynchronous operations (the `*_async` methods),
+//! which can be run by the executor in `axstd::executor` to serve many
+//! connections in one thread.
+
+extern crate alloc;
 
+mod icmp;
 mod socket_addr;
 mod tcp;
 mod udp;
 
+#[cfg(feature = "net-tls")]
+pub mod tls;
+
+pub use self::icmp::IcmpSocket;
 pub use self::socket_addr::{IpAddr, Ipv4Addr, Ipv6Addr};
 pub use self::socket_addr::{SocketAddr, SocketAddrV4, SocketAddrV6, ToSocketAddrs};
 pub use self::tcp::{TcpListener, TcpStream};
 pub use self::udp::UdpSocket;
 
+/// Information of a network interface, including its name, MAC address and
+/// IP addresses.
+pub use arceos_api::net::AxNetInterfaceInfo as InterfaceInfo;
+
 use crate::io;
+use alloc::vec::Vec;
+
+/// Returns all network interfaces and their addresses.
+pub fn interfaces() -> Vec<InterfaceInfo> {
+    arceos_api::net::ax_net_interfaces()
+}
+
+/// Replaces the DNS servers used to resolve host names, which take precedence
+/// over the ones from `/etc/resolv.conf` and DHCP. An empty list removes them.
+#[cfg(feature = "dns")]
+pub fn set_dns_servers(servers: &[IpAddr]) -> io::Result<()> {
+    arceos_api::net::ax_set_dns_servers(servers)
+}
+
+/// Returns the DNS servers used to resolve host names.
+#[cfg(feature = "dns")]
+pub fn dns_servers() -> Vec<IpAddr> {
+    arceos_api::net::ax_dns_servers()
+}
 
 fn each_addr<A: ToSocketAddrs, F, T>(addr: A, mut f: F) -> io::Result<T>
 where
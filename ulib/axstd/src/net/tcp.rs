@@ -1,5 +1,7 @@
 use super::{SocketAddr, ToSocketAddrs};
 use crate::io::{self, prelude::*};
+use core::future::poll_fn;
+use core::time::Duration;
 
 use arceos_api::net::{self as api, AxTcpSocketHandle};
 
@@ -29,6 +31,46 @@ impl TcpStream {
         })
     }
 
+    /// Asynchronous version of [`connect`](Self::connect).
+    ///
+    /// Note that the host names in `addr` are resolved synchronously.
+    pub async fn connect_async<A: ToSocketAddrs>(addr: A) -> io::Result<TcpStream> {
+        let mut last_err = None;
+        for addr in addr.to_socket_addrs()? {
+            let socket = api::ax_tcp_socket();
+            match poll_fn(|cx| api::ax_tcp_poll_connect(&socket, cx, addr)).await {
+                Ok(()) => return Ok(TcpStream(socket)),
+                Err(e) => last_err = Some(e),
+            }
+        }
+        Err(last_err.unwrap_or_else(|| {
+            axerrno::ax_err_type!(InvalidInput, "could not resolve to any addresses")
+        }))
+    }
+
+    /// Reads data from the stream asynchronously, returns the number of bytes
+    /// read. `Ok(0)` means the connection is closed by the peer.
+    pub async fn read_async(&self, buf: &mut [u8]) -> io::Result<usize> {
+        poll_fn(|cx| api::ax_tcp_poll_recv(&self.0, cx, buf)).await
+    }
+
+    /// Writes data to the stream asynchronously, returns the number of bytes
+    /// written.
+    pub async fn write_async(&self, buf: &[u8]) -> io::Result<usize> {
+        poll_fn(|cx| api::ax_tcp_poll_send(&self.0, cx, buf)).await
+    }
+
+    /// Writes the entire buffer to the stream asynchronously.
+    pub async fn write_all_async(&self, mut buf: &[u8]) -> io::Result<()> {
+        while !buf.is_empty() {
+            match self.write_async(buf).await? {
+                0 => return axerrno::ax_err!(WriteZero, "failed to write whole buffer"),
+                n => buf = &buf[n..],
+            }
+        }
+        Ok(())
+    }
+
     /// Returns the socket address of the local half of this TCP connection.
     pub fn local_addr(&self) -> io::Result<SocketAddr> {
         api::ax_tcp_socket_addr(&self.0)
@@ -43,6 +85,53 @@ impl TcpStream {
     pub fn shutdown(&self) -> io::Result<()> {
         api::ax_tcp_shutdown(&self.0)
     }
+
+    /// Sets the value of the `TCP_NODELAY` option on this socket.
+    ///
+    /// If set, this option disables the Nagle algorithm, so that segments are
+    /// always sent as soon as possible.
+    pub fn set_nodelay(&self, nodelay: bool) -> io::Result<()> {
+        api::ax_tcp_set_nodelay(&self.0, nodelay)
+    }
+
+    /// Gets the value of the `TCP_NODELAY` option on this socket.
+    pub fn nodelay(&self) -> io::Result<bool> {
+        api::ax_tcp_nodelay(&self.0)
+    }
+
+    /// Sets the read timeout to the timeout specified.
+    ///
+    /// If the value specified is `None`, then read calls will block
+    /// indefinitely. An error is returned if the zero duration is passed.
+    pub fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        api::ax_tcp_set_recv_timeout(&self.0, dur)
+    }
+
+    /// Returns the read timeout of this socket.
+    pub fn read_timeout(&self) -> io::Result<Option<Duration>> {
+        api::ax_tcp_recv_timeout(&self.0)
+    }
+
+    /// Sets the write timeout to the timeout specified.
+    ///
+    /// If the value specified is `None`, then write calls will block
+    /// indefinitely. An error is returned if the zero duration is passed.
+    pub fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        api::ax_tcp_set_send_timeout(&self.0, dur)
+    }
+
+    /// Returns the write timeout of this socket.
+    pub fn write_timeout(&self) -> io::Result<Option<Duration>> {
+        api::ax_tcp_send_timeout(&self.0)
+    }
+
+    /// Gets the value of the `SO_ERROR` option on this socket.
+    ///
+    /// This will retrieve the stored error in the underlying socket, clearing
+    /// the field in the process.
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        api::ax_tcp_take_error(&self.0)
+    }
 }
 
 impl Read for TcpStream {
@@ -83,6 +172,8 @@ impl TcpListener {
             let addr = addr?;
             let backlog = 128;
             let socket = api::ax_tcp_socket();
+            // allow restarting a server while its old connections remain
+            api::ax_tcp_set_reuse_addr(&socket, true)?;
             api::ax_tcp_bind(&socket, *addr)?;
             api::ax_tcp_listen(&socket, backlog)?;
             Ok(TcpListener(socket))
@@ -102,4 +193,19 @@ impl TcpListener {
     pub fn accept(&self) -> io::Result<(TcpStream, SocketAddr)> {
         api::ax_tcp_accept(&self.0).map(|(a, b)| (TcpStream(a), b))
     }
+
+    /// Asynchronous version of [`accept`](Self::accept).
+    pub async fn accept_async(&self) -> io::Result<(TcpStream, SocketAddr)> {
+        poll_fn(|cx| api::ax_tcp_poll_accept(&self.0, cx))
+            .await
+            .map(|(a, b)| (TcpStream(a), b))
+    }
+
+    /// Gets the value of the `SO_ERROR` option on this socket.
+    ///
+    /// This will retrieve the stored error in the underlying socket, clearing
+    /// the field in the process.
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        api::ax_tcp_take_error(&self.0)
+    }
 }
@@ -0,0 +1,442 @@
+//! TLS 1.3 streams over TCP, implemented with [rustls].
+//!
+//! A client connects by [`TlsConnector::connect`], which verifies the server
+//! certificate against the embedded Mozilla root store ([webpki-roots]) or the
+//! given root certificates. A server accepts connections by
+//! [`TlsAcceptor::accept`], with its certificate chain and private key in PEM
+//! format, which can be loaded from files with the `fs` feature.
+//!
+//! ArceOS has no real-time clock, so the validity period of the certificates
+//! can be checked only after the current time is given by [`set_unix_time`];
+//! the verification fails until then. Likewise, the handshake fails if there
+//! is no hardware random number generator, until a seed is given by
+//! [`set_random_seed`].
+//!
+//! [rustls]: https://docs.rs/rustls
+//! [webpki-roots]: https://docs.rs/webpki-roots
+
+mod rng;
+
+pub use self::rng::set_random_seed;
+
+use alloc::{collections::VecDeque, string::String, sync::Arc, vec::Vec};
+use core::sync::atomic::{AtomicU64, Ordering};
+use core::time::Duration;
+
+use axerrno::{ax_err, ax_err_type};
+use rustls::client::UnbufferedClientConnection;
+use rustls::crypto::CryptoProvider;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
+use rustls::server::UnbufferedServerConnection;
+use rustls::time_provider::TimeProvider;
+use rustls::unbuffered::{
+    AppDataRecord, ConnectionState, EncodeError, EncryptError, InsufficientSizeError,
+    UnbufferedStatus,
+};
+use rustls::{ClientConfig, RootCertStore, ServerConfig};
+
+use super::TcpStream;
+use crate::io::{self, prelude::*};
+
+/// The size of each read of TLS records from the TCP stream.
+const READ_CHUNK_SIZE: usize = 16 * 1024 + 256;
+/// The initial size of the buffer of the outgoing TLS records.
+const WRITE_BUF_SIZE: usize = 4096;
+
+/// The UNIX time at boot in nanoseconds, 0 if unknown.
+static UNIX_TIME_AT_BOOT: AtomicU64 = AtomicU64::new(0);
+
+/// Sets the current time, as the duration since the UNIX epoch.
+///
+/// It's required to verify the certificates.
+pub fn set_unix_time(now: Duration) {
+    let boot = now.saturating_sub(arceos_api::time::ax_current_time());
+    UNIX_TIME_AT_BOOT.store(boot.as_nanos().max(1) as u64, Ordering::Relaxed);
+}
+
+/// The time of the certificate verification, from [`set_unix_time`].
+#[derive(Debug)]
+struct AxTimeProvider;
+
+impl TimeProvider for AxTimeProvider {
+    fn current_time(&self) -> Option<UnixTime> {
+        match UNIX_TIME_AT_BOOT.load(Ordering::Relaxed) {
+            0 => None,
+            boot => Some(UnixTime::since_unix_epoch(
+                Duration::from_nanos(boot) + arceos_api::time::ax_current_time(),
+            )),
+        }
+    }
+}
+
+fn tls_error<E: core::fmt::Debug>(e: E) -> io::Error {
+    ax_err_type!(InvalidData, format_args!("TLS error: {:?}", e))
+}
+
+fn check_random() -> io::Result<()> {
+    match rng::is_seeded() {
+        true => Ok(()),
+        false => ax_err!(Unsupported, "TLS error: no source of random numbers"),
+    }
+}
+
+fn crypto_provider() -> Arc<CryptoProvider> {
+    Arc::new(CryptoProvider {
+        secure_random: &rng::AxRandom,
+        ..rustls_rustcrypto::provider()
+    })
+}
+
+/// Creates connections of TLS clients.
+#[derive(Clone)]
+pub struct TlsConnector(Arc<ClientConfig>);
+
+impl TlsConnector {
+    /// Creates a connector trusting the root certificates embedded from
+    /// [webpki-roots](https://docs.rs/webpki-roots).
+    pub fn new() -> io::Result<Self> {
+        let roots = RootCertStore {
+            roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+        };
+        Self::with_roots(roots)
+    }
+
+    /// Creates a connector trusting only the root certificates in `pem`,
+    /// e.g. the self-signed certificate of a test server.
+    pub fn with_root_certs_pem(pem: &[u8]) -> io::Result<Self> {
+        let mut roots = RootCertStore::empty();
+        for cert in CertificateDer::pem_slice_iter(pem) {
+            roots.add(cert.map_err(tls_error)?).map_err(tls_error)?;
+        }
+        if roots.is_empty() {
+            return ax_err!(InvalidInput, "TLS error: no root certificate");
+        }
+        Self::with_roots(roots)
+    }
+
+    fn with_roots(roots: RootCertStore) -> io::Result<Self> {
+        let config =
+            ClientConfig::builder_with_details(crypto_provider(), Arc::new(AxTimeProvider))
+                .with_protocol_versions(&[&rustls::version::TLS13])
+                .map_err(tls_error)?
+                .with_root_certificates(roots)
+                .with_no_client_auth();
+        Ok(Self(Arc::new(config)))
+    }
+
+    /// Performs the TLS handshake over `stream` as a client, verifying that
+    /// the server certificate is valid for `server_name`.
+    pub fn connect(&self, server_name: &str, stream: TcpStream) -> io::Result<TlsStream> {
+        let name = ServerName::try_from(String::from(server_name))
+            .map_err(|_| ax_err_type!(InvalidInput, "TLS error: invalid server name"))?;
+        check_random()?;
+        let conn = UnbufferedClientConnection::new(self.0.clone(), name).map_err(tls_error)?;
+        TlsStream::handshake(Conn::Client(conn), stream)
+    }
+}
+
+/// Accepts connections of TLS clients.
+#[derive(Clone)]
+pub struct TlsAcceptor(Arc<ServerConfig>);
+
+impl TlsAcceptor {
+    /// Creates an acceptor presenting the certificate chain in `cert_pem`,
+    /// whose private key is in `key_pem`.
+    pub fn from_pem(cert_pem: &[u8], key_pem: &[u8]) -> io::Result<Self> {
+        let certs = CertificateDer::pem_slice_iter(cert_pem)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(tls_error)?;
+        let key = PrivateKeyDer::from_pem_slice(key_pem).map_err(tls_error)?;
+        let config =
+            ServerConfig::builder_with_details(crypto_provider(), Arc::new(AxTimeProvider))
+                .with_protocol_versions(&[&rustls::version::TLS13])
+                .map_err(tls_error)?
+                .with_no_client_auth()
+                .with_single_cert(certs, key)
+                .map_err(tls_error)?;
+        Ok(Self(Arc::new(config)))
+    }
+
+    /// Creates an acceptor presenting the certificate chain in the PEM file
+    /// `cert_path`, whose private key is in the PEM file `key_path`.
+    #[cfg(feature = "fs")]
+    pub fn from_pem_files(cert_path: &str, key_path: &str) -> io::Result<Self> {
+        Self::from_pem(&crate::fs::read(cert_path)?, &crate::fs::read(key_path)?)
+    }
+
+    /// Performs the TLS handshake over `stream` as a server.
+    pub fn accept(&self, stream: TcpStream) -> io::Result<TlsStream> {
+        check_random()?;
+        let conn = UnbufferedServerConnection::new(self.0.clone()).map_err(tls_error)?;
+        TlsStream::handshake(Conn::Server(conn), stream)
+    }
+}
+
+enum Conn {
+    Client(UnbufferedClientConnection),
+    Server(UnbufferedServerConnection),
+}
+
+/// What to do when the connection is ready for application data.
+#[derive(Clone, Copy)]
+enum Op<'a> {
+    None,
+    Write(&'a [u8]),
+    Close,
+}
+
+/// The result of processing the buffered TLS records once.
+enum Step {
+    /// Some progress was made, process again.
+    Progress,
+    /// More TLS records from the peer are needed.
+    NeedData,
+    /// Ready for application data, and nothing to do.
+    Ready,
+    /// Done with `Op::Write` or `Op::Close`, returns the bytes written.
+    Done(usize),
+    /// The peer has closed the connection.
+    Closed,
+}
+
+/// The buffers and the TCP stream under a connection.
+struct TlsIo {
+    stream: TcpStream,
+    outgoing: Vec<u8>,
+    received: VecDeque<u8>,
+}
+
+impl TlsIo {
+    /// Calls `f` with free space at the end of the outgoing buffer, retrying
+    /// with more space if it's not enough.
+    fn fill_outgoing<E>(
+        &mut self,
+        mut f: impl FnMut(&mut [u8]) -> Result<usize, E>,
+        insufficient: impl Fn(&E) -> Option<usize>,
+    ) -> io::Result<()>
+    where
+        E: core::fmt::Debug,
+    {
+        let used = self.outgoing.len();
+        let mut size = WRITE_BUF_SIZE;
+        loop {
+            self.outgoing.resize(used + size, 0);
+            match f(&mut self.outgoing[used..]) {
+                Ok(n) => {
+                    self.outgoing.truncate(used + n);
+                    return Ok(());
+                }
+                Err(e) => match insufficient(&e) {
+                    Some(required) => size = required,
+                    None => {
+                        self.outgoing.truncate(used);
+                        return Err(tls_error(e));
+                    }
+                },
+            }
+        }
+    }
+
+    fn flush_outgoing(&mut self) -> io::Result<()> {
+        self.stream.write_all(&self.outgoing)?;
+        self.outgoing.clear();
+        Ok(())
+    }
+
+    /// Acts on the state of the connection, returns the bytes of the processed
+    /// TLS records to discard, and the result.
+    fn process<Data>(
+        &mut self,
+        status: UnbufferedStatus<'_, '_, Data>,
+        op: Op,
+    ) -> io::Result<(usize, Step)> {
+        let UnbufferedStatus { mut discard, state } = status;
+        let step = match state.map_err(tls_error)? {
+            ConnectionState::ReadTraffic(mut state) => {
+                while let Some(record) = state.next_record() {
+                    let AppDataRecord {
+                        discard: n,
+                        payload,
+                    } = record.map_err(tls_error)?;
+                    discard += n;
+                    self.received.extend(payload);
+                }
+                Step::Progress
+            }
+            ConnectionState::EncodeTlsData(mut state) => {
+                self.fill_outgoing(
+                    |buf| state.encode(buf),
+                    |e| match e {
+                        EncodeError::InsufficientSize(InsufficientSizeError { required_size }) => {
+                            Some(*required_size)
+                        }
+                        _ => None,
+                    },
+                )?;
+                Step::Progress
+            }
+            ConnectionState::TransmitTlsData(state) => {
+                self.flush_outgoing()?;
+                state.done();
+                Step::Progress
+            }
+            ConnectionState::BlockedHandshake => Step::NeedData,
+            ConnectionState::WriteTraffic(mut state) => {
+                let insufficient = |e: &EncryptError| match e {
+                    EncryptError::InsufficientSize(InsufficientSizeError { required_size }) => {
+                        Some(*required_size)
+                    }
+                    _ => None,
+                };
+                match op {
+                    Op::None => Step::Ready,
+                    Op::Write(data) => {
+                        self.fill_outgoing(|buf| state.encrypt(data, buf), insufficient)?;
+                        self.flush_outgoing()?;
+                        Step::Done(data.len())
+                    }
+                    Op::Close => {
+                        self.fill_outgoing(|buf| state.queue_close_notify(buf), insufficient)?;
+                        self.flush_outgoing()?;
+                        Step::Done(0)
+                    }
+                }
+            }
+            ConnectionState::PeerClosed | ConnectionState::Closed => Step::Closed,
+            _ => return ax_err!(Unsupported, "TLS error: unexpected connection state"),
+        };
+        Ok((discard, step))
+    }
+}
+
+/// A TLS stream over a TCP connection, created by [`TlsConnector::connect`]
+/// or [`TlsAcceptor::accept`].
+///
+/// The application data is encrypted and sent by each write, there is no
+/// buffering.
+pub struct TlsStream {
+    conn: Conn,
+    /// The TLS records received but not processed yet.
+    incoming: Vec<u8>,
+    io: TlsIo,
+    peer_closed: bool,
+}
+
+impl TlsStream {
+    fn handshake(conn: Conn, stream: TcpStream) -> io::Result<Self> {
+        let mut tls = Self {
+            conn,
+            incoming: Vec::new(),
+            io: TlsIo {
+                stream,
+                outgoing: Vec::new(),
+                received: VecDeque::new(),
+            },
+            peer_closed: false,
+        };
+        loop {
+            match tls.step(Op::None)? {
+                Step::Progress => {}
+                Step::NeedData => tls.read_tls()?,
+                Step::Ready => return Ok(tls),
+                Step::Done(_) => unreachable!(),
+                Step::Closed => return ax_err!(ConnectionReset, "TLS handshake failed"),
+            }
+        }
+    }
+
+    /// Processes the buffered TLS records once, and performs `op` if the
+    /// connection is ready for application data.
+    fn step(&mut self, op: Op) -> io::Result<Step> {
+        let incoming = &mut self.incoming[..];
+        let (discard, step) = match &mut self.conn {
+            Conn::Client(conn) => self.io.process(conn.process_tls_records(incoming), op)?,
+            Conn::Server(conn) => self.io.process(conn.process_tls_records(incoming), op)?,
+        };
+        self.incoming.drain(..discard);
+        Ok(step)
+    }
+
+    /// Receives more TLS records from the TCP stream.
+    fn read_tls(&mut self) -> io::Result<()> {
+        let used = self.incoming.len();
+        self.incoming.resize(used + READ_CHUNK_SIZE, 0);
+        let res = self.io.stream.read(&mut self.incoming[used..]);
+        self.incoming.truncate(used + *res.as_ref().unwrap_or(&0));
+        match res? {
+            0 => ax_err!(
+                UnexpectedEof,
+                "TLS error: connection closed without close_notify"
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns a reference to the underlying TCP stream.
+    pub fn get_ref(&self) -> &TcpStream {
+        &self.io.stream
+    }
+
+    /// Sends the `close_notify` alert to the peer, and shuts down the TCP
+    /// connection.
+    pub fn shutdown(&mut self) -> io::Result<()> {
+        loop {
+            match self.step(Op::Close)? {
+                Step::Progress => {}
+                Step::NeedData => self.read_tls()?,
+                Step::Done(_) | Step::Closed | Step::Ready => break,
+            }
+        }
+        self.io.stream.shutdown()
+    }
+}
+
+impl Read for TlsStream {
+    /// Reads the application data, returns 0 if the peer has closed the
+    /// connection by `close_notify`.
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if !self.io.received.is_empty() || buf.is_empty() {
+                let n = buf.len().min(self.io.received.len());
+                for (dst, src) in buf.iter_mut().zip(self.io.received.drain(..n)) {
+                    *dst = src;
+                }
+                return Ok(n);
+            }
+            if self.peer_closed {
+                return Ok(0);
+            }
+            match self.step(Op::None)? {
+                Step::Progress => {}
+                Step::NeedData | Step::Ready => self.read_tls()?,
+                Step::Done(_) => unreachable!(),
+                Step::Closed => self.peer_closed = true,
+            }
+        }
+    }
+}
+
+impl Write for TlsStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            match self.step(Op::Write(buf))? {
+                Step::Progress => {}
+                Step::NeedData => self.read_tls()?,
+                Step::Done(n) => return Ok(n),
+                Step::Ready => unreachable!(),
+                Step::Closed => {
+                    self.peer_closed = true;
+                    return ax_err!(ConnectionReset, "TLS error: connection closed");
+                }
+            }
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
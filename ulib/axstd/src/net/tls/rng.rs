@@ -0,0 +1,81 @@
+//! Random numbers for TLS, generated by ChaCha20 seeded from the hardware.
+//!
+//! The seed comes from `RDRAND` on x86_64 if it's supported. Otherwise, there
+//! is no entropy source, and the seed must be given by [`set_random_seed`]
+//! before any TLS handshake, which fails until then.
+
+use rand_chacha::rand_core::{RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use rustls::crypto::{GetRandomFailed, SecureRandom};
+use spinlock::SpinNoIrq;
+
+static RNG: SpinNoIrq<Option<ChaCha20Rng>> = SpinNoIrq::new(None);
+
+/// Seeds the random number generator of TLS with 32 bytes from a source of
+/// true randomness.
+///
+/// It's required if the CPU has no hardware random number generator. The
+/// generator is reseeded if it has been seeded.
+pub fn set_random_seed(seed: [u8; 32]) {
+    *RNG.lock() = Some(ChaCha20Rng::from_seed(seed));
+}
+
+/// Whether the random number generator is seeded, seeds it from the hardware
+/// if not yet.
+pub(super) fn is_seeded() -> bool {
+    let mut rng = RNG.lock();
+    if rng.is_none() {
+        *rng = hardware_seed().map(ChaCha20Rng::from_seed);
+    }
+    rng.is_some()
+}
+
+/// The random number generator of rustls.
+#[derive(Debug)]
+pub struct AxRandom;
+
+impl SecureRandom for AxRandom {
+    fn fill(&self, buf: &mut [u8]) -> Result<(), GetRandomFailed> {
+        fill_random(buf).ok_or(GetRandomFailed)
+    }
+}
+
+fn fill_random(buf: &mut [u8]) -> Option<()> {
+    is_seeded().then(|| RNG.lock().as_mut().unwrap().fill_bytes(buf))
+}
+
+/// The source of `getrandom`, used by the crypto provider to generate keys.
+fn ax_getrandom(buf: &mut [u8]) -> Result<(), getrandom::Error> {
+    fill_random(buf).ok_or(getrandom::Error::UNSUPPORTED)
+}
+
+getrandom::register_custom_getrandom!(ax_getrandom);
+
+#[cfg(target_arch = "x86_64")]
+fn hardware_seed() -> Option<[u8; 32]> {
+    use core::arch::x86_64::{__cpuid, _rdrand64_step};
+
+    #[target_feature(enable = "rdrand")]
+    unsafe fn rdrand() -> Option<u64> {
+        let mut val = 0;
+        // retry as recommended by Intel, it may fail transiently
+        (0..10)
+            .any(|_| _rdrand64_step(&mut val) == 1)
+            .then_some(val)
+    }
+
+    // CPUID.01H:ECX.RDRAND[bit 30]
+    if unsafe { __cpuid(1) }.ecx & (1 << 30) == 0 {
+        return None;
+    }
+    let mut seed = [0; 32];
+    for chunk in seed.chunks_mut(8) {
+        chunk.copy_from_slice(&unsafe { rdrand() }?.to_le_bytes());
+    }
+    Some(seed)
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn hardware_seed() -> Option<[u8; 32]> {
+    None
+}
@@ -1,5 +1,7 @@
 use super::{SocketAddr, ToSocketAddrs};
 use crate::io;
+use core::future::poll_fn;
+use core::time::Duration;
 
 use arceos_api::net::{self as api, AxUdpSocketHandle};
 
@@ -25,6 +27,13 @@ impl UdpSocket {
         })
     }
 
+    /// Binds this socket to the network interface named `name`, so that it
+    /// only sends and receives packets through it. An empty name removes the
+    /// binding.
+    pub fn bind_to_device(&self, name: &str) -> io::Result<()> {
+        api::ax_udp_bind_to_device(&self.0, name)
+    }
+
     /// Returns the socket address that this socket was created from.
     pub fn local_addr(&self) -> io::Result<SocketAddr> {
         api::ax_udp_socket_addr(&self.0)
@@ -93,4 +102,53 @@ impl UdpSocket {
     pub fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
         api::ax_udp_recv(&self.0, buf)
     }
+
+    /// Asynchronous version of [`recv_from`](Self::recv_from).
+    pub async fn recv_from_async(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        poll_fn(|cx| api::ax_udp_poll_recv_from(&self.0, cx, buf)).await
+    }
+
+    /// Asynchronous version of [`send_to`](Self::send_to).
+    pub async fn send_to_async<A: ToSocketAddrs>(&self, buf: &[u8], addr: A) -> io::Result<usize> {
+        match addr.to_socket_addrs()?.next() {
+            Some(addr) => poll_fn(|cx| api::ax_udp_poll_send_to(&self.0, cx, buf, addr)).await,
+            None => axerrno::ax_err!(InvalidInput, "no addresses to send data to"),
+        }
+    }
+
+    /// Asynchronous version of [`send`](Self::send).
+    pub async fn send_async(&self, buf: &[u8]) -> io::Result<usize> {
+        poll_fn(|cx| api::ax_udp_poll_send(&self.0, cx, buf)).await
+    }
+
+    /// Asynchronous version of [`recv`](Self::recv).
+    pub async fn recv_async(&self, buf: &mut [u8]) -> io::Result<usize> {
+        poll_fn(|cx| api::ax_udp_poll_recv(&self.0, cx, buf)).await
+    }
+
+    /// Sets the read timeout to the timeout specified.
+    ///
+    /// If the value specified is `None`, then read calls will block
+    /// indefinitely. An error is returned if the zero duration is passed.
+    pub fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        api::ax_udp_set_recv_timeout(&self.0, dur)
+    }
+
+    /// Returns the read timeout of this socket.
+    pub fn read_timeout(&self) -> io::Result<Option<Duration>> {
+        api::ax_udp_recv_timeout(&self.0)
+    }
+
+    /// Sets the write timeout to the timeout specified.
+    ///
+    /// If the value specified is `None`, then write calls will block
+    /// indefinitely. An error is returned if the zero duration is passed.
+    pub fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        api::ax_udp_set_send_timeout(&self.0, dur)
+    }
+
+    /// Returns the write timeout of this socket.
+    pub fn write_timeout(&self) -> io::Result<Option<Duration>> {
+        api::ax_udp_send_timeout(&self.0)
+    }
 }
@@ -8,11 +8,14 @@ pub use core::sync::atomic;
 pub use alloc::sync::{Arc, Weak};
 
 #[cfg(feature = "multitask")]
-mod mutex;
-
+#[doc(cfg(feature = "multitask"))]
+pub use arceos_api::sync::{Barrier, BarrierWaitResult, Condvar, WaitTimeoutResult};
+#[cfg(feature = "multitask")]
+#[doc(cfg(feature = "multitask"))]
+pub use arceos_api::sync::{Mutex, MutexGuard};
 #[cfg(feature = "multitask")]
 #[doc(cfg(feature = "multitask"))]
-pub use self::mutex::{Mutex, MutexGuard};
+pub use arceos_api::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard, Semaphore};
 
 #[cfg(not(feature = "multitask"))]
 #[doc(cfg(not(feature = "multitask")))]
@@ -49,6 +49,8 @@ pub struct Builder {
     name: Option<String>,
     // The size of the stack for the spawned thread in bytes
     stack_size: Option<usize>,
+    // The CPUs that the spawned thread is allowed to run on
+    cpumask: Option<usize>,
 }
 
 impl Builder {
@@ -58,6 +60,7 @@ impl Builder {
         Builder {
             name: None,
             stack_size: None,
+            cpumask: None,
         }
     }
 
@@ -73,6 +76,19 @@ impl Builder {
         self
     }
 
+    /// Sets the CPUs that the new thread is allowed to run on.
+    ///
+    /// The invalid CPU IDs are ignored. Spawning fails if no valid CPU is
+    /// given.
+    pub fn affinity(mut self, cpus: &[usize]) -> Builder {
+        let cpumask = cpus
+            .iter()
+            .filter(|&&cpu| cpu < usize::BITS as usize)
+            .fold(0, |mask, cpu| mask | (1 << cpu));
+        self.cpumask = Some(cpumask);
+        self
+    }
+
     /// Spawns a new thread by taking ownership of the `Builder`, and returns an
     /// [`io::Result`] to its [`JoinHandle`].
     ///
@@ -115,7 +131,10 @@ impl Builder {
             drop(their_packet);
         };
 
-        let task = api::ax_spawn(main, name, stack_size);
+        let task = match self.cpumask {
+            Some(cpumask) => api::ax_spawn_with_affinity(main, name, stack_size, cpumask)?,
+            None => api::ax_spawn(main, name, stack_size),
+        };
         Ok(JoinHandle {
             thread: Thread::from_id(task.id()),
             native: task,